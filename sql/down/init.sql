@@ -0,0 +1,12 @@
+-- Revert v1: drops every table and all data in them
+DROP TABLE IF EXISTS auth;
+DROP TABLE IF EXISTS wafer_stack_stats;
+DROP TABLE IF EXISTS wafer_maps;
+DROP TABLE IF EXISTS substrate_defect;
+DROP TABLE IF EXISTS product_defect_map;
+DROP TABLE IF EXISTS product_bin_selection;
+DROP TABLE IF EXISTS product_size;
+DROP TABLE IF EXISTS product_offsets;
+DROP TABLE IF EXISTS folder_index;
+DROP TABLE IF EXISTS file_index;
+DROP TABLE IF EXISTS oem_product_map;
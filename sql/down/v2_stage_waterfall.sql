@@ -0,0 +1,29 @@
+-- Revert v2: rebuild wafer_stack_stats in its v1 shape (ALTER TABLE ... DROP
+-- COLUMN needs SQLite 3.35+). The v3 triggers are gone by the time this runs.
+CREATE TABLE wafer_stack_stats_v1 (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    oem_product_id TEXT NOT NULL,
+    batch_id TEXT NOT NULL,
+    wafer_id TEXT NOT NULL,
+    total_tested INTEGER NOT NULL,
+    total_pass INTEGER NOT NULL,
+    total_fail INTEGER NOT NULL,
+    yield_percentage REAL NOT NULL,
+    bin_counts TEXT NOT NULL DEFAULT '{}',
+    start_time TEXT,
+    stop_time TEXT,
+    created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+    UNIQUE(oem_product_id, batch_id, wafer_id)
+);
+
+INSERT INTO wafer_stack_stats_v1 (
+    id, oem_product_id, batch_id, wafer_id, total_tested, total_pass, total_fail,
+    yield_percentage, bin_counts, start_time, stop_time, created_at
+)
+SELECT
+    id, oem_product_id, batch_id, wafer_id, total_tested, total_pass, total_fail,
+    yield_percentage, bin_counts, start_time, stop_time, created_at
+FROM wafer_stack_stats;
+
+DROP TABLE wafer_stack_stats;
+ALTER TABLE wafer_stack_stats_v1 RENAME TO wafer_stack_stats;
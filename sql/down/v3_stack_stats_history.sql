@@ -0,0 +1,4 @@
+-- Revert v3: the archived results are dropped with the table
+DROP TRIGGER IF EXISTS trg_wafer_stack_stats_delete;
+DROP TRIGGER IF EXISTS trg_wafer_stack_stats_update;
+DROP TABLE IF EXISTS wafer_stack_stats_history;
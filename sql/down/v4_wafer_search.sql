@@ -0,0 +1,9 @@
+-- Revert v4: parsed headers are re-read by `rust_search_reindex` after upgrading again
+DROP TRIGGER IF EXISTS trg_wafer_search_meta_delete;
+DROP TRIGGER IF EXISTS trg_wafer_search_meta_update;
+DROP TRIGGER IF EXISTS trg_wafer_search_meta_insert;
+DROP TRIGGER IF EXISTS trg_wafer_search_map_delete;
+DROP TRIGGER IF EXISTS trg_wafer_search_map_update;
+DROP TRIGGER IF EXISTS trg_wafer_search_map_insert;
+DROP TABLE IF EXISTS wafer_search;
+DROP TABLE IF EXISTS wafer_map_meta;
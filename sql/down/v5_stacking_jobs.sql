@@ -0,0 +1,3 @@
+-- Revert v5: queued and finished stacking jobs are dropped
+DROP TABLE IF EXISTS stacking_job_tasks;
+DROP TABLE IF EXISTS stacking_jobs;
@@ -0,0 +1,2 @@
+-- Revert v6: stored bin translation tables are dropped
+DROP TABLE IF EXISTS product_bin_map;
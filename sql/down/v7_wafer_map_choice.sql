@@ -0,0 +1,2 @@
+-- Revert v7: stacking falls back to the recommended map everywhere
+DROP TABLE IF EXISTS wafer_map_choice;
@@ -0,0 +1,7 @@
+-- =======================================
+-- v2: Cross-stage yield waterfall
+-- =======================================
+
+-- JSON of `rust_stage_waterfall` (yield after each stage, incremental loss,
+-- per-stage fail-bin Pareto); NULL for wafers stacked before v2
+ALTER TABLE wafer_stack_stats ADD COLUMN stage_waterfall TEXT;
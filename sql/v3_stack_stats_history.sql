@@ -0,0 +1,51 @@
+-- =======================================
+-- v3: Keep superseded stacking results
+-- =======================================
+
+-- Re-stacking a wafer upserts wafer_stack_stats in place; the previous
+-- result is copied here first so yield history survives re-runs and deletes
+CREATE TABLE IF NOT EXISTS wafer_stack_stats_history (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    oem_product_id TEXT NOT NULL,
+    batch_id TEXT NOT NULL,
+    wafer_id TEXT NOT NULL,
+    total_tested INTEGER NOT NULL,
+    total_pass INTEGER NOT NULL,
+    total_fail INTEGER NOT NULL,
+    yield_percentage REAL NOT NULL,
+    bin_counts TEXT NOT NULL DEFAULT '{}',
+    start_time TEXT,
+    stop_time TEXT,
+    stage_waterfall TEXT,
+    created_at TIMESTAMP,                       -- when the old result was stacked
+    superseded_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX IF NOT EXISTS idx_wafer_stack_stats_history_wafer
+ON wafer_stack_stats_history (oem_product_id, batch_id, wafer_id);
+
+CREATE TRIGGER IF NOT EXISTS trg_wafer_stack_stats_update
+BEFORE UPDATE ON wafer_stack_stats
+BEGIN
+    INSERT INTO wafer_stack_stats_history (
+        oem_product_id, batch_id, wafer_id, total_tested, total_pass, total_fail,
+        yield_percentage, bin_counts, start_time, stop_time, stage_waterfall, created_at
+    ) VALUES (
+        OLD.oem_product_id, OLD.batch_id, OLD.wafer_id, OLD.total_tested, OLD.total_pass,
+        OLD.total_fail, OLD.yield_percentage, OLD.bin_counts, OLD.start_time, OLD.stop_time,
+        OLD.stage_waterfall, OLD.created_at
+    );
+END;
+
+CREATE TRIGGER IF NOT EXISTS trg_wafer_stack_stats_delete
+BEFORE DELETE ON wafer_stack_stats
+BEGIN
+    INSERT INTO wafer_stack_stats_history (
+        oem_product_id, batch_id, wafer_id, total_tested, total_pass, total_fail,
+        yield_percentage, bin_counts, start_time, stop_time, stage_waterfall, created_at
+    ) VALUES (
+        OLD.oem_product_id, OLD.batch_id, OLD.wafer_id, OLD.total_tested, OLD.total_pass,
+        OLD.total_fail, OLD.yield_percentage, OLD.bin_counts, OLD.start_time, OLD.stop_time,
+        OLD.stage_waterfall, OLD.created_at
+    );
+END;
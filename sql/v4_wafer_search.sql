@@ -0,0 +1,88 @@
+-- =======================================
+-- v4: Wafer search
+-- =======================================
+
+-- Header fields of each indexed wafer map, filled in when the map is parsed
+-- (live ingestion or `rust_search_reindex`)
+CREATE TABLE IF NOT EXISTS wafer_map_meta (
+    file_path TEXT PRIMARY KEY,
+    device TEXT,
+    operator TEXT,
+    meas_time INTEGER,                          -- epoch ms of the header time
+    total_tested INTEGER,
+    total_pass INTEGER,
+    yield_percentage REAL,
+    bins TEXT NOT NULL DEFAULT '',              -- tested bins present, space-separated
+
+    FOREIGN KEY (file_path) REFERENCES wafer_maps(file_path)
+        ON DELETE CASCADE ON UPDATE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS idx_wafer_map_meta_yield
+ON wafer_map_meta (yield_percentage);
+
+CREATE INDEX IF NOT EXISTS idx_wafer_map_meta_time
+ON wafer_map_meta (meas_time);
+
+-- Free text over a wafer map and its header; rowid = wafer_maps.idx
+CREATE VIRTUAL TABLE IF NOT EXISTS wafer_search USING fts5 (
+    product_id,
+    batch_id,
+    stage,
+    device,
+    operator,
+    bins,
+    file_path,
+    tokenize = "unicode61 tokenchars '-_.'"
+);
+
+INSERT INTO wafer_search (rowid, product_id, batch_id, stage, device, operator, bins, file_path)
+SELECT w.idx, w.product_id, w.batch_id, w.stage || ' ' || COALESCE(w.sub_stage, ''),
+       m.device, m.operator, m.bins, w.file_path
+FROM wafer_maps w LEFT JOIN wafer_map_meta m ON m.file_path = w.file_path;
+
+CREATE TRIGGER IF NOT EXISTS trg_wafer_search_map_insert
+AFTER INSERT ON wafer_maps
+BEGIN
+    INSERT INTO wafer_search (rowid, product_id, batch_id, stage, device, operator, bins, file_path)
+    SELECT NEW.idx, NEW.product_id, NEW.batch_id, NEW.stage || ' ' || COALESCE(NEW.sub_stage, ''),
+           m.device, m.operator, m.bins, NEW.file_path
+    FROM (SELECT 1) LEFT JOIN wafer_map_meta m ON m.file_path = NEW.file_path;
+END;
+
+CREATE TRIGGER IF NOT EXISTS trg_wafer_search_map_update
+AFTER UPDATE ON wafer_maps
+BEGIN
+    DELETE FROM wafer_search WHERE rowid = OLD.idx;
+    INSERT INTO wafer_search (rowid, product_id, batch_id, stage, device, operator, bins, file_path)
+    SELECT NEW.idx, NEW.product_id, NEW.batch_id, NEW.stage || ' ' || COALESCE(NEW.sub_stage, ''),
+           m.device, m.operator, m.bins, NEW.file_path
+    FROM (SELECT 1) LEFT JOIN wafer_map_meta m ON m.file_path = NEW.file_path;
+END;
+
+CREATE TRIGGER IF NOT EXISTS trg_wafer_search_map_delete
+AFTER DELETE ON wafer_maps
+BEGIN
+    DELETE FROM wafer_search WHERE rowid = OLD.idx;
+END;
+
+CREATE TRIGGER IF NOT EXISTS trg_wafer_search_meta_insert
+AFTER INSERT ON wafer_map_meta
+BEGIN
+    UPDATE wafer_search SET device = NEW.device, operator = NEW.operator, bins = NEW.bins
+    WHERE rowid = (SELECT idx FROM wafer_maps WHERE file_path = NEW.file_path);
+END;
+
+CREATE TRIGGER IF NOT EXISTS trg_wafer_search_meta_update
+AFTER UPDATE ON wafer_map_meta
+BEGIN
+    UPDATE wafer_search SET device = NEW.device, operator = NEW.operator, bins = NEW.bins
+    WHERE rowid = (SELECT idx FROM wafer_maps WHERE file_path = NEW.file_path);
+END;
+
+CREATE TRIGGER IF NOT EXISTS trg_wafer_search_meta_delete
+AFTER DELETE ON wafer_map_meta
+BEGIN
+    UPDATE wafer_search SET device = NULL, operator = NULL, bins = NULL
+    WHERE rowid = (SELECT idx FROM wafer_maps WHERE file_path = OLD.file_path);
+END;
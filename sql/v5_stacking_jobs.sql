@@ -0,0 +1,36 @@
+-- =======================================
+-- v5: Persistent stacking job queue
+-- =======================================
+
+-- A queued stacking run: one task per wafer. `options` is the frontend's
+-- job options as JSON; the backend only stores it
+CREATE TABLE IF NOT EXISTS stacking_jobs (
+    job_id TEXT PRIMARY KEY,
+    name TEXT NOT NULL DEFAULT '',
+    options TEXT NOT NULL DEFAULT '{}',
+    max_attempts INTEGER NOT NULL DEFAULT 1,
+    cancelled INTEGER NOT NULL DEFAULT 0,
+    created_at INTEGER NOT NULL,                -- epoch ms
+    updated_at INTEGER NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS stacking_job_tasks (
+    job_id TEXT NOT NULL,
+    seq INTEGER NOT NULL,                       -- position in the job
+    label TEXT NOT NULL DEFAULT '',
+    wafer TEXT NOT NULL,                        -- the wafer's job item as JSON
+    status TEXT NOT NULL DEFAULT 'queued',      -- queued | leased | done | failed | cancelled
+    attempts INTEGER NOT NULL DEFAULT 0,        -- failed attempts
+    worker TEXT,                                -- holder of the lease
+    progress REAL NOT NULL DEFAULT 0,           -- 0..1
+    message TEXT,
+    error TEXT,
+    result TEXT,                                -- JSON
+    started_at INTEGER,
+    finished_at INTEGER,
+    PRIMARY KEY (job_id, seq),
+    FOREIGN KEY (job_id) REFERENCES stacking_jobs(job_id) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS idx_stacking_job_tasks_status
+ON stacking_job_tasks (status, job_id, seq);
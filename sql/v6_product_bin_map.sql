@@ -0,0 +1,12 @@
+-- =======================================
+-- v6: Bin translation table per product
+-- =======================================
+
+-- `BinMapTable` JSON (`rust_parse_bin_map_xls` / `_json`, customer chosen on
+-- import); applied when stacking the product's maps and writing its outputs
+CREATE TABLE IF NOT EXISTS product_bin_map (
+    oem_product_id TEXT PRIMARY KEY,
+    bin_map TEXT NOT NULL,
+
+    FOREIGN KEY (oem_product_id) REFERENCES oem_product_map(oem_product_id) ON DELETE CASCADE
+);
@@ -0,0 +1,19 @@
+-- =======================================
+-- v7: Map chosen to stack per wafer and stage
+-- =======================================
+
+-- The user's pick among several maps of one product / lot / wafer / stage /
+-- sub-stage (see `rust_wafer_map_duplicates`); without a row the recommended
+-- map (highest retest, then newest) stacks. `sub_stage` is '' for none
+CREATE TABLE IF NOT EXISTS wafer_map_choice (
+    product_id TEXT NOT NULL,
+    batch_id TEXT NOT NULL,
+    wafer_id INTEGER NOT NULL,
+    stage TEXT NOT NULL,
+    sub_stage TEXT NOT NULL DEFAULT '',
+    file_path TEXT NOT NULL,
+
+    PRIMARY KEY (product_id, batch_id, wafer_id, stage, sub_stage),
+    -- the choice goes when its file leaves the index
+    FOREIGN KEY (file_path) REFERENCES file_index(file_path) ON DELETE CASCADE
+);
@@ -0,0 +1,122 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+use crate::wafer::bins::{is_tested, BinSet};
+use crate::wafer::ds::{AsciiDie, BinValue};
+
+use super::percent;
+
+// =============================================================================
+// Lot composite map / commonality
+//
+// Overlays N stacked wafers of a lot position by position: how often each die
+// position fails, and which positions fail often enough to be systematic
+// rather than random. Wafers must share the product grid (stacking output).
+// =============================================================================
+
+/// A stacked wafer result.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CompositeWafer {
+    pub wafer_id: String,
+    pub dies: Vec<AsciiDie>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct CompositeOptions {
+    /// Pass bins ("BIN 1", ...); empty uses the defaults
+    pub pass_bins: Vec<String>,
+    /// Fail percentage at or above which a position is systematic
+    pub systematic_threshold: f64,
+    /// Positions tested on fewer wafers are never flagged
+    pub min_wafers: u32,
+}
+
+impl Default for CompositeOptions {
+    fn default() -> Self {
+        Self {
+            pass_bins: Vec::new(),
+            systematic_threshold: 50.0,
+            min_wafers: 3,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PositionStat {
+    pub x: i32,
+    pub y: i32,
+    /// Wafers on which the position was tested
+    pub tested: u32,
+    pub fail: u32,
+    pub fail_percent: f64,
+    pub systematic: bool,
+    pub failing_wafers: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CompositeMap {
+    pub wafer_count: u32,
+    pub systematic_threshold: f64,
+    /// Ordered by row, then column
+    pub positions: Vec<PositionStat>,
+    pub systematic_count: u32,
+}
+
+impl CompositeMap {
+    /// The composite grid as a map whose bin is the fail count.
+    pub fn dies(&self) -> Vec<AsciiDie> {
+        self.positions
+            .iter()
+            .map(|p| AsciiDie {
+                x: p.x,
+                y: p.y,
+                bin: BinValue::Number(p.fail as i32),
+            })
+            .collect()
+    }
+}
+
+pub fn composite_map(wafers: &[CompositeWafer], options: &CompositeOptions) -> CompositeMap {
+    let pass = BinSet::pass_or_default(&options.pass_bins);
+
+    // (y, x) keys give row-major order
+    let mut positions: BTreeMap<(i32, i32), (u32, Vec<String>)> = BTreeMap::new();
+    for wafer in wafers {
+        for die in wafer.dies.iter().filter(|d| is_tested(d.bin)) {
+            let entry = positions.entry((die.y, die.x)).or_default();
+            entry.0 += 1;
+            if !pass.matches(die.bin) {
+                entry.1.push(wafer.wafer_id.clone());
+            }
+        }
+    }
+
+    let positions: Vec<PositionStat> = positions
+        .into_iter()
+        .map(|((y, x), (tested, failing_wafers))| {
+            let fail = failing_wafers.len() as u32;
+            let fail_percent = percent(fail, tested);
+            PositionStat {
+                x,
+                y,
+                tested,
+                fail,
+                fail_percent,
+                systematic: tested >= options.min_wafers
+                    && fail_percent >= options.systematic_threshold,
+                failing_wafers,
+            }
+        })
+        .collect();
+
+    CompositeMap {
+        wafer_count: wafers.len() as u32,
+        systematic_threshold: options.systematic_threshold,
+        systematic_count: positions.iter().filter(|p| p.systematic).count() as u32,
+        positions,
+    }
+}
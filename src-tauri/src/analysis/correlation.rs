@@ -0,0 +1,219 @@
+use std::collections::{HashMap, HashSet};
+
+use serde::{Deserialize, Serialize};
+
+use crate::render::MapLayer;
+use crate::wafer::bins::{is_tested, BinSet};
+use crate::wafer::ds::BinValue;
+use crate::wafer::stats::{bin_label, bin_order};
+
+use super::percent;
+
+// =============================================================================
+// Map-to-map correlation
+//
+// Compares two aligned maps die by die (CP1 vs CP2, AOI vs CP2, wafer vs
+// wafer). Only positions tested in both maps are compared. "a" is the
+// reference (earlier stage), so a fail → pass flip is a retest recovery and a
+// pass → fail flip is an escape of the reference stage.
+// =============================================================================
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct CorrelationOptions {
+    /// Pass bins ("BIN 1", ...); empty uses the defaults
+    pub pass_bins: Vec<String>,
+}
+
+/// Pass/fail confusion matrix, a (rows) against b (columns).
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PassFailMatrix {
+    pub pass_pass: u32,
+    pub pass_fail: u32,
+    pub fail_pass: u32,
+    pub fail_fail: u32,
+}
+
+/// Bin transition counts; `counts[i][j]` dies went from `from_bins[i]` in a
+/// to `to_bins[j]` in b.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TransitionMatrix {
+    pub from_bins: Vec<String>,
+    pub to_bins: Vec<String>,
+    pub counts: Vec<Vec<u32>>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum Flip {
+    /// Failed in a, passes in b
+    Recovered,
+    /// Passed in a, fails in b
+    Escaped,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FlippedDie {
+    pub x: i32,
+    pub y: i32,
+    pub from: String,
+    pub to: String,
+    pub flip: Flip,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MapComparison {
+    pub a: String,
+    pub b: String,
+    /// Positions tested in both maps
+    pub compared: u32,
+    pub only_a: u32,
+    pub only_b: u32,
+    pub confusion: PassFailMatrix,
+    /// Same pass/fail verdict, percent of compared
+    pub agreement: f64,
+    /// Cohen's kappa of the pass/fail verdicts
+    pub kappa: f64,
+    /// Same bin, percent of compared
+    pub bin_agreement: f64,
+    /// Cohen's kappa over bins
+    pub bin_kappa: f64,
+    /// Share of a's fails that pass in b
+    pub recovery_rate: f64,
+    /// Share of a's passes that fail in b
+    pub escape_rate: f64,
+    pub transitions: TransitionMatrix,
+    /// Dies whose pass/fail verdict differs, in map order of a
+    pub flipped: Vec<FlippedDie>,
+}
+
+/// Cohen's kappa from a square contingency table.
+fn cohen_kappa(table: &[Vec<u32>]) -> f64 {
+    let n: u32 = table.iter().flatten().sum();
+    if n == 0 {
+        return 0.0;
+    }
+    let n = n as f64;
+    let observed = (0..table.len()).map(|i| table[i][i]).sum::<u32>() as f64 / n;
+    let expected = (0..table.len())
+        .map(|i| {
+            let row: u32 = table[i].iter().sum();
+            let col: u32 = table.iter().map(|r| r[i]).sum();
+            row as f64 * col as f64
+        })
+        .sum::<f64>()
+        / (n * n);
+    if (1.0 - expected).abs() < f64::EPSILON {
+        // one category only: full agreement is perfect, anything else is chance
+        return if (observed - 1.0).abs() < f64::EPSILON {
+            1.0
+        } else {
+            0.0
+        };
+    }
+    (observed - expected) / (1.0 - expected)
+}
+
+/// A die listed twice at one position counts once, with its first bin.
+pub fn compare_maps(a: &MapLayer, b: &MapLayer, options: &CorrelationOptions) -> MapComparison {
+    let pass = BinSet::pass_or_default(&options.pass_bins);
+    let mut b_bins: HashMap<(i32, i32), BinValue> = HashMap::new();
+    for die in b.dies.iter().filter(|d| is_tested(d.bin)) {
+        b_bins.entry((die.x, die.y)).or_insert(die.bin);
+    }
+
+    let mut confusion = PassFailMatrix::default();
+    let mut pairs: HashMap<(BinValue, BinValue), u32> = HashMap::new();
+    let mut flipped = Vec::new();
+    let mut only_a = 0;
+    let mut compared = 0;
+    let mut seen = HashSet::new();
+    for die in a.dies.iter().filter(|d| is_tested(d.bin)) {
+        if !seen.insert((die.x, die.y)) {
+            continue;
+        }
+        let Some(&to) = b_bins.get(&(die.x, die.y)) else {
+            only_a += 1;
+            continue;
+        };
+        compared += 1;
+        let (pass_a, pass_b) = (pass.matches(die.bin), pass.matches(to));
+        match (pass_a, pass_b) {
+            (true, true) => confusion.pass_pass += 1,
+            (true, false) => confusion.pass_fail += 1,
+            (false, true) => confusion.fail_pass += 1,
+            (false, false) => confusion.fail_fail += 1,
+        }
+        if pass_a != pass_b {
+            flipped.push(FlippedDie {
+                x: die.x,
+                y: die.y,
+                from: bin_label(die.bin),
+                to: bin_label(to),
+                flip: if pass_b {
+                    Flip::Recovered
+                } else {
+                    Flip::Escaped
+                },
+            });
+        }
+        *pairs.entry((die.bin, to)).or_insert(0) += 1;
+    }
+
+    // rows and columns share one bin order so the diagonal is "unchanged"
+    let mut bins: Vec<BinValue> = pairs.keys().flat_map(|(f, t)| [*f, *t]).collect();
+    bins.sort_by_key(|b| bin_order(*b));
+    bins.dedup();
+    let index = |bin: BinValue| bins.iter().position(|b| *b == bin).unwrap_or(0);
+    let mut square = vec![vec![0u32; bins.len()]; bins.len()];
+    for ((from, to), count) in &pairs {
+        square[index(*from)][index(*to)] += count;
+    }
+    let used_from: Vec<usize> = (0..bins.len())
+        .filter(|&i| square[i].iter().any(|&c| c > 0))
+        .collect();
+    let used_to: Vec<usize> = (0..bins.len())
+        .filter(|&j| square.iter().any(|r| r[j] > 0))
+        .collect();
+    let transitions = TransitionMatrix {
+        from_bins: used_from.iter().map(|&i| bin_label(bins[i])).collect(),
+        to_bins: used_to.iter().map(|&j| bin_label(bins[j])).collect(),
+        counts: used_from
+            .iter()
+            .map(|&i| used_to.iter().map(|&j| square[i][j]).collect())
+            .collect(),
+    };
+
+    let same_bin: u32 = (0..bins.len()).map(|i| square[i][i]).sum();
+    let pass_fail_table = vec![
+        vec![confusion.pass_pass, confusion.pass_fail],
+        vec![confusion.fail_pass, confusion.fail_fail],
+    ];
+
+    MapComparison {
+        a: a.name.clone(),
+        b: b.name.clone(),
+        compared,
+        only_a,
+        only_b: b_bins.len() as u32 - compared,
+        agreement: percent(confusion.pass_pass + confusion.fail_fail, compared),
+        kappa: cohen_kappa(&pass_fail_table),
+        bin_agreement: percent(same_bin, compared),
+        bin_kappa: cohen_kappa(&square),
+        recovery_rate: percent(
+            confusion.fail_pass,
+            confusion.fail_pass + confusion.fail_fail,
+        ),
+        escape_rate: percent(
+            confusion.pass_fail,
+            confusion.pass_pass + confusion.pass_fail,
+        ),
+        confusion,
+        transitions,
+        flipped,
+    }
+}
@@ -0,0 +1,92 @@
+mod tests;
+
+pub mod composite;
+pub mod correlation;
+pub mod signature;
+pub mod spc;
+pub mod waterfall;
+pub mod zones;
+
+use crate::wafer::coords::DieCoordinateSystem;
+use crate::wafer::ds::AsciiDie;
+use crate::wafer::geometry::WaferGeometry;
+
+// =============================================================================
+// Yield analytics over merged maps
+//
+// Everything here works on stacked `AsciiDie` results (already aligned to one
+// grid) and decides pass/fail with the same `BinSet` rules as the exporters.
+// =============================================================================
+
+/// `part` as a percentage of `total`; 0 when nothing was counted.
+pub(crate) fn percent(part: u32, total: u32) -> f64 {
+    if total > 0 {
+        part as f64 / total as f64 * 100.0
+    } else {
+        0.0
+    }
+}
+
+/// Die centres relative to the wafer centre, for radial and angular analysis.
+#[derive(Debug, Clone, Copy)]
+pub struct WaferFrame {
+    coords: DieCoordinateSystem,
+    radius_mm: f64,
+}
+
+impl WaferFrame {
+    /// Physical frame when the geometry is known; otherwise 1 mm dies centred
+    /// on the map, with the radius reaching the outermost die corner.
+    pub fn new(
+        dies: &[AsciiDie],
+        geometry: Option<&WaferGeometry>,
+        coords: Option<&DieCoordinateSystem>,
+    ) -> Self {
+        if let Some(g) = geometry {
+            return Self {
+                coords: coords.copied().unwrap_or_else(|| g.centered_coords(dies)),
+                radius_mm: g.radius_mm(),
+            };
+        }
+        let coords = coords.copied().unwrap_or_else(|| {
+            WaferGeometry::new(1.0, 1.0, 1.0)
+                .map(|g| g.centered_coords(dies))
+                .unwrap_or_else(|| DieCoordinateSystem::new(1.0, 1.0))
+        });
+        let radius_mm = dies
+            .iter()
+            .map(|d| {
+                let r = coords.die_rect(d.x, d.y);
+                [
+                    (r.left, r.top),
+                    (r.right, r.top),
+                    (r.left, r.bottom),
+                    (r.right, r.bottom),
+                ]
+                .iter()
+                .map(|(x, y)| x.hypot(*y))
+                .fold(0.0, f64::max)
+            })
+            .fold(0.0, f64::max)
+            .max(f64::EPSILON);
+        Self { coords, radius_mm }
+    }
+
+    /// Die centre in mm, y up.
+    pub fn center(&self, x: i32, y: i32) -> (f64, f64) {
+        let r = self.coords.die_rect(x, y);
+        ((r.left + r.right) / 2.0, (r.top + r.bottom) / 2.0)
+    }
+
+    /// Distance of the die centre from the wafer centre, as a fraction of the radius.
+    pub fn radius_fraction(&self, x: i32, y: i32) -> f64 {
+        let (cx, cy) = self.center(x, y);
+        cx.hypot(cy) / self.radius_mm
+    }
+
+    /// Angle of the die centre, degrees counter-clockwise from +x, in `[0, 360)`.
+    pub fn angle_deg(&self, x: i32, y: i32) -> f64 {
+        let (cx, cy) = self.center(x, y);
+        cy.atan2(cx).to_degrees().rem_euclid(360.0)
+    }
+}
@@ -0,0 +1,419 @@
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+
+use crate::wafer::bins::{is_tested, BinSet};
+use crate::wafer::coords::DieCoordinateSystem;
+use crate::wafer::ds::AsciiDie;
+use crate::wafer::geometry::WaferGeometry;
+
+use super::WaferFrame;
+
+// =============================================================================
+// Spatial failure signatures
+//
+// Rule-based labelling of a merged map, scored 0..1:
+// - edge ring / center: fail rate of the outer / inner radial zone against
+//   the rest, the ring also weighted by how many edge sectors are affected;
+// - clusters and scratches: 8-connected components of failing dies, split by
+//   elongation along their principal axis;
+// - reticle: fail rate per position inside the exposure shot.
+// =============================================================================
+
+const EDGE_SECTORS: usize = 8;
+/// Largest exposure shot side, in dies
+const MAX_RETICLE_DIES: u32 = 256;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum SignatureKind {
+    EdgeRing,
+    CenterCluster,
+    Cluster,
+    Scratch,
+    Reticle,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct SignatureOptions {
+    /// Pass bins ("BIN 1", ...); empty uses the defaults
+    pub pass_bins: Vec<String>,
+    /// Radius fraction inside which a die is in the center zone
+    pub center_radius: f64,
+    /// Radius fraction beyond which a die is in the edge zone
+    pub edge_radius: f64,
+    /// Smallest failing component reported as a cluster or scratch
+    pub min_cluster_size: usize,
+    /// Scratch: minimum length in dies and length/width ratio
+    pub min_line_length: usize,
+    pub line_elongation: f64,
+    /// Exposure shot size in dies; reticle analysis runs when both are set
+    pub reticle_cols: Option<u32>,
+    pub reticle_rows: Option<u32>,
+    /// Grid index of a shot's first die
+    pub reticle_offset_x: i32,
+    pub reticle_offset_y: i32,
+    /// Signatures scoring below this are dropped
+    pub min_score: f64,
+}
+
+impl Default for SignatureOptions {
+    fn default() -> Self {
+        Self {
+            pass_bins: Vec::new(),
+            center_radius: 0.35,
+            edge_radius: 0.8,
+            min_cluster_size: 5,
+            min_line_length: 5,
+            line_elongation: 4.0,
+            reticle_cols: None,
+            reticle_rows: None,
+            reticle_offset_x: 0,
+            reticle_offset_y: 0,
+            min_score: 0.3,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Signature {
+    pub kind: SignatureKind,
+    pub score: f64,
+    pub detail: String,
+    /// Failing dies making up the signature
+    pub dies: Vec<(i32, i32)>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SignatureReport {
+    pub tested: u32,
+    pub fail: u32,
+    /// Fail percentage of the center, middle and edge zones
+    pub center_fail_percent: f64,
+    pub middle_fail_percent: f64,
+    pub edge_fail_percent: f64,
+    /// Highest score first
+    pub signatures: Vec<Signature>,
+}
+
+#[derive(Default, Clone, Copy)]
+struct Rate {
+    tested: u32,
+    fail: u32,
+}
+
+impl Rate {
+    fn add(&mut self, fail: bool) {
+        self.tested += 1;
+        self.fail += fail as u32;
+    }
+
+    fn value(self) -> f64 {
+        if self.tested == 0 {
+            0.0
+        } else {
+            self.fail as f64 / self.tested as f64
+        }
+    }
+}
+
+/// Excess of `inner` over `outer`, normalised to the room left above `outer`.
+fn excess(inner: f64, outer: f64) -> f64 {
+    if outer >= 1.0 {
+        return 0.0;
+    }
+    ((inner - outer) / (1.0 - outer)).clamp(0.0, 1.0)
+}
+
+/// 8-connected components of the given positions.
+fn components(fails: &HashSet<(i32, i32)>) -> Vec<Vec<(i32, i32)>> {
+    let mut seen: HashSet<(i32, i32)> = HashSet::new();
+    let mut sorted: Vec<&(i32, i32)> = fails.iter().collect();
+    sorted.sort_by_key(|&&(x, y)| (y, x));
+    let mut out = Vec::new();
+    for &start in sorted {
+        if !seen.insert(start) {
+            continue;
+        }
+        let mut stack = vec![start];
+        let mut component = Vec::new();
+        while let Some((x, y)) = stack.pop() {
+            component.push((x, y));
+            for dy in -1..=1 {
+                for dx in -1..=1 {
+                    let next = (x + dx, y + dy);
+                    if fails.contains(&next) && seen.insert(next) {
+                        stack.push(next);
+                    }
+                }
+            }
+        }
+        component.sort_by_key(|&(x, y)| (y, x));
+        out.push(component);
+    }
+    out
+}
+
+/// Length along the principal axis (in dies) and length/width ratio.
+fn elongation(dies: &[(i32, i32)]) -> (f64, f64) {
+    let n = dies.len() as f64;
+    let mx = dies.iter().map(|d| d.0 as f64).sum::<f64>() / n;
+    let my = dies.iter().map(|d| d.1 as f64).sum::<f64>() / n;
+    let (mut sxx, mut syy, mut sxy) = (0.0, 0.0, 0.0);
+    for &(x, y) in dies {
+        let (dx, dy) = (x as f64 - mx, y as f64 - my);
+        sxx += dx * dx;
+        syy += dy * dy;
+        sxy += dx * dy;
+    }
+    let (sxx, syy, sxy) = (sxx / n, syy / n, sxy / n);
+    let mid = (sxx + syy) / 2.0;
+    let spread = (((sxx - syy) / 2.0).powi(2) + sxy * sxy).sqrt();
+    // a single die has variance 1/12 along any axis
+    let major = mid + spread + 1.0 / 12.0;
+    let minor = (mid - spread).max(0.0) + 1.0 / 12.0;
+
+    let angle = 0.5 * (2.0 * sxy).atan2(sxx - syy);
+    let (ux, uy) = (angle.cos(), angle.sin());
+    let proj = dies.iter().map(|&(x, y)| x as f64 * ux + y as f64 * uy);
+    let (lo, hi) = proj.fold((f64::INFINITY, f64::NEG_INFINITY), |(lo, hi), p| {
+        (lo.min(p), hi.max(p))
+    });
+    (hi - lo + 1.0, (major / minor).sqrt())
+}
+
+pub fn classify_signatures(
+    dies: &[AsciiDie],
+    geometry: Option<&WaferGeometry>,
+    coords: Option<&DieCoordinateSystem>,
+    options: &SignatureOptions,
+) -> Result<SignatureReport, String> {
+    for (side, value) in [
+        ("columns", options.reticle_cols),
+        ("rows", options.reticle_rows),
+    ] {
+        if value.is_some_and(|v| !(1..=MAX_RETICLE_DIES).contains(&v)) {
+            return Err(format!(
+                "Reticle {} must be between 1 and {}",
+                side, MAX_RETICLE_DIES
+            ));
+        }
+    }
+
+    let pass = BinSet::pass_or_default(&options.pass_bins);
+    let frame = WaferFrame::new(dies, geometry, coords);
+
+    let tested: Vec<(&AsciiDie, bool)> = dies
+        .iter()
+        .filter(|d| is_tested(d.bin))
+        .map(|d| (d, !pass.matches(d.bin)))
+        .collect();
+    let fails: HashSet<(i32, i32)> = tested
+        .iter()
+        .filter(|(_, fail)| *fail)
+        .map(|(d, _)| (d.x, d.y))
+        .collect();
+
+    let (mut center, mut middle, mut edge, mut inner, mut outer) = (
+        Rate::default(),
+        Rate::default(),
+        Rate::default(),
+        Rate::default(),
+        Rate::default(),
+    );
+    let mut sectors = [Rate::default(); EDGE_SECTORS];
+    let mut radius: HashMap<(i32, i32), f64> = HashMap::new();
+    for &(d, fail) in &tested {
+        let r = frame.radius_fraction(d.x, d.y);
+        radius.insert((d.x, d.y), r);
+        if r < options.center_radius {
+            center.add(fail);
+        } else {
+            outer.add(fail);
+            if r < options.edge_radius {
+                middle.add(fail);
+            }
+        }
+        if r >= options.edge_radius {
+            edge.add(fail);
+            let sector = (frame.angle_deg(d.x, d.y) / 360.0 * EDGE_SECTORS as f64) as usize;
+            sectors[sector.min(EDGE_SECTORS - 1)].add(fail);
+        } else {
+            inner.add(fail);
+        }
+    }
+
+    let mut signatures = Vec::new();
+    let zone_dies = |keep: &dyn Fn(f64) -> bool| -> Vec<(i32, i32)> {
+        let mut v: Vec<(i32, i32)> = fails
+            .iter()
+            .copied()
+            .filter(|p| radius.get(p).is_some_and(|r| keep(*r)))
+            .collect();
+        v.sort_by_key(|&(x, y)| (y, x));
+        v
+    };
+
+    // Edge ring: the edge zone fails more than the inside, all around
+    let (edge_rate, inner_rate) = (edge.value(), inner.value());
+    let threshold = (edge_rate + inner_rate) / 2.0;
+    let covered = sectors
+        .iter()
+        .filter(|s| s.tested > 0 && s.value() >= threshold)
+        .count();
+    let populated = sectors.iter().filter(|s| s.tested > 0).count().max(1);
+    let ring_score = excess(edge_rate, inner_rate) * covered as f64 / populated as f64;
+    let edge_ring = ring_score >= options.min_score;
+    if edge_ring {
+        signatures.push(Signature {
+            kind: SignatureKind::EdgeRing,
+            score: ring_score,
+            detail: format!(
+                "edge {:.1}% vs inner {:.1}% failing, {}/{} sectors",
+                edge_rate * 100.0,
+                inner_rate * 100.0,
+                covered,
+                populated
+            ),
+            dies: zone_dies(&|r| r >= options.edge_radius),
+        });
+    }
+
+    // Center: the center zone fails more than everything outside it
+    let center_score = excess(center.value(), outer.value());
+    let center_hit = center_score >= options.min_score;
+    if center_hit {
+        signatures.push(Signature {
+            kind: SignatureKind::CenterCluster,
+            score: center_score,
+            detail: format!(
+                "center {:.1}% vs outer {:.1}% failing",
+                center.value() * 100.0,
+                outer.value() * 100.0
+            ),
+            dies: zone_dies(&|r| r < options.center_radius),
+        });
+    }
+
+    // Clusters and scratches; components already explained by a zone are skipped
+    for component in components(&fails) {
+        if component.len() < options.min_cluster_size {
+            continue;
+        }
+        let share = |keep: &dyn Fn(f64) -> bool| {
+            component
+                .iter()
+                .filter(|p| radius.get(p).is_some_and(|r| keep(*r)))
+                .count() as f64
+                / component.len() as f64
+        };
+        let (length, ratio) = elongation(&component);
+        let is_scratch =
+            length >= options.min_line_length as f64 && ratio >= options.line_elongation;
+        if !is_scratch
+            && ((edge_ring && share(&|r| r >= options.edge_radius) > 0.5)
+                || (center_hit && share(&|r| r < options.center_radius) > 0.5))
+        {
+            continue;
+        }
+
+        let (min_x, max_x, min_y, max_y) = component.iter().fold(
+            (i32::MAX, i32::MIN, i32::MAX, i32::MIN),
+            |(a, b, c, d), &(x, y)| (a.min(x), b.max(x), c.min(y), d.max(y)),
+        );
+        let signature = if is_scratch {
+            Signature {
+                kind: SignatureKind::Scratch,
+                score: 0.5 + 0.5 * (1.0 - options.line_elongation / ratio).clamp(0.0, 1.0),
+                detail: format!(
+                    "{} dies, length {:.0}, length/width {:.1}",
+                    component.len(),
+                    length,
+                    ratio
+                ),
+                dies: component,
+            }
+        } else {
+            let area = ((max_x - min_x + 1) * (max_y - min_y + 1)) as f64;
+            let density = component.len() as f64 / area;
+            let size = (component.len() as f64 / (2 * options.min_cluster_size) as f64).min(1.0);
+            Signature {
+                kind: SignatureKind::Cluster,
+                score: density * size,
+                detail: format!(
+                    "{} dies around ({}, {}), density {:.0}%",
+                    component.len(),
+                    (min_x + max_x) / 2,
+                    (min_y + max_y) / 2,
+                    density * 100.0
+                ),
+                dies: component,
+            }
+        };
+        if signature.score >= options.min_score {
+            signatures.push(signature);
+        }
+    }
+
+    // Reticle: one position inside the shot fails more than the wafer
+    if let (Some(cols), Some(rows)) = (options.reticle_cols, options.reticle_rows) {
+        if cols * rows > 1 {
+            let (cols, rows) = (cols as i32, rows as i32);
+            let slot = |d: &AsciiDie| {
+                (
+                    (d.x - options.reticle_offset_x).rem_euclid(cols),
+                    (d.y - options.reticle_offset_y).rem_euclid(rows),
+                )
+            };
+            let mut slots: HashMap<(i32, i32), Rate> = HashMap::new();
+            let mut overall = Rate::default();
+            for &(d, fail) in &tested {
+                slots.entry(slot(d)).or_default().add(fail);
+                overall.add(fail);
+            }
+            let mut ranked: Vec<((i32, i32), Rate)> = slots.into_iter().collect();
+            ranked.sort_by_key(|&((sx, sy), _)| (sy, sx));
+            let hot = ranked
+                .iter()
+                .filter(|(_, r)| r.fail >= 3)
+                .max_by(|a, b| a.1.value().total_cmp(&b.1.value()));
+            if let Some(&(pos, rate)) = hot {
+                let score = excess(rate.value(), overall.value());
+                if score >= options.min_score {
+                    let mut hot_dies: Vec<(i32, i32)> = tested
+                        .iter()
+                        .filter(|(d, fail)| *fail && slot(d) == pos)
+                        .map(|(d, _)| (d.x, d.y))
+                        .collect();
+                    hot_dies.sort_by_key(|&(x, y)| (y, x));
+                    signatures.push(Signature {
+                        kind: SignatureKind::Reticle,
+                        score,
+                        detail: format!(
+                            "shot position ({}, {}) of {}x{}: {:.1}% vs {:.1}% failing",
+                            pos.0,
+                            pos.1,
+                            cols,
+                            rows,
+                            rate.value() * 100.0,
+                            overall.value() * 100.0
+                        ),
+                        dies: hot_dies,
+                    });
+                }
+            }
+        }
+    }
+
+    signatures.sort_by(|a, b| b.score.total_cmp(&a.score));
+    Ok(SignatureReport {
+        tested: tested.len() as u32,
+        fail: fails.len() as u32,
+        center_fail_percent: center.value() * 100.0,
+        middle_fail_percent: middle.value() * 100.0,
+        edge_fail_percent: edge.value() * 100.0,
+        signatures,
+    })
+}
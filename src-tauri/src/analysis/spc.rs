@@ -0,0 +1,382 @@
+use serde::{Deserialize, Serialize};
+
+use crate::wafer::stats::WaferStackStats;
+
+use super::percent;
+
+// =============================================================================
+// Statistical process control over `wafer_stack_stats` (and its history)
+//
+// One subgroup per lot (batch), ordered by the lot's first stacking time:
+// - X-bar/R on wafer yields (%), limits from R-bar with the constants for each
+//   lot's wafer count, so lots of different size get their own limits
+// - p-chart on the fail fraction (%) with per-lot limits from the tested dies
+// - Western Electric rules 1-4 on the X-bar chart
+// - outlier wafers by modified z-score (median / MAD) over the whole product
+// =============================================================================
+
+/// Shewhart constants (A2, D3, D4) for subgroup sizes 2..=25.
+const CONSTANTS: [(f64, f64, f64); 24] = [
+    (1.880, 0.0, 3.267),
+    (1.023, 0.0, 2.574),
+    (0.729, 0.0, 2.282),
+    (0.577, 0.0, 2.114),
+    (0.483, 0.0, 2.004),
+    (0.419, 0.076, 1.924),
+    (0.373, 0.136, 1.864),
+    (0.337, 0.184, 1.816),
+    (0.308, 0.223, 1.777),
+    (0.285, 0.256, 1.744),
+    (0.266, 0.283, 1.717),
+    (0.249, 0.307, 1.693),
+    (0.235, 0.328, 1.672),
+    (0.223, 0.347, 1.653),
+    (0.212, 0.363, 1.637),
+    (0.203, 0.378, 1.622),
+    (0.194, 0.391, 1.608),
+    (0.187, 0.403, 1.597),
+    (0.180, 0.415, 1.585),
+    (0.173, 0.425, 1.575),
+    (0.167, 0.434, 1.566),
+    (0.162, 0.443, 1.557),
+    (0.157, 0.451, 1.548),
+    (0.153, 0.459, 1.541),
+];
+
+fn constants(n: usize) -> Option<(f64, f64, f64)> {
+    // larger lots use the n = 25 constants
+    n.checked_sub(2)
+        .map(|i| CONSTANTS[i.min(CONSTANTS.len() - 1)])
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct SpcOptions {
+    /// Only the most recent lots
+    pub max_lots: Option<usize>,
+    /// Only lots whose first wafer was stacked at or after this time
+    /// (`wafer_stack_stats.start_time` format)
+    pub since: Option<String>,
+    /// Modified z-score above which a wafer is an outlier
+    pub outlier_z: f64,
+}
+
+impl Default for SpcOptions {
+    fn default() -> Self {
+        Self {
+            max_lots: None,
+            since: None,
+            outlier_z: 3.5,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LotSubgroup {
+    pub batch_id: String,
+    /// Earliest `start_time` of the lot's wafers
+    pub time: Option<String>,
+    pub wafers: usize,
+    pub mean_yield: f64,
+    pub yield_range: f64,
+    pub tested: u32,
+    pub fail: u32,
+    /// Fail fraction, percent
+    pub fail_percent: f64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ChartPoint {
+    pub value: f64,
+    /// `None` when the subgroup is too small for limits
+    pub ucl: Option<f64>,
+    pub lcl: Option<f64>,
+    pub out_of_control: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ControlChart {
+    pub center: f64,
+    /// One point per lot, in lot order
+    pub points: Vec<ChartPoint>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RuleViolation {
+    /// Western Electric rule 1-4
+    pub rule: u8,
+    /// Lot index of the point completing the pattern
+    pub index: usize,
+    pub batch_id: String,
+    pub description: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OutlierWafer {
+    pub batch_id: String,
+    pub wafer_id: String,
+    pub yield_percentage: f64,
+    pub z: f64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SpcReport {
+    pub oem_product_id: String,
+    pub lots: Vec<LotSubgroup>,
+    pub xbar: ControlChart,
+    pub range: ControlChart,
+    pub p: ControlChart,
+    pub violations: Vec<RuleViolation>,
+    pub outliers: Vec<OutlierWafer>,
+}
+
+fn lot_subgroups<'a>(
+    stats: &'a [WaferStackStats],
+    options: &SpcOptions,
+) -> Vec<(LotSubgroup, Vec<&'a WaferStackStats>)> {
+    let mut lots: Vec<(LotSubgroup, Vec<&WaferStackStats>)> = Vec::new();
+    for s in stats {
+        match lots.iter_mut().find(|(l, _)| l.batch_id == s.batch_id) {
+            Some((_, wafers)) => wafers.push(s),
+            None => lots.push((
+                LotSubgroup {
+                    batch_id: s.batch_id.clone(),
+                    time: None,
+                    wafers: 0,
+                    mean_yield: 0.0,
+                    yield_range: 0.0,
+                    tested: 0,
+                    fail: 0,
+                    fail_percent: 0.0,
+                },
+                vec![s],
+            )),
+        }
+    }
+    for (lot, wafers) in &mut lots {
+        let yields: Vec<f64> = wafers.iter().map(|w| w.yield_percentage).collect();
+        let max = yields.iter().copied().fold(f64::MIN, f64::max);
+        let min = yields.iter().copied().fold(f64::MAX, f64::min);
+        lot.time = wafers.iter().filter_map(|w| w.start_time.clone()).min();
+        lot.wafers = wafers.len();
+        lot.mean_yield = yields.iter().sum::<f64>() / yields.len() as f64;
+        lot.yield_range = max - min;
+        lot.tested = wafers.iter().map(|w| w.total_tested).sum();
+        lot.fail = wafers.iter().map(|w| w.total_fail).sum();
+        lot.fail_percent = percent(lot.fail, lot.tested);
+    }
+    if let Some(since) = &options.since {
+        lots.retain(|(l, _)| l.time.as_ref().is_some_and(|t| t >= since));
+    }
+    // undated lots first, like an empty string
+    lots.sort_by(|(a, _), (b, _)| (&a.time, &a.batch_id).cmp(&(&b.time, &b.batch_id)));
+    if let Some(max) = options.max_lots {
+        let skip = lots.len().saturating_sub(max);
+        lots.drain(..skip);
+    }
+    lots
+}
+
+fn point(value: f64, limits: Option<(f64, f64)>) -> ChartPoint {
+    ChartPoint {
+        value,
+        ucl: limits.map(|l| l.0),
+        lcl: limits.map(|l| l.1),
+        out_of_control: limits.is_some_and(|(ucl, lcl)| value > ucl || value < lcl),
+    }
+}
+
+/// Western Electric rules on a chart whose limits are at ±3 sigma.
+fn western_electric(chart: &ControlChart, lots: &[LotSubgroup]) -> Vec<RuleViolation> {
+    // signed distance from the center in sigma; None where there are no limits
+    let z: Vec<Option<f64>> = chart
+        .points
+        .iter()
+        .map(|p| {
+            let sigma = (p.ucl? - chart.center) / 3.0;
+            (sigma > 0.0).then(|| (p.value - chart.center) / sigma)
+        })
+        .collect();
+    let mut out = Vec::new();
+    let mut push = |rule: u8, index: usize, description: String| {
+        out.push(RuleViolation {
+            rule,
+            index,
+            batch_id: lots[index].batch_id.clone(),
+            description,
+        })
+    };
+
+    // k of the last n points beyond `limit` sigma on the same side
+    let run = |i: usize, n: usize, k: usize, limit: f64| -> bool {
+        if i + 1 < n {
+            return false;
+        }
+        let window = &z[i + 1 - n..=i];
+        [1.0, -1.0].iter().any(|side| {
+            z[i].is_some_and(|v| v * side > limit)
+                && window
+                    .iter()
+                    .filter(|v| v.is_some_and(|v| v * side > limit))
+                    .count()
+                    >= k
+        })
+    };
+
+    for (i, v) in z.iter().enumerate() {
+        let Some(v) = *v else { continue };
+        if v.abs() > 3.0 {
+            push(1, i, "1 point beyond 3 sigma".to_string());
+        } else if run(i, 3, 2, 2.0) {
+            push(2, i, "2 of 3 points beyond 2 sigma, same side".to_string());
+        } else if run(i, 5, 4, 1.0) {
+            push(3, i, "4 of 5 points beyond 1 sigma, same side".to_string());
+        } else if run(i, 8, 8, 0.0) {
+            push(
+                4,
+                i,
+                "8 points in a row on one side of the center".to_string(),
+            );
+        }
+    }
+    out
+}
+
+fn median(values: &mut [f64]) -> f64 {
+    values.sort_by(f64::total_cmp);
+    let n = values.len();
+    if n % 2 == 1 {
+        values[n / 2]
+    } else {
+        (values[n / 2 - 1] + values[n / 2]) / 2.0
+    }
+}
+
+fn outlier_wafers(wafers: &[&WaferStackStats], threshold: f64) -> Vec<OutlierWafer> {
+    if wafers.len() < 3 {
+        return Vec::new();
+    }
+    let mut yields: Vec<f64> = wafers.iter().map(|w| w.yield_percentage).collect();
+    let med = median(&mut yields);
+    let mut deviations: Vec<f64> = yields.iter().map(|y| (y - med).abs()).collect();
+    let mad = median(&mut deviations);
+    // MAD of 0 (most wafers identical): fall back to the mean absolute deviation
+    let scale = if mad > 0.0 {
+        mad / 0.6745
+    } else {
+        deviations.iter().sum::<f64>() / deviations.len() as f64 * 1.2533
+    };
+    if scale <= 0.0 {
+        return Vec::new();
+    }
+    wafers
+        .iter()
+        .filter_map(|w| {
+            let z = (w.yield_percentage - med) / scale;
+            (z.abs() > threshold).then(|| OutlierWafer {
+                batch_id: w.batch_id.clone(),
+                wafer_id: w.wafer_id.clone(),
+                yield_percentage: w.yield_percentage,
+                z,
+            })
+        })
+        .collect()
+}
+
+/// SPC of one product's stacked wafers.
+pub fn spc_report(
+    oem_product_id: &str,
+    stats: &[WaferStackStats],
+    options: &SpcOptions,
+) -> SpcReport {
+    let grouped = lot_subgroups(stats, options);
+    let lots: Vec<LotSubgroup> = grouped.iter().map(|(l, _)| l.clone()).collect();
+
+    let total_wafers: usize = lots.iter().map(|l| l.wafers).sum();
+    let grand_mean = if total_wafers > 0 {
+        lots.iter()
+            .map(|l| l.mean_yield * l.wafers as f64)
+            .sum::<f64>()
+            / total_wafers as f64
+    } else {
+        0.0
+    };
+    let ranged: Vec<&LotSubgroup> = lots.iter().filter(|l| l.wafers >= 2).collect();
+    let r_bar = if ranged.is_empty() {
+        0.0
+    } else {
+        ranged.iter().map(|l| l.yield_range).sum::<f64>() / ranged.len() as f64
+    };
+
+    let xbar = ControlChart {
+        center: grand_mean,
+        points: lots
+            .iter()
+            .map(|l| {
+                let limits = constants(l.wafers)
+                    .filter(|_| r_bar > 0.0)
+                    .map(|(a2, _, _)| (grand_mean + a2 * r_bar, grand_mean - a2 * r_bar));
+                point(l.mean_yield, limits)
+            })
+            .collect(),
+    };
+    let range = ControlChart {
+        center: r_bar,
+        points: lots
+            .iter()
+            .map(|l| {
+                let limits = constants(l.wafers)
+                    .filter(|_| r_bar > 0.0)
+                    .map(|(_, d3, d4)| (d4 * r_bar, d3 * r_bar));
+                point(l.yield_range, limits)
+            })
+            .collect(),
+    };
+
+    let tested: u32 = lots.iter().map(|l| l.tested).sum();
+    let fail: u32 = lots.iter().map(|l| l.fail).sum();
+    let p_bar = if tested > 0 {
+        fail as f64 / tested as f64
+    } else {
+        0.0
+    };
+    let p = ControlChart {
+        center: p_bar * 100.0,
+        points: lots
+            .iter()
+            .map(|l| {
+                let limits = (l.tested > 0 && p_bar > 0.0).then(|| {
+                    let sigma = (p_bar * (1.0 - p_bar) / l.tested as f64).sqrt();
+                    (
+                        (p_bar + 3.0 * sigma).min(1.0) * 100.0,
+                        (p_bar - 3.0 * sigma).max(0.0) * 100.0,
+                    )
+                });
+                point(l.fail_percent, limits)
+            })
+            .collect(),
+    };
+
+    let violations = western_electric(&xbar, &lots);
+    let wafers: Vec<&WaferStackStats> = grouped
+        .iter()
+        .flat_map(|(_, w)| w.iter().copied())
+        .collect();
+    let outliers = outlier_wafers(&wafers, options.outlier_z);
+
+    SpcReport {
+        oem_product_id: oem_product_id.to_string(),
+        lots,
+        xbar,
+        range,
+        p,
+        violations,
+        outliers,
+    }
+}
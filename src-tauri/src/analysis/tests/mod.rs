@@ -0,0 +1,439 @@
+#[cfg(test)]
+fn grid(bins: &[&[i32]]) -> Vec<crate::wafer::ds::AsciiDie> {
+    use crate::wafer::ds::{AsciiDie, BinValue};
+    // rows listed top to bottom, centred like the stacking grid
+    let rows = bins.len() as i32;
+    bins.iter()
+        .enumerate()
+        .flat_map(|(r, row)| {
+            let cols = row.len() as i32;
+            row.iter().enumerate().map(move |(c, &bin)| AsciiDie {
+                x: c as i32 - cols / 2,
+                y: r as i32 - rows / 2,
+                bin: if bin < 0 {
+                    BinValue::Special('.')
+                } else {
+                    BinValue::Number(bin)
+                },
+            })
+        })
+        .collect()
+}
+
+#[test]
+fn composite_flags_systematic_positions() {
+    use super::composite::{composite_map, CompositeOptions, CompositeWafer};
+    let wafers: Vec<CompositeWafer> = [
+        [[1, 3, 1], [1, 1, 1], [-1, 1, 5]],
+        [[1, 3, 1], [1, 1, 1], [-1, 1, 1]],
+        [[1, 3, 1], [1, 4, 1], [-1, 1, 1]],
+        [[1, 1, 1], [1, 1, 1], [-1, 1, 1]],
+    ]
+    .iter()
+    .enumerate()
+    .map(|(i, rows)| CompositeWafer {
+        wafer_id: format!("{:02}", i + 1),
+        dies: grid(&rows.iter().map(|r| &r[..]).collect::<Vec<_>>()),
+    })
+    .collect();
+
+    let composite = composite_map(&wafers, &CompositeOptions::default());
+    assert_eq!(composite.wafer_count, 4);
+    // untested '.' position is dropped
+    assert_eq!(composite.positions.len(), 8);
+    let top = composite
+        .positions
+        .iter()
+        .find(|p| p.x == 0 && p.y == -1)
+        .unwrap();
+    assert_eq!((top.tested, top.fail), (4, 3));
+    assert!((top.fail_percent - 75.0).abs() < 1e-9);
+    assert!(top.systematic);
+    assert_eq!(top.failing_wafers, vec!["01", "02", "03"]);
+    assert_eq!(composite.systematic_count, 1);
+
+    let strict = CompositeOptions {
+        min_wafers: 5,
+        ..CompositeOptions::default()
+    };
+    assert_eq!(composite_map(&wafers, &strict).systematic_count, 0);
+}
+
+#[test]
+fn composite_heat_map_scene() {
+    use super::composite::{composite_map, CompositeOptions, CompositeWafer};
+    use crate::render::raster::render_composite;
+    use crate::render::{heat_color, ImageFormat, MapScene, WaferRenderOptions};
+    let wafers = vec![
+        CompositeWafer {
+            wafer_id: "01".into(),
+            dies: grid(&[&[3, 1], &[1, 1]]),
+        },
+        CompositeWafer {
+            wafer_id: "02".into(),
+            dies: grid(&[&[3, 1], &[1, 2]]),
+        },
+    ];
+    let composite = composite_map(&wafers, &CompositeOptions::default());
+    let header = std::collections::HashMap::new();
+    let options = WaferRenderOptions::default();
+    let scene = MapScene::build_composite(&composite, &header, None, &options).unwrap();
+    assert_eq!(scene.dies[0].color, heat_color(1.0));
+    assert_eq!(scene.dies[1].color, heat_color(0.0));
+    assert!(scene.header_lines[1].contains("2 wafers"));
+    let labels: Vec<&str> = scene.legend.iter().map(|e| e.label.as_str()).collect();
+    assert_eq!(labels[0], "0-20% = 2");
+    assert_eq!(labels[2], "40-60% = 1");
+    assert_eq!(labels[4], "80-100% = 1");
+
+    let png = render_composite(&composite, &header, None, &options, ImageFormat::Png).unwrap();
+    assert!(png.starts_with(b"\x89PNG"));
+}
+
+/// Round map of radius `r` dies; `fail(x, y)` dies get bin 3, others bin 1.
+#[cfg(test)]
+fn round_wafer(r: i32, fail: impl Fn(i32, i32) -> bool) -> Vec<crate::wafer::ds::AsciiDie> {
+    use crate::wafer::ds::{AsciiDie, BinValue};
+    let mut dies = Vec::new();
+    for y in -r..=r {
+        for x in -r..=r {
+            if x * x + y * y <= r * r {
+                dies.push(AsciiDie {
+                    x,
+                    y,
+                    bin: BinValue::Number(if fail(x, y) { 3 } else { 1 }),
+                });
+            }
+        }
+    }
+    dies
+}
+
+#[test]
+fn signature_edge_ring() {
+    use super::signature::{classify_signatures, SignatureKind, SignatureOptions};
+    let dies = round_wafer(10, |x, y| x * x + y * y >= 81);
+    let report = classify_signatures(&dies, None, None, &SignatureOptions::default()).unwrap();
+    assert_eq!(report.signatures[0].kind, SignatureKind::EdgeRing);
+    assert!(report.signatures[0].score > 0.7);
+    assert_eq!(report.center_fail_percent, 0.0);
+    assert!(report.edge_fail_percent > 50.0);
+    // the ring is not reported again as clusters
+    assert!(report
+        .signatures
+        .iter()
+        .all(|s| s.kind != SignatureKind::Cluster));
+}
+
+#[test]
+fn signature_scratch_and_cluster() {
+    use super::signature::{classify_signatures, SignatureKind, SignatureOptions};
+    let dies = round_wafer(10, |x, y| {
+        // diagonal scratch, plus a 3x3 blob in the middle zone
+        (x == y && (-6..=1).contains(&x)) || ((3..=5).contains(&x) && (-6..=-4).contains(&y))
+    });
+    let report = classify_signatures(&dies, None, None, &SignatureOptions::default()).unwrap();
+    let kinds: Vec<SignatureKind> = report.signatures.iter().map(|s| s.kind).collect();
+    assert!(kinds.contains(&SignatureKind::Scratch));
+    assert!(kinds.contains(&SignatureKind::Cluster));
+    assert!(!kinds.contains(&SignatureKind::EdgeRing));
+    let scratch = report
+        .signatures
+        .iter()
+        .find(|s| s.kind == SignatureKind::Scratch)
+        .unwrap();
+    assert_eq!(scratch.dies.len(), 8);
+    let cluster = report
+        .signatures
+        .iter()
+        .find(|s| s.kind == SignatureKind::Cluster)
+        .unwrap();
+    assert_eq!(cluster.dies.len(), 9);
+    assert!((cluster.score - 0.9).abs() < 1e-9);
+}
+
+#[test]
+fn signature_reticle_periodicity() {
+    use super::signature::{classify_signatures, SignatureKind, SignatureOptions};
+    let dies = round_wafer(10, |x, y| x.rem_euclid(3) == 1 && y.rem_euclid(3) == 2);
+    let without = classify_signatures(&dies, None, None, &SignatureOptions::default()).unwrap();
+    assert!(without
+        .signatures
+        .iter()
+        .all(|s| s.kind != SignatureKind::Reticle));
+
+    let options = SignatureOptions {
+        reticle_cols: Some(3),
+        reticle_rows: Some(3),
+        ..SignatureOptions::default()
+    };
+    let report = classify_signatures(&dies, None, None, &options).unwrap();
+    assert_eq!(report.signatures[0].kind, SignatureKind::Reticle);
+    assert!((report.signatures[0].score - 1.0).abs() < 1e-9);
+    assert!(report.signatures[0]
+        .detail
+        .starts_with("shot position (1, 2) of 3x3"));
+
+    for (cols, rows) in [(0, 3), (3, u32::MAX)] {
+        let bad = SignatureOptions {
+            reticle_cols: Some(cols),
+            reticle_rows: Some(rows),
+            ..SignatureOptions::default()
+        };
+        assert!(classify_signatures(&dies, None, None, &bad).is_err());
+    }
+}
+
+#[test]
+fn zone_yield_rings_and_quadrants() {
+    use super::zones::{zone_yield, ZoneOptions};
+    // edge ring of fails plus the right half failing outright
+    let dies = round_wafer(10, |x, y| x * x + y * y >= 81 || x > 0);
+    let report = zone_yield(&dies, None, None, &ZoneOptions::default()).unwrap();
+
+    assert_eq!(report.rings.len(), 3);
+    assert_eq!(report.sectors.len(), 4);
+    assert_eq!(report.cells.len(), 12);
+    assert_eq!(report.rings[0].label, "R 0-50%");
+    assert_eq!(report.rings[2].label, "R >80%");
+    assert_eq!(report.sectors[0].label, "Q1 0-90°");
+    for zones in [&report.rings, &report.sectors, &report.cells] {
+        let tested: u32 = zones.iter().map(|z| z.summary.total_tested).sum();
+        assert_eq!(tested, report.total.total_tested);
+    }
+    // yield drops towards the edge
+    assert!(report.rings[0].summary.yield_percentage > report.rings[2].summary.yield_percentage);
+    assert!(report.rings[2].summary.yield_percentage < 25.0);
+    // right half (Q1, Q4) fails, left half (Q2, Q3) only at the edge
+    // the centre die sits at 0° and is the only pass in Q1
+    assert_eq!(report.sectors[0].summary.total_pass, 1);
+    assert!(report.sectors[1].summary.yield_percentage > 50.0);
+    assert!(report.sectors[2].summary.yield_percentage > 50.0);
+    // the cell of ring 0 / Q2 has no fails
+    let cell = &report.cells[1];
+    assert_eq!((cell.ring, cell.sector), (Some(0), Some(1)));
+    assert_eq!(cell.summary.total_fail, 0);
+
+    let bad = [
+        ZoneOptions {
+            ring_edges: vec![0.8, 0.5],
+            ..ZoneOptions::default()
+        },
+        ZoneOptions {
+            ring_edges: vec![0.5, 1.0],
+            ..ZoneOptions::default()
+        },
+        ZoneOptions {
+            ring_edges: (1..100).map(|i| i as f64 / 100.0).collect(),
+            ..ZoneOptions::default()
+        },
+        ZoneOptions {
+            sectors: u32::MAX,
+            ..ZoneOptions::default()
+        },
+    ];
+    for options in &bad {
+        assert!(zone_yield(&dies, None, None, options).is_err());
+    }
+}
+
+#[test]
+fn waterfall_orders_stages_and_splits_loss() {
+    use super::waterfall::{stage_waterfall, Stage};
+    use crate::render::MapLayer;
+    let layer = |name: &str, rows: &[&[i32]]| MapLayer {
+        name: name.to_string(),
+        dies: grid(rows),
+    };
+    // merge order (priority) differs from process order
+    let layers = vec![
+        layer("CP2", &[&[1, 1, 5], &[1, 1, 1]]),
+        layer("WLBI", &[&[1, 4, 1], &[1, 1, 1]]),
+        layer("CP1", &[&[3, 1, 1], &[1, 1, 3]]),
+        layer("DieLayout", &[&[1, 1, 1], &[1, 1, 1]]),
+        layer("AOI", &[&[3, 1, 1], &[1, 1, -1]]),
+    ];
+    let w = stage_waterfall(&layers, &[]);
+
+    assert_eq!(w.positions, 6);
+    assert_eq!(w.skipped, vec!["DieLayout".to_string()]);
+    let order: Vec<Stage> = w.stages.iter().map(|s| s.stage).collect();
+    assert_eq!(order, vec![Stage::Cp1, Stage::Wlbi, Stage::Cp2, Stage::Aoi]);
+    let lost: Vec<u32> = w.stages.iter().map(|s| s.lost).collect();
+    // AOI fails (0, 0) again, which CP1 already lost
+    assert_eq!(lost, vec![2, 1, 1, 0]);
+    assert_eq!(w.stages[3].fail, 1);
+    assert_eq!(w.stages[3].tested, 5);
+    assert_eq!(w.stages[3].incremental_loss, 0.0);
+    let total_loss: f64 = w.stages.iter().map(|s| s.incremental_loss).sum();
+    assert!((100.0 - total_loss - w.final_yield).abs() < 1e-9);
+    assert!((w.final_yield - 100.0 / 3.0).abs() < 1e-9);
+    assert_eq!(w.stages[0].pareto[0].bin, "3");
+    assert_eq!(w.stages[0].pareto[0].count, 2);
+}
+
+#[test]
+fn correlation_confusion_kappa_and_flips() {
+    use super::correlation::{compare_maps, CorrelationOptions, Flip};
+    use crate::render::MapLayer;
+    let cp1 = MapLayer {
+        name: "CP1".to_string(),
+        dies: grid(&[&[1, 1, 3, 3], &[1, 1, 1, 4], &[1, 1, -1, 1]]),
+    };
+    let cp2 = MapLayer {
+        name: "CP2".to_string(),
+        dies: grid(&[&[1, 1, 1, 3], &[1, 1, 5, 3], &[-1, 1, 1, 1]]),
+    };
+    let c = compare_maps(&cp1, &cp2, &CorrelationOptions::default());
+
+    assert_eq!((c.compared, c.only_a, c.only_b), (10, 1, 1));
+    assert_eq!(
+        (
+            c.confusion.pass_pass,
+            c.confusion.pass_fail,
+            c.confusion.fail_pass,
+            c.confusion.fail_fail
+        ),
+        (6, 1, 1, 2)
+    );
+    assert!((c.agreement - 80.0).abs() < 1e-9);
+    // po = 0.8, pe = (7*7 + 3*3) / 100 = 0.58
+    assert!((c.kappa - 0.22 / 0.42).abs() < 1e-9);
+    assert!((c.recovery_rate - 100.0 / 3.0).abs() < 1e-9);
+    assert!((c.escape_rate - 100.0 / 7.0).abs() < 1e-9);
+    assert!((c.bin_agreement - 70.0).abs() < 1e-9);
+
+    assert_eq!(c.transitions.from_bins, vec!["1", "3", "4"]);
+    assert_eq!(c.transitions.to_bins, vec!["1", "3", "5"]);
+    assert_eq!(c.transitions.counts[1], vec![1, 1, 0]);
+    assert_eq!(c.transitions.counts[2], vec![0, 1, 0]);
+
+    let flips: Vec<(i32, i32, Flip)> = c.flipped.iter().map(|f| (f.x, f.y, f.flip)).collect();
+    assert_eq!(flips, vec![(0, -1, Flip::Recovered), (0, 0, Flip::Escaped)]);
+    assert_eq!(
+        (c.flipped[1].from.as_str(), c.flipped[1].to.as_str()),
+        ("1", "5")
+    );
+}
+
+#[test]
+fn correlation_counts_duplicate_dies_once() {
+    use super::correlation::{compare_maps, CorrelationOptions};
+    use crate::render::MapLayer;
+    use crate::wafer::ds::{AsciiDie, BinValue};
+    let die = |x, bin| AsciiDie {
+        x,
+        y: 0,
+        bin: BinValue::Number(bin),
+    };
+    // a lists (0,0) three times, b lists (1,0) twice
+    let a = MapLayer {
+        name: "CP1".to_string(),
+        dies: vec![die(0, 1), die(0, 3), die(0, 1), die(1, 1)],
+    };
+    let b = MapLayer {
+        name: "CP2".to_string(),
+        dies: vec![die(0, 1), die(1, 1), die(1, 5), die(2, 1)],
+    };
+    let c = compare_maps(&a, &b, &CorrelationOptions::default());
+
+    assert_eq!((c.compared, c.only_a, c.only_b), (2, 0, 1));
+    assert_eq!(c.confusion.pass_pass, 2);
+    assert!(c.flipped.is_empty());
+}
+
+#[cfg(test)]
+fn stack_stats(
+    batch: &str,
+    wafer: u32,
+    yield_percentage: f64,
+) -> crate::wafer::stats::WaferStackStats {
+    let total_pass = (yield_percentage * 10.0).round() as u32;
+    crate::wafer::stats::WaferStackStats {
+        oem_product_id: "OEM".to_string(),
+        batch_id: batch.to_string(),
+        wafer_id: wafer.to_string(),
+        total_tested: 1000,
+        total_pass,
+        total_fail: 1000 - total_pass,
+        yield_percentage,
+        bin_counts: String::new(),
+        start_time: Some(format!(
+            "2025/01/{:02} 08:00",
+            batch[1..].parse::<u32>().unwrap()
+        )),
+        stop_time: None,
+    }
+}
+
+#[test]
+fn spc_limits_rules_and_outliers() {
+    use super::spc::{spc_report, SpcOptions};
+    let mut stats = Vec::new();
+    // 10 stable lots of 5 wafers, then a lot that drops
+    for lot in 1..=11 {
+        for w in 1..=5 {
+            let y =
+                if lot == 11 { 80.0 } else { 95.0 } + [0.0, 1.0, -1.0, 0.5, -0.5][w as usize - 1];
+            stats.push(stack_stats(&format!("L{:02}", lot), w, y));
+        }
+    }
+    // one wafer far below everything else
+    stats.push(stack_stats("L05", 6, 40.0));
+
+    let report = spc_report("OEM", &stats, &SpcOptions::default());
+    assert_eq!(report.lots.len(), 11);
+    assert_eq!(report.lots[0].batch_id, "L01");
+    assert_eq!(report.lots[4].wafers, 6);
+
+    let last = report.xbar.points.last().unwrap();
+    assert!(last.out_of_control);
+    assert!(last.value < last.lcl.unwrap());
+    assert!(report
+        .violations
+        .iter()
+        .any(|v| v.rule == 1 && v.batch_id == "L11"));
+    // the p-chart sees the same drop as a rise in fail fraction
+    let p_last = report.p.points.last().unwrap();
+    assert!(p_last.out_of_control && p_last.value > report.p.center);
+
+    assert!(report
+        .outliers
+        .iter()
+        .any(|o| o.batch_id == "L05" && o.wafer_id == "6"));
+    assert!(report.outliers.iter().all(|o| o.z < 0.0));
+
+    let recent = spc_report(
+        "OEM",
+        &stats,
+        &SpcOptions {
+            max_lots: Some(3),
+            ..SpcOptions::default()
+        },
+    );
+    let ids: Vec<&str> = recent.lots.iter().map(|l| l.batch_id.as_str()).collect();
+    assert_eq!(ids, vec!["L09", "L10", "L11"]);
+}
+
+#[test]
+fn spc_western_electric_runs() {
+    use super::spc::{spc_report, SpcOptions};
+    let mut stats = Vec::new();
+    for lot in 1..=16 {
+        // 8 lots slightly above, then 8 slightly below the grand mean
+        let shift = if lot <= 8 { 0.4 } else { -0.4 };
+        for w in 1..=4 {
+            let y = 90.0 + shift + [1.0, -1.0, 0.5, -0.5][w as usize - 1];
+            stats.push(stack_stats(&format!("L{:02}", lot), w, y));
+        }
+    }
+    let report = spc_report("OEM", &stats, &SpcOptions::default());
+    let rule4: Vec<usize> = report
+        .violations
+        .iter()
+        .filter(|v| v.rule == 4)
+        .map(|v| v.index)
+        .collect();
+    assert_eq!(rule4, vec![7, 15]);
+    assert!(report.xbar.points.iter().all(|p| !p.out_of_control));
+}
@@ -0,0 +1,148 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::render::MapLayer;
+use crate::wafer::bins::{is_tested, BinSet};
+use crate::wafer::stats::{bin_pareto, summarize, ParetoEntry};
+
+use super::percent;
+
+// =============================================================================
+// Cross-stage yield loss
+//
+// Walks the layers of one wafer in process order (substrate → FAB CP → CP1 →
+// WLBI → CP2 → AOI). A position stays good until the first stage that tests
+// it with a failing bin; the cumulative yield after a stage is the share of
+// all positions still good, so the incremental losses add up to the total
+// loss. Each stage also keeps its own fail-bin Pareto.
+// =============================================================================
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum Stage {
+    Substrate,
+    FabCp,
+    Cp1,
+    Wlbi,
+    Cp2,
+    Aoi,
+}
+
+impl Stage {
+    /// Stage of a stacking layer by its name ("Substrate", "FAB CP", "CP1",
+    /// "WLBI", "CP2", "AOI"); a bare "CP" is CP1.
+    pub fn from_layer_name(name: &str) -> Option<Self> {
+        let key: String = name
+            .chars()
+            .filter(|c| !c.is_whitespace() && *c != '-' && *c != '_')
+            .collect::<String>()
+            .to_ascii_uppercase();
+        match key.as_str() {
+            "SUBSTRATE" => Some(Stage::Substrate),
+            "FABCP" | "CP3" => Some(Stage::FabCp),
+            "CP" | "CP1" => Some(Stage::Cp1),
+            "WLBI" => Some(Stage::Wlbi),
+            "CP2" => Some(Stage::Cp2),
+            "AOI" => Some(Stage::Aoi),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StageYield {
+    pub stage: Stage,
+    /// Layer name as stacked
+    pub layer: String,
+    /// The stage's own map
+    pub tested: u32,
+    pub pass: u32,
+    pub fail: u32,
+    pub yield_percentage: f64,
+    /// Positions first failed at this stage
+    pub lost: u32,
+    /// Positions still good after this stage, over all positions
+    pub cumulative_pass: u32,
+    pub cumulative_yield: f64,
+    /// Drop in cumulative yield caused by this stage, percentage points
+    pub incremental_loss: f64,
+    pub pareto: Vec<ParetoEntry>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StageWaterfall {
+    /// Positions tested by any stage
+    pub positions: u32,
+    pub stages: Vec<StageYield>,
+    pub final_yield: f64,
+    /// Layers that do not belong to a known stage (e.g. die layout)
+    pub skipped: Vec<String>,
+}
+
+pub fn stage_waterfall(layers: &[MapLayer], pass_bins: &[String]) -> StageWaterfall {
+    let pass = BinSet::pass_or_default(pass_bins);
+    let mut skipped = Vec::new();
+    let mut staged: Vec<(Stage, &MapLayer)> = Vec::new();
+    for layer in layers {
+        match Stage::from_layer_name(&layer.name) {
+            Some(stage) => staged.push((stage, layer)),
+            None => skipped.push(layer.name.clone()),
+        }
+    }
+    staged.sort_by_key(|(stage, _)| *stage);
+
+    // position -> still good
+    let mut good: HashMap<(i32, i32), bool> = HashMap::new();
+    for (_, layer) in &staged {
+        for die in layer.dies.iter().filter(|d| is_tested(d.bin)) {
+            good.insert((die.x, die.y), true);
+        }
+    }
+    let positions = good.len() as u32;
+    let mut remaining = positions;
+    let mut previous_yield = percent(remaining, positions);
+
+    let stages = staged
+        .into_iter()
+        .map(|(stage, layer)| {
+            let mut lost = 0;
+            for die in layer.dies.iter().filter(|d| is_tested(d.bin)) {
+                if pass.matches(die.bin) {
+                    continue;
+                }
+                if let Some(alive) = good.get_mut(&(die.x, die.y)).filter(|a| **a) {
+                    *alive = false;
+                    lost += 1;
+                }
+            }
+            remaining -= lost;
+            let cumulative_yield = percent(remaining, positions);
+            let summary = summarize(&layer.dies, &pass);
+            let entry = StageYield {
+                stage,
+                layer: layer.name.clone(),
+                tested: summary.total_tested,
+                pass: summary.total_pass,
+                fail: summary.total_fail,
+                yield_percentage: summary.yield_percentage,
+                lost,
+                cumulative_pass: remaining,
+                cumulative_yield,
+                incremental_loss: previous_yield - cumulative_yield,
+                pareto: bin_pareto(&summary.bins, &pass),
+            };
+            previous_yield = cumulative_yield;
+            entry
+        })
+        .collect();
+
+    StageWaterfall {
+        positions,
+        stages,
+        final_yield: percent(remaining, positions),
+        skipped,
+    }
+}
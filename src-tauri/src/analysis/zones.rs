@@ -0,0 +1,187 @@
+use serde::{Deserialize, Serialize};
+
+use crate::wafer::bins::BinSet;
+use crate::wafer::coords::DieCoordinateSystem;
+use crate::wafer::ds::AsciiDie;
+use crate::wafer::geometry::WaferGeometry;
+use crate::wafer::stats::{summarize, MapSummary};
+
+use super::WaferFrame;
+
+// =============================================================================
+// Zone yield
+//
+// Splits a merged map into radial rings, angular sectors and their
+// intersections, each summarised like `calculateStatsFromDies`. Rings are
+// fractions of the wafer radius (die centres); sectors are counted
+// counter-clockwise from +x, so with 4 sectors and no offset they are the
+// usual quadrants Q1..Q4.
+// =============================================================================
+
+/// One-degree sectors at most
+const MAX_SECTORS: u32 = 360;
+const MAX_RING_EDGES: usize = 32;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct ZoneOptions {
+    /// Pass bins ("BIN 1", ...); empty uses the defaults
+    pub pass_bins: Vec<String>,
+    /// Inner ring boundaries as radius fractions, ascending; the last ring
+    /// takes everything outside the last boundary
+    pub ring_edges: Vec<f64>,
+    pub sectors: u32,
+    /// Rotation of the first sector boundary, degrees counter-clockwise
+    pub sector_offset_deg: f64,
+}
+
+impl Default for ZoneOptions {
+    fn default() -> Self {
+        Self {
+            pass_bins: Vec::new(),
+            ring_edges: vec![0.5, 0.8],
+            sectors: 4,
+            sector_offset_deg: 0.0,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ZoneStat {
+    pub label: String,
+    /// Index into `rings` / `sectors` of the report; `None` when not split that way
+    pub ring: Option<usize>,
+    pub sector: Option<usize>,
+    #[serde(flatten)]
+    pub summary: MapSummary,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ZoneReport {
+    pub total: MapSummary,
+    pub rings: Vec<ZoneStat>,
+    pub sectors: Vec<ZoneStat>,
+    /// Ring × sector cells, ring-major
+    pub cells: Vec<ZoneStat>,
+}
+
+fn ring_label(edges: &[f64], ring: usize) -> String {
+    let from = if ring == 0 { 0.0 } else { edges[ring - 1] } * 100.0;
+    match edges.get(ring) {
+        Some(&to) => format!("R {:.0}-{:.0}%", from, to * 100.0),
+        None => format!("R >{:.0}%", from),
+    }
+}
+
+fn sector_label(options: &ZoneOptions, sector: usize) -> String {
+    let step = 360.0 / options.sectors as f64;
+    let from = (options.sector_offset_deg + step * sector as f64).rem_euclid(360.0);
+    let to = (from + step).rem_euclid(360.0);
+    let prefix = if options.sectors == 4 { "Q" } else { "S" };
+    format!(
+        "{}{} {:.0}-{:.0}°",
+        prefix,
+        sector + 1,
+        from,
+        if to == 0.0 { 360.0 } else { to }
+    )
+}
+
+pub fn zone_yield(
+    dies: &[AsciiDie],
+    geometry: Option<&WaferGeometry>,
+    coords: Option<&DieCoordinateSystem>,
+    options: &ZoneOptions,
+) -> Result<ZoneReport, String> {
+    if !(1..=MAX_SECTORS).contains(&options.sectors) {
+        return Err(format!(
+            "Sector count must be between 1 and {}",
+            MAX_SECTORS
+        ));
+    }
+    if options.ring_edges.len() > MAX_RING_EDGES {
+        return Err(format!("At most {} ring edges are allowed", MAX_RING_EDGES));
+    }
+    // an edge at or past the rim leaves the outer ring empty
+    if options
+        .ring_edges
+        .windows(2)
+        .any(|w| w[0].partial_cmp(&w[1]) != Some(std::cmp::Ordering::Less))
+        || options
+            .ring_edges
+            .iter()
+            .any(|e| !e.is_finite() || *e <= 0.0 || *e >= 1.0)
+    {
+        return Err("Ring edges must be ascending and between 0 and 1".to_string());
+    }
+
+    let pass = BinSet::pass_or_default(&options.pass_bins);
+    let frame = WaferFrame::new(dies, geometry, coords);
+    let ring_count = options.ring_edges.len() + 1;
+    let sector_count = options.sectors as usize;
+    let step = 360.0 / options.sectors as f64;
+
+    let mut cells: Vec<Vec<AsciiDie>> = vec![Vec::new(); ring_count * sector_count];
+    for die in dies {
+        let r = frame.radius_fraction(die.x, die.y);
+        let ring = options.ring_edges.partition_point(|&e| e <= r);
+        // snap float noise so dies on a sector boundary fall on the same side
+        let angle =
+            ((frame.angle_deg(die.x, die.y) - options.sector_offset_deg) * 1e6).round() / 1e6;
+        let angle = angle.rem_euclid(360.0);
+        let sector = ((angle / step) as usize).min(sector_count - 1);
+        cells[ring * sector_count + sector].push(*die);
+    }
+
+    let collect = |pick: &dyn Fn(usize, usize) -> bool| -> Vec<AsciiDie> {
+        cells
+            .iter()
+            .enumerate()
+            .filter(|(i, _)| pick(i / sector_count, i % sector_count))
+            .flat_map(|(_, v)| v.iter().copied())
+            .collect()
+    };
+
+    let rings = (0..ring_count)
+        .map(|ring| ZoneStat {
+            label: ring_label(&options.ring_edges, ring),
+            ring: Some(ring),
+            sector: None,
+            summary: summarize(&collect(&|r, _| r == ring), &pass),
+        })
+        .collect();
+    let sectors = (0..sector_count)
+        .map(|sector| ZoneStat {
+            label: sector_label(options, sector),
+            ring: None,
+            sector: Some(sector),
+            summary: summarize(&collect(&|_, s| s == sector), &pass),
+        })
+        .collect();
+    let cell_stats = cells
+        .iter()
+        .enumerate()
+        .map(|(i, cell)| {
+            let (ring, sector) = (i / sector_count, i % sector_count);
+            ZoneStat {
+                label: format!(
+                    "{} / {}",
+                    ring_label(&options.ring_edges, ring),
+                    sector_label(options, sector)
+                ),
+                ring: Some(ring),
+                sector: Some(sector),
+                summary: summarize(cell, &pass),
+            }
+        })
+        .collect();
+
+    Ok(ZoneReport {
+        total: summarize(dies, &pass),
+        rings,
+        sectors,
+        cells: cell_stats,
+    })
+}
@@ -0,0 +1,4 @@
+// Headless stacking for servers; see `wafer_overlay_lib::cli`.
+fn main() {
+    wafer_overlay_lib::cli::main()
+}
@@ -0,0 +1,253 @@
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use crate::db::repo::ProductContext;
+use crate::parser::{parse_bin_map_json, parse_bin_map_xls};
+use crate::wafer::bin_map::BinMapTable;
+use crate::wafer::bins::DEFAULT_PASS_IDS;
+use crate::wafer::coords::{DieCoordinateSystem, ProductOffset};
+use crate::wafer::edge::InkRules;
+
+// =============================================================================
+// CLI configuration
+//
+// TOML file standing in for, or overriding, what the app keeps in its
+// database. Every key is optional:
+//
+//   db = "/data/aoi/data.db"
+//   outputs = ["mapEx", "bin", "HEX", "image"]
+//   pass_bins = ["BIN 1", "G"]
+//
+//   [ink]                    # inked copies in <wafer>/Ink, as edge removal does
+//   edge_exclusion = true
+//   edge_exclusion_mm = 3.0
+//   neighbor_ink = true
+//   fail_threshold = 2
+//   marker = "z"
+//   fail_bins = ["BIN 3"]    # unset: every bin that does not pass
+//
+//   [products.OEM123]
+//   x_offset = 0.12          # mm, product_offsets
+//   y_offset = -0.3
+//   defect_offset_x = 5.0    # µm
+//   defect_offset_y = 5.0
+//   die_x = 1.2              # mm, product_size
+//   die_y = 1.4
+//   defect_classes = ["Particle", "Scratch"]   # product_bin_selection
+//   bin_map = "bin_maps.xlsx"  # as imported in the app, else product_bin_map
+//   customer = "ACME"
+// =============================================================================
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct CliConfig {
+    /// App database; relative paths are resolved against the config file
+    pub db: Option<PathBuf>,
+    /// Output formats of `stack` and `export`
+    pub outputs: Vec<String>,
+    /// Pass bins as in the bin configuration ("BIN 1", "G"); empty uses the defaults
+    pub pass_bins: Vec<String>,
+    /// Inked outputs next to the plain ones; none without the table
+    pub ink: Option<InkConfig>,
+    /// Per OEM product id
+    pub products: HashMap<String, ProductConfig>,
+}
+
+/// `[ink]`: the app's ink rules (edge removal) for `stack` and `export`.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct InkConfig {
+    pub edge_exclusion: bool,
+    pub edge_exclusion_mm: Option<f64>,
+    pub neighbor_ink: Option<bool>,
+    pub fail_threshold: Option<u32>,
+    /// Bin written over inked dies
+    pub marker: Option<char>,
+    /// Bins counted as failing; unset means every bin that does not pass
+    pub fail_bins: Option<Vec<String>>,
+}
+
+impl InkConfig {
+    /// The rules with the run's pass bins as the good bins.
+    pub fn rules(&self, pass_bins: &[String]) -> InkRules {
+        let defaults = InkRules::default();
+        let marker = self.marker.unwrap_or(defaults.ink_marker);
+        InkRules {
+            edge_exclusion_enabled: self.edge_exclusion,
+            edge_exclusion_mm: self.edge_exclusion_mm.unwrap_or(defaults.edge_exclusion_mm),
+            edge_marker: marker,
+            neighbor_ink_enabled: self.neighbor_ink.unwrap_or(defaults.neighbor_ink_enabled),
+            fail_threshold: self.fail_threshold.unwrap_or(defaults.fail_threshold),
+            ink_marker: marker,
+            good_bins: match pass_bins {
+                [] => DEFAULT_PASS_IDS.iter().map(|id| id.to_string()).collect(),
+                ids => ids.to_vec(),
+            },
+            fail_bins: self.fail_bins.clone(),
+        }
+    }
+}
+
+/// Overrides for one OEM product; unset keys fall back to the database.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ProductConfig {
+    pub x_offset: Option<f64>,
+    pub y_offset: Option<f64>,
+    pub defect_offset_x: Option<f64>,
+    pub defect_offset_y: Option<f64>,
+    pub die_x: Option<f64>,
+    pub die_y: Option<f64>,
+    /// Substrate defect classes stacked; unset or empty means all
+    pub defect_classes: Option<Vec<String>>,
+    /// Bin translation file (`.xlsx` / `.json`); its sheet or key is the OEM
+    /// or the product id. Relative paths are resolved against the config file
+    pub bin_map: Option<PathBuf>,
+    /// Customer whose bin translation rules take precedence
+    pub customer: Option<String>,
+}
+
+impl ProductConfig {
+    /// The table of `bin_map` for this product, when the config names a file.
+    fn bin_map_table(
+        &self,
+        oem_product_id: &str,
+        product_id: Option<&str>,
+    ) -> Result<Option<BinMapTable>, String> {
+        let Some(path) = &self.bin_map else {
+            return Ok(None);
+        };
+        let file = path.display().to_string();
+        let json = path
+            .extension()
+            .is_some_and(|ext| ext.eq_ignore_ascii_case("json"));
+        let mut tables = if json {
+            parse_bin_map_json(file.clone())?
+        } else {
+            parse_bin_map_xls(file.clone())?
+        };
+        [Some(oem_product_id), product_id]
+            .into_iter()
+            .flatten()
+            .find_map(|key| tables.remove(key))
+            .map(Some)
+            .ok_or_else(|| format!("Bin map {} has no table for {}", file, oem_product_id))
+    }
+}
+
+impl CliConfig {
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let text = std::fs::read_to_string(path)
+            .map_err(|e| format!("Failed to read config {}: {}", path.display(), e))?;
+        let mut config: CliConfig = toml::from_str(&text)
+            .map_err(|e| format!("Invalid config {}: {}", path.display(), e))?;
+        if let Some(dir) = path.parent() {
+            let resolve = |file: &mut Option<PathBuf>| {
+                if let Some(relative) = file.as_ref().filter(|f| f.is_relative()) {
+                    *file = Some(dir.join(relative));
+                }
+            };
+            resolve(&mut config.db);
+            config
+                .products
+                .values_mut()
+                .for_each(|product| resolve(&mut product.bin_map));
+        }
+        Ok(config)
+    }
+}
+
+/// Offsets, die size, defect classes and bin map stacking uses for one product.
+#[derive(Debug, Clone, Default)]
+pub struct ProductSettings {
+    pub offset: ProductOffset,
+    /// Die width and height in mm, when known
+    pub die_size: Option<(f64, f64)>,
+    /// `None` stacks every defect class
+    pub defect_classes: Option<Vec<String>>,
+    /// Translates the layers' bins on reading and the outputs' on writing
+    pub bin_map: Option<BinMapTable>,
+}
+
+impl ProductSettings {
+    /// Database values with the config's overrides on top.
+    pub fn resolve(
+        oem_product_id: &str,
+        context: Option<&ProductContext>,
+        config: Option<&ProductConfig>,
+    ) -> Result<Self, String> {
+        let mut settings = ProductSettings::default();
+        if let Some(context) = context {
+            if let Some(row) = &context.offset {
+                settings.offset = ProductOffset {
+                    x_offset: row.x_offset,
+                    y_offset: row.y_offset,
+                    defect_offset_x: row.defect_offset_x,
+                    defect_offset_y: row.defect_offset_y,
+                };
+            }
+            settings.die_size = context.size.as_ref().map(|row| (row.die_x, row.die_y));
+            settings.defect_classes = context
+                .bin_selection
+                .as_ref()
+                .map(|row| row.selected_bin_ids.trim())
+                .filter(|ids| *ids != "*")
+                .map(|ids| {
+                    ids.split(',')
+                        .map(str::trim)
+                        .filter(|id| !id.is_empty())
+                        .map(String::from)
+                        .collect::<Vec<_>>()
+                })
+                .filter(|classes| !classes.is_empty());
+            settings.bin_map = match &context.bin_map {
+                Some(row) => {
+                    let table: BinMapTable = serde_json::from_str(&row.bin_map).map_err(|e| {
+                        format!("Invalid bin map of OEM product {}: {}", oem_product_id, e)
+                    })?;
+                    table.check()?;
+                    Some(table)
+                }
+                None => None,
+            };
+        }
+
+        if let Some(config) = config {
+            let offset = &mut settings.offset;
+            offset.x_offset = config.x_offset.unwrap_or(offset.x_offset);
+            offset.y_offset = config.y_offset.unwrap_or(offset.y_offset);
+            offset.defect_offset_x = config.defect_offset_x.unwrap_or(offset.defect_offset_x);
+            offset.defect_offset_y = config.defect_offset_y.unwrap_or(offset.defect_offset_y);
+            let (die_x, die_y) = settings.die_size.unzip();
+            if let (Some(x), Some(y)) = (config.die_x.or(die_x), config.die_y.or(die_y)) {
+                settings.die_size = Some((x, y));
+            }
+            if let Some(classes) = &config.defect_classes {
+                settings.defect_classes = Some(classes.clone()).filter(|c| !c.is_empty());
+            }
+            let product_id = context
+                .and_then(|c| c.mapping.as_ref())
+                .map(|m| m.product_id.as_str());
+            if let Some(table) = config.bin_map_table(oem_product_id, product_id)? {
+                settings.bin_map = Some(table);
+            }
+            if let (Some(table), Some(customer)) = (&mut settings.bin_map, &config.customer) {
+                table.customer = Some(customer.clone());
+            }
+        }
+        Ok(settings)
+    }
+
+    /// Physical die placement; needs the die size.
+    pub fn coords(&self) -> Option<DieCoordinateSystem> {
+        self.die_size
+            .map(|(x, y)| DieCoordinateSystem::new(x, y).with_offset(&self.offset))
+    }
+
+    pub fn includes_defect_class(&self, class: &str) -> bool {
+        self.defect_classes
+            .as_ref()
+            .is_none_or(|classes| classes.iter().any(|c| c == class))
+    }
+}
@@ -0,0 +1,579 @@
+mod tests;
+
+mod config;
+mod stack;
+
+pub(crate) use stack::{stack_job, OutputFormat, Run, WaferJob};
+#[cfg(feature = "http-api")]
+pub(crate) use stack::{stack_wafers, StackRequest};
+
+use rayon::prelude::*;
+use serde::Serialize;
+use sqlx::SqlitePool;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use crate::db::migrations::{schema_status, SchemaStatus};
+use crate::db::repo::{self, WaferMapFilter};
+use crate::file::scanner::{scan_data_sources, DataSourceType, FileKind, ScanOptions};
+use crate::parser::{
+    parse_substrate_defect_xls, parse_wafer, parse_wafer_bin, parse_wafer_map_data,
+};
+use crate::wafer::bins::BinSet;
+use crate::wafer::edge::InkRules;
+use crate::wafer::stack::{read_layer, sub_stage_number};
+use config::CliConfig;
+pub(crate) use config::ProductSettings;
+
+// =============================================================================
+// aoi-stack-cli
+//
+// Headless entry point for stacking on a server: the same parsers, merge and
+// writers as the app, configured from the app database (`--db`) and/or a TOML
+// file (`--config`, see `config.rs`). Arguments are parsed by hand; every
+// option takes one value and may be given as `--name value` or
+// `--name=value`.
+// =============================================================================
+
+const USAGE: &str = "\
+aoi-stack-cli: wafer map stacking without the GUI
+
+USAGE:
+    aoi-stack-cli <command> [options]
+
+COMMANDS:
+    scan <root>             Recognise data-source folders and wafer map files
+        --max-depth <n>     Directory levels searched for source folders (3)
+        --json              Print the full scan report
+
+    parse <file>            Parse one map and print it as JSON
+        --stage <stage>     cp1, cp2, wlbi, aoi, fabCp or substrate
+        --summary           Print header and bin counts instead of the dies
+
+    stack                   Stack wafers into <out>/<name>/
+        --out <dir>         Output root (required)
+        --oem <id>          OEM product; with --db its lots and maps are stacked
+        --lot <id>          Only this lot (repeatable)
+        --wafer <n>         Only these wafers (repeatable, comma separated)
+        --product <id>      Product id when the database has no OEM mapping
+        --layer <stage>=<file>
+                            Stack these files instead of the database (repeatable)
+        --substrate <xls>   Substrate defect list for --layer stacking
+        --name <name>       Output name for --layer stacking
+        --format <list>     mapEx, bin, HEX, fab, image, svg, SILAN (default: mapEx)
+        --pass-bins <list>  Pass bins, e.g. \"BIN 1,G\" (default: 1,G,H,I,J)
+        --no-stats          Do not write wafer_stack_stats
+        --json              Print the results as JSON
+
+    export <file>...        Convert maps through the stacking writers
+        --out <dir>         Output root; each map goes to <out>/<name>/ (required)
+        --stage <stage>     Stage of the files, when not clear from the name
+        --oem <id>          Product whose die size and offsets are used
+        --format <list>     As for stack
+        --pass-bins <list>  As for stack
+
+    validate [<file>...]    Check the database schema and that maps parse
+        --stage <stage>     Stage of the files, when not clear from the name
+        --root <dir>        Also check every map found under this folder
+        --product <id>      Limit database maps to one product
+        --json              Print the report as JSON
+
+    serve                   Local HTTP/JSON API (needs the http-api feature)
+        --port <n>          Port on 127.0.0.1 (8765)
+
+    help                    Show this text
+
+COMMON OPTIONS:
+    --db <file>             App database (data.db); must exist
+    --config <file>         TOML config: db, outputs, pass_bins, [ink], [products.<oem>]
+
+Exit status is 0 on success and 1 when anything failed.
+";
+
+/// Entry point of the `aoi-stack-cli` binary.
+pub fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    match run(&args) {
+        Ok(true) => {}
+        Ok(false) => std::process::exit(1),
+        Err(err) => {
+            eprintln!("❌ {}", err);
+            std::process::exit(1);
+        }
+    }
+}
+
+/// Runs one command; `Ok(false)` when it finished but something failed.
+fn run(args: &[String]) -> Result<bool, String> {
+    let Some((command, rest)) = args.split_first() else {
+        eprint!("{}", USAGE);
+        return Ok(false);
+    };
+    match command.as_str() {
+        "scan" => scan(&Args::parse(rest, &["max-depth"], &["json"])?),
+        "parse" => parse(&Args::parse(rest, &["stage"], &["summary"])?),
+        "stack" => stack::stack(&Args::parse(
+            rest,
+            &[
+                "out",
+                "oem",
+                "lot",
+                "wafer",
+                "product",
+                "layer",
+                "substrate",
+                "name",
+                "format",
+                "pass-bins",
+                "db",
+                "config",
+            ],
+            &["no-stats", "json"],
+        )?),
+        "export" => stack::export(&Args::parse(
+            rest,
+            &["out", "stage", "oem", "format", "pass-bins", "db", "config"],
+            &[],
+        )?),
+        "validate" => validate(&Args::parse(
+            rest,
+            &["stage", "root", "product", "db", "config"],
+            &["json"],
+        )?),
+        "serve" => serve(&Args::parse(rest, &["port", "db", "config"], &[])?),
+        "help" | "--help" | "-h" => {
+            print!("{}", USAGE);
+            Ok(true)
+        }
+        other => Err(format!(
+            "Unknown command '{}'; run `aoi-stack-cli help`",
+            other
+        )),
+    }
+}
+
+/// Parsed command line of one command.
+#[derive(Debug, Default)]
+struct Args {
+    positional: Vec<String>,
+    options: Vec<(String, String)>,
+    flags: Vec<String>,
+}
+
+impl Args {
+    /// Splits `args` into positionals, the given `options` (with a value) and
+    /// `flags`; anything else starting with `--` is an error.
+    fn parse(args: &[String], options: &[&str], flags: &[&str]) -> Result<Self, String> {
+        let mut parsed = Args::default();
+        let mut it = args.iter();
+        while let Some(arg) = it.next() {
+            let Some(name) = arg.strip_prefix("--") else {
+                parsed.positional.push(arg.clone());
+                continue;
+            };
+            let (name, inline) = match name.split_once('=') {
+                Some((name, value)) => (name, Some(value.to_string())),
+                None => (name, None),
+            };
+            if options.contains(&name) {
+                let value = inline
+                    .or_else(|| it.next().cloned())
+                    .ok_or_else(|| format!("--{} needs a value", name))?;
+                parsed.options.push((name.to_string(), value));
+            } else if flags.contains(&name) && inline.is_none() {
+                parsed.flags.push(name.to_string());
+            } else {
+                return Err(format!(
+                    "Unknown option --{}; run `aoi-stack-cli help`",
+                    name
+                ));
+            }
+        }
+        Ok(parsed)
+    }
+
+    /// Last value of an option.
+    fn value(&self, name: &str) -> Option<&str> {
+        self.options
+            .iter()
+            .rev()
+            .find(|(n, _)| n == name)
+            .map(|(_, v)| v.as_str())
+    }
+
+    /// Every value of a repeatable option.
+    fn values(&self, name: &str) -> Vec<&str> {
+        self.options
+            .iter()
+            .filter(|(n, _)| n == name)
+            .map(|(_, v)| v.as_str())
+            .collect()
+    }
+
+    /// Values of a repeatable option, also split at commas.
+    fn list(&self, name: &str) -> Vec<String> {
+        self.values(name)
+            .iter()
+            .flat_map(|v| v.split(','))
+            .map(str::trim)
+            .filter(|v| !v.is_empty())
+            .map(String::from)
+            .collect()
+    }
+
+    fn number<T: FromStr>(&self, name: &str) -> Result<Option<T>, String> {
+        self.value(name)
+            .map(|v| {
+                v.trim()
+                    .parse()
+                    .map_err(|_| format!("--{} expects a number, got '{}'", name, v))
+            })
+            .transpose()
+    }
+
+    fn flag(&self, name: &str) -> bool {
+        self.flags.iter().any(|f| f == name)
+    }
+
+    fn required(&self, name: &str) -> Result<&str, String> {
+        self.value(name)
+            .ok_or_else(|| format!("--{} is required", name))
+    }
+}
+
+/// Config file and database shared by the commands and the HTTP API.
+pub(crate) struct Context {
+    config: CliConfig,
+    pool: Option<SqlitePool>,
+}
+
+impl Context {
+    /// The app's own database without a config file (HTTP API in the app).
+    pub(crate) fn with_pool(pool: SqlitePool) -> Self {
+        Self {
+            config: CliConfig::default(),
+            pool: Some(pool),
+        }
+    }
+
+    #[cfg(feature = "http-api")]
+    pub(crate) fn pool(&self) -> Option<&SqlitePool> {
+        self.pool.as_ref()
+    }
+
+    fn open(args: &Args) -> Result<Self, String> {
+        let config = match args.value("config") {
+            Some(path) => CliConfig::load(Path::new(path))?,
+            None => CliConfig::default(),
+        };
+        let db = args.value("db").map(PathBuf::from).or(config.db.clone());
+        let pool = match db {
+            Some(path) => Some(block_on(crate::db::file_pool(&path))?),
+            None => None,
+        };
+        Ok(Self { config, pool })
+    }
+
+    /// Offsets, die size, defect classes and bin map of an OEM product:
+    /// database values with the config's overrides on top.
+    fn product(&self, oem_product_id: Option<&str>) -> Result<ProductSettings, String> {
+        let Some(oem) = oem_product_id else {
+            return Ok(ProductSettings::default());
+        };
+        let context = match &self.pool {
+            Some(pool) => Some(block_on(repo::product_context(pool, oem))?),
+            None => None,
+        };
+        ProductSettings::resolve(oem, context.as_ref(), self.config.products.get(oem))
+    }
+
+    /// The config's ink rules, with `pass_bins` as the good bins.
+    fn ink_rules(&self, pass_bins: &[String]) -> Option<InkRules> {
+        self.config.ink.as_ref().map(|ink| ink.rules(pass_bins))
+    }
+
+    /// The requested pass bins, else the config's; empty means the defaults.
+    fn pass_bins(&self, requested: &[String]) -> Vec<String> {
+        if requested.is_empty() {
+            self.config.pass_bins.clone()
+        } else {
+            requested.to_vec()
+        }
+    }
+}
+
+fn block_on<F: std::future::Future>(future: F) -> F::Output {
+    tauri::async_runtime::block_on(future)
+}
+
+fn print_json<T: Serialize>(value: &T) -> Result<(), String> {
+    let text = serde_json::to_string_pretty(value)
+        .map_err(|e| format!("Failed to serialize output: {}", e))?;
+    println!("{}", text);
+    Ok(())
+}
+
+/// Stage as stored in `wafer_maps.stage` ("cpProber", "wlbi", ...).
+pub(crate) fn stored_stage(stage: &str) -> Option<DataSourceType> {
+    DataSourceType::ALL
+        .into_iter()
+        .find(|s| s.as_str().eq_ignore_ascii_case(stage.trim()))
+}
+
+/// `--stage` values: `cp1`, `cp2`, `wlbi`, `aoi`, `fabCp`, `substrate`, or a
+/// stored stage with its sub stage such as `cpProber:2`.
+fn parse_stage(text: &str) -> Result<(DataSourceType, Option<u32>), String> {
+    let key = text
+        .trim()
+        .to_ascii_lowercase()
+        .replace([' ', '-', '_'], "");
+    if let Some(n) = key.strip_prefix("cp").and_then(|n| n.parse::<u32>().ok()) {
+        return Ok((DataSourceType::CpProber, Some(n)));
+    }
+    let (stage, sub_stage) = match key.split_once(':') {
+        Some((stage, sub)) => (stage, sub_stage_number(sub)),
+        None => (key.as_str(), None),
+    };
+    let stage = match stage {
+        "cp" => DataSourceType::CpProber,
+        "fab" => DataSourceType::FabCp,
+        other => stored_stage(other).ok_or_else(|| {
+            format!(
+                "Unknown stage '{}' (cp1, cp2, wlbi, aoi, fabCp, substrate)",
+                text
+            )
+        })?,
+    };
+    Ok((stage, sub_stage))
+}
+
+/// Stage from `--stage`, else from the file name: `.WaferMap` is WLBI and
+/// `_mapEx.txt` CP1.
+fn file_stage(path: &str, stage: Option<&str>) -> Result<(DataSourceType, Option<u32>), String> {
+    if let Some(stage) = stage {
+        return parse_stage(stage);
+    }
+    let name = Path::new(path)
+        .file_name()
+        .and_then(|n| n.to_str())
+        .unwrap_or(path)
+        .to_ascii_lowercase();
+    if name.ends_with(".wafermap") {
+        Ok((DataSourceType::Wlbi, None))
+    } else if name.ends_with("_mapex.txt") {
+        Ok((DataSourceType::CpProber, Some(1)))
+    } else if name.ends_with(".xls") || name.ends_with(".xlsx") {
+        Ok((DataSourceType::Substrate, None))
+    } else {
+        Err(format!("--stage is required for {}", path))
+    }
+}
+
+// =============================================================================
+// scan / parse / validate
+// =============================================================================
+
+fn scan(args: &Args) -> Result<bool, String> {
+    let [root] = args.positional.as_slice() else {
+        return Err("scan takes one folder".into());
+    };
+    let mut options = ScanOptions::default();
+    if let Some(depth) = args.number("max-depth")? {
+        options.max_depth = depth;
+    }
+    let report = scan_data_sources(root, &options)?;
+    if args.flag("json") {
+        print_json(&report)?;
+        return Ok(report.unreadable.is_empty());
+    }
+
+    println!(
+        "📂 {}: {} folders, {} files visited in {:.0} ms",
+        report.root, report.dirs_visited, report.files_visited, report.elapsed_ms
+    );
+    for stage in DataSourceType::ALL {
+        let folders = report.folders.iter().filter(|f| f.stage == stage).count();
+        let files = report.files.iter().filter(|f| f.stage == stage).count();
+        if folders > 0 || files > 0 {
+            println!(
+                "   {:<10} {:>4} folders {:>7} files",
+                stage.as_str(),
+                folders,
+                files
+            );
+        }
+    }
+    for path in &report.misaligned {
+        println!("⚠️ misnamed: {}", path);
+    }
+    for path in &report.unreadable {
+        println!("❌ unreadable: {}", path);
+    }
+    Ok(report.unreadable.is_empty())
+}
+
+fn parse(args: &Args) -> Result<bool, String> {
+    let [path] = args.positional.as_slice() else {
+        return Err("parse takes one file".into());
+    };
+    let (stage, sub_stage) = file_stage(path, args.value("stage"))?;
+
+    if args.flag("summary") {
+        let Some(layer) = read_layer(path, stage, sub_stage, None)? else {
+            return Err(format!("{} has no stackable dies", path));
+        };
+        let summary = crate::wafer::stats::summarize(&layer.dies, &BinSet::default_pass());
+        print_json(&serde_json::json!({
+            "name": layer.name,
+            "priority": layer.priority,
+            "header": layer.header,
+            "summary": summary,
+        }))?;
+        return Ok(true);
+    }
+
+    let path = path.clone();
+    match stage {
+        DataSourceType::Substrate => print_json(&parse_substrate_defect_xls(path)?)?,
+        DataSourceType::Wlbi => print_json(&parse_wafer_bin(path)?)?,
+        DataSourceType::FabCp => print_json(&parse_wafer(path)?)?,
+        DataSourceType::CpProber if !matches!(sub_stage.unwrap_or(1), 1 | 2) => {
+            print_json(&parse_wafer(path)?)?
+        }
+        DataSourceType::CpProber | DataSourceType::Aoi => print_json(&parse_wafer_map_data(path)?)?,
+    }
+    Ok(true)
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct MapProblem {
+    file_path: String,
+    error: String,
+}
+
+#[derive(Debug, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ValidateReport {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    schema: Option<SchemaStatus>,
+    checked: usize,
+    ok: usize,
+    /// Maps of stages that are not stacked (CP3) or without dies
+    skipped: usize,
+    problems: Vec<MapProblem>,
+}
+
+/// Reads every map in parallel; returns the skipped count and the failures.
+fn check_maps(maps: Vec<(String, DataSourceType, Option<u32>)>) -> (usize, Vec<MapProblem>) {
+    let results: Vec<Result<bool, MapProblem>> = maps
+        .into_par_iter()
+        .map(|(file_path, stage, sub_stage)| {
+            if !Path::new(&file_path).is_file() {
+                return Err(MapProblem {
+                    file_path,
+                    error: "file not found".into(),
+                });
+            }
+            match stage {
+                DataSourceType::Substrate => parse_substrate_defect_xls(file_path.clone())
+                    .map(|_| true)
+                    .map_err(|error| MapProblem { file_path, error }),
+                _ => read_layer(&file_path, stage, sub_stage, None)
+                    .map(|layer| layer.is_some())
+                    .map_err(|error| MapProblem { file_path, error }),
+            }
+        })
+        .collect();
+    let skipped = results.iter().filter(|r| matches!(r, Ok(false))).count();
+    (
+        skipped,
+        results.into_iter().filter_map(Result::err).collect(),
+    )
+}
+
+fn validate(args: &Args) -> Result<bool, String> {
+    let context = Context::open(args)?;
+    let mut report = ValidateReport::default();
+    let mut maps = Vec::new();
+
+    if let Some(pool) = &context.pool {
+        let status = block_on(schema_status(pool))?;
+        let filter = WaferMapFilter {
+            product_id: args.value("product").map(String::from),
+            ..WaferMapFilter::default()
+        };
+        for row in block_on(repo::wafer_maps(pool, &filter))? {
+            match stored_stage(&row.stage) {
+                Some(stage) => {
+                    let sub_stage = row.sub_stage.as_deref().and_then(sub_stage_number);
+                    maps.push((row.file_path, stage, sub_stage));
+                }
+                None => report.problems.push(MapProblem {
+                    file_path: row.file_path,
+                    error: format!("unknown stage '{}'", row.stage),
+                }),
+            }
+        }
+        report.schema = Some(status);
+    }
+    if let Some(root) = args.value("root") {
+        let scan = scan_data_sources(root, &ScanOptions::default())?;
+        maps.extend(
+            scan.files
+                .into_iter()
+                .filter(|f| f.kind == FileKind::WaferMap)
+                .map(|f| (f.file_path, f.stage, f.process_sub_stage)),
+        );
+    }
+    for path in &args.positional {
+        let (stage, sub_stage) = file_stage(path, args.value("stage"))?;
+        maps.push((path.clone(), stage, sub_stage));
+    }
+    if report.schema.is_none() && maps.is_empty() {
+        return Err("Nothing to validate: give files, --root or --db".into());
+    }
+
+    report.checked = maps.len();
+    let (skipped, problems) = check_maps(maps);
+    report.ok = report.checked - skipped - problems.len();
+    report.skipped = skipped;
+    report.problems.extend(problems);
+    let schema_ok = report.schema.as_ref().is_none_or(|s| s.compatible());
+
+    if args.flag("json") {
+        print_json(&report)?;
+        return Ok(schema_ok && report.problems.is_empty());
+    }
+    if let Some(status) = &report.schema {
+        match status.check() {
+            Ok(()) if status.pending.is_empty() => {
+                println!("✅ schema v{} (latest v{})", status.current, status.latest)
+            }
+            Ok(()) => println!(
+                "⚠️ schema v{}, pending {:?}; start the app once to apply them",
+                status.current, status.pending
+            ),
+            Err(err) => println!("❌ {}", err),
+        }
+    }
+    for problem in &report.problems {
+        println!("❌ {}: {}", problem.file_path, problem.error);
+    }
+    println!(
+        "{} maps checked: {} ok, {} skipped, {} failed",
+        report.checked,
+        report.ok,
+        report.skipped,
+        report.problems.len()
+    );
+    Ok(schema_ok && report.problems.is_empty())
+}
+
+/// Serves the HTTP API on 127.0.0.1 until the process is stopped.
+fn serve(args: &Args) -> Result<bool, String> {
+    let context = Context::open(args)?;
+    let port = args.number("port")?.unwrap_or(crate::http::DEFAULT_PORT);
+    crate::http::serve(context, port)?;
+    Ok(true)
+}
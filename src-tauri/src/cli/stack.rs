@@ -0,0 +1,662 @@
+use chrono::Local;
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use super::config::ProductSettings;
+use super::{block_on, file_stage, parse_stage, print_json, stored_stage, Args, Context};
+use crate::analysis::waterfall::{stage_waterfall, StageWaterfall};
+use crate::db::repo::{self, WaferMapFilter};
+use crate::db::tables::WaferStackStatsRow;
+use crate::file::scanner::DataSourceType;
+use crate::parser::parse_substrate_defect_xls;
+use crate::render::raster::render_map;
+use crate::render::svg::render_map_svg;
+use crate::render::{ImageFormat, RenderStyle, WaferRenderOptions};
+use crate::wafer::bin_map::{apply_bin_map, BinMapDirection};
+use crate::wafer::bins::BinSet;
+use crate::wafer::coords::{DefectRect, DieCoordinateSystem};
+use crate::wafer::ds::AsciiDie;
+use crate::wafer::edge::InkRules;
+use crate::wafer::stack::{
+    bin_counts_json, ink_stacked, layer_name, read_layer, stack_layers, sub_stage_number,
+    substrate_layer, to_bin_map_data, to_fab_wafer, to_hex_map_data, to_map_data,
+    to_silan_map_data, StackLayer, StackedWafer,
+};
+use crate::wafer::stats::{MapSummary, WaferStackStats};
+
+// =============================================================================
+// stack / export
+//
+// One `WaferJob` per wafer, run in parallel; each writes its outputs into its
+// own folder named like the app's (`<oem>_<product>_<lot>_<wafer>_<sub>`).
+// The product's bin map translates the layers on reading and the outputs on
+// writing; with `[ink]` in the config the inked copies go to `Ink/`.
+// `stack_wafers` also serves `POST /api/stack` of the HTTP API, and
+// `stack_job` the app's job runner (`jobs::runner`).
+// =============================================================================
+
+/// Output formats, named like `WaferStackingOutputId`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum OutputFormat {
+    MapEx,
+    Bin,
+    Hex,
+    Fab,
+    Image,
+    Svg,
+    Silan,
+}
+
+impl OutputFormat {
+    pub(crate) fn parse(name: &str) -> Result<Self, String> {
+        match name.trim().to_ascii_lowercase().as_str() {
+            "mapex" => Ok(Self::MapEx),
+            "bin" | "wafermap" => Ok(Self::Bin),
+            "hex" | "sinf" => Ok(Self::Hex),
+            "fab" => Ok(Self::Fab),
+            "image" | "jpg" => Ok(Self::Image),
+            "svg" => Ok(Self::Svg),
+            "silan" => Ok(Self::Silan),
+            other => Err(format!(
+                "Unknown output format '{}' (mapEx, bin, HEX, fab, image, svg, SILAN)",
+                other
+            )),
+        }
+    }
+
+    /// The requested formats, else the config's `outputs`, else mapEx.
+    /// `exportInkWaferFiles` writes these only.
+    fn inked(self) -> bool {
+        matches!(self, Self::MapEx | Self::Bin | Self::Hex | Self::Image)
+    }
+
+    fn selected(requested: &[String], context: &Context) -> Result<Vec<Self>, String> {
+        let names = match requested {
+            [] => &context.config.outputs,
+            names => names,
+        };
+        if names.is_empty() {
+            return Ok(vec![Self::MapEx]);
+        }
+        let mut formats = Vec::new();
+        for name in names {
+            let format = Self::parse(name)?;
+            if !formats.contains(&format) {
+                formats.push(format);
+            }
+        }
+        Ok(formats)
+    }
+}
+
+/// One map layer of a `StackRequest`.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct LayerFile {
+    /// `cp1`, `wlbi`, `cpProber:2`, ... as for `--layer`
+    pub stage: String,
+    pub path: String,
+}
+
+/// What to stack: the options of `stack`, or the body of `POST /api/stack`.
+/// `layers` are stacked as one wafer; without them the OEM product's wafers
+/// are read from the database.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, rename_all = "camelCase", deny_unknown_fields)]
+pub struct StackRequest {
+    /// Output root; each wafer goes to `<outputDir>/<name>/`
+    pub output_dir: String,
+    pub oem_product_id: Option<String>,
+    /// Product id when the database has no OEM mapping
+    pub product_id: Option<String>,
+    pub lot_ids: Vec<String>,
+    pub wafer_ids: Vec<String>,
+    pub layers: Vec<LayerFile>,
+    /// Substrate defect list stacked with `layers`
+    pub substrate: Option<String>,
+    /// Output name for `layers`; defaults to the first file's name
+    pub name: Option<String>,
+    /// Defaults to the config's `outputs`, else mapEx
+    pub formats: Vec<String>,
+    /// Defaults to the config's `pass_bins`, else the default pass bins
+    pub pass_bins: Vec<String>,
+    /// Do not write `wafer_stack_stats`
+    pub no_stats: bool,
+}
+
+impl StackRequest {
+    fn from_args(args: &Args) -> Result<Self, String> {
+        let mut layers = Vec::new();
+        for spec in args.values("layer") {
+            let (stage, path) = spec
+                .split_once('=')
+                .ok_or_else(|| format!("--layer expects <stage>=<file>, got '{}'", spec))?;
+            layers.push(LayerFile {
+                stage: stage.to_string(),
+                path: path.to_string(),
+            });
+        }
+        Ok(Self {
+            output_dir: args.required("out")?.to_string(),
+            oem_product_id: args.value("oem").map(String::from),
+            product_id: args.value("product").map(String::from),
+            lot_ids: args.values("lot").into_iter().map(String::from).collect(),
+            wafer_ids: args.list("wafer"),
+            layers,
+            substrate: args.value("substrate").map(String::from),
+            name: args.value("name").map(String::from),
+            formats: args.list("format"),
+            pass_bins: args.list("pass-bins"),
+            no_stats: args.flag("no-stats"),
+        })
+    }
+}
+
+/// Inputs of one stacked wafer.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct WaferJob {
+    /// Output folder and file name prefix
+    pub name: String,
+    pub oem_product_id: Option<String>,
+    /// Batch and wafer id for `wafer_stack_stats`
+    pub stats_key: Option<(String, String)>,
+    pub layers: Vec<(String, DataSourceType, Option<u32>)>,
+    pub substrate: Option<String>,
+    /// Die layout stacked on top of every layer (the app's die layout sheet)
+    pub layout: Vec<AsciiDie>,
+}
+
+/// Outcome of one wafer; `error` is set when it failed.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WaferResult {
+    name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    output_dir: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    merged_die_count: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    summary: Option<MapSummary>,
+    files: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+/// Settings shared by every job of one run.
+pub(crate) struct Run<'a> {
+    pub out: &'a Path,
+    pub formats: &'a [OutputFormat],
+    pub pass_bins: &'a [String],
+    pub pass: &'a BinSet,
+    pub ink: Option<&'a InkRules>,
+    pub style: RenderStyle,
+}
+
+/// What `stack_job` made of one wafer.
+pub(crate) struct JobOutput {
+    pub wafer: StackedWafer,
+    pub dir: PathBuf,
+    pub files: Vec<String>,
+    /// Set when the job has a stats key
+    pub stats: Option<WaferStackStatsRow>,
+}
+
+/// Called with the progress (0..1) and step before each step of a job; an
+/// error stops the job with it.
+pub(crate) type Checkpoint<'a> = &'a (dyn Fn(f64, &str) -> Result<(), String> + Sync);
+
+fn no_checkpoint(_: f64, _: &str) -> Result<(), String> {
+    Ok(())
+}
+
+/// `normalizeSubstrateDefects` plus the product's defect class filter.
+fn substrate_defects(path: &str, settings: &ProductSettings) -> Result<Vec<DefectRect>, String> {
+    let sheets = parse_substrate_defect_xls(path.to_string())?;
+    Ok(["PL defect list", "Surface defect list"]
+        .iter()
+        .filter_map(|sheet| sheets.get(*sheet))
+        .flatten()
+        .filter(|d| settings.includes_defect_class(&d.class))
+        .map(|d| DefectRect {
+            x: d.x,
+            y: d.y,
+            w: d.w,
+            h: d.h,
+        })
+        .collect())
+}
+
+fn write_file(path: &Path, data: impl AsRef<[u8]>) -> Result<String, String> {
+    fs::write(path, data).map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+    Ok(path.display().to_string())
+}
+
+/// `exportNormalWaferFiles`, in the codes of `bin_map`.
+fn write_outputs(
+    dir: &Path,
+    name: &str,
+    wafer: &StackedWafer,
+    formats: &[OutputFormat],
+    settings: &ProductSettings,
+    run: &Run,
+) -> Result<Vec<String>, String> {
+    fs::create_dir_all(dir).map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+    let render_options = WaferRenderOptions {
+        pass_bins: run.pass_bins.to_vec(),
+        style: run.style,
+        ..WaferRenderOptions::default()
+    };
+    let path = |suffix: &str| dir.join(format!("{}_{}", name, suffix));
+    let (dies, header, summary) = (&wafer.dies, &wafer.header, &wafer.summary);
+    let coords = settings.coords();
+    let coords = coords.as_ref();
+    let (bin_map, export) = (settings.bin_map.as_ref(), BinMapDirection::Export);
+
+    let mut files = Vec::new();
+    for format in formats {
+        let file = match format {
+            OutputFormat::MapEx => write_file(
+                &path("overlayed.txt"),
+                apply_bin_map(to_map_data(dies, summary, header), bin_map, export).to_string(),
+            )?,
+            OutputFormat::Hex => write_file(
+                &path("overlayed.sinf"),
+                apply_bin_map(to_hex_map_data(dies, header, coords), bin_map, export).to_string(),
+            )?,
+            OutputFormat::Bin => write_file(
+                &path("overlayed.WaferMap"),
+                apply_bin_map(to_bin_map_data(dies, header), bin_map, export).to_string(),
+            )?,
+            OutputFormat::Image => write_file(
+                &path("overlayed.jpg"),
+                render_map(dies, header, coords, &render_options, ImageFormat::Jpeg)?,
+            )?,
+            OutputFormat::Svg => write_file(
+                &path("overlayed.svg"),
+                render_map_svg(dies, header, coords, &wafer.layers, &render_options)?,
+            )?,
+            OutputFormat::Fab => write_file(
+                &path("FAB.txt"),
+                apply_bin_map(to_fab_wafer(dies, summary, header), bin_map, export).to_string(),
+            )?,
+            OutputFormat::Silan => write_file(
+                &path("SILAN.txt"),
+                apply_bin_map(to_silan_map_data(dies, summary, header), bin_map, export)
+                    .to_string(),
+            )?,
+        };
+        files.push(file);
+    }
+    Ok(files)
+}
+
+/// Reads, stacks and writes one wafer, calling
+/// `checkpoint` between the layers and before merging and writing.
+pub(crate) fn stack_job(
+    job: &WaferJob,
+    settings: &ProductSettings,
+    run: &Run,
+    checkpoint: Checkpoint,
+) -> Result<JobOutput, String> {
+    let start_time = Local::now().format("%Y/%m/%d %H:%M").to_string();
+    let mut layers = Vec::new();
+    if !job.layout.is_empty() {
+        layers.push(StackLayer {
+            name: "DieLayout".to_string(),
+            priority: 100,
+            header: Default::default(),
+            dies: job.layout.clone(),
+        });
+    }
+    let steps = job.layers.len() + job.substrate.is_some() as usize;
+    for (i, (path, stage, sub_stage)) in job.layers.iter().enumerate() {
+        checkpoint(
+            0.1 + 0.5 * i as f64 / steps as f64,
+            &format!("Reading {}", layer_name(*stage, *sub_stage)),
+        )?;
+        if let Some(layer) = read_layer(path, *stage, *sub_stage, settings.bin_map.as_ref())? {
+            layers.push(layer);
+        }
+    }
+    if let Some(path) = &job.substrate {
+        checkpoint(
+            0.1 + 0.5 * job.layers.len() as f64 / steps as f64,
+            "Reading substrate defects",
+        )?;
+        let defects = substrate_defects(path, settings)?;
+        // the app draws substrate defects on 1 mm dies when the size is unknown
+        let coords = settings
+            .coords()
+            .unwrap_or_else(|| DieCoordinateSystem::new(1.0, 1.0).with_offset(&settings.offset));
+        if let Some(layer) = substrate_layer(&layers, &defects, &coords) {
+            layers.push(layer);
+        }
+    }
+
+    checkpoint(0.6, "Stacking")?;
+    let wafer = stack_layers(layers, run.pass)?;
+    checkpoint(0.8, "Writing outputs")?;
+    let dir = run.out.join(&job.name);
+    let mut files = write_outputs(&dir, &job.name, &wafer, run.formats, settings, run)?;
+    if let Some(rules) = run.ink {
+        let inked = ink_stacked(&wafer, rules, settings.coords().as_ref(), run.pass)?;
+        let formats: Vec<OutputFormat> =
+            run.formats.iter().copied().filter(|f| f.inked()).collect();
+        files.extend(write_outputs(
+            &dir.join("Ink"),
+            &job.name,
+            &inked,
+            &formats,
+            settings,
+            run,
+        )?);
+    }
+
+    let stats = match (&job.oem_product_id, &job.stats_key) {
+        (Some(oem), Some((batch_id, wafer_id))) => {
+            let waterfall: StageWaterfall = stage_waterfall(&wafer.layers, run.pass_bins);
+            Some(WaferStackStatsRow {
+                stats: WaferStackStats {
+                    oem_product_id: oem.clone(),
+                    batch_id: batch_id.clone(),
+                    wafer_id: wafer_id.clone(),
+                    total_tested: wafer.summary.total_tested,
+                    total_pass: wafer.summary.total_pass,
+                    total_fail: wafer.summary.total_fail,
+                    yield_percentage: wafer.summary.yield_percentage,
+                    bin_counts: bin_counts_json(&wafer.dies),
+                    start_time: Some(start_time),
+                    stop_time: Some(Local::now().format("%Y/%m/%d %H:%M").to_string()),
+                },
+                stage_waterfall: serde_json::to_string(&waterfall).ok(),
+            })
+        }
+        _ => None,
+    };
+
+    Ok(JobOutput {
+        wafer,
+        dir,
+        files,
+        stats,
+    })
+}
+
+/// `stack_job` as a CLI result. Returns the stats row to store when the job
+/// has a stats key.
+fn run_job(
+    job: &WaferJob,
+    settings: &ProductSettings,
+    run: &Run,
+) -> Result<(WaferResult, Option<WaferStackStatsRow>), String> {
+    let output = stack_job(job, settings, run, &no_checkpoint)?;
+    Ok((
+        WaferResult {
+            name: job.name.clone(),
+            output_dir: Some(output.dir.display().to_string()),
+            merged_die_count: Some(output.wafer.dies.len()),
+            summary: Some(output.wafer.summary),
+            files: output.files,
+            error: None,
+        },
+        output.stats,
+    ))
+}
+
+/// Wafers of an OEM product from the database, like "add lot to queue":
+/// substrate from `product_defect_map`, maps from `wafer_maps`.
+fn database_jobs(
+    request: &StackRequest,
+    context: &Context,
+    oem: &str,
+) -> Result<Vec<WaferJob>, String> {
+    let pool = context
+        .pool
+        .as_ref()
+        .ok_or("Stacking an OEM product's wafers needs the app database")?;
+    let product_context = block_on(repo::product_context(pool, oem))?;
+    let product_id = request
+        .product_id
+        .clone()
+        .or(product_context.mapping.map(|m| m.product_id))
+        .ok_or_else(|| {
+            format!(
+                "No product id mapped to OEM product {}; give the product id",
+                oem
+            )
+        })?;
+
+    let lots = &request.lot_ids;
+    let wafers: HashSet<&str> = request.wafer_ids.iter().map(|w| w.trim()).collect();
+    let mut rows = Vec::new();
+    if lots.is_empty() {
+        rows = block_on(repo::product_defect_maps(pool, oem, None))?;
+    } else {
+        for lot in lots {
+            rows.extend(block_on(repo::product_defect_maps(pool, oem, Some(lot)))?);
+        }
+    }
+
+    let mut seen = HashSet::new();
+    let mut jobs = Vec::new();
+    for row in rows {
+        let wafer_id = row.wafer_id.trim().to_string();
+        if !wafers.is_empty() && !wafers.contains(wafer_id.as_str()) {
+            continue;
+        }
+        // several sub ids per wafer: the first one is stacked, as in the app
+        if !seen.insert((row.lot_id.clone(), wafer_id.clone())) {
+            continue;
+        }
+        let Ok(wafer_num) = wafer_id.parse::<i64>() else {
+            eprintln!(
+                "⚠️ [stack] {} {}: wafer id is not a number",
+                row.lot_id, wafer_id
+            );
+            continue;
+        };
+        let filter = WaferMapFilter {
+            product_id: Some(product_id.clone()),
+            batch_id: Some(row.lot_id.clone()),
+            wafer_id: Some(wafer_num),
+            stage: None,
+        };
+        let layers = block_on(repo::wafer_maps(pool, &filter))?
+            .into_iter()
+            .filter_map(|map| {
+                let stage = stored_stage(&map.stage)?;
+                let sub_stage = map.sub_stage.as_deref().and_then(sub_stage_number);
+                Some((map.file_path, stage, sub_stage))
+            })
+            .collect();
+        let substrate = block_on(repo::substrate_defects(
+            pool,
+            std::slice::from_ref(&row.sub_id),
+        ))?
+        .into_iter()
+        .next()
+        .map(|s| s.file_path);
+        jobs.push(WaferJob {
+            name: format!(
+                "{}_{}_{}_{}_{}",
+                oem, product_id, row.lot_id, wafer_num, row.sub_id
+            ),
+            oem_product_id: Some(oem.to_string()),
+            stats_key: Some((row.lot_id, wafer_num.to_string())),
+            layers,
+            substrate,
+            layout: Vec::new(),
+        });
+    }
+    if jobs.is_empty() {
+        return Err(format!("No wafers of OEM product {} match", oem));
+    }
+    Ok(jobs)
+}
+
+/// One wafer from the request's layer files.
+fn file_job(request: &StackRequest) -> Result<WaferJob, String> {
+    let mut layers = Vec::new();
+    for layer in &request.layers {
+        let (stage, sub_stage) = parse_stage(&layer.stage)?;
+        if stage == DataSourceType::Substrate {
+            return Err("Give the substrate defect list as the substrate, not as a layer".into());
+        }
+        layers.push((layer.path.clone(), stage, sub_stage));
+    }
+    let name = match &request.name {
+        Some(name) => name.clone(),
+        None => Path::new(&layers[0].0)
+            .file_stem()
+            .and_then(|s| s.to_str())
+            .unwrap_or("stacked")
+            .to_string(),
+    };
+    Ok(WaferJob {
+        name,
+        oem_product_id: request.oem_product_id.clone(),
+        stats_key: None,
+        layers,
+        substrate: request.substrate.clone(),
+        layout: Vec::new(),
+    })
+}
+
+/// Runs the jobs in parallel and stores the stats of the stacked wafers.
+fn run_jobs(
+    jobs: Vec<WaferJob>,
+    settings: &ProductSettings,
+    run: &Run,
+    context: &Context,
+    save_stats: bool,
+) -> Result<Vec<WaferResult>, String> {
+    let outcomes: Vec<_> = jobs
+        .par_iter()
+        .map(|job| {
+            run_job(job, settings, run).unwrap_or_else(|error| {
+                (
+                    WaferResult {
+                        name: job.name.clone(),
+                        output_dir: None,
+                        merged_die_count: None,
+                        summary: None,
+                        files: Vec::new(),
+                        error: Some(error),
+                    },
+                    None,
+                )
+            })
+        })
+        .collect();
+
+    let (results, stats): (Vec<_>, Vec<_>) = outcomes.into_iter().unzip();
+    let stats: Vec<WaferStackStatsRow> = stats.into_iter().flatten().collect();
+    if let (Some(pool), true, false) = (&context.pool, save_stats, stats.is_empty()) {
+        block_on(repo::upsert_many(pool, &stats))?;
+    }
+    Ok(results)
+}
+
+/// Prints one line (or the JSON) per wafer.
+fn report(results: &[WaferResult], json: bool) -> Result<bool, String> {
+    if json {
+        print_json(&results)?;
+    } else {
+        for result in results {
+            match (&result.error, &result.summary) {
+                (Some(error), _) => println!("❌ {}: {}", result.name, error),
+                (None, Some(summary)) => println!(
+                    "✅ {}: {} dies, yield {:.2}% -> {}",
+                    result.name,
+                    result.merged_die_count.unwrap_or(0),
+                    summary.yield_percentage,
+                    result.output_dir.as_deref().unwrap_or("")
+                ),
+                (None, None) => {}
+            }
+        }
+    }
+    Ok(results.iter().all(|r| r.error.is_none()))
+}
+
+/// Stacks what `request` asks for and writes the outputs.
+pub(crate) fn stack_wafers(
+    context: &Context,
+    request: &StackRequest,
+) -> Result<Vec<WaferResult>, String> {
+    if request.output_dir.trim().is_empty() {
+        return Err("No output folder given".into());
+    }
+    let out = PathBuf::from(&request.output_dir);
+    let formats = OutputFormat::selected(&request.formats, context)?;
+    let pass_bins = context.pass_bins(&request.pass_bins);
+    let pass = BinSet::pass_or_default(&pass_bins);
+    let oem = request.oem_product_id.as_deref();
+
+    let jobs = if !request.layers.is_empty() {
+        vec![file_job(request)?]
+    } else if let Some(oem) = oem {
+        database_jobs(request, context, oem)?
+    } else {
+        return Err(
+            "Nothing to stack: give layer files, or an OEM product with the database".into(),
+        );
+    };
+    let settings = context.product(oem)?;
+    let ink = context.ink_rules(&pass_bins);
+    let run = Run {
+        out: &out,
+        formats: &formats,
+        pass_bins: &pass_bins,
+        pass: &pass,
+        ink: ink.as_ref(),
+        style: RenderStyle::Bin,
+    };
+    run_jobs(jobs, &settings, &run, context, !request.no_stats)
+}
+
+pub(super) fn stack(args: &Args) -> Result<bool, String> {
+    let request = StackRequest::from_args(args)?;
+    let context = Context::open(args)?;
+    report(&stack_wafers(&context, &request)?, args.flag("json"))
+}
+
+/// Every file becomes a one-layer stack written with the stacking writers.
+pub(super) fn export(args: &Args) -> Result<bool, String> {
+    if args.positional.is_empty() {
+        return Err("export needs at least one map file".into());
+    }
+    let context = Context::open(args)?;
+    let out = PathBuf::from(args.required("out")?);
+    let formats = OutputFormat::selected(&args.list("format"), &context)?;
+    let pass_bins = context.pass_bins(&args.list("pass-bins"));
+    let pass = BinSet::pass_or_default(&pass_bins);
+    let settings = context.product(args.value("oem"))?;
+
+    let mut jobs = Vec::new();
+    for path in &args.positional {
+        let (stage, sub_stage) = file_stage(path, args.value("stage"))?;
+        jobs.push(WaferJob {
+            name: Path::new(path)
+                .file_stem()
+                .and_then(|s| s.to_str())
+                .unwrap_or("map")
+                .to_string(),
+            layers: vec![(path.clone(), stage, sub_stage)],
+            ..WaferJob::default()
+        });
+    }
+    let ink = context.ink_rules(&pass_bins);
+    let run = Run {
+        out: &out,
+        formats: &formats,
+        pass_bins: &pass_bins,
+        pass: &pass,
+        ink: ink.as_ref(),
+        style: RenderStyle::Bin,
+    };
+    report(&run_jobs(jobs, &settings, &run, &context, false)?, false)
+}
@@ -0,0 +1,277 @@
+#[cfg(test)]
+fn strings(args: &[&str]) -> Vec<String> {
+    args.iter().map(|a| a.to_string()).collect()
+}
+
+#[cfg(test)]
+fn map_file(dir: &std::path::Path, name: &str, lot: &str, rows: &[&str]) -> String {
+    let (cols, count) = (rows[0].len().to_string(), rows.len().to_string());
+    let header = [
+        ("Device Name", "DEV1"),
+        ("Lot No.", lot),
+        ("Wafer ID", "7"),
+        ("Wafer Size", "6"),
+        ("Dice SizeX", "1200.000"),
+        ("Dice SizeY", "1400.000"),
+        ("Flat/Notch", "Down"),
+        ("Map Column", cols.as_str()),
+        ("Map Row", count.as_str()),
+        ("Total Tested", "0"),
+        ("Total Pass", "0"),
+        ("Total Fail", "0"),
+        ("Yield", "0.00%"),
+    ];
+    let mut text: String = header
+        .iter()
+        .map(|(key, value)| format!("{:<18}: {}\n", key, value))
+        .collect();
+    text.push('\n');
+    for row in rows {
+        text.push_str(row);
+        text.push('\n');
+    }
+    let path = dir.join(name);
+    std::fs::write(&path, text).unwrap();
+    path.display().to_string()
+}
+
+#[test]
+fn cli_parses_arguments_and_stages() {
+    use super::{file_stage, parse_stage, Args};
+    use crate::file::scanner::DataSourceType;
+
+    let args = Args::parse(
+        &strings(&[
+            "a.txt",
+            "--format=mapEx,bin",
+            "--wafer",
+            "1",
+            "--wafer=2,3",
+            "--json",
+        ]),
+        &["format", "wafer"],
+        &["json"],
+    )
+    .unwrap();
+    assert_eq!(args.positional, vec!["a.txt".to_string()]);
+    assert_eq!(args.list("format"), vec!["mapEx", "bin"]);
+    assert_eq!(args.list("wafer"), vec!["1", "2", "3"]);
+    assert_eq!(args.value("wafer"), Some("2,3"));
+    assert!(args.flag("json"));
+    assert!(Args::parse(&strings(&["--nope"]), &[], &[]).is_err());
+    assert!(Args::parse(&strings(&["--format"]), &["format"], &[]).is_err());
+    assert!(Args::parse(&strings(&["--json=1"]), &[], &["json"]).is_err());
+
+    assert_eq!(
+        parse_stage("CP2").unwrap(),
+        (DataSourceType::CpProber, Some(2))
+    );
+    assert_eq!(
+        parse_stage("cpProber:1").unwrap(),
+        (DataSourceType::CpProber, Some(1))
+    );
+    assert_eq!(
+        parse_stage("FAB CP").unwrap(),
+        (DataSourceType::FabCp, None)
+    );
+    assert_eq!(parse_stage("wlbi").unwrap(), (DataSourceType::Wlbi, None));
+    assert!(parse_stage("cp9x").is_err());
+
+    assert_eq!(
+        file_stage("/d/L1_01_20240101_101010.WaferMap", None).unwrap(),
+        (DataSourceType::Wlbi, None)
+    );
+    assert_eq!(
+        file_stage("/d/P_L1_01_mapEx.txt", None).unwrap(),
+        (DataSourceType::CpProber, Some(1))
+    );
+    assert_eq!(
+        file_stage("/d/P_L1_01_mapEx.txt", Some("aoi")).unwrap(),
+        (DataSourceType::Aoi, None)
+    );
+    assert!(file_stage("/d/map.txt", None).is_err());
+}
+
+#[test]
+fn cli_config_overrides_database_settings() {
+    use super::config::{CliConfig, ProductSettings};
+    use crate::db::repo::ProductContext;
+    use crate::db::tables::{ProductBinSelectionRow, ProductOffsetRow, ProductSizeRow};
+
+    let config: CliConfig = toml::from_str(
+        r#"
+        outputs = ["mapEx", "HEX"]
+        pass_bins = ["BIN 1"]
+
+        [products.OEM1]
+        y_offset = -0.5
+        die_y = 2.0
+        "#,
+    )
+    .unwrap();
+    assert_eq!(config.outputs, vec!["mapEx", "HEX"]);
+    assert!(toml::from_str::<CliConfig>("unknown = 1").is_err());
+
+    let context = ProductContext {
+        oem_product_id: "OEM1".into(),
+        mapping: None,
+        offset: Some(ProductOffsetRow {
+            oem_product_id: "OEM1".into(),
+            x_offset: 0.1,
+            y_offset: 0.2,
+            defect_offset_x: 3.0,
+            defect_offset_y: 4.0,
+        }),
+        size: Some(ProductSizeRow {
+            oem_product_id: "OEM1".into(),
+            die_x: 1.2,
+            die_y: 1.4,
+        }),
+        bin_selection: Some(ProductBinSelectionRow {
+            oem_product_id: "OEM1".into(),
+            selected_bin_ids: "Particle, Pit".into(),
+        }),
+        bin_map: None,
+    };
+    let settings =
+        ProductSettings::resolve("OEM1", Some(&context), config.products.get("OEM1")).unwrap();
+    assert_eq!(settings.offset.x_offset, 0.1);
+    assert_eq!(settings.offset.y_offset, -0.5);
+    assert_eq!(settings.die_size, Some((1.2, 2.0)));
+    assert!(settings.includes_defect_class("Pit"));
+    assert!(!settings.includes_defect_class("Scratch"));
+
+    // without a database the config alone must give both die sides
+    let settings = ProductSettings::resolve("OEM1", None, config.products.get("OEM1")).unwrap();
+    assert_eq!(settings.die_size, None);
+    assert!(settings.coords().is_none());
+    assert!(settings.includes_defect_class("Scratch"));
+}
+
+#[test]
+fn cli_stacks_and_exports_layer_files() {
+    use super::run;
+    use crate::parser::parse_wafer;
+
+    let dir = std::env::temp_dir().join("aoi_stack_cli_layers");
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    let cp1 = map_file(&dir, "P_L1_07_mapEx.txt", "L1", &["S1115", "..1.."]);
+    let aoi = map_file(&dir, "aoi.txt", "AOI", &[".S1E1", "...1."]);
+    let out = dir.join("out");
+    let out_arg = out.display().to_string();
+
+    let ok = run(&strings(&[
+        "stack",
+        "--layer",
+        &format!("aoi={}", aoi),
+        "--layer",
+        &format!("cp1={}", cp1),
+        "--name",
+        "L1_07",
+        "--out",
+        &out_arg,
+        "--format",
+        "mapEx,bin,HEX,fab,svg",
+    ]))
+    .unwrap();
+    assert!(ok);
+
+    let base = out.join("L1_07");
+    let merged = std::fs::read_to_string(base.join("L1_07_overlayed.txt")).unwrap();
+    let lines: Vec<&str> = merged.lines().collect();
+    assert!(lines.contains(&"Lot No.          : L1"));
+    assert!(lines.contains(&"Total Pass       : 3"));
+    // the AOI layer is one column to the right; its E fails a die CP1 passed
+    assert!(lines.ends_with(&["S1E15", "..1.."]));
+    let bin = std::fs::read_to_string(base.join("L1_07_overlayed.WaferMap")).unwrap();
+    let dies = bin.lines().skip_while(|line| *line != "[MAP]:").skip(1);
+    assert_eq!(dies.take_while(|line| !line.trim().is_empty()).count(), 6);
+    assert!(parse_wafer(base.join("L1_07_FAB.txt").display().to_string()).is_ok());
+    assert!(base.join("L1_07_overlayed.sinf").is_file());
+    assert!(base.join("L1_07_overlayed.svg").is_file());
+
+    // a map that does not parse fails that file only
+    std::fs::write(dir.join("broken_mapEx.txt"), "garbage").unwrap();
+    let broken = dir.join("broken_mapEx.txt").display().to_string();
+    let ok = run(&strings(&["export", &cp1, &broken, "--out", &out_arg])).unwrap();
+    assert!(!ok);
+    assert!(out
+        .join("P_L1_07_mapEx")
+        .join("P_L1_07_mapEx_overlayed.txt")
+        .is_file());
+
+    assert!(run(&strings(&["validate", &cp1])).unwrap());
+    assert!(!run(&strings(&["validate", &broken])).unwrap());
+    assert!(run(&strings(&["stack", "--out", &out_arg])).is_err());
+}
+
+#[test]
+fn cli_applies_config_bin_map_and_ink_rules() {
+    use super::run;
+
+    let dir = std::env::temp_dir().join("aoi_stack_cli_config");
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    let cp1 = map_file(&dir, "P_L1_07_mapEx.txt", "L1", &["S1535", "..5.."]);
+    std::fs::write(
+        dir.join("bins.json"),
+        r#"{ "OEM1": [{ "format": "ascii", "native": "5", "internal": "1" }] }"#,
+    )
+    .unwrap();
+    let config = dir.join("aoi.toml");
+    std::fs::write(
+        &config,
+        "[ink]\nfail_threshold = 1\n\n[products.OEM1]\nbin_map = \"bins.json\"\n",
+    )
+    .unwrap();
+    let out = dir.join("out");
+
+    let ok = run(&strings(&[
+        "stack",
+        "--config",
+        &config.display().to_string(),
+        "--oem",
+        "OEM1",
+        "--layer",
+        &format!("cp1={}", cp1),
+        "--name",
+        "W",
+        "--out",
+        &out.display().to_string(),
+    ]))
+    .unwrap();
+    assert!(ok);
+
+    // 5 stacks as pass bin 1 and is written back as 5
+    let merged = std::fs::read_to_string(out.join("W").join("W_overlayed.txt")).unwrap();
+    let lines: Vec<&str> = merged.lines().collect();
+    assert!(lines.contains(&"Total Pass       : 4"));
+    assert!(lines.ends_with(&["S5535", "..5.."]));
+    // the dies next to the fail are inked
+    let inked = std::fs::read_to_string(out.join("W").join("Ink").join("W_overlayed.txt")).unwrap();
+    let lines: Vec<&str> = inked.lines().collect();
+    assert!(lines.contains(&"Total Pass       : 1"));
+    assert!(lines.ends_with(&["S5z3z", "..z.."]));
+}
+
+#[test]
+fn cli_refuses_a_database_behind_the_schema() {
+    use crate::db::migrations::{latest_version, migrate_up};
+
+    let path = std::env::temp_dir().join("aoi_stack_cli_v1.db");
+    let _ = std::fs::remove_file(&path);
+    tauri::async_runtime::block_on(async {
+        let options = sqlx::sqlite::SqliteConnectOptions::new()
+            .filename(&path)
+            .create_if_missing(true);
+        let pool = sqlx::SqlitePool::connect_with(options).await.unwrap();
+        migrate_up(&pool, 1).await.unwrap();
+        let error = crate::db::file_pool(&path).await.unwrap_err();
+        assert!(error.contains("open it with the app first"), "{}", error);
+
+        migrate_up(&pool, latest_version()).await.unwrap();
+        assert!(crate::db::file_pool(&path).await.is_ok());
+        pool.close().await;
+    });
+}
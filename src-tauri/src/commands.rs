@@ -3,6 +3,7 @@ use std::fs;
 use std::path::Path;
 
 use crate::file::file_io::{build_file_info, FolderRequest, FolderResult};
+use crate::file::scanner::{scan_data_sources, ScanOptions, ScanReport};
 
 // #[tauri::command]
 // pub fn check_folder_exists(path: String) -> Result<bool, String> {
@@ -80,6 +81,88 @@ pub fn rust_read_dir(dir: String) -> Vec<FolderResult> {
     out
 }
 
+/// Walk `root` once, recognise data-source folders by `options.regex` and
+/// extract product/lot/wafer/stage metadata from every matching file.
+#[tauri::command]
+pub fn rust_scan_data_sources(
+    root: String,
+    options: Option<ScanOptions>,
+) -> Result<ScanReport, String> {
+    scan_data_sources(&root, &options.unwrap_or_default())
+}
+
+// =============================================================================
+// Live ingestion
+
+use crate::file::watcher::{start_watch, stop_watch, watched_roots, WatchOptions};
+
+/// Watch `root` for new, changed and removed map files; each debounced batch
+/// updates `file_index` / `wafer_maps` and emits `data-source-changed`.
+#[tauri::command]
+pub fn rust_watch_start(
+    app: tauri::AppHandle,
+    root: String,
+    options: Option<WatchOptions>,
+) -> Result<Vec<String>, String> {
+    start_watch(app, root, options.unwrap_or_default())?;
+    Ok(watched_roots())
+}
+
+#[tauri::command]
+pub fn rust_watch_stop(root: String) -> Vec<String> {
+    stop_watch(&root);
+    watched_roots()
+}
+
+#[tauri::command]
+pub fn rust_watch_list() -> Vec<String> {
+    watched_roots()
+}
+
+// =============================================================================
+// Incremental file index
+
+use crate::file::index::{index_files, IndexOptions, IndexReport};
+
+/// Bring `file_index` up to date for `paths`: unchanged files are skipped,
+/// the rest hashed by content, and renamed/moved files re-keyed in place.
+#[tauri::command]
+pub async fn rust_index_files(
+    db: tauri::State<'_, tauri_plugin_sql::DbInstances>,
+    paths: Vec<String>,
+    options: Option<IndexOptions>,
+) -> Result<IndexReport, String> {
+    let pool = crate::db::app_pool(&db).await?;
+    let mut tx = pool
+        .begin()
+        .await
+        .map_err(|e| format!("Failed to open transaction: {}", e))?;
+    let report = index_files(&mut tx, &paths, &options.unwrap_or_default()).await?;
+    tx.commit()
+        .await
+        .map_err(|e| format!("Failed to commit file_index: {}", e))?;
+    Ok(report)
+}
+
+// =============================================================================
+// Duplicate maps
+
+use crate::file::duplicates::{duplicate_report, DuplicateReport};
+
+/// Exact duplicates, retest chains and conflicting maps among the indexed
+/// wafer maps, optionally of one product, with the recommended and the chosen
+/// (`wafer_map_choice`) map per key.
+#[tauri::command]
+pub async fn rust_wafer_map_duplicates(
+    db: tauri::State<'_, tauri_plugin_sql::DbInstances>,
+    product_id: Option<String>,
+) -> Result<DuplicateReport, String> {
+    let pool = crate::db::app_pool(&db).await?;
+    let files = crate::db::repo::wafer_map_files(&pool, product_id.as_deref()).await?;
+    let choices = crate::db::repo::wafer_map_choices(&pool, product_id.as_deref()).await?;
+    Ok(duplicate_report(&files, &choices))
+}
+
 // =============================================================================
 
 use crate::crypto;
@@ -99,17 +182,31 @@ pub fn rust_sha1_batch(inputs: Vec<String>) -> Vec<String> {
     inputs.into_iter().map(crypto::sha1_hash).collect()
 }
 
+/// SHA256 of file contents, hashed in parallel; `None` for unreadable files.
+#[tauri::command]
+pub async fn rust_sha256_file_batch(paths: Vec<String>) -> Result<Vec<Option<String>>, String> {
+    tauri::async_runtime::spawn_blocking(move || crypto::sha256_files(&paths))
+        .await
+        .map_err(|e| format!("Thread join error: {e}"))
+}
+
 // =============================================================================
 
 use crate::parser::{
     debug_print_die_layout_coords, parse_die_layout_xls, parse_product_mapping_xls,
     parse_product_xls, parse_substrate_defect_xls, parse_wafer, parse_wafer_bin,
-    parse_wafer_map_data, DieLayoutSheet,
+    parse_bin_map_json, parse_bin_map_xls, parse_wafer_map_data, DieLayoutSheet,
 };
 use crate::inference;
+use crate::wafer::bin_map::{apply_bin_map, BinMapDirection, BinMapTable};
+use crate::wafer::coords::{
+    overlay_substrate_defects, place_dies, DefectRect, DieCoordinateSystem, SubstrateLayout,
+};
+use crate::wafer::edge::{apply_ink_rules, InkOutcome, InkRules};
+use crate::wafer::geometry::WaferGeometry;
 
 use crate::wafer::ds::{
-    BinMapData, DefectRecord, HexMapData, MapData, ProductMappingRecord, ProductRecord, Wafer, SilanMapData
+    AsciiDie, BinMapData, DefectRecord, HexMapData, MapData, ProductMappingRecord, ProductRecord, Wafer, SilanMapData
 };
 
 #[tauri::command]
@@ -144,6 +241,19 @@ pub fn rust_parse_die_layout_xls(
     parse_die_layout_xls(path)
 }
 
+#[tauri::command]
+/// Object key is the sheet name (product ID)<br/>
+/// Typescript eqv. Record<string, BinMapTable>;
+pub fn rust_parse_bin_map_xls(path: String) -> Result<HashMap<String, BinMapTable>, String> {
+    parse_bin_map_xls(path)
+}
+
+#[tauri::command]
+/// Same shape as `rust_parse_bin_map_xls`, read from a JSON file.
+pub fn rust_parse_bin_map_json(path: String) -> Result<HashMap<String, BinMapTable>, String> {
+    parse_bin_map_json(path)
+}
+
 #[tauri::command]
 pub fn rust_debug_print_die_layout_coords(path: String) -> Result<(), String> {
     debug_print_die_layout_coords(path)
@@ -153,20 +263,26 @@ pub fn rust_debug_print_die_layout_coords(path: String) -> Result<(), String> {
 
 #[tauri::command]
 /// Typescript eqv. Record<string, DefectRecord[]>;
-pub fn rust_parse_wafer(path: String) -> Result<Wafer, String> {
-    parse_wafer(path)
+pub fn rust_parse_wafer(path: String, bin_map: Option<BinMapTable>) -> Result<Wafer, String> {
+    parse_wafer(path).map(|w| apply_bin_map(w, bin_map.as_ref(), BinMapDirection::Import))
 }
 
 #[tauri::command]
 /// Typescript eqv. Record<string, DefectRecord[]>;
-pub fn rust_parse_wafer_bin(path: String) -> Result<BinMapData, String> {
-    parse_wafer_bin(path)
+pub fn rust_parse_wafer_bin(
+    path: String,
+    bin_map: Option<BinMapTable>,
+) -> Result<BinMapData, String> {
+    parse_wafer_bin(path).map(|w| apply_bin_map(w, bin_map.as_ref(), BinMapDirection::Import))
 }
 
 #[tauri::command]
 /// Typescript eqv. Record<string, DefectRecord[]>;
-pub fn rust_parse_wafer_map_data(path: String) -> Result<MapData, String> {
-    parse_wafer_map_data(path)
+pub fn rust_parse_wafer_map_data(
+    path: String,
+    bin_map: Option<BinMapTable>,
+) -> Result<MapData, String> {
+    parse_wafer_map_data(path).map(|w| apply_bin_map(w, bin_map.as_ref(), BinMapDirection::Import))
 }
 
 fn export_bytes<L: AsRef<str>, D: Into<Vec<u8>>>(label: L, output_path: &str, data: D) -> Result<(), String> {
@@ -180,7 +296,12 @@ fn print_value(content: String) -> Result<(), String> {
 }
 
 #[tauri::command]
-pub fn rust_export_wafer(wafer: Wafer, output_path: String) -> Result<(), String> {
+pub fn rust_export_wafer(
+    wafer: Wafer,
+    output_path: String,
+    bin_map: Option<BinMapTable>,
+) -> Result<(), String> {
+    let wafer = apply_bin_map(wafer, bin_map.as_ref(), BinMapDirection::Export);
     export_bytes("wafer", &output_path, wafer.to_string())
 }
 
@@ -190,7 +311,12 @@ pub fn rust_print_wafer(wafer: Wafer) -> Result<(), String> {
 }
 
 #[tauri::command]
-pub fn rust_export_wafer_bin(wafer_bin: BinMapData, output_path: String) -> Result<(), String> {
+pub fn rust_export_wafer_bin(
+    wafer_bin: BinMapData,
+    output_path: String,
+    bin_map: Option<BinMapTable>,
+) -> Result<(), String> {
+    let wafer_bin = apply_bin_map(wafer_bin, bin_map.as_ref(), BinMapDirection::Export);
     export_bytes("bin map", &output_path, wafer_bin.to_string())
 }
 
@@ -200,7 +326,12 @@ pub fn rust_print_wafer_bin(wafer_bin: BinMapData) -> Result<(), String> {
 }
 
 #[tauri::command]
-pub fn rust_export_wafer_map_data(data: MapData, output_path: String) -> Result<(), String> {
+pub fn rust_export_wafer_map_data(
+    data: MapData,
+    output_path: String,
+    bin_map: Option<BinMapTable>,
+) -> Result<(), String> {
+    let data = apply_bin_map(data, bin_map.as_ref(), BinMapDirection::Export);
     export_bytes("map data", &output_path, data.to_string())
 }
 
@@ -212,7 +343,12 @@ pub fn rust_print_wafer_map_data(data: MapData) -> Result<(), String> {
 // HEX/.sinf
 
 #[tauri::command]
-pub fn rust_export_wafer_hex(wafer_hex: HexMapData, output_path: String) -> Result<(), String> {
+pub fn rust_export_wafer_hex(
+    wafer_hex: HexMapData,
+    output_path: String,
+    bin_map: Option<BinMapTable>,
+) -> Result<(), String> {
+    let wafer_hex = apply_bin_map(wafer_hex, bin_map.as_ref(), BinMapDirection::Export);
     export_bytes("map data", &output_path, wafer_hex.to_string())
 }
 
@@ -221,14 +357,13 @@ pub fn rust_print_wafer_hex(wafer_hex: HexMapData) -> Result<(), String> {
     print_value(wafer_hex.to_string())
 }
 
-
-#[tauri::command]
-pub fn rust_export_wafer_jpg(image_data: Vec<u8>, output_path: String) -> Result<(), String> {
-   export_bytes("image data", &output_path, image_data)
-}
-
 #[tauri::command]
-pub fn rust_export_wafer_silan(silan: SilanMapData, output_path: String) -> Result<(), String> {
+pub fn rust_export_wafer_silan(
+    silan: SilanMapData,
+    output_path: String,
+    bin_map: Option<BinMapTable>,
+) -> Result<(), String> {
+    let silan = apply_bin_map(silan, bin_map.as_ref(), BinMapDirection::Export);
     export_bytes("SILAN map", &output_path, silan.to_string())
 }
 
@@ -238,7 +373,12 @@ pub fn rust_print_wafer_silan(silan: SilanMapData) -> Result<(), String> {
 }
 
 #[tauri::command]
-pub fn rust_export_wafer_fab(fab: Wafer, output_path: String) -> Result<(), String> {
+pub fn rust_export_wafer_fab(
+    fab: Wafer,
+    output_path: String,
+    bin_map: Option<BinMapTable>,
+) -> Result<(), String> {
+    let fab = apply_bin_map(fab, bin_map.as_ref(), BinMapDirection::Export);
     export_bytes("FAB map", &output_path, fab.to_string())
 }   
 
@@ -247,6 +387,531 @@ pub fn rust_print_wafer_fab(fab: Wafer) -> Result<(), String> {
     print_value(fab.to_string())    
 }
 
+// =============================================================================
+// Physical die coordinates
+
+#[tauri::command]
+/// Mark dies overlapped by substrate defects with `E` (the substrate stacking layer).
+pub fn rust_overlay_substrate_defects(
+    dies: Vec<AsciiDie>,
+    defects: Vec<DefectRect>,
+    coords: DieCoordinateSystem,
+) -> Vec<AsciiDie> {
+    overlay_substrate_defects(&dies, &defects, &coords)
+}
+
+#[tauri::command]
+/// Die and defect rectangles (mm) for the substrate viewer; `positions` are `[x, y]` grid indices.
+pub fn rust_place_substrate_dies(
+    positions: Vec<(i32, i32)>,
+    defects: Vec<DefectRect>,
+    coords: DieCoordinateSystem,
+) -> SubstrateLayout {
+    place_dies(&positions, &defects, &coords)
+}
+
+// =============================================================================
+// Edge exclusion & inking
+
+#[tauri::command]
+/// `geometry` wins over `header`; the header is the merged stacking header
+/// (`Wafer Size`, `Dice SizeX/Y` or `Index X/Y`). `coords` places the dies on
+/// the wafer (product die size and offsets).
+pub fn rust_apply_ink_rules(
+    dies: Vec<AsciiDie>,
+    rules: InkRules,
+    header: Option<HashMap<String, String>>,
+    geometry: Option<WaferGeometry>,
+    coords: Option<DieCoordinateSystem>,
+) -> Result<InkOutcome, String> {
+    let geometry = geometry.or_else(|| header.as_ref().and_then(WaferGeometry::from_header));
+    apply_ink_rules(&dies, geometry.as_ref(), coords.as_ref(), &rules)
+}
+
+// =============================================================================
+// Gross die
+
+use crate::wafer::gross_die::{gross_die, GrossDieResult, GrossDieSpec};
+
+/// Theoretical gross-die grid; with `layout` (a `DieLayoutSheet` or a parsed
+/// map) also the missing and extra positions. `coords` fixes the placement.
+#[tauri::command]
+pub fn rust_gross_die(
+    spec: GrossDieSpec,
+    layout: Option<Vec<AsciiDie>>,
+    coords: Option<DieCoordinateSystem>,
+) -> Result<GrossDieResult, String> {
+    gross_die(&spec, layout.as_deref(), coords.as_ref())
+}
+
+// =============================================================================
+// Rendering
+
+use crate::render::raster::render_map;
+use crate::render::report::{build_lot_report, LotReport};
+use crate::render::svg::render_map_svg;
+use crate::render::{ImageFormat, MapLayer, WaferRenderOptions};
+
+#[tauri::command]
+/// Render a merged map to PNG/JPEG. The format comes from `options.format`,
+/// else from the extension of `output_path` (JPEG by default).
+pub fn rust_render_wafer_map(
+    dies: Vec<AsciiDie>,
+    header: HashMap<String, String>,
+    output_path: String,
+    coords: Option<DieCoordinateSystem>,
+    options: Option<WaferRenderOptions>,
+) -> Result<(), String> {
+    let options = options.unwrap_or_default();
+    let format = options
+        .format
+        .or_else(|| ImageFormat::from_path(&output_path))
+        .unwrap_or_default();
+    let bytes = render_map(&dies, &header, coords.as_ref(), &options, format)?;
+    export_bytes("image data", &output_path, bytes)
+}
+
+#[tauri::command]
+/// Write a map as SVG (one `<rect>` per die). Pass the aligned stacking
+/// `layers` in merge order to record each die's source layer.
+pub fn rust_export_wafer_svg(
+    dies: Vec<AsciiDie>,
+    header: HashMap<String, String>,
+    output_path: String,
+    coords: Option<DieCoordinateSystem>,
+    layers: Option<Vec<MapLayer>>,
+    options: Option<WaferRenderOptions>,
+) -> Result<(), String> {
+    let options = options.unwrap_or_default();
+    let svg = render_map_svg(
+        &dies,
+        &header,
+        coords.as_ref(),
+        layers.as_deref().unwrap_or_default(),
+        &options,
+    )?;
+    export_bytes("SVG", &output_path, svg)
+}
+
+#[tauri::command]
+/// Write the PDF report of one lot: cover page plus one page per wafer.
+pub fn rust_export_lot_report(report: LotReport, output_path: String) -> Result<(), String> {
+    let pdf = build_lot_report(&report)?;
+    export_bytes("PDF report", &output_path, pdf)
+}
+
+// =============================================================================
+// Lot analytics
+
+use crate::analysis::composite::{composite_map, CompositeMap, CompositeOptions, CompositeWafer};
+use crate::analysis::correlation::{compare_maps, CorrelationOptions, MapComparison};
+use crate::analysis::signature::{classify_signatures, SignatureOptions, SignatureReport};
+use crate::analysis::waterfall::{stage_waterfall, StageWaterfall};
+use crate::analysis::zones::{zone_yield, ZoneOptions, ZoneReport};
+use crate::render::raster::render_composite;
+
+#[tauri::command]
+/// Per-position fail counts over the stacked wafers of a lot.
+pub fn rust_composite_lot_map(
+    wafers: Vec<CompositeWafer>,
+    options: Option<CompositeOptions>,
+) -> Result<CompositeMap, String> {
+    if wafers.is_empty() {
+        return Err("No wafers to combine".to_string());
+    }
+    Ok(composite_map(&wafers, &options.unwrap_or_default()))
+}
+
+#[tauri::command]
+/// Write the composite of a lot as a PNG/JPEG heat map and return its data.
+pub fn rust_render_composite_map(
+    wafers: Vec<CompositeWafer>,
+    header: HashMap<String, String>,
+    output_path: String,
+    coords: Option<DieCoordinateSystem>,
+    options: Option<CompositeOptions>,
+    render_options: Option<WaferRenderOptions>,
+) -> Result<CompositeMap, String> {
+    let composite = rust_composite_lot_map(wafers, options)?;
+    let render_options = render_options.unwrap_or_default();
+    let format = render_options
+        .format
+        .or_else(|| ImageFormat::from_path(&output_path))
+        .unwrap_or_default();
+    let bytes = render_composite(
+        &composite,
+        &header,
+        coords.as_ref(),
+        &render_options,
+        format,
+    )?;
+    export_bytes("composite map", &output_path, bytes)?;
+    Ok(composite)
+}
+
+#[tauri::command]
+/// Label a merged map with spatial signatures. Geometry is resolved like
+/// `rust_apply_ink_rules`; without it radii are taken from the grid.
+pub fn rust_classify_signatures(
+    dies: Vec<AsciiDie>,
+    header: Option<HashMap<String, String>>,
+    geometry: Option<WaferGeometry>,
+    coords: Option<DieCoordinateSystem>,
+    options: Option<SignatureOptions>,
+) -> Result<SignatureReport, String> {
+    let geometry = geometry.or_else(|| header.as_ref().and_then(WaferGeometry::from_header));
+    classify_signatures(
+        &dies,
+        geometry.as_ref(),
+        coords.as_ref(),
+        &options.unwrap_or_default(),
+    )
+}
+
+#[tauri::command]
+/// Yield and bin distribution per radial ring, sector and ring × sector cell.
+pub fn rust_zone_yield(
+    dies: Vec<AsciiDie>,
+    header: Option<HashMap<String, String>>,
+    geometry: Option<WaferGeometry>,
+    coords: Option<DieCoordinateSystem>,
+    options: Option<ZoneOptions>,
+) -> Result<ZoneReport, String> {
+    let geometry = geometry.or_else(|| header.as_ref().and_then(WaferGeometry::from_header));
+    zone_yield(
+        &dies,
+        geometry.as_ref(),
+        coords.as_ref(),
+        &options.unwrap_or_default(),
+    )
+}
+
+#[tauri::command]
+/// Yield after each stage of one wafer, its incremental loss and per-stage
+/// fail-bin Pareto. `layers` are the aligned stacking layers.
+pub fn rust_stage_waterfall(
+    layers: Vec<MapLayer>,
+    pass_bins: Option<Vec<String>>,
+) -> Result<StageWaterfall, String> {
+    if layers.is_empty() {
+        return Err("No layers to analyse".to_string());
+    }
+    Ok(stage_waterfall(&layers, &pass_bins.unwrap_or_default()))
+}
+
+#[tauri::command]
+/// Die-by-die comparison of two aligned maps; `a` is the reference stage.
+pub fn rust_compare_maps(
+    a: MapLayer,
+    b: MapLayer,
+    options: Option<CorrelationOptions>,
+) -> MapComparison {
+    compare_maps(&a, &b, &options.unwrap_or_default())
+}
+
+// =============================================================================
+// SPC
+
+use crate::analysis::spc::{spc_report, SpcOptions, SpcReport};
+use crate::db::{app_pool, repo};
+use tauri_plugin_sql::DbInstances;
+
+#[tauri::command]
+/// X-bar/R and p-charts, Western Electric violations and outlier wafers over
+/// the lots of one product, read from `wafer_stack_stats` and, for exported
+/// lots, `wafer_stack_stats_history`.
+pub async fn rust_spc_lot_yields(
+    db: tauri::State<'_, DbInstances>,
+    oem_product_id: String,
+    options: Option<SpcOptions>,
+) -> Result<SpcReport, String> {
+    let pool = app_pool(&db).await?;
+    let stats: Vec<_> = repo::stack_stats_with_history(&pool, &oem_product_id)
+        .await?
+        .into_iter()
+        .map(|row| row.stats)
+        .collect();
+    if stats.is_empty() {
+        return Err(format!("No stacking stats for product {}", oem_product_id));
+    }
+    Ok(spc_report(
+        &oem_product_id,
+        &stats,
+        &options.unwrap_or_default(),
+    ))
+}
+
+// =============================================================================
+// Database
+
+use crate::db::backup::{
+    self, BackupInfo, BundleManifest, ExportOptions, ImportOptions, ImportReport,
+};
+use crate::db::migrations::{self, SchemaStatus};
+use crate::db::repo::{ProductContext, WaferMapFilter};
+use crate::db::tables::{
+    FileIndexRow, ProductDefectMapRow, SubstrateDefectRow, TableRows, WaferMapRow,
+    WaferStackStatsRow,
+};
+
+#[tauri::command]
+/// Upsert `{ table, rows }` in one transaction; returns the rows written.
+pub async fn rust_db_upsert(
+    db: tauri::State<'_, DbInstances>,
+    batch: TableRows,
+) -> Result<u64, String> {
+    repo::upsert_table(&app_pool(&db).await?, &batch).await
+}
+
+#[tauri::command]
+/// Mapping, offsets, die size and bin selection of one OEM product.
+pub async fn rust_db_product_context(
+    db: tauri::State<'_, DbInstances>,
+    oem_product_id: String,
+) -> Result<ProductContext, String> {
+    repo::product_context(&app_pool(&db).await?, &oem_product_id).await
+}
+
+#[tauri::command]
+pub async fn rust_db_wafer_maps(
+    db: tauri::State<'_, DbInstances>,
+    filter: Option<WaferMapFilter>,
+) -> Result<Vec<WaferMapRow>, String> {
+    repo::wafer_maps(&app_pool(&db).await?, &filter.unwrap_or_default()).await
+}
+
+#[tauri::command]
+pub async fn rust_db_product_defect_maps(
+    db: tauri::State<'_, DbInstances>,
+    oem_product_id: String,
+    lot_id: Option<String>,
+) -> Result<Vec<ProductDefectMapRow>, String> {
+    repo::product_defect_maps(&app_pool(&db).await?, &oem_product_id, lot_id.as_deref()).await
+}
+
+#[tauri::command]
+pub async fn rust_db_substrate_defects(
+    db: tauri::State<'_, DbInstances>,
+    sub_ids: Vec<String>,
+) -> Result<Vec<SubstrateDefectRow>, String> {
+    repo::substrate_defects(&app_pool(&db).await?, &sub_ids).await
+}
+
+#[tauri::command]
+pub async fn rust_db_file_index(
+    db: tauri::State<'_, DbInstances>,
+    paths: Vec<String>,
+) -> Result<Vec<FileIndexRow>, String> {
+    repo::file_index(&app_pool(&db).await?, &paths).await
+}
+
+#[tauri::command]
+pub async fn rust_db_stack_stats(
+    db: tauri::State<'_, DbInstances>,
+    oem_product_id: String,
+) -> Result<Vec<WaferStackStatsRow>, String> {
+    repo::stack_stats(&app_pool(&db).await?, &oem_product_id).await
+}
+
+#[tauri::command]
+/// Applied, pending and incompatible schema versions of the app database.
+pub async fn rust_db_schema_status(
+    db: tauri::State<'_, DbInstances>,
+) -> Result<SchemaStatus, String> {
+    migrations::schema_status(&app_pool(&db).await?).await
+}
+
+#[tauri::command]
+/// Applies pending migrations (e.g. after `rust_db_revert`); the plugin only
+/// migrates on startup.
+pub async fn rust_db_migrate(db: tauri::State<'_, DbInstances>) -> Result<SchemaStatus, String> {
+    migrations::migrate_up(&app_pool(&db).await?, migrations::latest_version()).await
+}
+
+#[tauri::command]
+/// Runs down migrations to `target`. v1 holds every table, so it is never
+/// reverted from the app.
+pub async fn rust_db_revert(
+    db: tauri::State<'_, DbInstances>,
+    target: i64,
+) -> Result<SchemaStatus, String> {
+    if target < 1 {
+        return Err("Cannot revert below schema v1".to_string());
+    }
+    migrations::migrate_down(&app_pool(&db).await?, target).await
+}
+
+#[tauri::command]
+/// Consistent copy of the whole database, taken while the app keeps running.
+pub async fn rust_db_backup(
+    db: tauri::State<'_, DbInstances>,
+    dest: String,
+) -> Result<BackupInfo, String> {
+    backup::backup_database(&app_pool(&db).await?, Path::new(&dest)).await
+}
+
+#[tauri::command]
+/// Offsets, die sizes, bin selections, product mapping and stats as a `.json`
+/// or zip bundle for another workstation.
+pub async fn rust_db_export_config(
+    db: tauri::State<'_, DbInstances>,
+    path: String,
+    options: Option<ExportOptions>,
+) -> Result<BundleManifest, String> {
+    backup::export_config(
+        &app_pool(&db).await?,
+        Path::new(&path),
+        &options.unwrap_or_default(),
+    )
+    .await
+}
+
+#[tauri::command]
+/// Merges a bundle; run with `dryRun` first to list the conflicts.
+pub async fn rust_db_import_config(
+    db: tauri::State<'_, DbInstances>,
+    path: String,
+    options: Option<ImportOptions>,
+) -> Result<ImportReport, String> {
+    backup::import_config(
+        &app_pool(&db).await?,
+        Path::new(&path),
+        &options.unwrap_or_default(),
+    )
+    .await
+}
+
+// =============================================================================
+// Wafer search
+
+use crate::db::search::{
+    self, ReindexOptions, ReindexReport, WaferSearchPage, WaferSearchQuery,
+};
+
+#[tauri::command]
+/// Indexed wafers by product, lot, stage, header fields, yield range, time
+/// range and bins present, one page at a time.
+pub async fn rust_search_wafers(
+    db: tauri::State<'_, DbInstances>,
+    query: Option<WaferSearchQuery>,
+) -> Result<WaferSearchPage, String> {
+    search::search_wafers(&app_pool(&db).await?, &query.unwrap_or_default()).await
+}
+
+#[tauri::command]
+/// Parses the headers of indexed maps that have none yet (or all with `force`).
+pub async fn rust_search_reindex(
+    db: tauri::State<'_, DbInstances>,
+    options: Option<ReindexOptions>,
+) -> Result<ReindexReport, String> {
+    search::reindex_meta(&app_pool(&db).await?, &options.unwrap_or_default()).await
+}
+
+// =============================================================================
+// Stacking job queue
+//
+// The runner in `jobs::runner` stacks the queued wafers; the frontend
+// enqueues, cancels and retries jobs and follows `STACKING_JOB_CHANGED`.
+
+use crate::jobs::{self, runner, JobSummary, JobTask, NewJob};
+
+#[tauri::command]
+/// Queues one stacking job with a task per wafer; the runner picks it up.
+pub async fn rust_jobs_enqueue(
+    app: tauri::AppHandle,
+    db: tauri::State<'_, DbInstances>,
+    job: NewJob,
+) -> Result<JobSummary, String> {
+    let pool = app_pool(&db).await?;
+    let summary = jobs::enqueue(&pool, &job).await?;
+    jobs::notify(&app, &pool, &summary.job_id, None).await;
+    runner::wake();
+    Ok(summary)
+}
+
+#[tauri::command]
+pub async fn rust_jobs_list(db: tauri::State<'_, DbInstances>) -> Result<Vec<JobSummary>, String> {
+    jobs::list_jobs(&app_pool(&db).await?).await
+}
+
+#[tauri::command]
+pub async fn rust_jobs_tasks(
+    db: tauri::State<'_, DbInstances>,
+    job_id: String,
+) -> Result<Vec<JobTask>, String> {
+    jobs::job_tasks(&app_pool(&db).await?, &job_id).await
+}
+
+#[tauri::command]
+/// Cancels the queued wafers of a job and stops the ones being stacked.
+pub async fn rust_jobs_cancel(
+    app: tauri::AppHandle,
+    db: tauri::State<'_, DbInstances>,
+    job_id: String,
+) -> Result<JobSummary, String> {
+    let pool = app_pool(&db).await?;
+    let summary = jobs::cancel_job(&pool, &job_id).await?;
+    runner::cancel(&job_id);
+    jobs::notify(&app, &pool, &job_id, None).await;
+    Ok(summary)
+}
+
+#[tauri::command]
+/// Requeues the failed and cancelled wafers of a job.
+pub async fn rust_jobs_retry(
+    app: tauri::AppHandle,
+    db: tauri::State<'_, DbInstances>,
+    job_id: String,
+) -> Result<JobSummary, String> {
+    let pool = app_pool(&db).await?;
+    let summary = jobs::retry_job(&pool, &job_id).await?;
+    runner::forget(&job_id);
+    jobs::notify(&app, &pool, &job_id, None).await;
+    runner::wake();
+    Ok(summary)
+}
+
+#[tauri::command]
+pub async fn rust_jobs_remove(
+    db: tauri::State<'_, DbInstances>,
+    job_id: String,
+) -> Result<(), String> {
+    jobs::remove_job(&app_pool(&db).await?, &job_id).await?;
+    runner::forget(&job_id);
+    Ok(())
+}
+
+// =============================================================================
+// Local HTTP API
+
+use crate::http::{self, HttpApiStatus};
+
+#[tauri::command]
+/// Serves the HTTP API on 127.0.0.1 with the app database; errors when the
+/// build lacks the `http-api` feature.
+pub async fn rust_http_api_start(
+    db: tauri::State<'_, DbInstances>,
+    port: Option<u16>,
+) -> Result<HttpApiStatus, String> {
+    let context = crate::cli::Context::with_pool(app_pool(&db).await?);
+    let port = port.unwrap_or(http::DEFAULT_PORT);
+    tauri::async_runtime::spawn_blocking(move || http::start(context, port))
+        .await
+        .map_err(|e| format!("Task join error: {e}"))?
+}
+
+#[tauri::command]
+pub async fn rust_http_api_stop() -> Result<HttpApiStatus, String> {
+    tauri::async_runtime::spawn_blocking(http::stop)
+        .await
+        .map_err(|e| format!("Task join error: {e}"))
+}
+
+#[tauri::command]
+pub fn rust_http_api_status() -> HttpApiStatus {
+    http::status()
+}
+
 // =============================================================================
 // AOI TorchScript inference
 
@@ -19,4 +19,26 @@ pub fn sha256_hash(input: String) -> String {
     hasher.update(input.as_bytes());
     let result = hasher.finalize();
     hex::encode(result)
-}
\ No newline at end of file
+}
+
+/// SHA256 of a file's contents, read in 64 KiB chunks
+pub fn sha256_file(path: &str) -> std::io::Result<String> {
+    use std::io::Read;
+    let mut file = std::fs::File::open(path)?;
+    let mut hasher = Sha256::new();
+    let mut buf = vec![0u8; 64 * 1024];
+    loop {
+        let n = file.read(&mut buf)?;
+        if n == 0 {
+            break;
+        }
+        hasher.update(&buf[..n]);
+    }
+    Ok(hex::encode(hasher.finalize()))
+}
+
+/// SHA256 of many files on the rayon pool; `None` where a file can't be read
+pub fn sha256_files(paths: &[String]) -> Vec<Option<String>> {
+    use rayon::prelude::*;
+    paths.par_iter().map(|p| sha256_file(p).ok()).collect()
+}
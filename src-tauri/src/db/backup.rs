@@ -0,0 +1,450 @@
+use std::collections::BTreeMap;
+use std::fs::{self, File};
+use std::io::{Read, Write};
+use std::path::Path;
+use std::time::Instant;
+
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sqlx::SqlitePool;
+use zip::write::SimpleFileOptions;
+use zip::{CompressionMethod, ZipArchive, ZipWriter};
+
+use super::migrations::{latest_version, schema_status};
+use super::repo::{all_rows, upsert_rows};
+use super::tables::*;
+
+// =============================================================================
+// Backup, export and import
+//
+// - backup: a consistent copy of the whole database (`VACUUM INTO`), safe
+//   while the app keeps writing
+// - config bundle: the per-workstation tables (product mapping, offsets, die
+//   sizes, bin selections, stacking stats) as JSON, either one `.json` file or
+//   a zip of `manifest.json` + `tables/<table>.json`. Indexed files and wafer
+//   maps are left out: their paths only exist on the exporting PC.
+// - import: rows are matched on the table key; a row that exists with other
+//   values is a conflict, resolved by `ConflictPolicy`.
+// =============================================================================
+
+pub const BUNDLE_FORMAT: &str = "aoi-wafer-stacking/config";
+pub const BUNDLE_VERSION: u32 = 1;
+
+/// Exported tables, parents first so foreign keys hold on import.
+pub const CONFIG_TABLES: &[&str] = &[
+    OemProductMapRow::TABLE,
+    ProductOffsetRow::TABLE,
+    ProductSizeRow::TABLE,
+    ProductBinSelectionRow::TABLE,
+    ProductBinMapRow::TABLE,
+    WaferStackStatsRow::TABLE,
+];
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BackupInfo {
+    pub path: String,
+    pub bytes: u64,
+    pub elapsed_ms: u64,
+}
+
+/// Writes a snapshot of the database to `dest`, replacing it if present.
+pub async fn backup_database(pool: &SqlitePool, dest: &Path) -> Result<BackupInfo, String> {
+    let start = Instant::now();
+    // VACUUM INTO refuses an existing file; write next to it and swap
+    let tmp = dest.with_extension("tmp");
+    let _ = fs::remove_file(&tmp);
+    sqlx::query("VACUUM INTO ?")
+        .bind(tmp.to_string_lossy().as_ref())
+        .execute(pool)
+        .await
+        .map_err(|e| format!("Backup failed: {}", e))?;
+    fs::rename(&tmp, dest).map_err(|e| format!("Failed to write {}: {}", dest.display(), e))?;
+    Ok(BackupInfo {
+        path: dest.to_string_lossy().to_string(),
+        bytes: fs::metadata(dest).map(|m| m.len()).unwrap_or(0),
+        elapsed_ms: start.elapsed().as_millis() as u64,
+    })
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BundleManifest {
+    pub format: String,
+    pub version: u32,
+    /// Schema version of the exporting database
+    pub schema_version: i64,
+    pub app_version: String,
+    pub created_at: String,
+    /// Row count per table
+    pub tables: BTreeMap<String, usize>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Bundle {
+    manifest: BundleManifest,
+    tables: BTreeMap<String, Value>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct ExportOptions {
+    /// Subset of `CONFIG_TABLES`; all when unset
+    pub tables: Option<Vec<String>>,
+}
+
+fn selected(tables: &Option<Vec<String>>) -> Result<Vec<&'static str>, String> {
+    let Some(wanted) = tables else {
+        return Ok(CONFIG_TABLES.to_vec());
+    };
+    if let Some(bad) = wanted.iter().find(|t| !CONFIG_TABLES.contains(&t.as_str())) {
+        return Err(format!(
+            "Table {} is not part of the configuration ({})",
+            bad,
+            CONFIG_TABLES.join(", ")
+        ));
+    }
+    Ok(CONFIG_TABLES
+        .iter()
+        .copied()
+        .filter(|t| wanted.iter().any(|w| w == t))
+        .collect())
+}
+
+async fn table_json(pool: &SqlitePool, table: &str) -> Result<Value, String> {
+    async fn rows<T: Table + Serialize>(pool: &SqlitePool) -> Result<Value, String> {
+        serde_json::to_value(all_rows::<T>(pool).await?).map_err(|e| e.to_string())
+    }
+    match table {
+        OemProductMapRow::TABLE => rows::<OemProductMapRow>(pool).await,
+        ProductOffsetRow::TABLE => rows::<ProductOffsetRow>(pool).await,
+        ProductSizeRow::TABLE => rows::<ProductSizeRow>(pool).await,
+        ProductBinSelectionRow::TABLE => rows::<ProductBinSelectionRow>(pool).await,
+        ProductBinMapRow::TABLE => rows::<ProductBinMapRow>(pool).await,
+        WaferStackStatsRow::TABLE => rows::<WaferStackStatsRow>(pool).await,
+        other => Err(format!("Table {} is not part of the configuration", other)),
+    }
+}
+
+fn is_json(path: &Path) -> bool {
+    path.extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("json"))
+}
+
+/// Writes the configuration tables to `path` (`.json`, otherwise zip).
+pub async fn export_config(
+    pool: &SqlitePool,
+    path: &Path,
+    options: &ExportOptions,
+) -> Result<BundleManifest, String> {
+    let mut bundle = Bundle {
+        manifest: BundleManifest {
+            format: BUNDLE_FORMAT.to_string(),
+            version: BUNDLE_VERSION,
+            schema_version: schema_status(pool).await?.current,
+            app_version: env!("CARGO_PKG_VERSION").to_string(),
+            created_at: chrono::Utc::now().to_rfc3339(),
+            tables: BTreeMap::new(),
+        },
+        tables: BTreeMap::new(),
+    };
+    for table in selected(&options.tables)? {
+        let rows = table_json(pool, table).await?;
+        let count = rows.as_array().map_or(0, |r| r.len());
+        bundle.manifest.tables.insert(table.to_string(), count);
+        bundle.tables.insert(table.to_string(), rows);
+    }
+
+    let write_err =
+        |e: &dyn std::fmt::Display| format!("Failed to write {}: {}", path.display(), e);
+    let file = File::create(path).map_err(|e| write_err(&e))?;
+    if is_json(path) {
+        serde_json::to_writer_pretty(file, &bundle).map_err(|e| write_err(&e))?;
+        return Ok(bundle.manifest);
+    }
+    let mut entries = vec![("manifest.json".to_string(), pretty(&bundle.manifest)?)];
+    for (table, rows) in &bundle.tables {
+        entries.push((format!("tables/{}.json", table), pretty(rows)?));
+    }
+    let mut zip = ZipWriter::new(file);
+    let opts = SimpleFileOptions::default().compression_method(CompressionMethod::Deflated);
+    for (name, bytes) in entries {
+        zip.start_file(name, opts).map_err(|e| write_err(&e))?;
+        zip.write_all(&bytes).map_err(|e| write_err(&e))?;
+    }
+    zip.finish().map_err(|e| write_err(&e))?;
+    Ok(bundle.manifest)
+}
+
+fn pretty<T: Serialize>(value: &T) -> Result<Vec<u8>, String> {
+    serde_json::to_vec_pretty(value).map_err(|e| e.to_string())
+}
+
+fn read_bundle(path: &Path) -> Result<Bundle, String> {
+    let read_err = |e: &dyn std::fmt::Display| format!("Failed to read {}: {}", path.display(), e);
+    let file = File::open(path).map_err(|e| read_err(&e))?;
+    let bundle = if is_json(path) {
+        serde_json::from_reader(file).map_err(|e| read_err(&e))?
+    } else {
+        let mut zip = ZipArchive::new(file).map_err(|e| read_err(&e))?;
+        let mut json = |name: &str| -> Result<Option<Value>, String> {
+            let mut entry = match zip.by_name(name) {
+                Ok(entry) => entry,
+                Err(zip::result::ZipError::FileNotFound) => return Ok(None),
+                Err(e) => return Err(read_err(&e)),
+            };
+            let mut text = String::new();
+            entry.read_to_string(&mut text).map_err(|e| read_err(&e))?;
+            serde_json::from_str(&text)
+                .map(Some)
+                .map_err(|e| read_err(&e))
+        };
+        let manifest = json("manifest.json")?
+            .ok_or_else(|| read_err(&"not a configuration bundle (no manifest.json)"))?;
+        let mut tables = BTreeMap::new();
+        for table in CONFIG_TABLES {
+            if let Some(rows) = json(&format!("tables/{}.json", table))? {
+                tables.insert(table.to_string(), rows);
+            }
+        }
+        Bundle {
+            manifest: serde_json::from_value(manifest).map_err(|e| read_err(&e))?,
+            tables,
+        }
+    };
+    if bundle.manifest.format != BUNDLE_FORMAT {
+        return Err(read_err(&format!(
+            "unknown bundle format {:?}",
+            bundle.manifest.format
+        )));
+    }
+    if bundle.manifest.version > BUNDLE_VERSION {
+        return Err(read_err(&format!(
+            "bundle version {} needs a newer version of the app (this one reads up to {})",
+            bundle.manifest.version, BUNDLE_VERSION
+        )));
+    }
+    Ok(bundle)
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ConflictPolicy {
+    /// Keep the local row
+    #[default]
+    KeepExisting,
+    /// Replace the local row with the bundle's
+    Overwrite,
+    /// Import nothing if any row conflicts
+    Abort,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct ImportOptions {
+    pub conflict: ConflictPolicy,
+    /// Subset of the bundle's tables; all when unset
+    pub tables: Option<Vec<String>>,
+    /// Report what would change without writing
+    pub dry_run: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ImportConflict {
+    /// Key columns of the row
+    pub key: Value,
+    pub existing: Value,
+    pub incoming: Value,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TableImport {
+    pub table: String,
+    pub incoming: usize,
+    pub added: usize,
+    pub unchanged: usize,
+    /// Conflicting rows replaced by the bundle's
+    pub overwritten: usize,
+    /// Conflicting rows left as they were
+    pub kept: usize,
+    pub conflicts: Vec<ImportConflict>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ImportReport {
+    pub manifest: BundleManifest,
+    pub tables: Vec<TableImport>,
+    /// False for a dry run or an aborted import
+    pub applied: bool,
+    pub warnings: Vec<String>,
+}
+
+/// Rows of one table split by what importing them does.
+struct Plan<T> {
+    report: TableImport,
+    write: Vec<T>,
+}
+
+fn key_of(row: &Value, key: &[&str]) -> Value {
+    key.iter()
+        .map(|col| {
+            (
+                col.to_string(),
+                row.get(*col).cloned().unwrap_or(Value::Null),
+            )
+        })
+        .collect::<serde_json::Map<_, _>>()
+        .into()
+}
+
+async fn plan<T>(
+    pool: &SqlitePool,
+    table: &str,
+    rows: Value,
+    policy: ConflictPolicy,
+) -> Result<Plan<T>, String>
+where
+    T: Table + Serialize + DeserializeOwned,
+{
+    let incoming: Vec<T> = serde_json::from_value(rows)
+        .map_err(|e| format!("Invalid {} rows in bundle: {}", table, e))?;
+    let as_json = |row: &T| serde_json::to_value(row).map_err(|e| e.to_string());
+    let mut existing = BTreeMap::new();
+    for row in all_rows::<T>(pool).await? {
+        let value = as_json(&row)?;
+        existing.insert(key_of(&value, T::KEY).to_string(), value);
+    }
+
+    let mut plan = Plan {
+        report: TableImport {
+            table: table.to_string(),
+            incoming: incoming.len(),
+            ..Default::default()
+        },
+        write: Vec::new(),
+    };
+    for row in incoming {
+        let value = as_json(&row)?;
+        let key = key_of(&value, T::KEY);
+        match existing.get(&key.to_string()) {
+            None => {
+                plan.report.added += 1;
+                plan.write.push(row);
+            }
+            Some(old) if *old == value => plan.report.unchanged += 1,
+            Some(old) => {
+                plan.report.conflicts.push(ImportConflict {
+                    key,
+                    existing: old.clone(),
+                    incoming: value,
+                });
+                if policy == ConflictPolicy::Overwrite {
+                    plan.report.overwritten += 1;
+                    plan.write.push(row);
+                } else {
+                    plan.report.kept += 1;
+                }
+            }
+        }
+    }
+    Ok(plan)
+}
+
+enum Planned {
+    OemProductMap(Plan<OemProductMapRow>),
+    ProductOffsets(Plan<ProductOffsetRow>),
+    ProductSize(Plan<ProductSizeRow>),
+    ProductBinSelection(Plan<ProductBinSelectionRow>),
+    ProductBinMap(Plan<ProductBinMapRow>),
+    WaferStackStats(Plan<WaferStackStatsRow>),
+}
+
+impl Planned {
+    fn report(&self) -> &TableImport {
+        match self {
+            Planned::OemProductMap(p) => &p.report,
+            Planned::ProductOffsets(p) => &p.report,
+            Planned::ProductSize(p) => &p.report,
+            Planned::ProductBinSelection(p) => &p.report,
+            Planned::ProductBinMap(p) => &p.report,
+            Planned::WaferStackStats(p) => &p.report,
+        }
+    }
+}
+
+/// Merges a bundle from `path` into the database in one transaction.
+pub async fn import_config(
+    pool: &SqlitePool,
+    path: &Path,
+    options: &ImportOptions,
+) -> Result<ImportReport, String> {
+    let mut bundle = read_bundle(path)?;
+    let mut warnings = Vec::new();
+    if bundle.manifest.schema_version > latest_version() {
+        warnings.push(format!(
+            "Exported from schema v{} (this app has v{}); columns it does not know are dropped",
+            bundle.manifest.schema_version,
+            latest_version()
+        ));
+    }
+
+    let mut planned = Vec::new();
+    for table in selected(&options.tables)? {
+        let Some(rows) = bundle.tables.remove(table) else {
+            if options.tables.is_some() {
+                warnings.push(format!("Bundle has no {} table", table));
+            }
+            continue;
+        };
+        let policy = options.conflict;
+        planned.push(match table {
+            OemProductMapRow::TABLE => {
+                Planned::OemProductMap(plan(pool, table, rows, policy).await?)
+            }
+            ProductOffsetRow::TABLE => {
+                Planned::ProductOffsets(plan(pool, table, rows, policy).await?)
+            }
+            ProductSizeRow::TABLE => Planned::ProductSize(plan(pool, table, rows, policy).await?),
+            ProductBinSelectionRow::TABLE => {
+                Planned::ProductBinSelection(plan(pool, table, rows, policy).await?)
+            }
+            ProductBinMapRow::TABLE => {
+                Planned::ProductBinMap(plan(pool, table, rows, policy).await?)
+            }
+            _ => Planned::WaferStackStats(plan(pool, table, rows, policy).await?),
+        });
+    }
+
+    let conflicts = planned.iter().any(|p| !p.report().conflicts.is_empty());
+    let apply = !(options.dry_run || conflicts && options.conflict == ConflictPolicy::Abort);
+    if apply {
+        let mut tx = pool
+            .begin()
+            .await
+            .map_err(|e| format!("Failed to open transaction: {}", e))?;
+        for p in &planned {
+            match p {
+                Planned::OemProductMap(p) => upsert_rows(&mut tx, &p.write).await?,
+                Planned::ProductOffsets(p) => upsert_rows(&mut tx, &p.write).await?,
+                Planned::ProductSize(p) => upsert_rows(&mut tx, &p.write).await?,
+                Planned::ProductBinSelection(p) => upsert_rows(&mut tx, &p.write).await?,
+                Planned::ProductBinMap(p) => upsert_rows(&mut tx, &p.write).await?,
+                Planned::WaferStackStats(p) => upsert_rows(&mut tx, &p.write).await?,
+            };
+        }
+        tx.commit()
+            .await
+            .map_err(|e| format!("Failed to commit: {}", e))?;
+    }
+
+    Ok(ImportReport {
+        manifest: bundle.manifest,
+        tables: planned.iter().map(|p| p.report().clone()).collect(),
+        applied: apply,
+        warnings,
+    })
+}
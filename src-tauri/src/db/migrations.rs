@@ -0,0 +1,280 @@
+use std::future::Future;
+use std::pin::Pin;
+
+use serde::Serialize;
+use sqlx::error::BoxDynError;
+use sqlx::migrate::{Migration as SqlxMigration, MigrationSource, MigrationType, Migrator};
+use sqlx::{Row, SqlitePool};
+use tauri_plugin_sql::{Migration, MigrationKind};
+
+// =============================================================================
+// Schema migrations
+//
+// One entry per file in sql/ (see sql/README.md). tauri-plugin-sql applies the
+// up scripts on startup and records them in `_sqlx_migrations` with a
+// checksum, so an applied script must never change. The same chain is run
+// here for the down scripts (the plugin ignores those), for the schema check
+// and for the migration tests.
+// =============================================================================
+
+pub struct SchemaMigration {
+    pub version: i64,
+    pub description: &'static str,
+    pub up: &'static str,
+    pub down: &'static str,
+}
+
+pub const MIGRATIONS: &[SchemaMigration] = &[
+    // init.sql starts wafer_stack_stats with `DROP TABLE IF EXISTS`. v1 only
+    // runs on an empty database, and editing it would break the checksum
+    // recorded by every installed database, so it stays.
+    SchemaMigration {
+        version: 1,
+        description: "Create initial tables",
+        up: include_str!("../../../sql/init.sql"),
+        down: include_str!("../../../sql/down/init.sql"),
+    },
+    SchemaMigration {
+        version: 2,
+        description: "Add stage waterfall to wafer stack stats",
+        up: include_str!("../../../sql/v2_stage_waterfall.sql"),
+        down: include_str!("../../../sql/down/v2_stage_waterfall.sql"),
+    },
+    SchemaMigration {
+        version: 3,
+        description: "Keep superseded wafer stack stats",
+        up: include_str!("../../../sql/v3_stack_stats_history.sql"),
+        down: include_str!("../../../sql/down/v3_stack_stats_history.sql"),
+    },
+    SchemaMigration {
+        version: 4,
+        description: "Add wafer map metadata and full-text search",
+        up: include_str!("../../../sql/v4_wafer_search.sql"),
+        down: include_str!("../../../sql/down/v4_wafer_search.sql"),
+    },
+    SchemaMigration {
+        version: 5,
+        description: "Add persistent stacking job queue",
+        up: include_str!("../../../sql/v5_stacking_jobs.sql"),
+        down: include_str!("../../../sql/down/v5_stacking_jobs.sql"),
+    },
+    SchemaMigration {
+        version: 6,
+        description: "Add bin translation table per product",
+        up: include_str!("../../../sql/v6_product_bin_map.sql"),
+        down: include_str!("../../../sql/down/v6_product_bin_map.sql"),
+    },
+    SchemaMigration {
+        version: 7,
+        description: "Add chosen wafer map per stage",
+        up: include_str!("../../../sql/v7_wafer_map_choice.sql"),
+        down: include_str!("../../../sql/down/v7_wafer_map_choice.sql"),
+    },
+];
+
+pub fn latest_version() -> i64 {
+    MIGRATIONS.last().map_or(0, |m| m.version)
+}
+
+/// The chain for `tauri_plugin_sql::Builder::add_migrations`.
+pub fn plugin_migrations() -> Vec<Migration> {
+    MIGRATIONS
+        .iter()
+        .flat_map(|m| {
+            [
+                Migration {
+                    version: m.version,
+                    description: m.description,
+                    sql: m.up,
+                    kind: MigrationKind::Up,
+                },
+                Migration {
+                    version: m.version,
+                    description: m.description,
+                    sql: m.down,
+                    kind: MigrationKind::Down,
+                },
+            ]
+        })
+        .collect()
+}
+
+/// Migrations up to and including `target`, as sqlx sees them. The up
+/// checksums match the ones the plugin records.
+#[derive(Debug)]
+struct Chain {
+    target: i64,
+}
+
+impl MigrationSource<'static> for Chain {
+    fn resolve(
+        self,
+    ) -> Pin<Box<dyn Future<Output = Result<Vec<SqlxMigration>, BoxDynError>> + Send>> {
+        let migrations = MIGRATIONS
+            .iter()
+            .filter(|m| m.version <= self.target)
+            .flat_map(|m| {
+                [
+                    (MigrationType::ReversibleUp, m.up),
+                    (MigrationType::ReversibleDown, m.down),
+                ]
+                .map(|(kind, sql)| {
+                    SqlxMigration::new(m.version, m.description.into(), kind, sql.into(), false)
+                })
+            })
+            .collect();
+        Box::pin(async move { Ok(migrations) })
+    }
+}
+
+async fn migrator(target: i64) -> Result<Migrator, String> {
+    Migrator::new(Chain { target })
+        .await
+        .map_err(|e| format!("Invalid migration chain: {}", e))
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AppliedMigration {
+    pub version: i64,
+    pub description: String,
+    pub installed_on: String,
+}
+
+/// What `_sqlx_migrations` says about the database versus this build.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SchemaStatus {
+    /// Highest applied version, 0 for an empty database
+    pub current: i64,
+    pub latest: i64,
+    pub applied: Vec<AppliedMigration>,
+    pub pending: Vec<i64>,
+    /// Applied by a newer build; this one cannot open the database safely
+    pub unknown: Vec<i64>,
+    /// Applied with a script that differs from this build's
+    pub modified: Vec<i64>,
+    /// Started but not finished (`success = 0`)
+    pub failed: Vec<i64>,
+}
+
+impl SchemaStatus {
+    pub fn compatible(&self) -> bool {
+        self.unknown.is_empty() && self.modified.is_empty() && self.failed.is_empty()
+    }
+
+    pub fn check(&self) -> Result<(), String> {
+        if self.compatible() {
+            return Ok(());
+        }
+        let list = |v: &[i64]| {
+            v.iter()
+                .map(|n| format!("v{}", n))
+                .collect::<Vec<_>>()
+                .join(", ")
+        };
+        let mut problems = Vec::new();
+        if !self.unknown.is_empty() {
+            problems.push(format!(
+                "applied by a newer version of the app: {} (this build knows up to v{})",
+                list(&self.unknown),
+                self.latest
+            ));
+        }
+        if !self.modified.is_empty() {
+            problems.push(format!(
+                "changed after being applied: {}",
+                list(&self.modified)
+            ));
+        }
+        if !self.failed.is_empty() {
+            problems.push(format!("left half-applied: {}", list(&self.failed)));
+        }
+        Err(format!("Database schema mismatch, {}", problems.join("; ")))
+    }
+}
+
+pub async fn schema_status(pool: &SqlitePool) -> Result<SchemaStatus, String> {
+    let read_err = |e: sqlx::Error| format!("Failed to read _sqlx_migrations: {}", e);
+    let tracked: bool = sqlx::query_scalar(
+        "SELECT EXISTS (SELECT 1 FROM sqlite_master \
+         WHERE type = 'table' AND name = '_sqlx_migrations')",
+    )
+    .fetch_one(pool)
+    .await
+    .map_err(read_err)?;
+    let rows = if tracked {
+        sqlx::query(
+            "SELECT version, description, CAST(installed_on AS TEXT) AS installed_on, \
+             success, checksum FROM _sqlx_migrations ORDER BY version",
+        )
+        .fetch_all(pool)
+        .await
+        .map_err(read_err)?
+    } else {
+        Vec::new()
+    };
+
+    let known = migrator(latest_version()).await?;
+    let mut status = SchemaStatus {
+        current: 0,
+        latest: latest_version(),
+        applied: Vec::new(),
+        pending: Vec::new(),
+        unknown: Vec::new(),
+        modified: Vec::new(),
+        failed: Vec::new(),
+    };
+    for row in &rows {
+        let version: i64 = row.try_get("version").map_err(read_err)?;
+        let success: bool = row.try_get("success").map_err(read_err)?;
+        let checksum: Vec<u8> = row.try_get("checksum").map_err(read_err)?;
+        status.current = status.current.max(version);
+        if !success {
+            status.failed.push(version);
+        }
+        match known
+            .iter()
+            .find(|m| m.version == version && m.migration_type.is_up_migration())
+        {
+            Some(m) if *m.checksum != checksum[..] => status.modified.push(version),
+            Some(_) => {}
+            None => status.unknown.push(version),
+        }
+        status.applied.push(AppliedMigration {
+            version,
+            description: row.try_get("description").map_err(read_err)?,
+            installed_on: row.try_get("installed_on").map_err(read_err)?,
+        });
+    }
+    status.pending = MIGRATIONS
+        .iter()
+        .map(|m| m.version)
+        .filter(|v| !status.applied.iter().any(|a| a.version == *v))
+        .collect();
+    Ok(status)
+}
+
+/// Applies the pending up scripts through `target`.
+pub async fn migrate_up(pool: &SqlitePool, target: i64) -> Result<SchemaStatus, String> {
+    schema_status(pool).await?.check()?;
+    migrator(target)
+        .await?
+        .run(pool)
+        .await
+        .map_err(|e| format!("Migration failed: {}", e))?;
+    schema_status(pool).await
+}
+
+/// Runs the down scripts of every applied version above `target`, newest
+/// first. Used to roll back before installing an older build; this build
+/// re-applies them on its next start.
+pub async fn migrate_down(pool: &SqlitePool, target: i64) -> Result<SchemaStatus, String> {
+    schema_status(pool).await?.check()?;
+    migrator(latest_version())
+        .await?
+        .undo(pool, target)
+        .await
+        .map_err(|e| format!("Revert failed: {}", e))?;
+    schema_status(pool).await
+}
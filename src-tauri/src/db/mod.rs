@@ -0,0 +1,45 @@
+mod tests;
+
+pub mod backup;
+pub mod migrations;
+pub mod repo;
+pub mod search;
+pub mod tables;
+
+use sqlx::SqlitePool;
+use tauri_plugin_sql::{DbInstances, DbPool};
+
+/// Pool of the app database preloaded by tauri-plugin-sql, shared with the
+/// frontend's `getDb()` connection.
+pub async fn app_pool(db: &DbInstances) -> Result<SqlitePool, String> {
+    match db.0.read().await.get(crate::DB_URL) {
+        Some(DbPool::Sqlite(pool)) => Ok(pool.clone()),
+        None => Err(format!("Database {} is not loaded", crate::DB_URL)),
+    }
+}
+
+/// Pool of an app database file opened outside the app (`aoi-stack-cli`).
+/// The file must exist and be on this build's schema: the app applies the
+/// migrations, so pending ones mean it has not been opened with this version.
+pub async fn file_pool(path: &std::path::Path) -> Result<SqlitePool, String> {
+    let options = sqlx::sqlite::SqliteConnectOptions::new()
+        .filename(path)
+        .create_if_missing(false)
+        .busy_timeout(std::time::Duration::from_secs(30));
+    let pool = sqlx::sqlite::SqlitePoolOptions::new()
+        .max_connections(4)
+        .connect_with(options)
+        .await
+        .map_err(|e| format!("Failed to open database {}: {}", path.display(), e))?;
+    let status = migrations::schema_status(&pool).await?;
+    status.check()?;
+    if !status.pending.is_empty() {
+        return Err(format!(
+            "Database {} is at schema v{}, this build needs v{}; open it with the app first",
+            path.display(),
+            status.current,
+            status.latest
+        ));
+    }
+    Ok(pool)
+}
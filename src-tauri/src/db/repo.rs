@@ -0,0 +1,288 @@
+use serde::{Deserialize, Serialize};
+use sqlx::{Row, Sqlite, SqlitePool, Transaction};
+
+use super::tables::*;
+use crate::file::duplicates::WaferMapFile;
+
+// =============================================================================
+// Repository
+//
+// Queries over the app database for Rust callers (the stacking engine, the
+// watcher, the index) and the `rust_db_*` commands. Bulk writes run in one
+// transaction; lists of keys are bound as one JSON array (`json_each`) so
+// they are not limited by SQLite's parameter count.
+// =============================================================================
+
+fn read_err(table: &'static str) -> impl Fn(sqlx::Error) -> String {
+    move |e| format!("Failed to read {}: {}", table, e)
+}
+
+async fn fetch<T: Table>(query: SqliteQuery<'_>, pool: &SqlitePool) -> Result<Vec<T>, String> {
+    let rows = query.fetch_all(pool).await.map_err(read_err(T::TABLE))?;
+    rows.iter()
+        .map(T::from_row)
+        .collect::<Result<_, _>>()
+        .map_err(|e| format!("Invalid {} row: {}", T::TABLE, e))
+}
+
+fn json_keys(keys: &[String]) -> String {
+    serde_json::Value::from(keys.to_vec()).to_string()
+}
+
+/// Upserts `rows` inside an open transaction; returns the rows written.
+pub async fn upsert_rows<T: Table>(
+    tx: &mut Transaction<'_, Sqlite>,
+    rows: &[T],
+) -> Result<u64, String> {
+    let mut written = 0;
+    for row in rows {
+        written += row
+            .bind(sqlx::query(T::UPSERT))
+            .execute(&mut **tx)
+            .await
+            .map_err(|e| format!("Upsert failed: {}", e))?
+            .rows_affected();
+    }
+    Ok(written)
+}
+
+/// Upserts `rows` all-or-nothing.
+pub async fn upsert_many<T: Table>(pool: &SqlitePool, rows: &[T]) -> Result<u64, String> {
+    let mut tx = pool
+        .begin()
+        .await
+        .map_err(|e| format!("Failed to open transaction: {}", e))?;
+    let written = upsert_rows(&mut tx, rows).await?;
+    tx.commit()
+        .await
+        .map_err(|e| format!("Failed to commit: {}", e))?;
+    Ok(written)
+}
+
+pub async fn upsert_table(pool: &SqlitePool, rows: &TableRows) -> Result<u64, String> {
+    match rows {
+        TableRows::OemProductMap(r) => upsert_many(pool, r).await,
+        TableRows::ProductOffsets(r) => upsert_many(pool, r).await,
+        TableRows::ProductSize(r) => upsert_many(pool, r).await,
+        TableRows::ProductBinSelection(r) => upsert_many(pool, r).await,
+        TableRows::ProductBinMap(r) => upsert_many(pool, r).await,
+        TableRows::ProductDefectMap(r) => upsert_many(pool, r).await,
+        TableRows::SubstrateDefect(r) => upsert_many(pool, r).await,
+        TableRows::WaferMaps(r) => upsert_many(pool, r).await,
+        TableRows::WaferMapMeta(r) => upsert_many(pool, r).await,
+        TableRows::FileIndex(r) => upsert_many(pool, r).await,
+        TableRows::FolderIndex(r) => upsert_many(pool, r).await,
+        TableRows::WaferStackStats(r) => upsert_many(pool, r).await,
+    }
+}
+
+/// Every row of `T`'s table, in key order.
+pub async fn all_rows<T: Table>(pool: &SqlitePool) -> Result<Vec<T>, String> {
+    let sql = format!("SELECT * FROM {} ORDER BY {}", T::TABLE, T::KEY.join(", "));
+    fetch(sqlx::query(&sql), pool).await
+}
+
+/// Everything stored for one OEM product that stacking needs.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProductContext {
+    pub oem_product_id: String,
+    pub mapping: Option<OemProductMapRow>,
+    pub offset: Option<ProductOffsetRow>,
+    pub size: Option<ProductSizeRow>,
+    pub bin_selection: Option<ProductBinSelectionRow>,
+    pub bin_map: Option<ProductBinMapRow>,
+}
+
+pub async fn product_context(
+    pool: &SqlitePool,
+    oem_product_id: &str,
+) -> Result<ProductContext, String> {
+    async fn one<T: Table>(
+        pool: &SqlitePool,
+        sql: &'static str,
+        key: &str,
+    ) -> Result<Option<T>, String> {
+        Ok(fetch(sqlx::query(sql).bind(key), pool)
+            .await?
+            .into_iter()
+            .next())
+    }
+    Ok(ProductContext {
+        oem_product_id: oem_product_id.to_string(),
+        mapping: one(
+            pool,
+            "SELECT * FROM oem_product_map WHERE oem_product_id = ?",
+            oem_product_id,
+        )
+        .await?,
+        offset: one(
+            pool,
+            "SELECT * FROM product_offsets WHERE oem_product_id = ?",
+            oem_product_id,
+        )
+        .await?,
+        size: one(
+            pool,
+            "SELECT * FROM product_size WHERE oem_product_id = ?",
+            oem_product_id,
+        )
+        .await?,
+        bin_selection: one(
+            pool,
+            "SELECT * FROM product_bin_selection WHERE oem_product_id = ?",
+            oem_product_id,
+        )
+        .await?,
+        bin_map: one(
+            pool,
+            "SELECT * FROM product_bin_map WHERE oem_product_id = ?",
+            oem_product_id,
+        )
+        .await?,
+    })
+}
+
+/// `wafer_maps` filter; unset fields match everything.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct WaferMapFilter {
+    pub product_id: Option<String>,
+    pub batch_id: Option<String>,
+    pub wafer_id: Option<i64>,
+    pub stage: Option<String>,
+}
+
+pub async fn wafer_maps(
+    pool: &SqlitePool,
+    filter: &WaferMapFilter,
+) -> Result<Vec<WaferMapRow>, String> {
+    let query = sqlx::query(
+        "SELECT * FROM wafer_maps \
+         WHERE (?1 IS NULL OR product_id = ?1) AND (?2 IS NULL OR batch_id = ?2) \
+         AND (?3 IS NULL OR wafer_id = ?3) AND (?4 IS NULL OR stage = ?4) \
+         ORDER BY product_id, batch_id, wafer_id, stage, retest_count, COALESCE(time, 0), idx",
+    )
+    .bind(&filter.product_id)
+    .bind(&filter.batch_id)
+    .bind(filter.wafer_id)
+    .bind(&filter.stage);
+    fetch(query, pool).await
+}
+
+/// Wafer maps with their `file_index` content hash.
+pub async fn wafer_map_files(
+    pool: &SqlitePool,
+    product_id: Option<&str>,
+) -> Result<Vec<WaferMapFile>, String> {
+    let rows = sqlx::query(
+        "SELECT w.*, f.file_hash FROM wafer_maps w \
+         LEFT JOIN file_index f ON f.file_path = w.file_path \
+         WHERE ?1 IS NULL OR w.product_id = ?1",
+    )
+    .bind(product_id)
+    .fetch_all(pool)
+    .await
+    .map_err(read_err("wafer_maps"))?;
+    rows.iter()
+        .map(|row| {
+            let map = WaferMapRow::from_row(row)?;
+            Ok(WaferMapFile {
+                idx: map.idx.unwrap_or(0),
+                product_id: map.product_id,
+                batch_id: map.batch_id,
+                wafer_id: map.wafer_id,
+                stage: map.stage,
+                sub_stage: map.sub_stage,
+                retest_count: map.retest_count,
+                time: map.time,
+                file_path: map.file_path,
+                file_hash: row.try_get("file_hash")?,
+            })
+        })
+        .collect::<Result<_, sqlx::Error>>()
+        .map_err(|e| format!("Invalid wafer_maps row: {}", e))
+}
+
+/// Stacking choices, optionally of one product.
+pub async fn wafer_map_choices(
+    pool: &SqlitePool,
+    product_id: Option<&str>,
+) -> Result<Vec<WaferMapChoiceRow>, String> {
+    let query = sqlx::query("SELECT * FROM wafer_map_choice WHERE ?1 IS NULL OR product_id = ?1")
+        .bind(product_id);
+    fetch(query, pool).await
+}
+
+pub async fn product_defect_maps(
+    pool: &SqlitePool,
+    oem_product_id: &str,
+    lot_id: Option<&str>,
+) -> Result<Vec<ProductDefectMapRow>, String> {
+    let query = sqlx::query(
+        "SELECT * FROM product_defect_map \
+         WHERE oem_product_id = ?1 AND (?2 IS NULL OR lot_id = ?2) \
+         ORDER BY lot_id, wafer_id",
+    )
+    .bind(oem_product_id)
+    .bind(lot_id);
+    fetch(query, pool).await
+}
+
+pub async fn substrate_defects(
+    pool: &SqlitePool,
+    sub_ids: &[String],
+) -> Result<Vec<SubstrateDefectRow>, String> {
+    let query = sqlx::query(
+        "SELECT * FROM substrate_defect \
+         WHERE sub_id IN (SELECT value FROM json_each(?)) ORDER BY sub_id",
+    )
+    .bind(json_keys(sub_ids));
+    fetch(query, pool).await
+}
+
+pub async fn file_index(pool: &SqlitePool, paths: &[String]) -> Result<Vec<FileIndexRow>, String> {
+    let query = sqlx::query(
+        "SELECT * FROM file_index \
+         WHERE file_path IN (SELECT value FROM json_each(?)) ORDER BY file_path",
+    )
+    .bind(json_keys(paths));
+    fetch(query, pool).await
+}
+
+pub async fn stack_stats(
+    pool: &SqlitePool,
+    oem_product_id: &str,
+) -> Result<Vec<WaferStackStatsRow>, String> {
+    let query = sqlx::query(
+        "SELECT * FROM wafer_stack_stats WHERE oem_product_id = ? ORDER BY batch_id, wafer_id",
+    )
+    .bind(oem_product_id);
+    fetch(query, pool).await
+}
+
+/// Current stats plus, for wafers no longer in `wafer_stack_stats` (the
+/// report export clears it), their last archived result from
+/// `wafer_stack_stats_history`.
+pub async fn stack_stats_with_history(
+    pool: &SqlitePool,
+    oem_product_id: &str,
+) -> Result<Vec<WaferStackStatsRow>, String> {
+    const COLUMNS: &str = "oem_product_id, batch_id, wafer_id, total_tested, total_pass, \
+         total_fail, yield_percentage, bin_counts, start_time, stop_time, stage_waterfall";
+    let sql = format!(
+        "SELECT {columns} FROM wafer_stack_stats WHERE oem_product_id = ?1 \
+         UNION ALL \
+         SELECT {columns} FROM wafer_stack_stats_history h \
+         WHERE h.oem_product_id = ?1 \
+           AND h.id = (SELECT MAX(id) FROM wafer_stack_stats_history l \
+                       WHERE l.oem_product_id = h.oem_product_id \
+                         AND l.batch_id = h.batch_id AND l.wafer_id = h.wafer_id) \
+           AND NOT EXISTS (SELECT 1 FROM wafer_stack_stats s \
+                           WHERE s.oem_product_id = h.oem_product_id \
+                             AND s.batch_id = h.batch_id AND s.wafer_id = h.wafer_id) \
+         ORDER BY batch_id, wafer_id",
+        columns = COLUMNS
+    );
+    fetch(sqlx::query(&sql).bind(oem_product_id), pool).await
+}
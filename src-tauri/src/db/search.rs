@@ -0,0 +1,293 @@
+use std::time::Instant;
+
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+use sqlx::{QueryBuilder, Row, Sqlite, SqlitePool};
+
+use super::repo::upsert_many;
+use super::tables::{Table, WaferMapMetaRow, WaferMapRow};
+use crate::file::meta::read_map_meta;
+use crate::file::scanner::DataSourceType;
+
+// =============================================================================
+// Wafer search
+//
+// `wafer_maps` joined with the parsed headers in `wafer_map_meta` (v4).
+// Free text goes through the `wafer_search` FTS5 index; everything else is a
+// plain column filter. Results are paginated.
+// =============================================================================
+
+pub const DEFAULT_PAGE_SIZE: u32 = 50;
+pub const MAX_PAGE_SIZE: u32 = 500;
+
+#[derive(Debug, Clone, Copy, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum SearchSort {
+    /// Header time, else the time in the file name
+    #[default]
+    Time,
+    Yield,
+    Product,
+    Path,
+}
+
+/// Unset fields match everything; all set fields must match.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct WaferSearchQuery {
+    /// Words matched as prefixes against product, lot, stage, device,
+    /// operator, bins and path
+    pub text: Option<String>,
+    /// Internal or OEM product id
+    pub product_id: Option<String>,
+    pub batch_id: Option<String>,
+    pub wafer_id: Option<i64>,
+    pub stage: Option<String>,
+    pub sub_stage: Option<String>,
+    pub retest_count: Option<i64>,
+    /// Case-insensitive substring
+    pub device: Option<String>,
+    pub operator: Option<String>,
+    /// Inclusive, in percent
+    pub min_yield: Option<f64>,
+    pub max_yield: Option<f64>,
+    /// Epoch ms, `from` inclusive and `to` exclusive
+    pub from: Option<i64>,
+    pub to: Option<i64>,
+    /// Every listed bin must be present
+    pub bins: Vec<String>,
+    pub sort: SearchSort,
+    pub ascending: bool,
+    /// 0-based
+    pub page: u32,
+    pub page_size: Option<u32>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WaferSearchHit {
+    pub map: WaferMapRow,
+    /// `None` until the map's header has been parsed
+    pub meta: Option<WaferMapMetaRow>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WaferSearchPage {
+    pub total: i64,
+    pub page: u32,
+    pub page_size: u32,
+    pub hits: Vec<WaferSearchHit>,
+}
+
+/// Each word as a quoted prefix term, so user input is never FTS syntax.
+fn fts_query(text: &str) -> Option<String> {
+    let terms: Vec<String> = text
+        .split_whitespace()
+        .map(|word| format!("\"{}\"*", word.replace('"', "\"\"")))
+        .collect();
+    (!terms.is_empty()).then(|| terms.join(" "))
+}
+
+fn push_filters(qb: &mut QueryBuilder<'_, Sqlite>, query: &WaferSearchQuery) {
+    qb.push(
+        " FROM wafer_maps w LEFT JOIN wafer_map_meta m ON m.file_path = w.file_path WHERE 1 = 1",
+    );
+    if let Some(text) = query.text.as_deref().and_then(fts_query) {
+        qb.push(" AND w.idx IN (SELECT rowid FROM wafer_search WHERE wafer_search MATCH ")
+            .push_bind(text)
+            .push(")");
+    }
+    if let Some(product) = &query.product_id {
+        qb.push(" AND (w.product_id = ")
+            .push_bind(product.clone())
+            .push(" OR w.product_id IN (SELECT product_id FROM oem_product_map WHERE oem_product_id = ")
+            .push_bind(product.clone())
+            .push("))");
+    }
+    let mut eq = |column: &str, value: Option<String>| {
+        if let Some(value) = value {
+            qb.push(format!(" AND {} = ", column)).push_bind(value);
+        }
+    };
+    eq("w.batch_id", query.batch_id.clone());
+    eq("w.stage", query.stage.clone());
+    eq("w.sub_stage", query.sub_stage.clone());
+    if let Some(wafer) = query.wafer_id {
+        qb.push(" AND w.wafer_id = ").push_bind(wafer);
+    }
+    if let Some(retest) = query.retest_count {
+        qb.push(" AND w.retest_count = ").push_bind(retest);
+    }
+    for (column, value) in [("m.device", &query.device), ("m.operator", &query.operator)] {
+        if let Some(value) = value {
+            qb.push(format!(" AND {} LIKE ", column))
+                .push_bind(format!("%{}%", value.trim()));
+        }
+    }
+    if let Some(min) = query.min_yield {
+        qb.push(" AND m.yield_percentage >= ").push_bind(min);
+    }
+    if let Some(max) = query.max_yield {
+        qb.push(" AND m.yield_percentage <= ").push_bind(max);
+    }
+    if let Some(from) = query.from {
+        qb.push(" AND COALESCE(m.meas_time, w.time) >= ")
+            .push_bind(from);
+    }
+    if let Some(to) = query.to {
+        qb.push(" AND COALESCE(m.meas_time, w.time) < ")
+            .push_bind(to);
+    }
+    for bin in query
+        .bins
+        .iter()
+        .map(|b| b.trim())
+        .filter(|b| !b.is_empty())
+    {
+        qb.push(" AND instr(' ' || m.bins || ' ', ")
+            .push_bind(format!(" {} ", bin))
+            .push(") > 0");
+    }
+}
+
+pub async fn search_wafers(
+    pool: &SqlitePool,
+    query: &WaferSearchQuery,
+) -> Result<WaferSearchPage, String> {
+    let db_err = |e: sqlx::Error| format!("Wafer search failed: {}", e);
+    let page_size = query
+        .page_size
+        .unwrap_or(DEFAULT_PAGE_SIZE)
+        .clamp(1, MAX_PAGE_SIZE);
+
+    let mut count = QueryBuilder::new("SELECT COUNT(*)");
+    push_filters(&mut count, query);
+    let total: i64 = count
+        .build_query_scalar()
+        .fetch_one(pool)
+        .await
+        .map_err(db_err)?;
+
+    let mut select = QueryBuilder::new(
+        "SELECT w.*, m.file_path IS NOT NULL AS has_meta, m.device, m.operator, m.meas_time, \
+         m.total_tested, m.total_pass, m.yield_percentage, m.bins",
+    );
+    push_filters(&mut select, query);
+    let order = match query.sort {
+        SearchSort::Time => "COALESCE(m.meas_time, w.time)",
+        SearchSort::Yield => "m.yield_percentage",
+        SearchSort::Product => "w.product_id, w.batch_id, w.wafer_id",
+        SearchSort::Path => "w.file_path",
+    };
+    let dir = if query.ascending { "ASC" } else { "DESC" };
+    select
+        .push(format!(" ORDER BY {order} {dir}, w.idx {dir} LIMIT "))
+        .push_bind(page_size as i64)
+        .push(" OFFSET ")
+        .push_bind(query.page as i64 * page_size as i64);
+    let rows = select.build().fetch_all(pool).await.map_err(db_err)?;
+
+    let hits = rows
+        .iter()
+        .map(|row| {
+            let has_meta: bool = row.try_get("has_meta")?;
+            Ok(WaferSearchHit {
+                map: WaferMapRow::from_row(row)?,
+                meta: has_meta
+                    .then(|| WaferMapMetaRow::from_row(row))
+                    .transpose()?,
+            })
+        })
+        .collect::<Result<_, sqlx::Error>>()
+        .map_err(|e| format!("Invalid search row: {}", e))?;
+    Ok(WaferSearchPage {
+        total,
+        page: query.page,
+        page_size,
+        hits,
+    })
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct ReindexOptions {
+    pub product_id: Option<String>,
+    /// Reparse maps that already have a header
+    pub force: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReindexFailure {
+    pub file_path: String,
+    pub error: String,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReindexReport {
+    pub parsed: u32,
+    /// Maps of a stage without a header (substrate)
+    pub skipped: u32,
+    pub failed: Vec<ReindexFailure>,
+    pub elapsed_ms: u64,
+}
+
+/// Parses the headers of indexed maps that were ingested without one
+/// (the frontend's ingest writes `wafer_maps` only).
+pub async fn reindex_meta(
+    pool: &SqlitePool,
+    options: &ReindexOptions,
+) -> Result<ReindexReport, String> {
+    let start = Instant::now();
+    let rows = sqlx::query(
+        "SELECT w.* FROM wafer_maps w \
+         WHERE (?1 IS NULL OR w.product_id = ?1) \
+         AND (?2 OR NOT EXISTS (SELECT 1 FROM wafer_map_meta m WHERE m.file_path = w.file_path))",
+    )
+    .bind(&options.product_id)
+    .bind(options.force)
+    .fetch_all(pool)
+    .await
+    .map_err(|e| format!("Failed to read wafer_maps: {}", e))?;
+    let maps = rows
+        .iter()
+        .map(WaferMapRow::from_row)
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| format!("Invalid wafer_maps row: {}", e))?;
+
+    let parsed = tauri::async_runtime::spawn_blocking(move || {
+        maps.par_iter()
+            .map(|map| {
+                let Some(stage) = DataSourceType::ALL
+                    .into_iter()
+                    .find(|s| s.as_str() == map.stage)
+                else {
+                    return (map.file_path.clone(), Ok(None));
+                };
+                let sub_stage = map.sub_stage.as_deref().and_then(|s| s.parse().ok());
+                (
+                    map.file_path.clone(),
+                    read_map_meta(&map.file_path, stage, sub_stage),
+                )
+            })
+            .collect::<Vec<_>>()
+    })
+    .await
+    .map_err(|e| format!("Reindex task failed: {}", e))?;
+
+    let mut report = ReindexReport::default();
+    let mut metas = Vec::new();
+    for (file_path, result) in parsed {
+        match result {
+            Ok(Some(meta)) => metas.push(meta),
+            Ok(None) => report.skipped += 1,
+            Err(error) => report.failed.push(ReindexFailure { file_path, error }),
+        }
+    }
+    report.parsed = metas.len() as u32;
+    upsert_many(pool, &metas).await?;
+    report.elapsed_ms = start.elapsed().as_millis() as u64;
+    Ok(report)
+}
@@ -0,0 +1,544 @@
+use serde::{Deserialize, Serialize};
+use sqlx::query::Query;
+use sqlx::sqlite::{SqliteArguments, SqliteRow};
+use sqlx::{Row, Sqlite};
+
+use crate::wafer::stats::WaferStackStats;
+
+// =============================================================================
+// Typed rows of the tables in `sql/init.sql`
+//
+// Field names are the column names (snake_case), the same shapes as
+// `src/db/types.ts`. Each row knows its upsert statement, how to bind itself
+// to it and how to read itself back. `auth` stays with the login flow.
+// =============================================================================
+
+pub type SqliteQuery<'q> = Query<'q, Sqlite, SqliteArguments<'q>>;
+
+pub trait Table: Sized {
+    const TABLE: &'static str;
+    /// Columns of the upsert's conflict target
+    const KEY: &'static [&'static str];
+    /// Single-row `INSERT ... ON CONFLICT DO UPDATE`
+    const UPSERT: &'static str;
+    fn bind<'q>(&'q self, query: SqliteQuery<'q>) -> SqliteQuery<'q>;
+    fn from_row(row: &SqliteRow) -> Result<Self, sqlx::Error>;
+}
+
+/// Integer column that older rows may hold as REAL (or NaN, read as NULL).
+fn opt_i64(row: &SqliteRow, col: &str) -> Result<Option<i64>, sqlx::Error> {
+    match row.try_get::<Option<i64>, _>(col) {
+        Ok(v) => Ok(v),
+        Err(_) => Ok(row
+            .try_get::<Option<f64>, _>(col)?
+            .filter(|v| v.is_finite())
+            .map(|v| v as i64)),
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OemProductMapRow {
+    pub oem_product_id: String,
+    pub product_id: String,
+}
+
+impl Table for OemProductMapRow {
+    const TABLE: &'static str = "oem_product_map";
+    const KEY: &'static [&'static str] = &["oem_product_id"];
+    const UPSERT: &'static str = "INSERT INTO oem_product_map (oem_product_id, product_id) \
+        VALUES (?, ?) \
+        ON CONFLICT(oem_product_id) DO UPDATE SET product_id = excluded.product_id";
+
+    fn bind<'q>(&'q self, query: SqliteQuery<'q>) -> SqliteQuery<'q> {
+        query.bind(&self.oem_product_id).bind(&self.product_id)
+    }
+
+    fn from_row(row: &SqliteRow) -> Result<Self, sqlx::Error> {
+        Ok(Self {
+            oem_product_id: row.try_get("oem_product_id")?,
+            product_id: row.try_get("product_id")?,
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ProductOffsetRow {
+    pub oem_product_id: String,
+    pub x_offset: f64,
+    pub y_offset: f64,
+    pub defect_offset_x: f64,
+    pub defect_offset_y: f64,
+}
+
+impl Table for ProductOffsetRow {
+    const TABLE: &'static str = "product_offsets";
+    const KEY: &'static [&'static str] = &["oem_product_id"];
+    const UPSERT: &'static str = "INSERT INTO product_offsets \
+        (oem_product_id, x_offset, y_offset, defect_offset_x, defect_offset_y) \
+        VALUES (?, ?, ?, ?, ?) \
+        ON CONFLICT(oem_product_id) DO UPDATE SET \
+        x_offset = excluded.x_offset, y_offset = excluded.y_offset, \
+        defect_offset_x = excluded.defect_offset_x, defect_offset_y = excluded.defect_offset_y";
+
+    fn bind<'q>(&'q self, query: SqliteQuery<'q>) -> SqliteQuery<'q> {
+        query
+            .bind(&self.oem_product_id)
+            .bind(self.x_offset)
+            .bind(self.y_offset)
+            .bind(self.defect_offset_x)
+            .bind(self.defect_offset_y)
+    }
+
+    fn from_row(row: &SqliteRow) -> Result<Self, sqlx::Error> {
+        Ok(Self {
+            oem_product_id: row.try_get("oem_product_id")?,
+            x_offset: row.try_get("x_offset")?,
+            y_offset: row.try_get("y_offset")?,
+            defect_offset_x: row.try_get("defect_offset_x")?,
+            defect_offset_y: row.try_get("defect_offset_y")?,
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ProductSizeRow {
+    pub oem_product_id: String,
+    pub die_x: f64,
+    pub die_y: f64,
+}
+
+impl Table for ProductSizeRow {
+    const TABLE: &'static str = "product_size";
+    const KEY: &'static [&'static str] = &["oem_product_id"];
+    const UPSERT: &'static str = "INSERT INTO product_size (oem_product_id, die_x, die_y) \
+        VALUES (?, ?, ?) \
+        ON CONFLICT(oem_product_id) DO UPDATE SET die_x = excluded.die_x, die_y = excluded.die_y";
+
+    fn bind<'q>(&'q self, query: SqliteQuery<'q>) -> SqliteQuery<'q> {
+        query
+            .bind(&self.oem_product_id)
+            .bind(self.die_x)
+            .bind(self.die_y)
+    }
+
+    fn from_row(row: &SqliteRow) -> Result<Self, sqlx::Error> {
+        Ok(Self {
+            oem_product_id: row.try_get("oem_product_id")?,
+            die_x: row.try_get("die_x")?,
+            die_y: row.try_get("die_y")?,
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ProductBinSelectionRow {
+    pub oem_product_id: String,
+    /// Comma-separated bin ids, `*` for all
+    pub selected_bin_ids: String,
+}
+
+impl Table for ProductBinSelectionRow {
+    const TABLE: &'static str = "product_bin_selection";
+    const KEY: &'static [&'static str] = &["oem_product_id"];
+    const UPSERT: &'static str = "INSERT INTO product_bin_selection \
+        (oem_product_id, selected_bin_ids) VALUES (?, ?) \
+        ON CONFLICT(oem_product_id) DO UPDATE SET selected_bin_ids = excluded.selected_bin_ids";
+
+    fn bind<'q>(&'q self, query: SqliteQuery<'q>) -> SqliteQuery<'q> {
+        query
+            .bind(&self.oem_product_id)
+            .bind(&self.selected_bin_ids)
+    }
+
+    fn from_row(row: &SqliteRow) -> Result<Self, sqlx::Error> {
+        Ok(Self {
+            oem_product_id: row.try_get("oem_product_id")?,
+            selected_bin_ids: row.try_get("selected_bin_ids")?,
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ProductBinMapRow {
+    pub oem_product_id: String,
+    /// `BinMapTable` as JSON
+    pub bin_map: String,
+}
+
+impl Table for ProductBinMapRow {
+    const TABLE: &'static str = "product_bin_map";
+    const KEY: &'static [&'static str] = &["oem_product_id"];
+    const UPSERT: &'static str = "INSERT INTO product_bin_map (oem_product_id, bin_map) \
+        VALUES (?, ?) \
+        ON CONFLICT(oem_product_id) DO UPDATE SET bin_map = excluded.bin_map";
+
+    fn bind<'q>(&'q self, query: SqliteQuery<'q>) -> SqliteQuery<'q> {
+        query.bind(&self.oem_product_id).bind(&self.bin_map)
+    }
+
+    fn from_row(row: &SqliteRow) -> Result<Self, sqlx::Error> {
+        Ok(Self {
+            oem_product_id: row.try_get("oem_product_id")?,
+            bin_map: row.try_get("bin_map")?,
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WaferMapChoiceRow {
+    pub product_id: String,
+    pub batch_id: String,
+    pub wafer_id: i64,
+    pub stage: String,
+    /// '' when the maps have no sub-stage
+    pub sub_stage: String,
+    pub file_path: String,
+}
+
+impl Table for WaferMapChoiceRow {
+    const TABLE: &'static str = "wafer_map_choice";
+    const KEY: &'static [&'static str] =
+        &["product_id", "batch_id", "wafer_id", "stage", "sub_stage"];
+    const UPSERT: &'static str = "INSERT INTO wafer_map_choice \
+        (product_id, batch_id, wafer_id, stage, sub_stage, file_path) VALUES (?, ?, ?, ?, ?, ?) \
+        ON CONFLICT(product_id, batch_id, wafer_id, stage, sub_stage) \
+        DO UPDATE SET file_path = excluded.file_path";
+
+    fn bind<'q>(&'q self, query: SqliteQuery<'q>) -> SqliteQuery<'q> {
+        query
+            .bind(&self.product_id)
+            .bind(&self.batch_id)
+            .bind(self.wafer_id)
+            .bind(&self.stage)
+            .bind(&self.sub_stage)
+            .bind(&self.file_path)
+    }
+
+    fn from_row(row: &SqliteRow) -> Result<Self, sqlx::Error> {
+        Ok(Self {
+            product_id: row.try_get("product_id")?,
+            batch_id: row.try_get("batch_id")?,
+            wafer_id: row.try_get("wafer_id")?,
+            stage: row.try_get("stage")?,
+            sub_stage: row.try_get("sub_stage")?,
+            file_path: row.try_get("file_path")?,
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ProductDefectMapRow {
+    pub oem_product_id: String,
+    pub lot_id: String,
+    pub wafer_id: String,
+    pub sub_id: String,
+    pub file_path: String,
+}
+
+impl Table for ProductDefectMapRow {
+    const TABLE: &'static str = "product_defect_map";
+    const KEY: &'static [&'static str] = &["oem_product_id", "lot_id", "wafer_id"];
+    const UPSERT: &'static str = "INSERT INTO product_defect_map \
+        (oem_product_id, lot_id, wafer_id, sub_id, file_path) VALUES (?, ?, ?, ?, ?) \
+        ON CONFLICT(oem_product_id, lot_id, wafer_id) DO UPDATE SET \
+        sub_id = excluded.sub_id, file_path = excluded.file_path";
+
+    fn bind<'q>(&'q self, query: SqliteQuery<'q>) -> SqliteQuery<'q> {
+        query
+            .bind(&self.oem_product_id)
+            .bind(&self.lot_id)
+            .bind(&self.wafer_id)
+            .bind(&self.sub_id)
+            .bind(&self.file_path)
+    }
+
+    fn from_row(row: &SqliteRow) -> Result<Self, sqlx::Error> {
+        Ok(Self {
+            oem_product_id: row.try_get("oem_product_id")?,
+            lot_id: row.try_get("lot_id")?,
+            wafer_id: row.try_get("wafer_id")?,
+            sub_id: row.try_get("sub_id")?,
+            file_path: row.try_get("file_path")?,
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SubstrateDefectRow {
+    pub sub_id: String,
+    pub file_path: String,
+}
+
+impl Table for SubstrateDefectRow {
+    const TABLE: &'static str = "substrate_defect";
+    const KEY: &'static [&'static str] = &["sub_id"];
+    const UPSERT: &'static str = "INSERT INTO substrate_defect (sub_id, file_path) VALUES (?, ?) \
+        ON CONFLICT(sub_id) DO UPDATE SET file_path = excluded.file_path";
+
+    fn bind<'q>(&'q self, query: SqliteQuery<'q>) -> SqliteQuery<'q> {
+        query.bind(&self.sub_id).bind(&self.file_path)
+    }
+
+    fn from_row(row: &SqliteRow) -> Result<Self, sqlx::Error> {
+        Ok(Self {
+            sub_id: row.try_get("sub_id")?,
+            file_path: row.try_get("file_path")?,
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WaferMapRow {
+    /// Autoincrement key; ignored on upsert (rows are keyed by `file_path`)
+    #[serde(default)]
+    pub idx: Option<i64>,
+    pub product_id: String,
+    pub batch_id: String,
+    pub wafer_id: i64,
+    pub stage: String,
+    #[serde(default)]
+    pub sub_stage: Option<String>,
+    #[serde(default)]
+    pub retest_count: i64,
+    /// Epoch ms
+    #[serde(default)]
+    pub time: Option<i64>,
+    pub file_path: String,
+}
+
+impl Table for WaferMapRow {
+    const TABLE: &'static str = "wafer_maps";
+    const KEY: &'static [&'static str] = &["file_path"];
+    const UPSERT: &'static str = "INSERT INTO wafer_maps \
+        (product_id, batch_id, wafer_id, stage, sub_stage, retest_count, time, file_path) \
+        VALUES (?, ?, ?, ?, ?, ?, ?, ?) \
+        ON CONFLICT(file_path) DO UPDATE SET \
+        product_id = excluded.product_id, batch_id = excluded.batch_id, \
+        wafer_id = excluded.wafer_id, stage = excluded.stage, \
+        sub_stage = excluded.sub_stage, retest_count = excluded.retest_count, \
+        time = excluded.time";
+
+    fn bind<'q>(&'q self, query: SqliteQuery<'q>) -> SqliteQuery<'q> {
+        query
+            .bind(&self.product_id)
+            .bind(&self.batch_id)
+            .bind(self.wafer_id)
+            .bind(&self.stage)
+            .bind(&self.sub_stage)
+            .bind(self.retest_count)
+            .bind(self.time)
+            .bind(&self.file_path)
+    }
+
+    fn from_row(row: &SqliteRow) -> Result<Self, sqlx::Error> {
+        Ok(Self {
+            idx: row.try_get("idx")?,
+            product_id: row.try_get("product_id")?,
+            batch_id: row.try_get("batch_id")?,
+            wafer_id: row.try_get("wafer_id")?,
+            stage: row.try_get("stage")?,
+            sub_stage: row.try_get("sub_stage")?,
+            retest_count: opt_i64(row, "retest_count")?.unwrap_or(0),
+            time: opt_i64(row, "time")?,
+            file_path: row.try_get("file_path")?,
+        })
+    }
+}
+
+/// Parsed header of a `wafer_maps` file (v4).
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WaferMapMetaRow {
+    pub file_path: String,
+    #[serde(default)]
+    pub device: Option<String>,
+    #[serde(default)]
+    pub operator: Option<String>,
+    /// Epoch ms of the header's measurement time
+    #[serde(default)]
+    pub meas_time: Option<i64>,
+    #[serde(default)]
+    pub total_tested: Option<i64>,
+    #[serde(default)]
+    pub total_pass: Option<i64>,
+    #[serde(default)]
+    pub yield_percentage: Option<f64>,
+    /// Tested bins present, space-separated
+    #[serde(default)]
+    pub bins: String,
+}
+
+impl Table for WaferMapMetaRow {
+    const TABLE: &'static str = "wafer_map_meta";
+    const KEY: &'static [&'static str] = &["file_path"];
+    const UPSERT: &'static str = "INSERT INTO wafer_map_meta \
+        (file_path, device, operator, meas_time, total_tested, total_pass, yield_percentage, bins) \
+        VALUES (?, ?, ?, ?, ?, ?, ?, ?) \
+        ON CONFLICT(file_path) DO UPDATE SET \
+        device = excluded.device, operator = excluded.operator, \
+        meas_time = excluded.meas_time, total_tested = excluded.total_tested, \
+        total_pass = excluded.total_pass, yield_percentage = excluded.yield_percentage, \
+        bins = excluded.bins";
+
+    fn bind<'q>(&'q self, query: SqliteQuery<'q>) -> SqliteQuery<'q> {
+        query
+            .bind(&self.file_path)
+            .bind(&self.device)
+            .bind(&self.operator)
+            .bind(self.meas_time)
+            .bind(self.total_tested)
+            .bind(self.total_pass)
+            .bind(self.yield_percentage)
+            .bind(&self.bins)
+    }
+
+    fn from_row(row: &SqliteRow) -> Result<Self, sqlx::Error> {
+        Ok(Self {
+            file_path: row.try_get("file_path")?,
+            device: row.try_get("device")?,
+            operator: row.try_get("operator")?,
+            meas_time: opt_i64(row, "meas_time")?,
+            total_tested: opt_i64(row, "total_tested")?,
+            total_pass: opt_i64(row, "total_pass")?,
+            yield_percentage: row.try_get("yield_percentage")?,
+            bins: row
+                .try_get::<Option<String>, _>("bins")?
+                .unwrap_or_default(),
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FileIndexRow {
+    pub file_path: String,
+    /// Epoch ms
+    pub last_mtime: i64,
+    #[serde(default)]
+    pub file_hash: Option<String>,
+}
+
+impl Table for FileIndexRow {
+    const TABLE: &'static str = "file_index";
+    const KEY: &'static [&'static str] = &["file_path"];
+    const UPSERT: &'static str = "INSERT INTO file_index (file_path, last_mtime, file_hash) \
+        VALUES (?, ?, ?) \
+        ON CONFLICT(file_path) DO UPDATE SET \
+        last_mtime = excluded.last_mtime, file_hash = excluded.file_hash";
+
+    fn bind<'q>(&'q self, query: SqliteQuery<'q>) -> SqliteQuery<'q> {
+        query
+            .bind(&self.file_path)
+            .bind(self.last_mtime)
+            .bind(&self.file_hash)
+    }
+
+    fn from_row(row: &SqliteRow) -> Result<Self, sqlx::Error> {
+        Ok(Self {
+            file_path: row.try_get("file_path")?,
+            last_mtime: opt_i64(row, "last_mtime")?.unwrap_or(0),
+            file_hash: row.try_get("file_hash")?,
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FolderIndexRow {
+    pub folder_path: String,
+    #[serde(default)]
+    pub last_mtime: Option<i64>,
+}
+
+impl Table for FolderIndexRow {
+    const TABLE: &'static str = "folder_index";
+    const KEY: &'static [&'static str] = &["folder_path"];
+    const UPSERT: &'static str = "INSERT INTO folder_index (folder_path, last_mtime) \
+        VALUES (?, ?) \
+        ON CONFLICT(folder_path) DO UPDATE SET last_mtime = excluded.last_mtime";
+
+    fn bind<'q>(&'q self, query: SqliteQuery<'q>) -> SqliteQuery<'q> {
+        query.bind(&self.folder_path).bind(self.last_mtime)
+    }
+
+    fn from_row(row: &SqliteRow) -> Result<Self, sqlx::Error> {
+        Ok(Self {
+            folder_path: row.try_get("folder_path")?,
+            last_mtime: opt_i64(row, "last_mtime")?,
+        })
+    }
+}
+
+/// `wafer_stack_stats` with the per-stage waterfall added in v2.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WaferStackStatsRow {
+    #[serde(flatten)]
+    pub stats: WaferStackStats,
+    /// JSON `StageWaterfall`
+    #[serde(default)]
+    pub stage_waterfall: Option<String>,
+}
+
+impl Table for WaferStackStatsRow {
+    const TABLE: &'static str = "wafer_stack_stats";
+    const KEY: &'static [&'static str] = &["oem_product_id", "batch_id", "wafer_id"];
+    const UPSERT: &'static str = "INSERT INTO wafer_stack_stats \
+        (oem_product_id, batch_id, wafer_id, total_tested, total_pass, total_fail, \
+        yield_percentage, bin_counts, start_time, stop_time, stage_waterfall) \
+        VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?) \
+        ON CONFLICT(oem_product_id, batch_id, wafer_id) DO UPDATE SET \
+        total_tested = excluded.total_tested, total_pass = excluded.total_pass, \
+        total_fail = excluded.total_fail, yield_percentage = excluded.yield_percentage, \
+        bin_counts = excluded.bin_counts, start_time = excluded.start_time, \
+        stop_time = excluded.stop_time, stage_waterfall = excluded.stage_waterfall";
+
+    fn bind<'q>(&'q self, query: SqliteQuery<'q>) -> SqliteQuery<'q> {
+        let s = &self.stats;
+        query
+            .bind(&s.oem_product_id)
+            .bind(&s.batch_id)
+            .bind(&s.wafer_id)
+            .bind(s.total_tested as i64)
+            .bind(s.total_pass as i64)
+            .bind(s.total_fail as i64)
+            .bind(s.yield_percentage)
+            .bind(&s.bin_counts)
+            .bind(&s.start_time)
+            .bind(&s.stop_time)
+            .bind(&self.stage_waterfall)
+    }
+
+    fn from_row(row: &SqliteRow) -> Result<Self, sqlx::Error> {
+        let count = |col: &str| row.try_get::<i64, _>(col).map(|v| v.max(0) as u32);
+        Ok(Self {
+            stats: WaferStackStats {
+                oem_product_id: row.try_get("oem_product_id")?,
+                batch_id: row.try_get("batch_id")?,
+                wafer_id: row.try_get("wafer_id")?,
+                total_tested: count("total_tested")?,
+                total_pass: count("total_pass")?,
+                total_fail: count("total_fail")?,
+                yield_percentage: row.try_get("yield_percentage")?,
+                bin_counts: row.try_get("bin_counts")?,
+                start_time: row.try_get("start_time")?,
+                stop_time: row.try_get("stop_time")?,
+            },
+            stage_waterfall: row.try_get("stage_waterfall")?,
+        })
+    }
+}
+
+/// Rows of one table, as sent by the frontend: `{ table, rows }`.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "table", content = "rows", rename_all = "snake_case")]
+pub enum TableRows {
+    OemProductMap(Vec<OemProductMapRow>),
+    ProductOffsets(Vec<ProductOffsetRow>),
+    ProductSize(Vec<ProductSizeRow>),
+    ProductBinSelection(Vec<ProductBinSelectionRow>),
+    ProductBinMap(Vec<ProductBinMapRow>),
+    ProductDefectMap(Vec<ProductDefectMapRow>),
+    SubstrateDefect(Vec<SubstrateDefectRow>),
+    WaferMaps(Vec<WaferMapRow>),
+    WaferMapMeta(Vec<WaferMapMetaRow>),
+    FileIndex(Vec<FileIndexRow>),
+    FolderIndex(Vec<FolderIndexRow>),
+    WaferStackStats(Vec<WaferStackStatsRow>),
+}
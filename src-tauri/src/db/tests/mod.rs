@@ -0,0 +1,610 @@
+#[test]
+fn repo_upserts_and_reads_back_tables() {
+    use super::migrations::{latest_version, migrate_up};
+    use super::repo::*;
+    use super::tables::*;
+    use crate::wafer::stats::WaferStackStats;
+
+    tauri::async_runtime::block_on(async {
+        let pool = memory_pool().await;
+        migrate_up(&pool, latest_version()).await.unwrap();
+
+        let product = TableRows::OemProductMap(vec![OemProductMapRow {
+            oem_product_id: "OEM1".into(),
+            product_id: "P1".into(),
+        }]);
+        assert_eq!(upsert_table(&pool, &product).await.unwrap(), 1);
+        upsert_many(
+            &pool,
+            &[ProductSizeRow {
+                oem_product_id: "OEM1".into(),
+                die_x: 1.5,
+                die_y: 2.0,
+            }],
+        )
+        .await
+        .unwrap();
+
+        let context = product_context(&pool, "OEM1").await.unwrap();
+        assert_eq!(context.mapping.unwrap().product_id, "P1");
+        assert_eq!(context.size.unwrap().die_y, 2.0);
+        assert!(context.offset.is_none());
+
+        let files: Vec<FileIndexRow> = ["a.txt", "b.txt"]
+            .iter()
+            .map(|path| FileIndexRow {
+                file_path: path.to_string(),
+                last_mtime: 1,
+                file_hash: Some("h".into()),
+            })
+            .collect();
+        upsert_many(&pool, &files).await.unwrap();
+        let map = |path: &str, wafer_id: i64| WaferMapRow {
+            idx: None,
+            product_id: "P1".into(),
+            batch_id: "LOT1".into(),
+            wafer_id,
+            stage: "CP".into(),
+            sub_stage: Some("1".into()),
+            retest_count: 0,
+            time: None,
+            file_path: path.into(),
+        };
+        upsert_many(&pool, &[map("a.txt", 1), map("b.txt", 2)])
+            .await
+            .unwrap();
+        // same file re-ingested under another wafer id updates in place
+        upsert_many(&pool, &[map("b.txt", 3)]).await.unwrap();
+
+        let all = wafer_maps(&pool, &WaferMapFilter::default()).await.unwrap();
+        assert_eq!(
+            all.iter().map(|m| m.wafer_id).collect::<Vec<_>>(),
+            vec![1, 3]
+        );
+        let filter = WaferMapFilter {
+            wafer_id: Some(3),
+            ..Default::default()
+        };
+        let one = wafer_maps(&pool, &filter).await.unwrap();
+        assert_eq!(one.len(), 1);
+        assert_eq!(one[0].file_path, "b.txt");
+
+        let files = wafer_map_files(&pool, Some("P1")).await.unwrap();
+        assert!(files.iter().all(|f| f.file_hash.as_deref() == Some("h")));
+        let indexed = file_index(&pool, &["b.txt".into(), "missing".into()])
+            .await
+            .unwrap();
+        assert_eq!(indexed.len(), 1);
+
+        let stats = WaferStackStatsRow {
+            stats: WaferStackStats {
+                oem_product_id: "OEM1".into(),
+                batch_id: "LOT1".into(),
+                wafer_id: "1".into(),
+                total_tested: 10,
+                total_pass: 9,
+                total_fail: 1,
+                yield_percentage: 90.0,
+                bin_counts: String::new(),
+                start_time: None,
+                stop_time: None,
+            },
+            stage_waterfall: Some("{}".into()),
+        };
+        upsert_many(&pool, &[stats]).await.unwrap();
+        let read = stack_stats(&pool, "OEM1").await.unwrap();
+        assert_eq!(read.len(), 1);
+        assert_eq!(read[0].stats.total_pass, 9);
+        assert_eq!(read[0].stage_waterfall.as_deref(), Some("{}"));
+    });
+}
+
+#[test]
+fn stack_stats_with_history_keeps_exported_lots() {
+    use super::migrations::{latest_version, migrate_up};
+    use super::repo::{stack_stats_with_history, upsert_many};
+    use super::tables::WaferStackStatsRow;
+    use crate::wafer::stats::WaferStackStats;
+
+    let row = |lot: &str, pass: u32| WaferStackStatsRow {
+        stats: WaferStackStats {
+            oem_product_id: "OEM1".into(),
+            batch_id: lot.into(),
+            wafer_id: "1".into(),
+            total_tested: 10,
+            total_pass: pass,
+            total_fail: 10 - pass,
+            yield_percentage: pass as f64 * 10.0,
+            bin_counts: String::new(),
+            start_time: None,
+            stop_time: None,
+        },
+        stage_waterfall: None,
+    };
+    tauri::async_runtime::block_on(async {
+        let pool = memory_pool().await;
+        migrate_up(&pool, latest_version()).await.unwrap();
+        upsert_many(&pool, &[row("L1", 5), row("L2", 6)]).await.unwrap();
+        upsert_many(&pool, &[row("L1", 8)]).await.unwrap();
+        // what the report export does after writing the workbook
+        sqlx::query("DELETE FROM wafer_stack_stats WHERE oem_product_id = 'OEM1'")
+            .execute(&pool)
+            .await
+            .unwrap();
+
+        let read = stack_stats_with_history(&pool, "OEM1").await.unwrap();
+        let passes: Vec<_> = read
+            .iter()
+            .map(|r| (r.stats.batch_id.as_str(), r.stats.total_pass))
+            .collect();
+        assert_eq!(passes, [("L1", 8), ("L2", 6)]);
+
+        upsert_many(&pool, &[row("L2", 9)]).await.unwrap();
+        let read = stack_stats_with_history(&pool, "OEM1").await.unwrap();
+        assert_eq!(read.len(), 2);
+        assert_eq!(read[1].stats.total_pass, 9);
+    });
+}
+
+#[cfg(test)]
+async fn memory_pool() -> sqlx::SqlitePool {
+    // one connection, or every connection gets its own in-memory database
+    sqlx::sqlite::SqlitePoolOptions::new()
+        .max_connections(1)
+        .connect("sqlite::memory:")
+        .await
+        .unwrap()
+}
+
+/// Every row of every v1 table as JSON, restricted to the v1 columns.
+#[cfg(test)]
+async fn v1_snapshot(pool: &sqlx::SqlitePool, columns: &[(String, Vec<String>)]) -> Vec<String> {
+    let mut out = Vec::new();
+    for (table, cols) in columns {
+        let fields = cols
+            .iter()
+            .map(|c| format!("'{c}', {c}"))
+            .collect::<Vec<_>>()
+            .join(", ");
+        let sql = format!(
+            "SELECT COALESCE(json_group_array(json_object({fields})), '[]') FROM \
+             (SELECT * FROM {table} ORDER BY rowid)"
+        );
+        out.push(sqlx::query_scalar(&sql).fetch_one(pool).await.unwrap());
+    }
+    out
+}
+
+#[cfg(test)]
+async fn seed_v1(pool: &sqlx::SqlitePool) {
+    sqlx::raw_sql(
+        "INSERT INTO oem_product_map VALUES ('OEM1', 'P1'), ('OEM2', 'P2');
+         INSERT INTO product_offsets VALUES ('OEM1', 0.5, -0.5, 1.0, 2.0);
+         INSERT INTO product_size VALUES ('OEM1', 3.2, 4.1);
+         INSERT INTO product_bin_selection VALUES ('OEM1', '1,2');
+         INSERT INTO file_index VALUES ('a.txt', 10, 'h1'), ('b.txt', 20, NULL), ('s.xls', 30, 'h3');
+         INSERT INTO folder_index VALUES ('root', 5);
+         INSERT INTO product_defect_map VALUES ('OEM1', 'LOT1', '1', 'SUB1', 's.xls');
+         INSERT INTO substrate_defect VALUES ('SUB1', 's.xls');
+         INSERT INTO wafer_maps (product_id, batch_id, wafer_id, stage, sub_stage, retest_count, time, file_path)
+             VALUES ('P1', 'LOT1', 1, 'CP', '1', 0, 1700000000000, 'a.txt'),
+                    ('P1', 'LOT1', 1, 'AOI', NULL, 1, NULL, 'b.txt');
+         INSERT INTO wafer_stack_stats
+             (oem_product_id, batch_id, wafer_id, total_tested, total_pass, total_fail,
+              yield_percentage, bin_counts, start_time, stop_time)
+             VALUES ('OEM1', 'LOT1', '1', 100, 97, 3, 97.0, '{\"1\":97}', '2024-01-01', NULL);
+         INSERT INTO auth (username, role, password) VALUES ('op', 'user', 'pw');",
+    )
+    .execute(pool)
+    .await
+    .unwrap();
+}
+
+#[cfg(test)]
+async fn v1_columns(pool: &sqlx::SqlitePool) -> Vec<(String, Vec<String>)> {
+    let tables: Vec<String> = sqlx::query_scalar(
+        "SELECT name FROM sqlite_master WHERE type = 'table' \
+         AND name NOT LIKE 'sqlite_%' AND name != '_sqlx_migrations' ORDER BY name",
+    )
+    .fetch_all(pool)
+    .await
+    .unwrap();
+    let mut out = Vec::new();
+    for table in tables {
+        let cols = sqlx::query_scalar("SELECT name FROM pragma_table_info(?) ORDER BY cid")
+            .bind(&table)
+            .fetch_all(pool)
+            .await
+            .unwrap();
+        out.push((table, cols));
+    }
+    out
+}
+
+#[test]
+fn migrations_upgrade_v1_database_without_loss() {
+    use super::migrations::*;
+
+    tauri::async_runtime::block_on(async {
+        let pool = memory_pool().await;
+        let status = migrate_up(&pool, 1).await.unwrap();
+        assert_eq!(status.current, 1);
+        assert_eq!(status.pending, vec![2, 3, 4, 5, 6, 7]);
+
+        seed_v1(&pool).await;
+        let columns = v1_columns(&pool).await;
+        assert_eq!(columns.len(), 11);
+        let before = v1_snapshot(&pool, &columns).await;
+        assert!(before.iter().all(|rows| rows != "[]"));
+
+        let status = migrate_up(&pool, latest_version()).await.unwrap();
+        assert_eq!(status.current, latest_version());
+        assert!(status.pending.is_empty());
+        assert!(status.compatible());
+        assert_eq!(v1_snapshot(&pool, &columns).await, before);
+
+        let waterfall: Option<String> =
+            sqlx::query_scalar("SELECT stage_waterfall FROM wafer_stack_stats")
+                .fetch_one(&pool)
+                .await
+                .unwrap();
+        assert_eq!(waterfall, None);
+
+        // re-stacking keeps the previous result
+        sqlx::query(
+            "UPDATE wafer_stack_stats SET total_pass = 99, total_fail = 1, yield_percentage = 99.0",
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+        let archived: (i64, f64) =
+            sqlx::query_as("SELECT total_pass, yield_percentage FROM wafer_stack_stats_history")
+                .fetch_one(&pool)
+                .await
+                .unwrap();
+        assert_eq!(archived, (97, 97.0));
+
+        // running the chain again is a no-op
+        migrate_up(&pool, latest_version()).await.unwrap();
+        assert_eq!(v1_snapshot(&pool, &columns).await.len(), 11);
+    });
+}
+
+#[test]
+fn migrations_revert_and_reapply() {
+    use super::migrations::*;
+
+    tauri::async_runtime::block_on(async {
+        let pool = memory_pool().await;
+        migrate_up(&pool, 1).await.unwrap();
+        seed_v1(&pool).await;
+        let columns = v1_columns(&pool).await;
+        let before = v1_snapshot(&pool, &columns).await;
+        migrate_up(&pool, latest_version()).await.unwrap();
+
+        let status = migrate_down(&pool, 1).await.unwrap();
+        assert_eq!(status.current, 1);
+        assert_eq!(status.pending, vec![2, 3, 4, 5, 6, 7]);
+        assert_eq!(v1_columns(&pool).await, columns);
+        assert_eq!(v1_snapshot(&pool, &columns).await, before);
+
+        let status = migrate_up(&pool, latest_version()).await.unwrap();
+        assert_eq!(status.current, latest_version());
+        assert_eq!(v1_snapshot(&pool, &columns).await, before);
+    });
+}
+
+#[test]
+fn schema_status_rejects_newer_and_modified_databases() {
+    use super::migrations::*;
+
+    tauri::async_runtime::block_on(async {
+        let pool = memory_pool().await;
+        let status = schema_status(&pool).await.unwrap();
+        assert_eq!(status.current, 0);
+        assert_eq!(status.pending.len(), MIGRATIONS.len());
+
+        migrate_up(&pool, latest_version()).await.unwrap();
+        sqlx::query(
+            "INSERT INTO _sqlx_migrations (version, description, success, checksum, execution_time) \
+             VALUES (99, 'from the future', 1, x'00', 0)",
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+        let status = schema_status(&pool).await.unwrap();
+        assert_eq!(status.unknown, vec![99]);
+        assert!(status.check().unwrap_err().contains("v99"));
+        assert!(migrate_up(&pool, latest_version()).await.is_err());
+
+        sqlx::query("DELETE FROM _sqlx_migrations WHERE version = 99")
+            .execute(&pool)
+            .await
+            .unwrap();
+        sqlx::query("UPDATE _sqlx_migrations SET checksum = x'00' WHERE version = 2")
+            .execute(&pool)
+            .await
+            .unwrap();
+        let status = schema_status(&pool).await.unwrap();
+        assert_eq!(status.modified, vec![2]);
+        assert!(!status.compatible());
+    });
+}
+
+#[test]
+fn config_bundle_round_trips_and_resolves_conflicts() {
+    use super::backup::*;
+    use super::migrations::{latest_version, migrate_up};
+    use super::repo::{all_rows, upsert_many};
+    use super::tables::*;
+    use std::env;
+    use std::fs;
+
+    let dir = env::temp_dir().join("config_bundle");
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+
+    tauri::async_runtime::block_on(async {
+        let source = memory_pool().await;
+        migrate_up(&source, latest_version()).await.unwrap();
+        seed_v1(&source).await;
+
+        for name in ["config.zip", "config.json"] {
+            let manifest = export_config(&source, &dir.join(name), &ExportOptions::default())
+                .await
+                .unwrap();
+            assert_eq!(manifest.version, BUNDLE_VERSION);
+            assert_eq!(manifest.schema_version, latest_version());
+            assert_eq!(manifest.tables["oem_product_map"], 2);
+            assert_eq!(manifest.tables["wafer_stack_stats"], 1);
+        }
+
+        // target PC: one product with a different die size
+        let target = memory_pool().await;
+        migrate_up(&target, latest_version()).await.unwrap();
+        upsert_many(
+            &target,
+            &[OemProductMapRow {
+                oem_product_id: "OEM1".into(),
+                product_id: "P1".into(),
+            }],
+        )
+        .await
+        .unwrap();
+        let local = ProductSizeRow {
+            oem_product_id: "OEM1".into(),
+            die_x: 9.0,
+            die_y: 9.0,
+        };
+        upsert_many(&target, std::slice::from_ref(&local))
+            .await
+            .unwrap();
+        let sizes = |pool| async move { all_rows::<ProductSizeRow>(pool).await.unwrap() };
+
+        let preview = import_config(
+            &target,
+            &dir.join("config.zip"),
+            &ImportOptions {
+                dry_run: true,
+                ..Default::default()
+            },
+        )
+        .await
+        .unwrap();
+        assert!(!preview.applied);
+        let size = preview
+            .tables
+            .iter()
+            .find(|t| t.table == "product_size")
+            .unwrap();
+        assert_eq!(size.conflicts.len(), 1);
+        assert_eq!(size.conflicts[0].key["oem_product_id"], "OEM1");
+        let mapping = &preview.tables[0];
+        assert_eq!((mapping.unchanged, mapping.added), (1, 1));
+        assert_eq!(sizes(&target).await, vec![local.clone()]);
+
+        let abort = ImportOptions {
+            conflict: ConflictPolicy::Abort,
+            ..Default::default()
+        };
+        let report = import_config(&target, &dir.join("config.json"), &abort)
+            .await
+            .unwrap();
+        assert!(!report.applied);
+        assert_eq!(
+            all_rows::<OemProductMapRow>(&target).await.unwrap().len(),
+            1
+        );
+
+        // default keeps the local die size but adds everything else
+        let report = import_config(&target, &dir.join("config.zip"), &ImportOptions::default())
+            .await
+            .unwrap();
+        assert!(report.applied);
+        assert_eq!(sizes(&target).await, vec![local]);
+        assert_eq!(
+            all_rows::<OemProductMapRow>(&target).await.unwrap().len(),
+            2
+        );
+        assert_eq!(
+            all_rows::<WaferStackStatsRow>(&target).await.unwrap().len(),
+            1
+        );
+
+        let overwrite = ImportOptions {
+            conflict: ConflictPolicy::Overwrite,
+            ..Default::default()
+        };
+        let report = import_config(&target, &dir.join("config.json"), &overwrite)
+            .await
+            .unwrap();
+        assert_eq!(
+            report.tables.iter().map(|t| t.overwritten).sum::<usize>(),
+            1
+        );
+        assert_eq!(
+            sizes(&target).await,
+            all_rows::<ProductSizeRow>(&source).await.unwrap()
+        );
+
+        // VACUUM INTO from an in-memory database stays in memory; use a file
+        let file = |name: &str| format!("sqlite:{}?mode=rwc", dir.join(name).display());
+        let live = sqlx::SqlitePool::connect(&file("live.db")).await.unwrap();
+        migrate_up(&live, latest_version()).await.unwrap();
+        seed_v1(&live).await;
+        let backup = backup_database(&live, &dir.join("backup.db"))
+            .await
+            .unwrap();
+        assert!(backup.bytes > 0);
+        let copy = sqlx::SqlitePool::connect(&file("backup.db")).await.unwrap();
+        assert_eq!(all_rows::<WaferMapRow>(&copy).await.unwrap().len(), 2);
+    });
+}
+
+#[test]
+fn wafer_search_filters_and_follows_renames() {
+    use super::migrations::{latest_version, migrate_up};
+    use super::repo::upsert_many;
+    use super::search::*;
+    use super::tables::*;
+
+    tauri::async_runtime::block_on(async {
+        let pool = memory_pool().await;
+        migrate_up(&pool, latest_version()).await.unwrap();
+        upsert_many(
+            &pool,
+            &[OemProductMapRow {
+                oem_product_id: "OEM1".into(),
+                product_id: "P1".into(),
+            }],
+        )
+        .await
+        .unwrap();
+
+        let day = 86_400_000;
+        let files: Vec<FileIndexRow> = (1..=4)
+            .map(|i| format!("/data/LOT-7_{:02}.txt", i))
+            .chain(["/moved/w1.txt".to_string()])
+            .map(|file_path| FileIndexRow {
+                file_path,
+                last_mtime: 1,
+                file_hash: None,
+            })
+            .collect();
+        upsert_many(&pool, &files).await.unwrap();
+        let maps: Vec<WaferMapRow> = (1..=4)
+            .map(|wafer_id| WaferMapRow {
+                idx: None,
+                product_id: "P1".into(),
+                batch_id: "LOT-7".into(),
+                wafer_id,
+                stage: "CpProber".into(),
+                sub_stage: Some(if wafer_id < 4 { "2" } else { "1" }.into()),
+                retest_count: 0,
+                time: Some(wafer_id * day),
+                file_path: format!("/data/LOT-7_{:02}.txt", wafer_id),
+            })
+            .collect();
+        upsert_many(&pool, &maps).await.unwrap();
+        let metas: Vec<WaferMapMetaRow> = maps[..3]
+            .iter()
+            .map(|m| WaferMapMetaRow {
+                file_path: m.file_path.clone(),
+                device: Some("DEV-A".into()),
+                operator: Some(if m.wafer_id == 1 { "alice" } else { "bob" }.into()),
+                meas_time: None,
+                total_tested: Some(100),
+                total_pass: Some(80 + m.wafer_id * 5),
+                yield_percentage: Some(80.0 + m.wafer_id as f64 * 5.0),
+                bins: if m.wafer_id == 2 { "1 7" } else { "1" }.into(),
+            })
+            .collect();
+        upsert_many(&pool, &metas).await.unwrap();
+
+        let search = |query: WaferSearchQuery| {
+            let pool = pool.clone();
+            async move { search_wafers(&pool, &query).await.unwrap() }
+        };
+        let wafers = |page: &WaferSearchPage| -> Vec<i64> {
+            page.hits.iter().map(|h| h.map.wafer_id).collect()
+        };
+
+        // product X (by OEM id), yield up to 90 %, CP2, in a time window
+        let page = search(WaferSearchQuery {
+            product_id: Some("OEM1".into()),
+            stage: Some("CpProber".into()),
+            sub_stage: Some("2".into()),
+            max_yield: Some(90.0),
+            from: Some(day),
+            to: Some(4 * day),
+            ..Default::default()
+        })
+        .await;
+        assert_eq!(wafers(&page), vec![2, 1]);
+        assert_eq!(page.hits[0].meta.as_ref().unwrap().total_pass, Some(90));
+
+        let page = search(WaferSearchQuery {
+            bins: vec!["7".into()],
+            ..Default::default()
+        })
+        .await;
+        assert_eq!(wafers(&page), vec![2]);
+
+        let page = search(WaferSearchQuery {
+            text: Some("lot-7 ali".into()),
+            ..Default::default()
+        })
+        .await;
+        assert_eq!(wafers(&page), vec![1]);
+        // FTS syntax in the input is matched literally
+        let page = search(WaferSearchQuery {
+            text: Some("\"OR* NEAR(".into()),
+            ..Default::default()
+        })
+        .await;
+        assert_eq!(page.total, 0);
+
+        let page = search(WaferSearchQuery {
+            sort: SearchSort::Yield,
+            ascending: true,
+            page: 1,
+            page_size: Some(2),
+            ..Default::default()
+        })
+        .await;
+        assert_eq!(page.total, 4);
+        // the map without a header sorts first
+        assert_eq!(wafers(&page), vec![2, 3]);
+
+        // a moved file keeps its header and stays searchable under the new path
+        sqlx::query("UPDATE wafer_maps SET file_path = '/moved/w1.txt' WHERE wafer_id = 1")
+            .execute(&pool)
+            .await
+            .unwrap();
+        let page = search(WaferSearchQuery {
+            text: Some("moved alice".into()),
+            ..Default::default()
+        })
+        .await;
+        assert_eq!(wafers(&page), vec![1]);
+        assert_eq!(
+            page.hits[0].meta.as_ref().unwrap().file_path,
+            "/moved/w1.txt"
+        );
+
+        sqlx::query("DELETE FROM wafer_maps WHERE wafer_id = 1")
+            .execute(&pool)
+            .await
+            .unwrap();
+        let left: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM wafer_search")
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+        assert_eq!(left, 3);
+        let meta: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM wafer_map_meta")
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+        assert_eq!(meta, 2);
+    });
+}
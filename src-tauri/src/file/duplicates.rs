@@ -0,0 +1,276 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use serde::{Deserialize, Serialize};
+
+use super::index::is_content_hash;
+use crate::db::tables::{WaferMapChoiceRow, WaferMapRow};
+
+// =============================================================================
+// Duplicate and conflicting wafer maps
+//
+// Groups the indexed wafer maps by content hash and by their parsed key
+// (product, lot, wafer, stage, sub-stage):
+// - exact duplicates: the same bytes under several paths
+// - retest chains: one key tested several times (`retest_count`)
+// - conflicts: one key and retest with different contents
+// Every key with more than one map gets a recommended map (highest retest,
+// then newest) so the user can confirm or override what stacks; overrides
+// live in `wafer_map_choice`, and `pick_stacked_maps` applies them when a
+// wafer is stacked. Hashes that are not SHA-256 content digests
+// (legacy path hashes) count as missing.
+// =============================================================================
+
+/// `wafer_maps` joined with the `file_index` hash.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WaferMapFile {
+    pub idx: i64,
+    pub product_id: String,
+    pub batch_id: String,
+    pub wafer_id: i64,
+    pub stage: String,
+    pub sub_stage: Option<String>,
+    pub retest_count: i64,
+    pub time: Option<i64>,
+    pub file_path: String,
+    pub file_hash: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MapKey {
+    pub product_id: String,
+    pub batch_id: String,
+    pub wafer_id: i64,
+    pub stage: String,
+    pub sub_stage: Option<String>,
+}
+
+impl MapKey {
+    fn of(file: &WaferMapFile) -> Self {
+        Self {
+            product_id: file.product_id.clone(),
+            batch_id: file.batch_id.clone(),
+            wafer_id: file.wafer_id,
+            stage: file.stage.clone(),
+            sub_stage: sub_stage_key(file.sub_stage.as_deref()),
+        }
+    }
+}
+
+impl From<&WaferMapChoiceRow> for MapKey {
+    fn from(row: &WaferMapChoiceRow) -> Self {
+        Self {
+            product_id: row.product_id.clone(),
+            batch_id: row.batch_id.clone(),
+            wafer_id: row.wafer_id,
+            stage: row.stage.clone(),
+            sub_stage: sub_stage_key(Some(&row.sub_stage)),
+        }
+    }
+}
+
+impl From<&WaferMapRow> for MapKey {
+    fn from(row: &WaferMapRow) -> Self {
+        Self {
+            product_id: row.product_id.clone(),
+            batch_id: row.batch_id.clone(),
+            wafer_id: row.wafer_id,
+            stage: row.stage.clone(),
+            sub_stage: sub_stage_key(row.sub_stage.as_deref()),
+        }
+    }
+}
+
+/// "0" is what the ingest writes when there is no sub-stage.
+fn sub_stage_key(sub_stage: Option<&str>) -> Option<String> {
+    sub_stage
+        .filter(|s| !s.is_empty() && *s != "0")
+        .map(String::from)
+}
+
+fn content_hash(file: &WaferMapFile) -> Option<&str> {
+    file.file_hash.as_deref().filter(|h| is_content_hash(h))
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DuplicateGroup {
+    pub file_hash: String,
+    /// Oldest index entry first
+    pub files: Vec<WaferMapFile>,
+    /// Same content filed under different keys (misnamed copy)
+    pub keys_differ: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RetestChain {
+    pub key: MapKey,
+    /// Retest 0 first, newest last within a retest
+    pub files: Vec<WaferMapFile>,
+    pub retests: Vec<i64>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MapConflict {
+    pub key: MapKey,
+    pub retest_count: i64,
+    /// Distinct contents (one file per hash; unhashed files count as distinct)
+    pub files: Vec<WaferMapFile>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StackChoice {
+    pub key: MapKey,
+    pub candidates: u32,
+    /// Candidate paths, least preferred first
+    pub files: Vec<String>,
+    pub recommended: String,
+    /// The user's pick, while it is still one of the candidates
+    pub chosen: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DuplicateReport {
+    pub maps: u32,
+    /// Maps without a content hash; only key-based checks apply to them
+    pub unhashed: u32,
+    pub duplicates: Vec<DuplicateGroup>,
+    pub retest_chains: Vec<RetestChain>,
+    pub conflicts: Vec<MapConflict>,
+    pub choices: Vec<StackChoice>,
+}
+
+/// Highest retest, then newest, then most recently indexed.
+fn preference(file: &WaferMapFile) -> (i64, i64, i64) {
+    (file.retest_count, file.time.unwrap_or(0), file.idx)
+}
+
+pub fn duplicate_report(files: &[WaferMapFile], choices: &[WaferMapChoiceRow]) -> DuplicateReport {
+    let mut report = DuplicateReport {
+        maps: files.len() as u32,
+        ..Default::default()
+    };
+    let chosen: BTreeMap<MapKey, &str> = choices
+        .iter()
+        .map(|c| (MapKey::from(c), c.file_path.as_str()))
+        .collect();
+
+    let mut by_hash: BTreeMap<&str, Vec<&WaferMapFile>> = BTreeMap::new();
+    let mut by_key: BTreeMap<MapKey, Vec<&WaferMapFile>> = BTreeMap::new();
+    for file in files {
+        match content_hash(file) {
+            Some(hash) => by_hash.entry(hash).or_default().push(file),
+            None => report.unhashed += 1,
+        }
+        by_key.entry(MapKey::of(file)).or_default().push(file);
+    }
+
+    for (hash, mut group) in by_hash {
+        if group.len() < 2 {
+            continue;
+        }
+        group.sort_by_key(|f| f.idx);
+        let first = MapKey::of(group[0]);
+        report.duplicates.push(DuplicateGroup {
+            file_hash: hash.to_string(),
+            keys_differ: group.iter().any(|f| MapKey::of(f) != first),
+            files: group.into_iter().cloned().collect(),
+        });
+    }
+
+    for (key, mut group) in by_key {
+        if group.len() < 2 {
+            continue;
+        }
+        group.sort_by_key(|f| preference(f));
+
+        // sorted by preference, so retests come out ascending
+        let mut retests: Vec<i64> = group.iter().map(|f| f.retest_count).collect();
+        retests.dedup();
+
+        for &retest in &retests {
+            let mut distinct: Vec<&WaferMapFile> = Vec::new();
+            for file in group.iter().filter(|f| f.retest_count == retest) {
+                let hash = content_hash(file);
+                let seen = hash.is_some() && distinct.iter().any(|d| content_hash(d) == hash);
+                if !seen {
+                    distinct.push(file);
+                }
+            }
+            if distinct.len() > 1 {
+                report.conflicts.push(MapConflict {
+                    key: key.clone(),
+                    retest_count: retest,
+                    files: distinct.into_iter().cloned().collect(),
+                });
+            }
+        }
+
+        if retests.len() > 1 {
+            report.retest_chains.push(RetestChain {
+                key: key.clone(),
+                files: group.iter().map(|f| (*f).clone()).collect(),
+                retests,
+            });
+        }
+
+        let files: Vec<String> = group.iter().map(|f| f.file_path.clone()).collect();
+        let chosen = chosen
+            .get(&key)
+            .filter(|path| files.iter().any(|f| f == *path))
+            .map(|path| path.to_string());
+        report.choices.push(StackChoice {
+            key,
+            candidates: group.len() as u32,
+            recommended: files[files.len() - 1].clone(),
+            files,
+            chosen,
+        });
+    }
+
+    report
+}
+
+/// The stacking layer of a map, as the frontend's `selectedLayerKeys` name
+/// it ("cpprober|2").
+pub fn layer_key(map: &WaferMapRow) -> String {
+    format!(
+        "{}|{}",
+        map.stage.to_lowercase(),
+        map.sub_stage.as_deref().unwrap_or("")
+    )
+}
+
+/// One map per layer when a wafer has several for a stage: the one chosen in
+/// `choices`, else the recommended one. The maps keep their order.
+pub fn pick_stacked_maps(maps: &[WaferMapRow], choices: &[WaferMapChoiceRow]) -> Vec<WaferMapRow> {
+    let chosen: BTreeSet<(MapKey, &str)> = choices
+        .iter()
+        .map(|c| (MapKey::from(c), c.file_path.as_str()))
+        .collect();
+    let is_chosen =
+        |map: &WaferMapRow| chosen.contains(&(MapKey::from(map), map.file_path.as_str()));
+    let preference = |map: &WaferMapRow| {
+        (
+            map.retest_count,
+            map.time.unwrap_or(0),
+            map.idx.unwrap_or(0),
+        )
+    };
+
+    let mut picked: BTreeMap<String, &WaferMapRow> = BTreeMap::new();
+    for map in maps {
+        let current = picked.entry(layer_key(map)).or_insert(map);
+        if !is_chosen(current) && (is_chosen(map) || preference(map) > preference(current)) {
+            *current = map;
+        }
+    }
+    maps.iter()
+        .filter(|map| std::ptr::eq(picked[&layer_key(map)], *map))
+        .cloned()
+        .collect()
+}
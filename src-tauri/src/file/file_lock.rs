@@ -1,53 +1,393 @@
+use std::collections::hash_map::Entry;
 use std::collections::HashMap;
-use std::fs::{File};
+use std::fs::File;
+use std::path::Path;
 use std::sync::Mutex;
+use std::time::{Duration, Instant};
 
 use fs2::FileExt;
 use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+
+/// How long `lock_file` waits for a contended file unless told otherwise.
+const DEFAULT_TIMEOUT_MS: u64 = 30_000;
+
+/// Interval at which a contended lock is retried.
+const RETRY_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Suffix of the sidecar file naming the holder of an exclusive lock.
+const OWNER_SUFFIX: &str = ".lock-owner";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum LockMode {
+    /// Readers; any number may hold the file at once
+    Shared,
+    /// Writers; no one else may hold the file
+    Exclusive,
+}
+
+/// Who holds a lock, as written to the `<file>.lock-owner` sidecar.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LockOwner {
+    pub host: String,
+    pub pid: u32,
+    pub user: String,
+    pub mode: LockMode,
+    /// RFC 3339
+    pub acquired_at: String,
+}
+
+impl LockOwner {
+    fn current(mode: LockMode) -> Self {
+        LockOwner {
+            host: whoami::fallible::hostname().unwrap_or_else(|_| "unknown".into()),
+            pid: std::process::id(),
+            user: whoami::username(),
+            mode,
+            acquired_at: chrono::Utc::now().to_rfc3339(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct LockOptions {
+    /// Take a shared (reader) lock instead of an exclusive one
+    pub shared: bool,
+    /// How long to wait for a contended file; 0 tries once
+    pub timeout_ms: Option<u64>,
+}
+
+/// A lock as reported by `list_locks` and the lock commands.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LockInfo {
+    /// Canonical path; the key locks are tracked by
+    pub path: String,
+    /// Someone holds the file (this app or another process)
+    pub locked: bool,
+    /// Held by this app
+    pub held_here: bool,
+    /// Times this app holds it; shared locks are counted
+    pub holders: u32,
+    /// This app's record, else the sidecar of another holder. Shared locks of
+    /// other processes leave no sidecar, so `None` means unknown.
+    pub owner: Option<LockOwner>,
+}
+
+/// Result of `try_lock_file`: the lock, or who holds the file.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LockAttempt {
+    pub acquired: bool,
+    pub lock: LockInfo,
+}
+
+/// A lock held by this app and the handle keeping it.
+struct HeldLock {
+    file: File,
+    owner: LockOwner,
+    holders: u32,
+    /// Paths as given when locking; the key cannot be derived from them
+    /// again once the file is deleted or its share drops
+    requested: Vec<String>,
+}
+
+impl HeldLock {
+    fn hold(&mut self, path: &str) {
+        self.holders += 1;
+        if !self.requested.iter().any(|p| p == path) {
+            self.requested.push(path.to_string());
+        }
+    }
+}
 
 /// A globally shared map storing file handles for all currently locked files.
 ///
 /// This map is protected by a `Mutex` to ensure thread safety and is lazily
 /// initialized using `once_cell::sync::Lazy`. It allows the application to
 /// keep track of locked files and ensures the file handles remain in scope
-/// (preventing premature unlocking).
-static FILE_LOCKS: Lazy<Mutex<HashMap<String, File>>> =
+/// (preventing premature unlocking). Keys are canonical paths, so the same
+/// file reached through another spelling or a mapped drive is one entry.
+static FILE_LOCKS: Lazy<Mutex<HashMap<String, HeldLock>>> =
     Lazy::new(|| Mutex::new(HashMap::new()));
 
-/// Acquires an exclusive lock on the specified file and stores the handle in the global lock map.
+/// Canonical form of `path`; the path as given when it cannot be resolved.
+/// The `\\?\` prefix Windows adds to local drives is dropped.
+fn lock_key(path: &str) -> String {
+    let Ok(canonical) = std::fs::canonicalize(path) else {
+        return path.to_string();
+    };
+    let text = canonical.display().to_string();
+    match text.strip_prefix(r"\\?\") {
+        Some(rest) if !rest.starts_with("UNC\\") => rest.to_string(),
+        _ => text,
+    }
+}
+
+fn owner_path(key: &str) -> String {
+    format!("{}{}", key, OWNER_SUFFIX)
+}
+
+/// Holder recorded next to `key`, if any.
+fn read_owner(key: &str) -> Option<LockOwner> {
+    let text = std::fs::read_to_string(owner_path(key)).ok()?;
+    serde_json::from_str(&text).ok()
+}
+
+fn is_contended(err: &std::io::Error) -> bool {
+    err.kind() == fs2::lock_contended_error().kind()
+        || err.raw_os_error() == fs2::lock_contended_error().raw_os_error()
+}
+
+fn info(key: &str, held: &HeldLock) -> LockInfo {
+    LockInfo {
+        path: key.to_string(),
+        locked: true,
+        held_here: true,
+        holders: held.holders,
+        owner: Some(held.owner.clone()),
+    }
+}
+
+/// Opens `path` for `mode`: readers never create the file, writers do.
+fn open(path: &str, mode: LockMode) -> Result<File, String> {
+    let mut options = std::fs::OpenOptions::new();
+    options.read(true);
+    if mode == LockMode::Exclusive {
+        options.write(true).create(true);
+    }
+    options
+        .open(path)
+        .map_err(|e| format!("Failed to open file: {e}"))
+}
+
+/// Takes a `mode` lock on `path`, retrying until `timeout` has passed.
+///
+/// A shared lock this app already holds is counted rather than taken again;
+/// any other second lock on a file this app holds is refused, because the
+/// OS would let it wait on itself. On contention the attempt reports the
+/// holder from the sidecar when there is one.
+pub fn acquire(path: &str, mode: LockMode, timeout: Duration) -> Result<LockAttempt, String> {
+    let file = open(path, mode)?;
+    let key = lock_key(path);
+
+    {
+        let mut map = FILE_LOCKS.lock().unwrap();
+        if let Some(held) = map.get_mut(&key) {
+            if mode == LockMode::Shared && held.owner.mode == LockMode::Shared {
+                held.hold(path);
+                return Ok(LockAttempt {
+                    acquired: true,
+                    lock: info(&key, held),
+                });
+            }
+            return Err(format!(
+                "{} is already locked by this app ({:?})",
+                key, held.owner.mode
+            ));
+        }
+    }
+
+    let deadline = Instant::now() + timeout;
+    loop {
+        let result = match mode {
+            LockMode::Shared => FileExt::try_lock_shared(&file),
+            LockMode::Exclusive => FileExt::try_lock_exclusive(&file),
+        };
+        match result {
+            Ok(()) => break,
+            Err(e) if is_contended(&e) => {
+                if Instant::now() >= deadline {
+                    return Ok(LockAttempt {
+                        acquired: false,
+                        lock: LockInfo {
+                            path: key.clone(),
+                            locked: true,
+                            held_here: false,
+                            holders: 0,
+                            owner: read_owner(&key),
+                        },
+                    });
+                }
+                std::thread::sleep(
+                    RETRY_INTERVAL.min(deadline.saturating_duration_since(Instant::now())),
+                );
+            }
+            Err(e) => return Err(format!("Failed to lock file: {e}")),
+        }
+    }
+
+    let owner = LockOwner::current(mode);
+    if mode == LockMode::Exclusive {
+        // best effort: the lock itself does not depend on the sidecar
+        match serde_json::to_string_pretty(&owner) {
+            Ok(text) => {
+                if let Err(e) = std::fs::write(owner_path(&key), text) {
+                    eprintln!("⚠️ [lock] Failed to write owner of {}: {}", key, e);
+                }
+            }
+            Err(e) => eprintln!("⚠️ [lock] Failed to serialize owner of {}: {}", key, e),
+        }
+    }
+    let mut map = FILE_LOCKS.lock().unwrap();
+    let held = match map.entry(key.clone()) {
+        // another thread of this app took the same shared lock meanwhile;
+        // dropping our handle releases our OS lock only
+        Entry::Occupied(entry) => {
+            let held = entry.into_mut();
+            held.hold(path);
+            held
+        }
+        Entry::Vacant(entry) => entry.insert(HeldLock {
+            file,
+            owner,
+            holders: 1,
+            requested: vec![path.to_string()],
+        }),
+    };
+    Ok(LockAttempt {
+        acquired: true,
+        lock: info(&key, held),
+    })
+}
+
+/// Unlocks `held` and removes the sidecar an exclusive lock wrote.
+fn release(key: &str, held: HeldLock) -> Result<(), String> {
+    if held.owner.mode == LockMode::Exclusive {
+        let _ = std::fs::remove_file(owner_path(key));
+    }
+    FileExt::unlock(&held.file).map_err(|e| format!("Failed to unlock file: {e}"))
+}
+
+/// Drops one hold of this app on `path`; the file is unlocked when the last
+/// shared holder lets go.
+pub fn release_path(path: &str) -> Result<(), String> {
+    let key = lock_key(path);
+    let mut map = FILE_LOCKS.lock().unwrap();
+    // a deleted file or dropped share no longer canonicalizes to its key
+    let key = if map.contains_key(&key) {
+        key
+    } else {
+        map.iter()
+            .find(|(_, held)| held.requested.iter().any(|p| p == path))
+            .map_or(key, |(key, _)| key.clone())
+    };
+    match map.get_mut(&key) {
+        Some(held) if held.holders > 1 => {
+            held.holders -= 1;
+            Ok(())
+        }
+        Some(_) => {
+            let held = map.remove(&key).unwrap();
+            release(&key, held)
+        }
+        None => Err("No lock found for the specified path".into()),
+    }
+}
+
+/// This app's locks, then the state of each of `paths` it does not hold.
+///
+/// The paths are probed without holding `FILE_LOCKS`, since a network share
+/// may take a while to answer.
+pub fn lock_infos(paths: &[String]) -> Vec<LockInfo> {
+    let mut infos: Vec<LockInfo> = {
+        let map = FILE_LOCKS.lock().unwrap();
+        map.iter().map(|(key, held)| info(key, held)).collect()
+    };
+    infos.sort_by(|a, b| a.path.cmp(&b.path));
+
+    for path in paths {
+        let key = lock_key(path);
+        if infos.iter().any(|i| i.path == key) {
+            continue;
+        }
+        if !Path::new(&key).is_file() {
+            continue;
+        }
+        // only a writer blocks a shared probe, so readers are never reported
+        // as holders and the probe does not stop another app's reader
+        let locked = match File::open(&key).map(|file| FileExt::try_lock_shared(&file)) {
+            Ok(Ok(())) => false,
+            Ok(Err(e)) => is_contended(&e),
+            Err(_) => false,
+        };
+        infos.push(LockInfo {
+            owner: if locked { read_owner(&key) } else { None },
+            path: key,
+            locked,
+            held_here: false,
+            holders: 0,
+        });
+    }
+    infos
+}
+
+async fn run_blocking<T: Send + 'static>(
+    task: impl FnOnce() -> Result<T, String> + Send + 'static,
+) -> Result<T, String> {
+    tauri::async_runtime::spawn_blocking(task)
+        .await
+        .unwrap_or_else(|e| Err(format!("Thread join error: {e}")))
+}
+
+/// Acquires a lock on the specified file and stores the handle in the global lock map.
 ///
 /// This command is exposed to the Tauri frontend via `invoke('lock_file')`.
-/// If the file does not exist, it will be created. The file is opened with
-/// read and write permissions, and the lock is held until it is explicitly
-/// released or the application shuts down.
+/// Exclusive locks (the default) create the file if it does not exist and
+/// record the holder in a `<file>.lock-owner` sidecar; shared locks are for
+/// readers and need an existing file. The lock is held until it is
+/// explicitly released or the application shuts down.
 ///
 /// # Arguments
-/// * `path` - The absolute path to the file to be locked.
+/// * `path` - The path to the file to be locked; it is canonicalized.
+/// * `options` - `shared` and `timeoutMs` (default 30 s) to wait for a contended file.
 ///
 /// # Errors
-/// Returns a `String` error if the file cannot be opened or if the lock cannot be acquired.
+/// Returns a `String` error if the file cannot be opened, or if it is still
+/// locked when the timeout passes; the message names the holder when known.
 #[tauri::command]
-pub async fn lock_file(path: String) -> Result<(), String> {
-    tauri::async_runtime::spawn_blocking(move || {
-        let file = std::fs::OpenOptions::new()
-            .read(true)
-            .write(true)
-            .create(true)
-            .open(&path)
-            .map_err(|e| format!("Failed to open file: {e}"))?;
-
-        fs2::FileExt::lock_exclusive(&file)
-            .map_err(|e| format!("Failed to lock file: {e}"))?;
-
-        // ✅ Debug message
-        // println!("✅ [lock_file] Successfully acquired lock for: {}", path);
-
-        let mut map = FILE_LOCKS.lock().unwrap();
-        map.insert(path, file);
-        Ok(())
+pub async fn lock_file(path: String, options: Option<LockOptions>) -> Result<LockInfo, String> {
+    let options = options.unwrap_or_default();
+    let mode = if options.shared {
+        LockMode::Shared
+    } else {
+        LockMode::Exclusive
+    };
+    let timeout = Duration::from_millis(options.timeout_ms.unwrap_or(DEFAULT_TIMEOUT_MS));
+    run_blocking(move || {
+        let attempt = acquire(&path, mode, timeout)?;
+        if attempt.acquired {
+            return Ok(attempt.lock);
+        }
+        Err(match attempt.lock.owner {
+            Some(owner) => format!(
+                "{} is locked by {} on {} (pid {}) since {}",
+                attempt.lock.path, owner.user, owner.host, owner.pid, owner.acquired_at
+            ),
+            None => format!("{} is locked by another process", attempt.lock.path),
+        })
     })
     .await
-    .unwrap_or_else(|e| Err(format!("Thread join error: {e}")))
+}
+
+/// Like `lock_file`, but a file that stays locked is not an error: the
+/// attempt reports who holds it. `timeoutMs` defaults to 0 (try once).
+#[tauri::command]
+pub async fn try_lock_file(
+    path: String,
+    options: Option<LockOptions>,
+) -> Result<LockAttempt, String> {
+    let options = options.unwrap_or_default();
+    let mode = if options.shared {
+        LockMode::Shared
+    } else {
+        LockMode::Exclusive
+    };
+    let timeout = Duration::from_millis(options.timeout_ms.unwrap_or(0));
+    run_blocking(move || acquire(&path, mode, timeout)).await
 }
 
 /// Releases the lock on a previously locked file and removes it from the global lock map.
@@ -55,23 +395,23 @@ pub async fn lock_file(path: String) -> Result<(), String> {
 /// This command is exposed to the Tauri frontend via `invoke('unlock_file')`.
 /// It attempts to unlock the file associated with the provided path and
 /// deletes the corresponding file handle from the internal tracking map.
+/// A shared lock taken several times is released by the last unlock.
 ///
 /// # Arguments
-/// * `path` - The absolute path to the file to be unlocked.
+/// * `path` - The path to the file to be unlocked.
 ///
 /// # Errors
 /// Returns a `String` error if the file is not found in the lock map or if unlocking fails.
 #[tauri::command]
 pub fn unlock_file(path: String) -> Result<(), String> {
-    let mut map = FILE_LOCKS.lock().unwrap();
+    release_path(&path)
+}
 
-    if let Some(file) = map.remove(&path) {
-        FileExt::unlock(&file)
-            .map_err(|e| format!("Failed to unlock file: {e}"))?;
-        Ok(())
-    } else {
-        Err("No lock found for the specified path".into())
-    }
+/// Locks held by this app, plus the lock state and holder of each of `paths`
+/// (e.g. files on a network share another workstation may hold).
+#[tauri::command]
+pub async fn list_locks(paths: Option<Vec<String>>) -> Result<Vec<LockInfo>, String> {
+    run_blocking(move || Ok(lock_infos(&paths.unwrap_or_default()))).await
 }
 
 /// Releases all currently held file locks and clears the internal lock map.
@@ -85,9 +425,9 @@ pub fn unlock_file(path: String) -> Result<(), String> {
 pub fn clear_all_locks() {
     let mut map = FILE_LOCKS.lock().unwrap();
 
-    for (path, file) in map.drain() {
-        if let Err(e) = fs2::FileExt::unlock(&file) {
+    for (path, held) in map.drain() {
+        if let Err(e) = release(&path, held) {
             eprintln!("Warning: Failed to unlock file '{}': {}", path, e);
         }
     }
-}
\ No newline at end of file
+}
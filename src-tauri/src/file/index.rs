@@ -0,0 +1,210 @@
+use std::collections::HashSet;
+use std::fs;
+use std::path::Path;
+use std::time::Instant;
+
+use serde::{Deserialize, Serialize};
+use sqlx::{Row, Sqlite, Transaction};
+
+use super::file_io::get_js_time_ms;
+use crate::crypto::sha256_files;
+use crate::db::repo::upsert_rows;
+use crate::db::tables::FileIndexRow;
+
+// =============================================================================
+// Incremental file index
+//
+// Brings `file_index` up to date for a set of files. A file whose mtime
+// matches its row (and that already has a content hash) is skipped; the rest are
+// hashed (SHA-256 over the contents, in parallel). A new path whose hash
+// matches a row of a file that no longer exists is a rename/move: the row and
+// every reference to it (`wafer_maps`, `product_defect_map`,
+// `substrate_defect`, `wafer_map_choice`) move to the new path instead of
+// being re-ingested.
+// =============================================================================
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct IndexOptions {
+    /// Re-hash files even when their mtime is unchanged
+    pub force: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MovedFile {
+    pub from: String,
+    pub to: String,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct IndexReport {
+    pub unchanged: u32,
+    pub added: Vec<String>,
+    /// Content changed (or was not hashed before)
+    pub modified: Vec<String>,
+    /// mtime changed, same content
+    pub touched: Vec<String>,
+    pub moved: Vec<MovedFile>,
+    /// Missing or unreadable
+    pub failed: Vec<String>,
+    pub elapsed_ms: f64,
+}
+
+struct Stale {
+    path: String,
+    mtime: i64,
+    known: Option<Option<String>>,
+}
+
+/// Whether a stored hash is a SHA-256 content digest; older rows hold SHA-1
+/// digests of the path, which say nothing about the contents.
+pub(crate) fn is_content_hash(hash: &str) -> bool {
+    hash.len() == 64 && hash.bytes().all(|b| b.is_ascii_hexdigit())
+}
+
+/// Tables whose `file_path` references `file_index`.
+const REFERENCING: [&str; 4] = [
+    "wafer_maps",
+    "product_defect_map",
+    "substrate_defect",
+    "wafer_map_choice",
+];
+
+pub async fn index_files(
+    tx: &mut Transaction<'_, Sqlite>,
+    paths: &[String],
+    options: &IndexOptions,
+) -> Result<IndexReport, String> {
+    let start = Instant::now();
+    let db_err = |e: sqlx::Error| format!("Failed to update file_index: {}", e);
+    let mut report = IndexReport::default();
+
+    let mut stale = Vec::new();
+    for path in paths {
+        let mtime = match fs::metadata(path) {
+            Ok(meta) if meta.is_file() => get_js_time_ms(meta.modified()).unwrap_or(0.0) as i64,
+            _ => {
+                report.failed.push(path.clone());
+                continue;
+            }
+        };
+        let row = sqlx::query("SELECT last_mtime, file_hash FROM file_index WHERE file_path = ?")
+            .bind(path)
+            .fetch_optional(&mut **tx)
+            .await
+            .map_err(db_err)?;
+        let known = match row {
+            Some(row) => {
+                let last: i64 = row.try_get("last_mtime").map_err(db_err)?;
+                let hash: Option<String> = row.try_get("file_hash").map_err(db_err)?;
+                if !options.force && last == mtime && hash.as_deref().is_some_and(is_content_hash) {
+                    report.unchanged += 1;
+                    continue;
+                }
+                Some(hash)
+            }
+            None => None,
+        };
+        stale.push(Stale {
+            path: path.clone(),
+            mtime,
+            known,
+        });
+    }
+
+    let to_hash: Vec<String> = stale.iter().map(|s| s.path.clone()).collect();
+    let hashes = tauri::async_runtime::spawn_blocking(move || sha256_files(&to_hash))
+        .await
+        .map_err(|e| format!("Hashing failed: {}", e))?;
+    // a vanished row can be claimed by one new path only
+    let mut claimed: HashSet<String> = HashSet::new();
+
+    for (file, hash) in stale.into_iter().zip(hashes) {
+        let Some(hash) = hash else {
+            report.failed.push(file.path);
+            continue;
+        };
+
+        if file.known.is_none() {
+            let candidates: Vec<String> = sqlx::query_scalar(
+                "SELECT file_path FROM file_index WHERE file_hash = ? AND file_path != ?",
+            )
+            .bind(&hash)
+            .bind(&file.path)
+            .fetch_all(&mut **tx)
+            .await
+            .map_err(db_err)?;
+            let from = candidates
+                .into_iter()
+                .find(|p| !claimed.contains(p) && !Path::new(p).exists());
+            if let Some(from) = from {
+                move_row(tx, &from, &file.path, file.mtime).await?;
+                claimed.insert(from.clone());
+                report.moved.push(MovedFile {
+                    from,
+                    to: file.path,
+                });
+                continue;
+            }
+        }
+
+        upsert_rows(
+            tx,
+            &[FileIndexRow {
+                file_path: file.path.clone(),
+                last_mtime: file.mtime,
+                file_hash: Some(hash.clone()),
+            }],
+        )
+        .await?;
+
+        match file.known {
+            None => report.added.push(file.path),
+            Some(Some(previous)) if previous == hash => report.touched.push(file.path),
+            Some(_) => report.modified.push(file.path),
+        }
+    }
+
+    report.elapsed_ms = start.elapsed().as_secs_f64() * 1000.0;
+    Ok(report)
+}
+
+/// Re-keys a `file_index` row; the foreign keys have no ON UPDATE, so the
+/// new row is inserted, references repointed, then the old row dropped.
+async fn move_row(
+    tx: &mut Transaction<'_, Sqlite>,
+    from: &str,
+    to: &str,
+    mtime: i64,
+) -> Result<(), String> {
+    let db_err = |e: sqlx::Error| format!("Failed to move {} to {}: {}", from, to, e);
+    sqlx::query(
+        "INSERT INTO file_index (file_path, last_mtime, file_hash) \
+         SELECT ?, ?, file_hash FROM file_index WHERE file_path = ?",
+    )
+    .bind(to)
+    .bind(mtime)
+    .bind(from)
+    .execute(&mut **tx)
+    .await
+    .map_err(db_err)?;
+    for table in REFERENCING {
+        sqlx::query(&format!(
+            "UPDATE {} SET file_path = ? WHERE file_path = ?",
+            table
+        ))
+        .bind(to)
+        .bind(from)
+        .execute(&mut **tx)
+        .await
+        .map_err(db_err)?;
+    }
+    sqlx::query("DELETE FROM file_index WHERE file_path = ?")
+        .bind(from)
+        .execute(&mut **tx)
+        .await
+        .map_err(db_err)?;
+    Ok(())
+}
@@ -0,0 +1,111 @@
+use chrono::NaiveDateTime;
+
+use super::scanner::{local_to_utc, DataSourceType};
+use crate::db::tables::WaferMapMetaRow;
+use crate::parser::{parse_wafer, parse_wafer_bin, parse_wafer_map_data};
+use crate::wafer::bins::BinSet;
+use crate::wafer::ds::AsciiDie;
+use crate::wafer::stats::summarize;
+
+// =============================================================================
+// Wafer map headers for search
+//
+// Parses a wafer map with the reader of its stage (as ingestion does) and
+// keeps the searchable header: device, operator, measurement time, totals and
+// the bins present. Totals come from the header when the format has one.
+// =============================================================================
+
+/// Header times seen in the prober formats; local time, like name times.
+pub(crate) fn meas_time_ms(text: &str) -> Option<i64> {
+    let text = text.trim();
+    ["%Y-%m-%d %H:%M:%S", "%Y/%m/%d %H:%M:%S", "%Y%m%d%H%M%S"]
+        .iter()
+        .find_map(|fmt| NaiveDateTime::parse_from_str(text, fmt).ok())
+        .or_else(|| {
+            chrono::NaiveDate::parse_from_str(text, "%Y-%m-%d")
+                .ok()
+                .and_then(|d| d.and_hms_opt(0, 0, 0))
+        })
+        .and_then(|t| local_to_utc(&t))
+        .map(|t| t.timestamp_millis())
+}
+
+fn non_empty(text: &str) -> Option<String> {
+    Some(text.trim().to_string()).filter(|t| !t.is_empty())
+}
+
+fn bins_present(dies: &[AsciiDie]) -> String {
+    summarize(dies, &BinSet::default_pass())
+        .bins
+        .into_iter()
+        .map(|b| b.bin)
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Header of the map at `path`; `None` for sources without wafer maps.
+pub fn read_map_meta(
+    path: &str,
+    stage: DataSourceType,
+    sub_stage: Option<u32>,
+) -> Result<Option<WaferMapMetaRow>, String> {
+    let file_path = path.to_string();
+    let meta = match stage {
+        DataSourceType::Substrate => return Ok(None),
+        DataSourceType::CpProber if matches!(sub_stage, Some(1) | Some(2)) => {
+            map_data_meta(file_path)?
+        }
+        DataSourceType::Aoi => map_data_meta(file_path)?,
+        DataSourceType::Wlbi => {
+            let data = parse_wafer_bin(file_path.clone())?;
+            let dies: Vec<AsciiDie> = data
+                .map
+                .iter()
+                .map(|d| AsciiDie {
+                    x: d.x,
+                    y: d.y,
+                    bin: d.bin,
+                })
+                .collect();
+            let summary = summarize(&dies, &BinSet::default_pass());
+            WaferMapMetaRow {
+                file_path,
+                device: non_empty(&data.product),
+                operator: None,
+                meas_time: None,
+                total_tested: Some(summary.total_tested as i64),
+                total_pass: Some(summary.total_pass as i64),
+                yield_percentage: Some(summary.yield_percentage),
+                bins: bins_present(&dies),
+            }
+        }
+        DataSourceType::CpProber | DataSourceType::FabCp => {
+            let wafer = parse_wafer(file_path.clone())?;
+            WaferMapMetaRow {
+                file_path,
+                device: non_empty(&wafer.device),
+                operator: non_empty(&wafer.operator),
+                meas_time: meas_time_ms(&wafer.meas_time),
+                total_tested: Some(wafer.gross_die as i64),
+                total_pass: Some(wafer.pass_die as i64),
+                yield_percentage: Some(wafer.total_yield),
+                bins: bins_present(&wafer.map.dies),
+            }
+        }
+    };
+    Ok(Some(meta))
+}
+
+fn map_data_meta(file_path: String) -> Result<WaferMapMetaRow, String> {
+    let data = parse_wafer_map_data(file_path.clone())?;
+    Ok(WaferMapMetaRow {
+        file_path,
+        device: non_empty(&data.device_name),
+        operator: None,
+        meas_time: None,
+        total_tested: Some(data.total_tested as i64),
+        total_pass: Some(data.total_pass as i64),
+        yield_percentage: Some(data.yield_percent),
+        bins: bins_present(&data.map.dies),
+    })
+}
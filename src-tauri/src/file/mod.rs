@@ -1,7 +1,12 @@
 mod tests;
 
+pub mod duplicates;
 pub mod file_io;
 pub mod file_lock;
+pub mod index;
+pub mod meta;
+pub mod scanner;
+pub mod watcher;
 
 use calamine::{open_workbook, Xls};
 use std::fs::{metadata, File};
@@ -0,0 +1,552 @@
+use std::fs;
+use std::path::Path;
+use std::time::Instant;
+
+use chrono::{DateTime, Local, NaiveDate, NaiveDateTime, TimeZone, Utc};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+use super::file_io::get_js_time_ms;
+
+// =============================================================================
+// Data-source scanner
+//
+// Walks a data root once for the data-source page (`utils/dataSource.ts`):
+// folders whose name matches a source regex become source folders, and every
+// file below one is matched against the fixed layout of that source:
+//
+//   Substrate   Defect list/<id>.xls, <oem>_<yyyymmdd><hhmmss>.xlsx, Product list.xlsx
+//   FAB CP      BinMap/<lot>/<oem>_<lot>_<wafer>.txt
+//   CP-prober   <product>_<lot>_<stage>_<retest>/<product>_<lot>_<wafer>/<product>_<lot>_<wafer>_mapEx.txt
+//   WLBI        <product>_<lot>_<stage>_<retest>/WaferMap/<lot>_<wafer>_<yyyymmdd>_<hhmmss>.WaferMap
+//   AOI         <product>_<lot>/<product>_<lot>_<wafer>_<yyyymmdd><hhmmss>.txt
+// =============================================================================
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum DataSourceType {
+    Substrate,
+    FabCp,
+    CpProber,
+    Wlbi,
+    Aoi,
+}
+
+impl DataSourceType {
+    /// Name as in the frontend's `DataSourceType` and `wafer_maps.stage`.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            DataSourceType::Substrate => "substrate",
+            DataSourceType::FabCp => "fabCp",
+            DataSourceType::CpProber => "cpProber",
+            DataSourceType::Wlbi => "wlbi",
+            DataSourceType::Aoi => "aoi",
+        }
+    }
+
+    pub const ALL: [DataSourceType; 5] = [
+        DataSourceType::Substrate,
+        DataSourceType::FabCp,
+        DataSourceType::CpProber,
+        DataSourceType::Wlbi,
+        DataSourceType::Aoi,
+    ];
+}
+
+/// Folder-name regex per source, same shape as `DataSourceRegexState`.
+/// Patterns are unanchored like JS `RegExp#test`; an empty pattern disables
+/// the source.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct SourcePatterns {
+    pub substrate: String,
+    pub fab_cp: String,
+    pub cp_prober: String,
+    pub wlbi: String,
+    pub aoi: String,
+}
+
+impl Default for SourcePatterns {
+    fn default() -> Self {
+        Self {
+            substrate: "Substrate".to_string(),
+            fab_cp: "FAB CP".to_string(),
+            cp_prober: "CP-prober-[A-Za-z0-9]+".to_string(),
+            wlbi: "WLBI-[A-Za-z0-9]+".to_string(),
+            aoi: "AOI-[A-Za-z0-9]+".to_string(),
+        }
+    }
+}
+
+impl SourcePatterns {
+    fn get(&self, source: DataSourceType) -> &str {
+        match source {
+            DataSourceType::Substrate => &self.substrate,
+            DataSourceType::FabCp => &self.fab_cp,
+            DataSourceType::CpProber => &self.cp_prober,
+            DataSourceType::Wlbi => &self.wlbi,
+            DataSourceType::Aoi => &self.aoi,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct ScanOptions {
+    pub regex: SourcePatterns,
+    /// Directory levels below the root searched for source folders
+    pub max_depth: usize,
+    /// Source folders to read as given (e.g. picked by hand) instead of
+    /// recognising them below the root; only used by a scan
+    pub folders: Vec<SourceFolder>,
+}
+
+impl Default for ScanOptions {
+    fn default() -> Self {
+        Self {
+            regex: SourcePatterns::default(),
+            max_depth: 3,
+            folders: Vec::new(),
+        }
+    }
+}
+
+/// What a matched file is; the Excel kinds serialize like `ExcelType`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum FileKind {
+    #[serde(rename = "waferMap")]
+    WaferMap,
+    #[serde(rename = "defectList")]
+    DefectList,
+    #[serde(rename = "productDefectMapping")]
+    Product,
+    #[serde(rename = "productMapping")]
+    Mapping,
+}
+
+/// One recognised file with the metadata encoded in its path.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ScannedFile {
+    pub stage: DataSourceType,
+    pub kind: FileKind,
+    /// Product model; for FAB CP the OEM model, mapped by the caller
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub product_model: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub batch: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub wafer_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub process_sub_stage: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub retest_count: Option<u32>,
+    /// Defect list number (substrate)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+    /// OEM product (substrate product sheet)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub oem: Option<String>,
+    /// Timestamp from the file name, ISO 8601 UTC (local time in the name)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub time: Option<String>,
+    pub file_path: String,
+    /// Epoch milliseconds
+    pub last_modified: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SourceFolder {
+    pub stage: DataSourceType,
+    pub path: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ScanReport {
+    pub root: String,
+    /// Folders recognised per source; a folder matching several patterns is
+    /// listed once per source
+    pub folders: Vec<SourceFolder>,
+    pub files: Vec<ScannedFile>,
+    /// Files under a source folder whose names disagree with their folders
+    pub misaligned: Vec<String>,
+    /// Directories that could not be read
+    pub unreadable: Vec<String>,
+    pub dirs_visited: u32,
+    pub files_visited: u32,
+    pub elapsed_ms: f64,
+}
+
+struct Patterns {
+    sources: Vec<(DataSourceType, Regex)>,
+    defect_list_folder: Regex,
+    defect_xls: Regex,
+    product_xlsx: Regex,
+    product_list: Regex,
+    process_folder: Regex,
+    cp_wafer_folder: Regex,
+    cp_file: Regex,
+    wlbi_folder: Regex,
+    wlbi_file: Regex,
+    bin_map_folder: Regex,
+    lot_folder: Regex,
+    fab_cp_file: Regex,
+    aoi_folder: Regex,
+    aoi_file: Regex,
+}
+
+impl Patterns {
+    fn new(regex: &SourcePatterns) -> Result<Self, String> {
+        let mut sources = Vec::new();
+        for source in DataSourceType::ALL {
+            let pattern = regex.get(source);
+            if pattern.is_empty() {
+                continue;
+            }
+            let re = Regex::new(pattern)
+                .map_err(|e| format!("Invalid regex for {:?} ({}): {}", source, pattern, e))?;
+            sources.push((source, re));
+        }
+        let fixed = |p: &str| Regex::new(p).expect("built-in pattern");
+        Ok(Self {
+            sources,
+            defect_list_folder: fixed(r"^Defect list$"),
+            defect_xls: fixed(r"^([A-Za-z0-9]+)\.xls$"),
+            product_xlsx: fixed(r"^([A-Za-z0-9]+)_([0-9]{8})([0-9]{6})\.xlsx$"),
+            product_list: fixed(r"^Product list\.xlsx$"),
+            process_folder: fixed(r"^([A-Za-z0-9]+)_([A-Za-z0-9]+)_(\d+)_(\d+)$"),
+            cp_wafer_folder: fixed(r"^([A-Za-z0-9]+)_([A-Za-z0-9]+)_(\d+)$"),
+            cp_file: fixed(r"^([A-Za-z0-9]+)_([A-Za-z0-9]+)_(\d+)_mapEx\.txt$"),
+            wlbi_folder: fixed(r"^WaferMap$"),
+            wlbi_file: fixed(r"^([A-Za-z0-9]+)_([0-9]+)_([0-9]{8})_([0-9]{6})\.WaferMap$"),
+            bin_map_folder: fixed(r"^BinMap$"),
+            lot_folder: fixed(r"^([A-Za-z0-9]+)$"),
+            fab_cp_file: fixed(r"^([A-Za-z0-9]+)_([A-Za-z0-9]+)_(\d+)\.txt$"),
+            aoi_folder: fixed(r"^([A-Za-z0-9]+)_([A-Za-z0-9]+)$"),
+            aoi_file: fixed(r"^([A-Za-z0-9]+)_([A-Za-z0-9]+)_([0-9]+)_([0-9]{8})([0-9]{6})\.txt$"),
+        })
+    }
+}
+
+/// `yyyymmdd` + `hhmmss` in local time → ISO 8601 UTC, like
+/// `parseWaferMapTimestamp(...).toISOString()`.
+fn timestamp(date: &str, time: &str) -> Option<String> {
+    let num = |s: &str, r: std::ops::Range<usize>| s.get(r)?.parse::<u32>().ok();
+    let naive =
+        NaiveDate::from_ymd_opt(num(date, 0..4)? as i32, num(date, 4..6)?, num(date, 6..8)?)?
+            .and_hms_opt(num(time, 0..2)?, num(time, 2..4)?, num(time, 4..6)?)?;
+    Some(
+        local_to_utc(&naive)?
+            .format("%Y-%m-%dT%H:%M:%S%.3fZ")
+            .to_string(),
+    )
+}
+
+/// Wall-clock times in names and headers are the tester's local time.
+pub(crate) fn local_to_utc(naive: &NaiveDateTime) -> Option<DateTime<Utc>> {
+    Local
+        .from_local_datetime(naive)
+        .earliest()
+        .map(|t| t.with_timezone(&Utc))
+}
+
+fn file_record(stage: DataSourceType, kind: FileKind, path: &str, mtime: f64) -> ScannedFile {
+    ScannedFile {
+        stage,
+        kind,
+        product_model: None,
+        batch: None,
+        wafer_id: None,
+        process_sub_stage: None,
+        retest_count: None,
+        id: None,
+        oem: None,
+        time: None,
+        file_path: path.to_string(),
+        last_modified: mtime,
+    }
+}
+
+pub(crate) enum Matched {
+    File(Box<ScannedFile>),
+    Misaligned,
+    None,
+}
+
+/// Matches a file against the layout of its source. `rel` holds the folder
+/// names between the source folder and the file, then the file name.
+fn classify(p: &Patterns, stage: DataSourceType, rel: &[&str], path: &str, mtime: f64) -> Matched {
+    let record = |kind| file_record(stage, kind, path, mtime);
+    let cap = |re: &Regex, s: &str| -> Option<Vec<String>> {
+        re.captures(s).map(|c| {
+            c.iter()
+                .skip(1)
+                .map(|m| m.map_or(String::new(), |m| m.as_str().to_string()))
+                .collect()
+        })
+    };
+
+    match (stage, rel) {
+        (DataSourceType::Substrate, [folder, name]) if p.defect_list_folder.is_match(folder) => {
+            match cap(&p.defect_xls, name) {
+                Some(g) => Matched::File(Box::new(ScannedFile {
+                    id: Some(g[0].clone()),
+                    ..record(FileKind::DefectList)
+                })),
+                None => Matched::None,
+            }
+        }
+        (DataSourceType::Substrate, [name]) => {
+            if p.product_list.is_match(name) {
+                Matched::File(Box::new(record(FileKind::Mapping)))
+            } else if let Some(g) = cap(&p.product_xlsx, name) {
+                Matched::File(Box::new(ScannedFile {
+                    oem: Some(g[0].clone()),
+                    time: timestamp(&g[1], &g[2]),
+                    ..record(FileKind::Product)
+                }))
+            } else {
+                Matched::None
+            }
+        }
+        (DataSourceType::CpProber, [process, wafer, name]) => {
+            let (Some(proc), Some(_), Some(file)) = (
+                cap(&p.process_folder, process),
+                cap(&p.cp_wafer_folder, wafer),
+                cap(&p.cp_file, name),
+            ) else {
+                return Matched::None;
+            };
+            if file[0] != proc[0] || file[1] != proc[1] {
+                return Matched::Misaligned;
+            }
+            Matched::File(Box::new(ScannedFile {
+                product_model: Some(proc[0].clone()),
+                batch: Some(proc[1].clone()),
+                wafer_id: Some(file[2].clone()),
+                process_sub_stage: proc[2].parse().ok(),
+                retest_count: proc[3].parse().ok(),
+                ..record(FileKind::WaferMap)
+            }))
+        }
+        (DataSourceType::Wlbi, [process, folder, name]) if p.wlbi_folder.is_match(folder) => {
+            let (Some(proc), Some(file)) =
+                (cap(&p.process_folder, process), cap(&p.wlbi_file, name))
+            else {
+                return Matched::None;
+            };
+            if file[0] != proc[1] {
+                return Matched::Misaligned;
+            }
+            Matched::File(Box::new(ScannedFile {
+                product_model: Some(proc[0].clone()),
+                batch: Some(proc[1].clone()),
+                wafer_id: Some(file[1].clone()),
+                process_sub_stage: proc[2].parse().ok(),
+                retest_count: proc[3].parse().ok(),
+                time: timestamp(&file[2], &file[3]),
+                ..record(FileKind::WaferMap)
+            }))
+        }
+        (DataSourceType::FabCp, [folder, lot, name]) if p.bin_map_folder.is_match(folder) => {
+            let (Some(_), Some(file)) = (cap(&p.lot_folder, lot), cap(&p.fab_cp_file, name)) else {
+                return Matched::None;
+            };
+            if file[1] != *lot {
+                return Matched::Misaligned;
+            }
+            Matched::File(Box::new(ScannedFile {
+                product_model: Some(file[0].clone()),
+                batch: Some(file[1].clone()),
+                wafer_id: Some(file[2].clone()),
+                ..record(FileKind::WaferMap)
+            }))
+        }
+        (DataSourceType::Aoi, [folder, name]) => {
+            let (Some(lot), Some(file)) = (cap(&p.aoi_folder, folder), cap(&p.aoi_file, name))
+            else {
+                return Matched::None;
+            };
+            if file[0] != lot[0] || file[1] != lot[1] {
+                return Matched::Misaligned;
+            }
+            Matched::File(Box::new(ScannedFile {
+                product_model: Some(lot[0].clone()),
+                batch: Some(lot[1].clone()),
+                wafer_id: Some(file[2].clone()),
+                time: timestamp(&file[3], &file[4]),
+                ..record(FileKind::WaferMap)
+            }))
+        }
+        _ => Matched::None,
+    }
+}
+
+struct Walker<'a> {
+    patterns: &'a Patterns,
+    max_depth: usize,
+    report: ScanReport,
+}
+
+impl Walker<'_> {
+    /// `depth` is the level of the entries of `dir` below the root; `sources`
+    /// are the source folders `dir` is in, with the folder names below each.
+    fn walk(&mut self, dir: &Path, depth: usize, sources: &[(DataSourceType, Vec<String>)]) {
+        self.report.dirs_visited += 1;
+        let mut entries: Vec<fs::DirEntry> = match fs::read_dir(dir) {
+            Ok(entries) => entries.flatten().collect(),
+            Err(_) => {
+                self.report
+                    .unreadable
+                    .push(dir.to_string_lossy().to_string());
+                return;
+            }
+        };
+        entries.sort_by_key(|e| e.file_name());
+
+        for entry in entries {
+            // symlinks are not followed, so the walk cannot loop
+            let Ok(file_type) = entry.file_type() else {
+                continue;
+            };
+            let path = entry.path();
+            let path_str = path.to_string_lossy().to_string();
+            let name = entry.file_name().to_string_lossy().to_string();
+
+            if file_type.is_dir() {
+                let nested: Vec<(DataSourceType, Vec<String>)> = if sources.is_empty() {
+                    // only folders outside any source folder are candidates
+                    self.patterns
+                        .sources
+                        .iter()
+                        .filter(|(_, re)| re.is_match(&name))
+                        .map(|(stage, _)| (*stage, Vec::new()))
+                        .collect()
+                } else {
+                    sources
+                        .iter()
+                        .map(|(stage, rel)| {
+                            let mut rel = rel.clone();
+                            rel.push(name.clone());
+                            (*stage, rel)
+                        })
+                        .collect()
+                };
+                if sources.is_empty() {
+                    for (stage, _) in &nested {
+                        self.report.folders.push(SourceFolder {
+                            stage: *stage,
+                            path: path_str.clone(),
+                        });
+                    }
+                }
+                if !nested.is_empty() || depth < self.max_depth {
+                    self.walk(&path, depth + 1, &nested);
+                }
+            } else if file_type.is_file() {
+                self.report.files_visited += 1;
+                if sources.is_empty() {
+                    continue;
+                }
+                let mtime =
+                    get_js_time_ms(entry.metadata().and_then(|m| m.modified())).unwrap_or(0.0);
+                for (stage, rel) in sources {
+                    let mut parts: Vec<&str> = rel.iter().map(String::as_str).collect();
+                    parts.push(&name);
+                    match classify(self.patterns, *stage, &parts, &path_str, mtime) {
+                        Matched::File(file) => self.report.files.push(*file),
+                        Matched::Misaligned => self.report.misaligned.push(path_str.clone()),
+                        Matched::None => {}
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Classifies single paths the way a scan of `root` would, for callers that
+/// learn about files one by one (the watcher).
+pub(crate) struct Classifier {
+    patterns: Patterns,
+    max_depth: usize,
+}
+
+impl Classifier {
+    pub fn new(options: &ScanOptions) -> Result<Self, String> {
+        Ok(Self {
+            patterns: Patterns::new(&options.regex)?,
+            max_depth: options.max_depth,
+        })
+    }
+
+    /// One result per source folder `path` is in; empty when it is in none.
+    pub fn classify(&self, root: &Path, path: &Path, mtime: f64) -> Vec<Matched> {
+        let Ok(rel) = path.strip_prefix(root) else {
+            return Vec::new();
+        };
+        let parts: Vec<String> = rel
+            .components()
+            .map(|c| c.as_os_str().to_string_lossy().to_string())
+            .collect();
+        let path_str = path.to_string_lossy();
+        // the file name itself is never a source folder
+        let levels = parts.len().saturating_sub(1).min(self.max_depth.max(1));
+        for level in 0..levels {
+            let stages: Vec<DataSourceType> = self
+                .patterns
+                .sources
+                .iter()
+                .filter(|(_, re)| re.is_match(&parts[level]))
+                .map(|(stage, _)| *stage)
+                .collect();
+            if stages.is_empty() {
+                continue;
+            }
+            let below: Vec<&str> = parts[level + 1..].iter().map(String::as_str).collect();
+            return stages
+                .into_iter()
+                .map(|stage| classify(&self.patterns, stage, &below, &path_str, mtime))
+                .collect();
+        }
+        Vec::new()
+    }
+}
+
+/// Scans `root` in one pass. Source folders are searched up to
+/// `options.max_depth` levels down; below a source folder the walk follows
+/// the whole subtree. With `options.folders` only those folders are walked.
+pub fn scan_data_sources(root: &str, options: &ScanOptions) -> Result<ScanReport, String> {
+    let start = Instant::now();
+    let root_path = Path::new(root);
+    if options.folders.is_empty() && !root_path.is_dir() {
+        return Err(format!("Not a directory: {}", root));
+    }
+    let patterns = Patterns::new(&options.regex)?;
+    let mut walker = Walker {
+        patterns: &patterns,
+        max_depth: options.max_depth,
+        report: ScanReport {
+            root: root.to_string(),
+            folders: Vec::new(),
+            files: Vec::new(),
+            misaligned: Vec::new(),
+            unreadable: Vec::new(),
+            dirs_visited: 0,
+            files_visited: 0,
+            elapsed_ms: 0.0,
+        },
+    };
+    if options.folders.is_empty() {
+        walker.walk(root_path, 1, &[]);
+    } else {
+        for folder in &options.folders {
+            walker.report.folders.push(folder.clone());
+            walker.walk(Path::new(&folder.path), 1, &[(folder.stage, Vec::new())]);
+        }
+    }
+    let mut report = walker.report;
+    report.elapsed_ms = start.elapsed().as_secs_f64() * 1000.0;
+    Ok(report)
+}
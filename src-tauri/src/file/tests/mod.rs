@@ -96,3 +96,440 @@ fn read_xls_valid_defect_list() {
         "Workbook should not be empty (PL defect list)"
     );
 }
+
+#[test]
+fn scan_data_sources_classifies_tree() {
+    use super::scanner::{scan_data_sources, DataSourceType, FileKind, ScanOptions, SourceFolder};
+    use std::{env, fs};
+    let root = env::temp_dir().join("scan_data_sources_tree");
+    let _ = fs::remove_dir_all(&root);
+    let files = [
+        "data/Substrate/Defect list/86107919CNF1.xls",
+        "data/Substrate/S1M032120B_20250709120302.xlsx",
+        "data/Substrate/Product list.xlsx",
+        "data/CP-prober-01/S1M032120B_B003332_2_1/S1M032120B_B003332_01/S1M032120B_B003332_01_mapEx.txt",
+        "data/WLBI-01/S1M032120B_003332_2_0/WaferMap/003332_05_20250709_120302.WaferMap",
+        "data/FAB CP/BinMap/B003990/P0097B_B003990_02.txt",
+        "data/AOI-01/S1M040120B_B003990/S1M040120B_B003990_02_20250721095040.txt",
+        "data/AOI-01/S1M040120B_B003990/S1M040120B_B009999_03_20250721095040.txt",
+        "data/AOI-01/notes.txt",
+        "data/other/readme.txt",
+    ];
+    for f in files {
+        let path = root.join(f);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(&path, "").unwrap();
+    }
+
+    let report = scan_data_sources(root.to_str().unwrap(), &ScanOptions::default()).unwrap();
+    let stages: Vec<DataSourceType> = report.folders.iter().map(|f| f.stage).collect();
+    assert_eq!(
+        stages,
+        vec![
+            DataSourceType::Aoi,
+            DataSourceType::CpProber,
+            DataSourceType::FabCp,
+            DataSourceType::Substrate,
+            DataSourceType::Wlbi,
+        ]
+    );
+    assert_eq!(report.files_visited, files.len() as u32);
+    assert_eq!(report.files.len(), 7);
+    assert_eq!(report.misaligned.len(), 1);
+
+    let cp = report
+        .files
+        .iter()
+        .find(|f| f.stage == DataSourceType::CpProber)
+        .unwrap();
+    assert_eq!(cp.product_model.as_deref(), Some("S1M032120B"));
+    assert_eq!(cp.batch.as_deref(), Some("B003332"));
+    assert_eq!(cp.wafer_id.as_deref(), Some("01"));
+    assert_eq!((cp.process_sub_stage, cp.retest_count), (Some(2), Some(1)));
+
+    let wlbi = report
+        .files
+        .iter()
+        .find(|f| f.stage == DataSourceType::Wlbi)
+        .unwrap();
+    assert_eq!(wlbi.wafer_id.as_deref(), Some("05"));
+    assert!(wlbi.time.as_deref().unwrap().ends_with('Z'));
+
+    let kinds: Vec<FileKind> = report
+        .files
+        .iter()
+        .filter(|f| f.stage == DataSourceType::Substrate)
+        .map(|f| f.kind)
+        .collect();
+    assert_eq!(
+        kinds,
+        vec![FileKind::DefectList, FileKind::Mapping, FileKind::Product]
+    );
+
+    // folders given by hand are read whatever their names
+    let aoi = root.join("data/AOI-01").display().to_string();
+    let options = ScanOptions {
+        folders: vec![SourceFolder {
+            stage: DataSourceType::Aoi,
+            path: aoi,
+        }],
+        ..ScanOptions::default()
+    };
+    let picked = scan_data_sources("", &options).unwrap();
+    assert_eq!(picked.folders.len(), 1);
+    assert_eq!(picked.files.len(), 1);
+    assert_eq!(picked.files[0].wafer_id.as_deref(), Some("02"));
+
+    let mut options = ScanOptions::default();
+    options.regex.aoi = "(".to_string();
+    assert!(scan_data_sources(root.to_str().unwrap(), &options).is_err());
+    let _ = fs::remove_dir_all(&root);
+}
+
+#[test]
+fn classifier_matches_single_paths_like_scan() {
+    use super::scanner::{Classifier, DataSourceType, Matched, ScanOptions};
+    use std::path::Path;
+    let classifier = Classifier::new(&ScanOptions::default()).unwrap();
+    let root = Path::new("/data");
+
+    let matched = classifier.classify(
+        root,
+        Path::new("/data/lab/AOI-02/S1M040120B_B003990/S1M040120B_B003990_07_20250721095040.txt"),
+        1.0,
+    );
+    assert_eq!(matched.len(), 1);
+    let Matched::File(file) = &matched[0] else {
+        panic!("expected a file");
+    };
+    assert_eq!(file.stage, DataSourceType::Aoi);
+    assert_eq!(file.wafer_id.as_deref(), Some("07"));
+
+    // outside any source folder, and outside the root
+    assert!(classifier
+        .classify(root, Path::new("/data/lab/readme.txt"), 1.0)
+        .is_empty());
+    assert!(classifier
+        .classify(
+            root,
+            Path::new("/elsewhere/AOI-02/a_b/a_b_1_20250101000000.txt"),
+            1.0
+        )
+        .is_empty());
+    // too deep to be a source folder
+    assert!(classifier
+        .classify(
+            root,
+            Path::new("/data/a/b/c/AOI-02/S1M_B1/S1M_B1_01_20250721095040.txt"),
+            1.0
+        )
+        .is_empty());
+}
+
+#[test]
+fn header_times_are_local_like_name_times() {
+    use super::meta::meas_time_ms;
+    use super::scanner::{Classifier, Matched, ScanOptions};
+    use chrono::{DateTime, Local, TimeZone};
+    use std::path::Path;
+
+    let local = |d, h, mi| {
+        Local
+            .with_ymd_and_hms(2024, 3, d, h, mi, 0)
+            .earliest()
+            .map(|t| t.timestamp_millis())
+    };
+    // either side of midnight stays on its own local date
+    assert_eq!(meas_time_ms("2024-03-01 23:30:00"), local(1, 23, 30));
+    assert_eq!(meas_time_ms("2024/03/02 00:30:00"), local(2, 0, 30));
+    assert_eq!(meas_time_ms("20240302003000"), local(2, 0, 30));
+    assert_eq!(meas_time_ms("2024-03-02"), local(2, 0, 0));
+
+    // the same instant as the time in a file name
+    let classifier = Classifier::new(&ScanOptions::default()).unwrap();
+    let matched = classifier.classify(
+        Path::new("/data"),
+        Path::new("/data/lab/AOI-02/S1M040120B_B003990/S1M040120B_B003990_07_20240302003000.txt"),
+        1.0,
+    );
+    let Matched::File(file) = &matched[0] else {
+        panic!("expected a file");
+    };
+    let named = DateTime::parse_from_rfc3339(file.time.as_deref().unwrap()).unwrap();
+    assert_eq!(Some(named.timestamp_millis()), local(2, 0, 30));
+}
+
+#[test]
+fn index_files_skips_unchanged_and_detects_moves() {
+    use super::index::{index_files, IndexOptions};
+    use sqlx::sqlite::SqlitePoolOptions;
+    use std::{env, fs};
+
+    let dir = env::temp_dir().join("index_files_moves");
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    let a = dir.join("a.txt").to_string_lossy().to_string();
+    let b = dir.join("b.txt").to_string_lossy().to_string();
+    fs::write(&a, "wafer map").unwrap();
+
+    tauri::async_runtime::block_on(async {
+        // one connection, or every connection gets its own in-memory database
+        let pool = SqlitePoolOptions::new()
+            .max_connections(1)
+            .connect("sqlite::memory:")
+            .await
+            .unwrap();
+        for sql in [
+            "CREATE TABLE file_index (file_path TEXT PRIMARY KEY, last_mtime INTEGER NOT NULL, file_hash TEXT)",
+            "CREATE TABLE wafer_maps (file_path TEXT NOT NULL UNIQUE)",
+            "CREATE TABLE product_defect_map (file_path TEXT NOT NULL)",
+            "CREATE TABLE substrate_defect (file_path TEXT NOT NULL)",
+            "CREATE TABLE wafer_map_choice (file_path TEXT NOT NULL)",
+        ] {
+            sqlx::query(sql).execute(&pool).await.unwrap();
+        }
+        let run = |paths: Vec<String>| {
+            let pool = pool.clone();
+            async move {
+                let mut tx = pool.begin().await.unwrap();
+                let report = index_files(&mut tx, &paths, &IndexOptions::default())
+                    .await
+                    .unwrap();
+                tx.commit().await.unwrap();
+                report
+            }
+        };
+
+        let first = run(vec![a.clone()]).await;
+        assert_eq!(first.added, vec![a.clone()]);
+        sqlx::query("INSERT INTO wafer_maps (file_path) VALUES (?)")
+            .bind(&a)
+            .execute(&pool)
+            .await
+            .unwrap();
+
+        let second = run(vec![a.clone()]).await;
+        assert_eq!(second.unchanged, 1);
+        assert!(second.added.is_empty() && second.modified.is_empty());
+
+        // a legacy SHA-1 of the path is not a content hash, so it is redone
+        sqlx::query("UPDATE file_index SET file_hash = ? WHERE file_path = ?")
+            .bind("da39a3ee5e6b4b0d3255bfef95601890afd80709")
+            .bind(&a)
+            .execute(&pool)
+            .await
+            .unwrap();
+        assert_eq!(run(vec![a.clone()]).await.modified, vec![a.clone()]);
+        assert_eq!(run(vec![a.clone()]).await.unchanged, 1);
+
+        fs::rename(&a, &b).unwrap();
+        let third = run(vec![b.clone(), a.clone()]).await;
+        assert_eq!(third.moved.len(), 1);
+        assert_eq!(
+            (third.moved[0].from.as_str(), third.moved[0].to.as_str()),
+            (a.as_str(), b.as_str())
+        );
+        assert_eq!(third.failed, vec![a.clone()]);
+        let maps: Vec<String> = sqlx::query_scalar("SELECT file_path FROM wafer_maps")
+            .fetch_all(&pool)
+            .await
+            .unwrap();
+        assert_eq!(maps, vec![b.clone()]);
+    });
+    let _ = fs::remove_dir_all(&dir);
+}
+
+#[cfg(test)]
+fn map_file(
+    idx: i64,
+    wafer: i64,
+    retest: i64,
+    hash: Option<&str>,
+) -> super::duplicates::WaferMapFile {
+    super::duplicates::WaferMapFile {
+        idx,
+        product_id: "S1M032120B".to_string(),
+        batch_id: "B003332".to_string(),
+        wafer_id: wafer,
+        stage: "cpProber".to_string(),
+        sub_stage: Some("1".to_string()),
+        retest_count: retest,
+        time: Some(1_000 * idx),
+        file_path: format!("/data/{}.txt", idx),
+        // a SHA-256 digest is 64 hex digits
+        file_hash: hash.map(|h| h.repeat(32)),
+    }
+}
+
+#[test]
+fn duplicate_report_groups_hashes_retests_and_conflicts() {
+    use super::duplicates::{duplicate_report, WaferMapFile};
+    use crate::db::tables::WaferMapChoiceRow;
+    let files = vec![
+        // wafer 1: retest 0, a copy of it, then retest 1
+        map_file(1, 1, 0, Some("aa")),
+        map_file(2, 1, 0, Some("aa")),
+        map_file(3, 1, 1, Some("bb")),
+        // wafer 2: two different maps for the same retest
+        map_file(4, 2, 0, Some("cc")),
+        map_file(5, 2, 0, None),
+        // wafer 3: alone, but the same bytes as wafer 2's first map
+        map_file(6, 3, 0, Some("cc")),
+        // wafer 4: indexed before content hashes, so a SHA-1 of its path
+        WaferMapFile {
+            file_hash: Some("da39a3ee5e6b4b0d3255bfef95601890afd80709".to_string()),
+            ..map_file(7, 4, 0, None)
+        },
+    ];
+    let pick = |wafer_id: i64, path: &str| WaferMapChoiceRow {
+        product_id: "S1M032120B".to_string(),
+        batch_id: "B003332".to_string(),
+        wafer_id,
+        stage: "cpProber".to_string(),
+        sub_stage: "1".to_string(),
+        file_path: path.to_string(),
+    };
+    // wafer 2 keeps its first map; wafer 1's pick is no longer indexed
+    let choices = [pick(2, "/data/4.txt"), pick(1, "/data/9.txt")];
+    let report = duplicate_report(&files, &choices);
+
+    assert_eq!((report.maps, report.unhashed), (7, 2));
+    assert_eq!(report.duplicates.len(), 2);
+    assert!(!report.duplicates[0].keys_differ); // "aa": same wafer
+    assert!(report.duplicates[1].keys_differ); // "cc": wafers 2 and 3
+
+    assert_eq!(report.retest_chains.len(), 1);
+    assert_eq!(report.retest_chains[0].key.wafer_id, 1);
+    assert_eq!(report.retest_chains[0].retests, vec![0, 1]);
+
+    // wafer 1 retest 0 has one content; wafer 2 has two
+    assert_eq!(report.conflicts.len(), 1);
+    assert_eq!(report.conflicts[0].key.wafer_id, 2);
+    assert_eq!(report.conflicts[0].files.len(), 2);
+
+    let recommended: Vec<&str> = report
+        .choices
+        .iter()
+        .map(|c| c.recommended.as_str())
+        .collect();
+    assert_eq!(recommended, vec!["/data/3.txt", "/data/5.txt"]);
+    let chosen: Vec<Option<&str>> = report
+        .choices
+        .iter()
+        .map(|c| c.chosen.as_deref())
+        .collect();
+    assert_eq!(chosen, vec![None, Some("/data/4.txt")]);
+    assert_eq!(report.choices[1].files, vec!["/data/4.txt", "/data/5.txt"]);
+}
+
+#[test]
+fn file_locks_share_readers_time_out_and_name_the_holder() {
+    use super::file_lock::{acquire, lock_infos, release_path, LockMode, LockOwner};
+    use fs2::FileExt;
+    use std::time::{Duration, Instant};
+    use std::{env, fs};
+
+    let dir = env::temp_dir().join("file_locks_holder");
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(dir.join("sub")).unwrap();
+    // lock paths are canonical; the temp folder may be a symlink
+    let dir = fs::canonicalize(&dir).unwrap();
+    let path = dir.join("map.txt").to_string_lossy().to_string();
+    // another spelling of the same file
+    let alias = dir
+        .join("sub")
+        .join("..")
+        .join("map.txt")
+        .to_string_lossy()
+        .to_string();
+    let sidecar = format!("{}.lock-owner", path);
+    let none = Duration::ZERO;
+
+    // readers need the file; the first writer creates it
+    assert!(acquire(&path, LockMode::Shared, none).is_err());
+    let lock = acquire(&path, LockMode::Exclusive, none).unwrap().lock;
+    let owner = lock.owner.unwrap();
+    assert_eq!(
+        (lock.path.as_str(), owner.pid),
+        (path.as_str(), std::process::id())
+    );
+    let recorded: LockOwner = serde_json::from_str(&fs::read_to_string(&sidecar).unwrap()).unwrap();
+    assert_eq!(recorded, owner);
+    assert!(acquire(&alias, LockMode::Exclusive, none).is_err());
+    release_path(&alias).unwrap();
+    assert!(!std::path::Path::new(&sidecar).exists());
+
+    // shared locks are counted and leave no sidecar
+    acquire(&path, LockMode::Shared, none).unwrap();
+    let lock = acquire(&alias, LockMode::Shared, none).unwrap().lock;
+    assert_eq!((lock.holders, lock.held_here), (2, true));
+    assert!(!std::path::Path::new(&sidecar).exists());
+    release_path(&path).unwrap();
+    assert_eq!(lock_infos(&[]).iter().filter(|l| l.path == path).count(), 1);
+    release_path(&path).unwrap();
+    assert!(release_path(&path).is_err());
+
+    // another workstation holds the file exclusively
+    let other = fs::OpenOptions::new()
+        .read(true)
+        .write(true)
+        .open(&path)
+        .unwrap();
+    other.lock_exclusive().unwrap();
+    let foreign = LockOwner {
+        host: "qa-station-2".into(),
+        pid: 4242,
+        user: "operator".into(),
+        mode: LockMode::Exclusive,
+        acquired_at: "2026-10-18T08:00:00+00:00".into(),
+    };
+    fs::write(&sidecar, serde_json::to_string(&foreign).unwrap()).unwrap();
+
+    let start = Instant::now();
+    let attempt = acquire(&path, LockMode::Shared, Duration::from_millis(250)).unwrap();
+    assert!(start.elapsed() >= Duration::from_millis(250));
+    assert!(!attempt.acquired);
+    assert_eq!(attempt.lock.owner.as_ref(), Some(&foreign));
+    let listed = lock_infos(std::slice::from_ref(&alias));
+    let listed = listed.iter().find(|l| l.path == path).unwrap();
+    assert!(listed.locked && !listed.held_here);
+    assert_eq!(
+        listed.owner.as_ref().map(|o| o.host.as_str()),
+        Some("qa-station-2")
+    );
+
+    // released: the stale sidecar is not reported
+    FileExt::unlock(&other).unwrap();
+    let listed = lock_infos(std::slice::from_ref(&path));
+    let listed = listed.iter().find(|l| l.path == path).unwrap();
+    assert!(!listed.locked && listed.owner.is_none());
+    assert!(acquire(&path, LockMode::Exclusive, none).unwrap().acquired);
+    release_path(&path).unwrap();
+    let _ = fs::remove_dir_all(&dir);
+}
+
+#[cfg(unix)]
+#[test]
+fn file_locks_release_a_deleted_file() {
+    use super::file_lock::{acquire, lock_infos, release_path, LockMode};
+    use std::time::Duration;
+    use std::{env, fs};
+
+    let dir = env::temp_dir().join("file_locks_deleted");
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    // not the canonical spelling, so it maps to the key only while the file exists
+    // (Windows refuses to delete a locked file, hence unix only)
+    let path = dir.join(".").join("map.txt").to_string_lossy().to_string();
+    let lock = acquire(&path, LockMode::Exclusive, Duration::ZERO)
+        .unwrap()
+        .lock;
+    let sidecar = format!("{}.lock-owner", lock.path);
+    assert!(std::path::Path::new(&sidecar).exists());
+
+    fs::remove_file(&path).unwrap();
+    release_path(&path).unwrap();
+    assert!(!std::path::Path::new(&sidecar).exists());
+    assert!(lock_infos(&[]).iter().all(|l| l.path != lock.path));
+    let _ = fs::remove_dir_all(&dir);
+}
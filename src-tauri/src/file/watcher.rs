@@ -0,0 +1,385 @@
+use std::collections::{BTreeSet, HashMap};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use chrono::DateTime;
+use notify_debouncer_mini::notify::{RecommendedWatcher, RecursiveMode};
+use notify_debouncer_mini::{new_debouncer, DebounceEventResult, Debouncer};
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use sqlx::SqlitePool;
+use tauri::{AppHandle, Emitter, Manager};
+use tauri_plugin_sql::DbInstances;
+
+use super::file_io::get_js_time_ms;
+use super::index::{index_files, IndexOptions};
+use super::meta::read_map_meta;
+use super::scanner::{Classifier, FileKind, Matched, ScanOptions, ScannedFile};
+use crate::db::repo::upsert_rows;
+use crate::db::tables::{WaferMapMetaRow, WaferMapRow};
+
+// =============================================================================
+// Live ingestion
+//
+// Watches data roots recursively. Events are debounced per root; each batch
+// of changed paths is classified like a scan, wafer maps are reparsed for
+// their searchable header (which also keeps a half-written file out), and
+// `file_index` (incrementally, with move detection) and `wafer_maps` are
+// updated in one transaction before `DATA_SOURCE_CHANGED` is emitted.
+// Substrate Excel files are indexed only; the frontend re-ingests them.
+// =============================================================================
+
+/// Event emitted after each processed batch, payload `WatchBatch`.
+pub const DATA_SOURCE_CHANGED: &str = "data-source-changed";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct WatchOptions {
+    #[serde(flatten)]
+    pub scan: ScanOptions,
+    pub debounce_ms: u64,
+}
+
+impl Default for WatchOptions {
+    fn default() -> Self {
+        Self {
+            scan: ScanOptions::default(),
+            debounce_ms: 2000,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ChangeKind {
+    Added,
+    Modified,
+    /// Same contents as a vanished indexed file, see `from`
+    Moved,
+    Removed,
+    /// Recognised but unreadable or not parseable (yet)
+    Failed,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WatchChange {
+    pub path: String,
+    pub kind: ChangeKind,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub from: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub file: Option<ScannedFile>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WatchBatch {
+    pub root: String,
+    pub changes: Vec<WatchChange>,
+    /// Set when the database update failed; `changes` are then not stored
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// Active watchers by root; dropping a debouncer stops its watch.
+static WATCHERS: Lazy<Mutex<HashMap<String, Debouncer<RecommendedWatcher>>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// Starts watching `root`, replacing a previous watch of the same root.
+pub fn start_watch(app: AppHandle, root: String, options: WatchOptions) -> Result<(), String> {
+    let root_path = PathBuf::from(&root);
+    if !root_path.is_dir() {
+        return Err(format!("Not a directory: {}", root));
+    }
+    let classifier = Arc::new(Classifier::new(&options.scan)?);
+
+    let handler_root = root.clone();
+    let mut debouncer = new_debouncer(
+        Duration::from_millis(options.debounce_ms.max(1)),
+        move |result: DebounceEventResult| {
+            let paths: BTreeSet<PathBuf> = match result {
+                Ok(events) => events.into_iter().map(|e| e.path).collect(),
+                Err(e) => {
+                    eprintln!("⚠️ [watcher] {}: {}", handler_root, e);
+                    return;
+                }
+            };
+            let app = app.clone();
+            let root = handler_root.clone();
+            let classifier = classifier.clone();
+            tauri::async_runtime::spawn(async move {
+                let batch = process_batch(&app, &root, &classifier, paths).await;
+                if !batch.changes.is_empty() || batch.error.is_some() {
+                    if let Err(e) = app.emit(DATA_SOURCE_CHANGED, &batch) {
+                        eprintln!("⚠️ [watcher] Failed to emit event: {}", e);
+                    }
+                }
+            });
+        },
+    )
+    .map_err(|e| format!("Failed to create watcher: {}", e))?;
+
+    debouncer
+        .watcher()
+        .watch(&root_path, RecursiveMode::Recursive)
+        .map_err(|e| format!("Failed to watch {}: {}", root, e))?;
+
+    WATCHERS.lock().unwrap().insert(root, debouncer);
+    Ok(())
+}
+
+/// Stops watching `root`; returns whether it was watched.
+pub fn stop_watch(root: &str) -> bool {
+    WATCHERS.lock().unwrap().remove(root).is_some()
+}
+
+pub fn watched_roots() -> Vec<String> {
+    let mut roots: Vec<String> = WATCHERS.lock().unwrap().keys().cloned().collect();
+    roots.sort();
+    roots
+}
+
+/// Stops all watchers; called on exit.
+pub fn stop_all_watches() {
+    WATCHERS.lock().unwrap().clear();
+}
+
+/// Files at or below `path` (a copied-in folder arrives as one event).
+fn files_under(path: &Path, out: &mut Vec<PathBuf>) {
+    let Ok(meta) = fs::symlink_metadata(path) else {
+        return;
+    };
+    if meta.is_file() {
+        out.push(path.to_path_buf());
+    } else if meta.is_dir() {
+        if let Ok(entries) = fs::read_dir(path) {
+            for entry in entries.flatten() {
+                files_under(&entry.path(), out);
+            }
+        }
+    }
+}
+
+/// A file that still exists, with what is known about it.
+struct Present {
+    path: String,
+    files: Vec<ScannedFile>,
+    meta: Option<WaferMapMetaRow>,
+    error: Option<String>,
+}
+
+async fn process_batch(
+    app: &AppHandle,
+    root: &str,
+    classifier: &Classifier,
+    paths: BTreeSet<PathBuf>,
+) -> WatchBatch {
+    let root_path = Path::new(root);
+    let mut present = Vec::new();
+    let mut removed = Vec::new();
+    for path in paths {
+        if !path.exists() {
+            removed.push(path.to_string_lossy().to_string());
+            continue;
+        }
+        let mut files = Vec::new();
+        files_under(&path, &mut files);
+        for file in files {
+            let Ok(meta) = fs::metadata(&file) else {
+                continue;
+            };
+            let mtime = get_js_time_ms(meta.modified()).unwrap_or(0.0);
+            let mut entry = Present {
+                path: file.to_string_lossy().to_string(),
+                files: Vec::new(),
+                meta: None,
+                error: None,
+            };
+            for matched in classifier.classify(root_path, &file, mtime) {
+                if let Matched::File(scanned) = matched {
+                    // also rejects a half-written map
+                    match read_map_meta(&entry.path, scanned.stage, scanned.process_sub_stage) {
+                        Ok(meta) if scanned.kind == FileKind::WaferMap => entry.meta = meta,
+                        Ok(_) => {}
+                        Err(e) => entry.error = Some(e),
+                    }
+                    entry.files.push(*scanned);
+                }
+            }
+            if !entry.files.is_empty() {
+                present.push(entry);
+            }
+        }
+    }
+
+    let mut batch = WatchBatch {
+        root: root.to_string(),
+        changes: Vec::new(),
+        error: None,
+    };
+    let result = match crate::db::app_pool(&app.state::<DbInstances>()).await {
+        Ok(pool) => apply(&pool, present, removed, &mut batch.changes).await,
+        Err(e) => Err(e),
+    };
+    if let Err(e) = result {
+        batch.error = Some(e);
+    }
+    batch
+}
+
+/// Epoch ms of a scanned file's name timestamp.
+fn name_time_ms(file: &ScannedFile) -> Option<i64> {
+    file.time
+        .as_deref()
+        .and_then(|t| DateTime::parse_from_rfc3339(t).ok())
+        .map(|t| t.timestamp_millis())
+}
+
+async fn apply(
+    pool: &SqlitePool,
+    present: Vec<Present>,
+    removed: Vec<String>,
+    changes: &mut Vec<WatchChange>,
+) -> Result<(), String> {
+    let db_err = |e: sqlx::Error| format!("Failed to update index: {}", e);
+    let mut tx = pool.begin().await.map_err(db_err)?;
+
+    // present files first, so a move is matched before its old path is dropped
+    for entry in present {
+        let first = entry.files.first().cloned();
+        if let Some(error) = entry.error {
+            changes.push(WatchChange {
+                path: entry.path,
+                kind: ChangeKind::Failed,
+                from: None,
+                file: first,
+                error: Some(error),
+            });
+            continue;
+        }
+        // wafer_maps.product_id references oem_product_map; FAB CP names
+        // carry the OEM model, the others the internal product
+        let mut products = Vec::new();
+        for file in entry.files.iter().filter(|f| f.kind == FileKind::WaferMap) {
+            let model = file.product_model.clone().unwrap_or_default();
+            let product: Option<String> = sqlx::query_scalar(
+                "SELECT product_id FROM oem_product_map \
+                 WHERE product_id = ?1 OR oem_product_id = ?1 LIMIT 1",
+            )
+            .bind(&model)
+            .fetch_optional(&mut *tx)
+            .await
+            .map_err(db_err)?;
+            products.push((file, product.ok_or(model)));
+        }
+        if let Some(Err(model)) = products.iter().map(|(_, p)| p).find(|p| p.is_err()) {
+            changes.push(WatchChange {
+                path: entry.path,
+                kind: ChangeKind::Failed,
+                from: None,
+                file: first,
+                error: Some(format!("Unknown product {}", model)),
+            });
+            continue;
+        }
+
+        let report = index_files(
+            &mut tx,
+            std::slice::from_ref(&entry.path),
+            &IndexOptions::default(),
+        )
+        .await?;
+        let (kind, from) = if let Some(moved) = report.moved.into_iter().next() {
+            (ChangeKind::Moved, Some(moved.from))
+        } else if !report.added.is_empty() {
+            (ChangeKind::Added, None)
+        } else if !report.modified.is_empty() || !report.touched.is_empty() {
+            (ChangeKind::Modified, None)
+        } else if report.unchanged > 0 {
+            continue;
+        } else {
+            changes.push(WatchChange {
+                path: entry.path,
+                kind: ChangeKind::Failed,
+                from: None,
+                file: first,
+                error: Some("Unreadable".to_string()),
+            });
+            continue;
+        };
+
+        let rows: Vec<WaferMapRow> = products
+            .into_iter()
+            .map(|(file, product)| WaferMapRow {
+                idx: None,
+                product_id: product.unwrap_or_default(),
+                batch_id: file.batch.clone().unwrap_or_default(),
+                wafer_id: file
+                    .wafer_id
+                    .as_deref()
+                    .and_then(|w| w.parse().ok())
+                    .unwrap_or(0),
+                stage: file.stage.as_str().to_string(),
+                sub_stage: Some(file.process_sub_stage.unwrap_or(0).to_string()),
+                retest_count: file.retest_count.unwrap_or(0) as i64,
+                time: Some(name_time_ms(file).unwrap_or(0)),
+                file_path: entry.path.clone(),
+            })
+            .collect();
+        upsert_rows(&mut tx, &rows).await?;
+        // wafer_map_meta references wafer_maps, so only with a map row
+        if let Some(meta) = entry.meta.filter(|_| !rows.is_empty()) {
+            upsert_rows(&mut tx, &[meta]).await?;
+        }
+
+        changes.push(WatchChange {
+            path: entry.path,
+            kind,
+            from,
+            file: first,
+            error: None,
+        });
+    }
+
+    // whatever is left of a removed path (a moved file no longer is)
+    for path in removed {
+        // a removed folder takes everything below it
+        let prefix = format!("{}{}", path, std::path::MAIN_SEPARATOR);
+        let gone: Vec<String> = sqlx::query_scalar(
+            "SELECT file_path FROM file_index \
+             WHERE file_path = ?1 OR substr(file_path, 1, length(?2)) = ?2",
+        )
+        .bind(&path)
+        .bind(&prefix)
+        .fetch_all(&mut *tx)
+        .await
+        .map_err(db_err)?;
+        for file_path in gone {
+            sqlx::query("DELETE FROM wafer_maps WHERE file_path = ?")
+                .bind(&file_path)
+                .execute(&mut *tx)
+                .await
+                .map_err(db_err)?;
+            sqlx::query("DELETE FROM file_index WHERE file_path = ?")
+                .bind(&file_path)
+                .execute(&mut *tx)
+                .await
+                .map_err(db_err)?;
+            changes.push(WatchChange {
+                path: file_path,
+                kind: ChangeKind::Removed,
+                from: None,
+                file: None,
+                error: None,
+            });
+        }
+    }
+
+    tx.commit().await.map_err(db_err)?;
+    Ok(())
+}
@@ -0,0 +1,140 @@
+mod routes;
+mod tests;
+
+use once_cell::sync::Lazy;
+use serde::Serialize;
+use std::net::{Ipv4Addr, SocketAddr};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread::JoinHandle;
+use tiny_http::Server;
+
+use crate::cli::Context;
+
+// =============================================================================
+// Local HTTP/JSON API (`http-api` feature)
+//
+// Parse, stack, export, stats and inference status for scripts on the same
+// machine (MES), without the UI. Started from the app (`rust_http_api_start`)
+// or headless (`aoi-stack-cli serve`). Listens on 127.0.0.1 only; routes and
+// bodies are described by `GET /api/openapi.json` (see `openapi.json`).
+// =============================================================================
+
+pub const DEFAULT_PORT: u16 = 8765;
+
+/// Requests handled at the same time; stacking itself runs on rayon.
+const WORKERS: usize = 4;
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HttpApiStatus {
+    /// Built with the `http-api` feature
+    pub enabled: bool,
+    pub running: bool,
+    /// `http://127.0.0.1:<port>` while running
+    pub url: Option<String>,
+}
+
+/// A listening server and its worker threads.
+struct Listener {
+    server: Arc<Server>,
+    port: u16,
+    stopping: Arc<AtomicBool>,
+    workers: Vec<JoinHandle<()>>,
+}
+
+impl Listener {
+    /// Port 0 picks a free port.
+    fn bind(context: Context, port: u16) -> Result<Self, String> {
+        let server = Server::http(SocketAddr::from((Ipv4Addr::LOCALHOST, port)))
+            .map_err(|e| format!("Failed to listen on 127.0.0.1:{}: {}", port, e))?;
+        let port = server
+            .server_addr()
+            .to_ip()
+            .map(|addr| addr.port())
+            .unwrap_or(port);
+        let server = Arc::new(server);
+        let context = Arc::new(context);
+        let stopping = Arc::new(AtomicBool::new(false));
+        let workers = (0..WORKERS)
+            .map(|_| {
+                let (server, context) = (server.clone(), context.clone());
+                let stopping = stopping.clone();
+                std::thread::spawn(move || loop {
+                    match server.recv() {
+                        Ok(request) => routes::handle(request, &context, port),
+                        Err(_) if stopping.load(Ordering::SeqCst) => break,
+                        Err(err) => eprintln!("⚠️ [http] Failed to receive request: {}", err),
+                    }
+                })
+            })
+            .collect();
+        Ok(Self {
+            server,
+            port,
+            stopping,
+            workers,
+        })
+    }
+
+    fn url(&self) -> String {
+        format!("http://127.0.0.1:{}", self.port)
+    }
+
+    /// Lets requests in progress finish, then joins the workers.
+    fn shutdown(self) {
+        self.stopping.store(true, Ordering::SeqCst);
+        for _ in &self.workers {
+            self.server.unblock();
+        }
+        for worker in self.workers {
+            let _ = worker.join();
+        }
+    }
+}
+
+/// Server started from the app; at most one.
+static RUNNING: Lazy<Mutex<Option<Listener>>> = Lazy::new(|| Mutex::new(None));
+
+pub fn status() -> HttpApiStatus {
+    let running = RUNNING.lock().unwrap();
+    HttpApiStatus {
+        enabled: true,
+        running: running.is_some(),
+        url: running.as_ref().map(Listener::url),
+    }
+}
+
+/// Starts the server in the background; a running one is restarted on the
+/// new port.
+pub fn start(context: Context, port: u16) -> Result<HttpApiStatus, String> {
+    stop();
+    let listener = Listener::bind(context, port)?;
+    println!("🌐 HTTP API listening on {}", listener.url());
+    *RUNNING.lock().unwrap() = Some(listener);
+    Ok(status())
+}
+
+/// Stops the server started with `start`; called on exit.
+pub fn stop() -> HttpApiStatus {
+    let listener = RUNNING.lock().unwrap().take();
+    if let Some(listener) = listener {
+        listener.shutdown();
+        println!("🌐 HTTP API stopped");
+    }
+    status()
+}
+
+/// Serves in the foreground until the process ends (`aoi-stack-cli serve`).
+pub fn serve(context: Context, port: u16) -> Result<(), String> {
+    let listener = Listener::bind(context, port)?;
+    println!("🌐 HTTP API listening on {}", listener.url());
+    println!(
+        "   {}/api/openapi.json describes the routes",
+        listener.url()
+    );
+    for worker in listener.workers {
+        let _ = worker.join();
+    }
+    Ok(())
+}
@@ -0,0 +1,327 @@
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::io::Read;
+use tiny_http::{Header, Method, Request, Response};
+
+use crate::cli::{stack_wafers, Context, StackRequest};
+use crate::commands;
+use crate::db::repo;
+use crate::wafer::bin_map::BinMapTable;
+use crate::wafer::ds::{BinMapData, HexMapData, MapData, Wafer};
+
+// =============================================================================
+// Routes
+//
+// POST bodies carry the same arguments as the Tauri command of the same name
+// (camelCase, as passed to `invoke`); operation errors are the command's
+// error strings, answered as `{ "error": ... }` with status 422.
+// =============================================================================
+
+pub(super) const OPENAPI: &str = include_str!("openapi.json");
+
+/// Every route, for 405 answers and the OpenAPI check in the tests.
+pub(super) const ROUTES: [(&str, &str); 14] = [
+    ("GET", "/api/health"),
+    ("GET", "/api/openapi.json"),
+    ("GET", "/api/inference/status"),
+    ("GET", "/api/stats"),
+    ("POST", "/api/parse/wafer"),
+    ("POST", "/api/parse/wafer-bin"),
+    ("POST", "/api/parse/map-data"),
+    ("POST", "/api/parse/substrate-defects"),
+    ("POST", "/api/export/wafer"),
+    ("POST", "/api/export/wafer-bin"),
+    ("POST", "/api/export/map-data"),
+    ("POST", "/api/export/hex"),
+    ("POST", "/api/export/fab"),
+    ("POST", "/api/stack"),
+];
+
+/// Map JSON of a large wafer is a few MB.
+const MAX_BODY: usize = 64 * 1024 * 1024;
+
+struct ApiError {
+    status: u16,
+    message: String,
+}
+
+impl ApiError {
+    fn new(status: u16, message: impl Into<String>) -> Self {
+        Self {
+            status,
+            message: message.into(),
+        }
+    }
+}
+
+/// An operation that ran and failed.
+fn failed(message: String) -> ApiError {
+    ApiError::new(422, message)
+}
+
+enum Reply {
+    Json(String),
+    NoContent,
+}
+
+fn json<T: Serialize>(value: &T) -> Result<Reply, ApiError> {
+    serde_json::to_string(value)
+        .map(Reply::Json)
+        .map_err(|e| ApiError::new(500, format!("Failed to serialize response: {}", e)))
+}
+
+fn json_header() -> Header {
+    Header::from_bytes(&b"Content-Type"[..], &b"application/json"[..]).unwrap()
+}
+
+pub(super) fn handle(mut request: Request, context: &Context, port: u16) {
+    let response = match route(&mut request, context, port) {
+        Ok(Reply::Json(body)) => Response::from_string(body).with_header(json_header()),
+        Ok(Reply::NoContent) => Response::from_string("").with_status_code(204),
+        Err(err) => Response::from_string(serde_json::json!({ "error": err.message }).to_string())
+            .with_status_code(err.status)
+            .with_header(json_header()),
+    };
+    let url = request.url().to_string();
+    if let Err(err) = request.respond(response) {
+        eprintln!("⚠️ [http] Failed to answer {}: {}", url, err);
+    }
+}
+
+fn header<'a>(request: &'a Request, name: &'static str) -> Option<&'a str> {
+    request
+        .headers()
+        .iter()
+        .find(|h| h.field.equiv(name))
+        .map(|h| h.value.as_str())
+}
+
+/// Only requests addressed to the loopback port are answered, so a web page
+/// cannot reach the API through a rebound DNS name.
+fn check_host(request: &Request, port: u16) -> Result<(), ApiError> {
+    let Some(host) = header(request, "Host") else {
+        return Ok(());
+    };
+    let allowed = [format!("127.0.0.1:{}", port), format!("localhost:{}", port)];
+    if allowed.iter().any(|a| a.eq_ignore_ascii_case(host.trim())) {
+        Ok(())
+    } else {
+        Err(ApiError::new(
+            403,
+            format!("Host '{}' is not allowed", host),
+        ))
+    }
+}
+
+/// JSON body of a POST. Requiring `application/json` keeps web pages from
+/// posting here: browsers preflight it, and preflights are never allowed.
+fn body<T: DeserializeOwned>(request: &mut Request) -> Result<T, ApiError> {
+    let is_json = header(request, "Content-Type")
+        .and_then(|v| v.split(';').next())
+        .is_some_and(|v| v.trim().eq_ignore_ascii_case("application/json"));
+    if !is_json {
+        return Err(ApiError::new(415, "Send the body as application/json"));
+    }
+    if request.body_length().is_some_and(|len| len > MAX_BODY) {
+        return Err(ApiError::new(413, "Body is too large"));
+    }
+    let mut text = String::new();
+    request
+        .as_reader()
+        .take(MAX_BODY as u64 + 1)
+        .read_to_string(&mut text)
+        .map_err(|e| ApiError::new(400, format!("Failed to read body: {}", e)))?;
+    if text.len() > MAX_BODY {
+        return Err(ApiError::new(413, "Body is too large"));
+    }
+    serde_json::from_str(&text).map_err(|e| ApiError::new(400, format!("Invalid body: {}", e)))
+}
+
+/// Value of `name` in a query string, percent-decoded.
+fn query_param(query: &str, name: &str) -> Option<String> {
+    query
+        .split('&')
+        .filter_map(|pair| pair.split_once('='))
+        .find(|(key, _)| *key == name)
+        .map(|(_, value)| percent_decode(value))
+}
+
+fn percent_decode(text: &str) -> String {
+    let bytes = text.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let escaped = match bytes[i] {
+            b'%' => bytes
+                .get(i + 1..i + 3)
+                .and_then(|hex| std::str::from_utf8(hex).ok())
+                .and_then(|hex| u8::from_str_radix(hex, 16).ok()),
+            _ => None,
+        };
+        match (escaped, bytes[i]) {
+            (Some(byte), _) => {
+                out.push(byte);
+                i += 3;
+                continue;
+            }
+            (None, b'+') => out.push(b' '),
+            (None, byte) => out.push(byte),
+        }
+        i += 1;
+    }
+    String::from_utf8_lossy(&out).into_owned()
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Health {
+    version: &'static str,
+    /// An app database is open (stats, stacking by OEM product)
+    database: bool,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+struct ParseArgs {
+    path: String,
+    bin_map: Option<BinMapTable>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct PathArgs {
+    path: String,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+struct ExportWaferArgs {
+    wafer: Wafer,
+    output_path: String,
+    bin_map: Option<BinMapTable>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+struct ExportWaferBinArgs {
+    wafer_bin: BinMapData,
+    output_path: String,
+    bin_map: Option<BinMapTable>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+struct ExportMapDataArgs {
+    data: MapData,
+    output_path: String,
+    bin_map: Option<BinMapTable>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+struct ExportHexArgs {
+    wafer_hex: HexMapData,
+    output_path: String,
+    bin_map: Option<BinMapTable>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+struct ExportFabArgs {
+    fab: Wafer,
+    output_path: String,
+    bin_map: Option<BinMapTable>,
+}
+
+fn route(request: &mut Request, context: &Context, port: u16) -> Result<Reply, ApiError> {
+    check_host(request, port)?;
+    let url = request.url().to_string();
+    let (path, query) = url.split_once('?').unwrap_or((&url, ""));
+    let method = request.method().clone();
+
+    match (&method, path) {
+        (Method::Get, "/api/health") => json(&Health {
+            version: env!("CARGO_PKG_VERSION"),
+            database: context.pool().is_some(),
+        }),
+        (Method::Get, "/api/openapi.json") => {
+            let mut spec: serde_json::Value = serde_json::from_str(OPENAPI)
+                .map_err(|e| ApiError::new(500, format!("Invalid openapi.json: {}", e)))?;
+            spec["info"]["version"] = env!("CARGO_PKG_VERSION").into();
+            json(&spec)
+        }
+        (Method::Get, "/api/inference/status") => json(&commands::rust_aoi_inference_status()),
+        (Method::Get, "/api/stats") => {
+            let oem = query_param(query, "oemProductId")
+                .ok_or_else(|| ApiError::new(400, "oemProductId is required"))?;
+            let pool = context
+                .pool()
+                .ok_or_else(|| ApiError::new(503, "No app database is open"))?;
+            let mut rows =
+                tauri::async_runtime::block_on(repo::stack_stats(pool, &oem)).map_err(failed)?;
+            if let Some(batch_id) = query_param(query, "batchId") {
+                rows.retain(|row| row.stats.batch_id == batch_id);
+            }
+            json(&rows)
+        }
+
+        (Method::Post, "/api/parse/wafer") => {
+            let args: ParseArgs = body(request)?;
+            json(&commands::rust_parse_wafer(args.path, args.bin_map).map_err(failed)?)
+        }
+        (Method::Post, "/api/parse/wafer-bin") => {
+            let args: ParseArgs = body(request)?;
+            json(&commands::rust_parse_wafer_bin(args.path, args.bin_map).map_err(failed)?)
+        }
+        (Method::Post, "/api/parse/map-data") => {
+            let args: ParseArgs = body(request)?;
+            json(&commands::rust_parse_wafer_map_data(args.path, args.bin_map).map_err(failed)?)
+        }
+        (Method::Post, "/api/parse/substrate-defects") => {
+            let args: PathArgs = body(request)?;
+            json(&commands::rust_parse_substrate_defect_xls(args.path).map_err(failed)?)
+        }
+
+        (Method::Post, "/api/export/wafer") => {
+            let args: ExportWaferArgs = body(request)?;
+            commands::rust_export_wafer(args.wafer, args.output_path, args.bin_map)
+                .map_err(failed)?;
+            Ok(Reply::NoContent)
+        }
+        (Method::Post, "/api/export/wafer-bin") => {
+            let args: ExportWaferBinArgs = body(request)?;
+            commands::rust_export_wafer_bin(args.wafer_bin, args.output_path, args.bin_map)
+                .map_err(failed)?;
+            Ok(Reply::NoContent)
+        }
+        (Method::Post, "/api/export/map-data") => {
+            let args: ExportMapDataArgs = body(request)?;
+            commands::rust_export_wafer_map_data(args.data, args.output_path, args.bin_map)
+                .map_err(failed)?;
+            Ok(Reply::NoContent)
+        }
+        (Method::Post, "/api/export/hex") => {
+            let args: ExportHexArgs = body(request)?;
+            commands::rust_export_wafer_hex(args.wafer_hex, args.output_path, args.bin_map)
+                .map_err(failed)?;
+            Ok(Reply::NoContent)
+        }
+        (Method::Post, "/api/export/fab") => {
+            let args: ExportFabArgs = body(request)?;
+            commands::rust_export_wafer_fab(args.fab, args.output_path, args.bin_map)
+                .map_err(failed)?;
+            Ok(Reply::NoContent)
+        }
+
+        (Method::Post, "/api/stack") => {
+            let stack: StackRequest = body(request)?;
+            json(&stack_wafers(context, &stack).map_err(failed)?)
+        }
+
+        (_, path) if ROUTES.iter().any(|(_, route)| *route == path) => Err(ApiError::new(
+            405,
+            format!("{} is not allowed on {}", method, path),
+        )),
+        _ => Err(ApiError::new(404, format!("No route {}", path))),
+    }
+}
@@ -0,0 +1,190 @@
+#[cfg(test)]
+async fn stats_pool() -> sqlx::SqlitePool {
+    use crate::db::migrations::{latest_version, migrate_up};
+    use crate::db::repo::upsert_many;
+    use crate::db::tables::WaferStackStatsRow;
+    use crate::wafer::stats::WaferStackStats;
+
+    let pool = sqlx::sqlite::SqlitePoolOptions::new()
+        .max_connections(1)
+        .connect("sqlite::memory:")
+        .await
+        .unwrap();
+    migrate_up(&pool, latest_version()).await.unwrap();
+    let rows: Vec<WaferStackStatsRow> = ["L1", "L2"]
+        .iter()
+        .map(|lot| WaferStackStatsRow {
+            stats: WaferStackStats {
+                oem_product_id: "OEM 1".into(),
+                batch_id: lot.to_string(),
+                wafer_id: "7".into(),
+                total_tested: 10,
+                total_pass: 9,
+                total_fail: 1,
+                yield_percentage: 90.0,
+                bin_counts: "{}".into(),
+                start_time: None,
+                stop_time: None,
+            },
+            stage_waterfall: None,
+        })
+        .collect();
+    upsert_many(&pool, &rows).await.unwrap();
+    pool
+}
+
+/// One request over a fresh connection; the status and the JSON body.
+#[cfg(test)]
+fn call(
+    port: u16,
+    host: &str,
+    method: &str,
+    path: &str,
+    body: Option<(&str, &str)>,
+) -> (u16, serde_json::Value) {
+    use std::io::{Read, Write};
+
+    let mut stream = std::net::TcpStream::connect(("127.0.0.1", port)).unwrap();
+    let (content_type, body) = body.unwrap_or(("application/json", ""));
+    write!(
+        stream,
+        "{} {} HTTP/1.1\r\nHost: {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        method,
+        path,
+        host,
+        content_type,
+        body.len(),
+        body
+    )
+    .unwrap();
+    let mut response = String::new();
+    stream.read_to_string(&mut response).unwrap();
+    let status = response[9..12].parse().unwrap();
+    let (_, body) = response.split_once("\r\n\r\n").unwrap();
+    (
+        status,
+        serde_json::from_str(body).unwrap_or(serde_json::Value::Null),
+    )
+}
+
+#[test]
+fn http_api_serves_commands_and_rejects_foreign_requests() {
+    use super::routes::ROUTES;
+    use super::Listener;
+    use crate::cli::Context;
+    use serde_json::json;
+
+    let dir = std::env::temp_dir().join("aoi_http_api");
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    let map = dir.join("P_L1_07_mapEx.txt");
+    let header: String = [
+        ("Device Name", "DEV1"),
+        ("Lot No.", "L1"),
+        ("Wafer ID", "7"),
+        ("Wafer Size", "6"),
+        ("Dice SizeX", "1200.000"),
+        ("Dice SizeY", "1400.000"),
+        ("Flat/Notch", "Down"),
+        ("Map Column", "5"),
+        ("Map Row", "2"),
+        ("Total Tested", "6"),
+        ("Total Pass", "5"),
+        ("Total Fail", "1"),
+        ("Yield", "83.33%"),
+    ]
+    .iter()
+    .map(|(key, value)| format!("{:<18}: {}\n", key, value))
+    .collect();
+    std::fs::write(&map, format!("{}\nS1115\n..3..\n", header)).unwrap();
+    let map = map.display().to_string();
+
+    let pool = tauri::async_runtime::block_on(stats_pool());
+    let listener = Listener::bind(Context::with_pool(pool), 0).unwrap();
+    let port = listener.port;
+    let host = format!("127.0.0.1:{}", port);
+    let get = |path: &str| call(port, &host, "GET", path, None);
+    let post = |path: &str, body: serde_json::Value| {
+        call(
+            port,
+            &host,
+            "POST",
+            path,
+            Some(("application/json", &body.to_string())),
+        )
+    };
+
+    let (status, health) = get("/api/health");
+    assert_eq!((status, health["database"].clone()), (200, json!(true)));
+
+    let (status, spec) = get("/api/openapi.json");
+    assert_eq!(status, 200);
+    assert_eq!(spec["info"]["version"], json!(env!("CARGO_PKG_VERSION")));
+    for (method, route) in ROUTES {
+        let operation = &spec["paths"][route][method.to_ascii_lowercase()];
+        assert!(
+            operation.is_object(),
+            "{} {} is not documented",
+            method,
+            route
+        );
+    }
+
+    let (status, rows) = get("/api/stats?oemProductId=OEM%201");
+    assert_eq!((status, rows.as_array().map(Vec::len)), (200, Some(2)));
+    let (_, rows) = get("/api/stats?oemProductId=OEM+1&batchId=L2");
+    assert_eq!(rows[0]["batch_id"], json!("L2"));
+    assert_eq!(get("/api/stats").0, 400);
+
+    let (status, data) = post("/api/parse/map-data", json!({ "path": map }));
+    assert_eq!(status, 200);
+    assert_eq!(data["lotNo"], json!("L1"));
+    let (status, error) = post("/api/parse/map-data", json!({ "path": "/no/such/map.txt" }));
+    assert_eq!(status, 422);
+    assert!(error["error"].is_string());
+
+    let copy = dir.join("copy.txt").display().to_string();
+    let (status, _) = post(
+        "/api/export/map-data",
+        json!({ "data": data, "outputPath": copy }),
+    );
+    assert_eq!(status, 204);
+    assert!(std::path::Path::new(&copy).is_file());
+
+    let out = dir.join("out").display().to_string();
+    let (status, results) = post(
+        "/api/stack",
+        json!({
+            "outputDir": out,
+            "layers": [{ "stage": "cp1", "path": map }],
+            "name": "L1_07",
+            "formats": ["mapEx", "svg"],
+            "noStats": true,
+        }),
+    );
+    assert_eq!(status, 200);
+    assert_eq!(results[0]["summary"]["totalTested"], json!(5));
+    assert_eq!(results[0]["files"].as_array().map(Vec::len), Some(2));
+    assert_eq!(post("/api/stack", json!({})).0, 422);
+    assert_eq!(
+        post("/api/stack", json!({ "outputDir": out, "oops": 1 })).0,
+        400
+    );
+
+    // browsers can post text/plain without a preflight
+    let body = json!({ "path": map }).to_string();
+    let text = call(
+        port,
+        &host,
+        "POST",
+        "/api/parse/map-data",
+        Some(("text/plain", &body)),
+    );
+    assert_eq!(text.0, 415);
+    let rebound = format!("evil.example:{}", port);
+    assert_eq!(call(port, &rebound, "GET", "/api/health", None).0, 403);
+    assert_eq!(get("/api/parse/wafer").0, 405);
+    assert_eq!(get("/api/nope").0, 404);
+
+    listener.shutdown();
+}
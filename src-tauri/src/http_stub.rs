@@ -0,0 +1,35 @@
+use serde::Serialize;
+
+use crate::cli::Context;
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HttpApiStatus {
+    pub enabled: bool,
+    pub running: bool,
+    pub url: Option<String>,
+}
+
+pub const DEFAULT_PORT: u16 = 8765;
+
+const DISABLED: &str = "This build was compiled without the http-api feature.";
+
+pub fn status() -> HttpApiStatus {
+    HttpApiStatus {
+        enabled: false,
+        running: false,
+        url: None,
+    }
+}
+
+pub fn start(_context: Context, _port: u16) -> Result<HttpApiStatus, String> {
+    Err(DISABLED.to_string())
+}
+
+pub fn stop() -> HttpApiStatus {
+    status()
+}
+
+pub fn serve(_context: Context, _port: u16) -> Result<(), String> {
+    Err(DISABLED.to_string())
+}
@@ -0,0 +1,642 @@
+mod tests;
+
+pub mod runner;
+mod stacking;
+
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sqlx::sqlite::SqliteRow;
+use sqlx::{Row, SqlitePool};
+use tauri::{AppHandle, Emitter};
+
+// =============================================================================
+// Stacking job queue
+//
+// Jobs and their wafers live in `stacking_jobs` / `stacking_job_tasks` (v5),
+// so a queue survives closing the window and restarts. The frontend only
+// enqueues jobs and follows `STACKING_JOB_CHANGED`; the wafers are stacked
+// in this process by `runner`, whose workers each lease one task at a time,
+// report progress and finish the task as done or failed. Failed tasks are
+// requeued until the job's `max_attempts`; `retry_job` requeues the ones
+// left failed or cancelled. No lease outlives the process, so `resume`
+// hands every leased task back to the queue when the runner starts.
+// =============================================================================
+
+/// Event emitted whenever a job or one of its tasks changes, payload `JobEvent`.
+pub const STACKING_JOB_CHANGED: &str = "stacking-job-changed";
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NewTask {
+    /// Shown in the queue, e.g. "LOT1 #3"
+    #[serde(default)]
+    pub label: String,
+    /// The wafer's job item (`stacking::QueuedWafer`), handed back with the lease
+    pub wafer: Value,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NewJob {
+    #[serde(default)]
+    pub name: String,
+    /// Processing options (`stacking::StackingOptions`), handed back with every lease
+    #[serde(default)]
+    pub options: Value,
+    pub wafers: Vec<NewTask>,
+    /// Tries per wafer before it stays failed
+    #[serde(default = "default_max_attempts")]
+    pub max_attempts: u32,
+}
+
+fn default_max_attempts() -> u32 {
+    1
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum TaskStatus {
+    Queued,
+    Leased,
+    Done,
+    Failed,
+    Cancelled,
+}
+
+impl TaskStatus {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            TaskStatus::Queued => "queued",
+            TaskStatus::Leased => "leased",
+            TaskStatus::Done => "done",
+            TaskStatus::Failed => "failed",
+            TaskStatus::Cancelled => "cancelled",
+        }
+    }
+
+    fn parse(text: &str) -> Result<Self, String> {
+        [
+            TaskStatus::Queued,
+            TaskStatus::Leased,
+            TaskStatus::Done,
+            TaskStatus::Failed,
+            TaskStatus::Cancelled,
+        ]
+        .into_iter()
+        .find(|s| s.as_str() == text)
+        .ok_or_else(|| format!("Unknown task status: {}", text))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum JobStatus {
+    Queued,
+    Running,
+    Done,
+    /// Finished with wafers left failed
+    Failed,
+    Cancelled,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct JobCounts {
+    pub queued: u32,
+    pub leased: u32,
+    pub done: u32,
+    pub failed: u32,
+    pub cancelled: u32,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct JobSummary {
+    pub job_id: String,
+    pub name: String,
+    pub status: JobStatus,
+    pub total: u32,
+    pub counts: JobCounts,
+    /// 0..1 over all wafers, including the progress of leased ones
+    pub progress: f64,
+    pub max_attempts: u32,
+    pub cancelled: bool,
+    /// Epoch ms
+    pub created_at: i64,
+    pub updated_at: i64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct JobTask {
+    pub job_id: String,
+    pub seq: u32,
+    pub label: String,
+    pub status: TaskStatus,
+    /// Failed attempts so far
+    pub attempts: u32,
+    pub worker: Option<String>,
+    pub progress: f64,
+    pub message: Option<String>,
+    pub error: Option<String>,
+    pub result: Option<Value>,
+    pub started_at: Option<i64>,
+    pub finished_at: Option<i64>,
+}
+
+/// A task handed to a worker.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Lease {
+    pub job_id: String,
+    pub seq: u32,
+    pub label: String,
+    /// 1 for the first try
+    pub attempt: u32,
+    pub wafer: Value,
+    pub options: Value,
+}
+
+#[derive(Debug, Clone)]
+pub enum TaskOutcome {
+    Done { result: Value },
+    Failed { error: String },
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct JobEvent {
+    pub job: JobSummary,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub task: Option<JobTask>,
+}
+
+fn now_ms() -> i64 {
+    chrono::Utc::now().timestamp_millis()
+}
+
+fn db_err(e: sqlx::Error) -> String {
+    format!("Job queue query failed: {}", e)
+}
+
+static JOB_COUNTER: AtomicU64 = AtomicU64::new(0);
+
+fn new_job_id(now: i64) -> String {
+    format!(
+        "job-{:x}-{:x}",
+        now,
+        JOB_COUNTER.fetch_add(1, Ordering::Relaxed)
+    )
+}
+
+pub async fn enqueue(pool: &SqlitePool, job: &NewJob) -> Result<JobSummary, String> {
+    if job.wafers.is_empty() {
+        return Err("A stacking job needs at least one wafer".into());
+    }
+    let now = now_ms();
+    let job_id = new_job_id(now);
+    let mut tx = pool.begin().await.map_err(db_err)?;
+    sqlx::query(
+        "INSERT INTO stacking_jobs (job_id, name, options, max_attempts, created_at, updated_at) \
+         VALUES (?, ?, ?, ?, ?, ?)",
+    )
+    .bind(&job_id)
+    .bind(&job.name)
+    .bind(job.options.to_string())
+    .bind(job.max_attempts.max(1) as i64)
+    .bind(now)
+    .bind(now)
+    .execute(&mut *tx)
+    .await
+    .map_err(db_err)?;
+    for (seq, task) in job.wafers.iter().enumerate() {
+        sqlx::query(
+            "INSERT INTO stacking_job_tasks (job_id, seq, label, wafer) VALUES (?, ?, ?, ?)",
+        )
+        .bind(&job_id)
+        .bind(seq as i64)
+        .bind(&task.label)
+        .bind(task.wafer.to_string())
+        .execute(&mut *tx)
+        .await
+        .map_err(db_err)?;
+    }
+    tx.commit().await.map_err(db_err)?;
+    job_summary(pool, &job_id).await
+}
+
+const SUMMARY_SQL: &str = "SELECT j.job_id, j.name, j.max_attempts, j.cancelled, \
+     j.created_at, j.updated_at, COUNT(t.seq) AS total, \
+     COALESCE(SUM(t.status = 'queued'), 0) AS queued, \
+     COALESCE(SUM(t.status = 'leased'), 0) AS leased, \
+     COALESCE(SUM(t.status = 'done'), 0) AS done, \
+     COALESCE(SUM(t.status = 'failed'), 0) AS failed, \
+     COALESCE(SUM(t.status = 'cancelled'), 0) AS cancelled_tasks, \
+     TOTAL(CASE WHEN t.status = 'leased' THEN t.progress \
+         WHEN t.status = 'queued' THEN 0 ELSE 1 END) AS finished \
+     FROM stacking_jobs j LEFT JOIN stacking_job_tasks t ON t.job_id = j.job_id \
+     WHERE ?1 IS NULL OR j.job_id = ?1 \
+     GROUP BY j.job_id ORDER BY j.created_at, j.job_id";
+
+fn summary_from_row(row: &SqliteRow) -> Result<JobSummary, sqlx::Error> {
+    let count = |column: &str| row.try_get::<i64, _>(column).map(|n| n as u32);
+    let counts = JobCounts {
+        queued: count("queued")?,
+        leased: count("leased")?,
+        done: count("done")?,
+        failed: count("failed")?,
+        cancelled: count("cancelled_tasks")?,
+    };
+    let total = count("total")?;
+    let cancelled: bool = row.try_get("cancelled")?;
+    let status = if counts.leased > 0 {
+        JobStatus::Running
+    } else if cancelled {
+        JobStatus::Cancelled
+    } else if counts.queued > 0 {
+        if counts.queued == total {
+            JobStatus::Queued
+        } else {
+            JobStatus::Running
+        }
+    } else if counts.failed > 0 {
+        JobStatus::Failed
+    } else if counts.cancelled > 0 {
+        JobStatus::Cancelled
+    } else {
+        JobStatus::Done
+    };
+    let finished: f64 = row.try_get("finished")?;
+    Ok(JobSummary {
+        job_id: row.try_get("job_id")?,
+        name: row.try_get("name")?,
+        status,
+        total,
+        counts,
+        progress: if total == 0 {
+            1.0
+        } else {
+            finished / total as f64
+        },
+        max_attempts: count("max_attempts")?,
+        cancelled,
+        created_at: row.try_get("created_at")?,
+        updated_at: row.try_get("updated_at")?,
+    })
+}
+
+pub async fn list_jobs(pool: &SqlitePool) -> Result<Vec<JobSummary>, String> {
+    sqlx::query(SUMMARY_SQL)
+        .bind(None::<String>)
+        .fetch_all(pool)
+        .await
+        .map_err(db_err)?
+        .iter()
+        .map(summary_from_row)
+        .collect::<Result<_, _>>()
+        .map_err(|e| format!("Invalid stacking_jobs row: {}", e))
+}
+
+pub async fn job_summary(pool: &SqlitePool, job_id: &str) -> Result<JobSummary, String> {
+    let row = sqlx::query(SUMMARY_SQL)
+        .bind(job_id)
+        .fetch_optional(pool)
+        .await
+        .map_err(db_err)?
+        .ok_or_else(|| format!("No stacking job {}", job_id))?;
+    summary_from_row(&row).map_err(|e| format!("Invalid stacking_jobs row: {}", e))
+}
+
+fn task_from_row(row: &SqliteRow) -> Result<JobTask, String> {
+    let parse = || -> Result<JobTask, sqlx::Error> {
+        let status: String = row.try_get("status")?;
+        let result: Option<String> = row.try_get("result")?;
+        Ok(JobTask {
+            job_id: row.try_get("job_id")?,
+            seq: row.try_get::<i64, _>("seq")? as u32,
+            label: row.try_get("label")?,
+            status: TaskStatus::parse(&status).map_err(|e| sqlx::Error::Decode(e.into()))?,
+            attempts: row.try_get::<i64, _>("attempts")? as u32,
+            worker: row.try_get("worker")?,
+            progress: row.try_get("progress")?,
+            message: row.try_get("message")?,
+            error: row.try_get("error")?,
+            result: result.and_then(|r| serde_json::from_str(&r).ok()),
+            started_at: row.try_get("started_at")?,
+            finished_at: row.try_get("finished_at")?,
+        })
+    };
+    parse().map_err(|e| format!("Invalid stacking_job_tasks row: {}", e))
+}
+
+const TASK_COLUMNS: &str = "job_id, seq, label, status, attempts, worker, progress, message, \
+     error, result, started_at, finished_at";
+
+pub async fn job_tasks(pool: &SqlitePool, job_id: &str) -> Result<Vec<JobTask>, String> {
+    sqlx::query(&format!(
+        "SELECT {} FROM stacking_job_tasks WHERE job_id = ? ORDER BY seq",
+        TASK_COLUMNS
+    ))
+    .bind(job_id)
+    .fetch_all(pool)
+    .await
+    .map_err(db_err)?
+    .iter()
+    .map(task_from_row)
+    .collect()
+}
+
+pub async fn job_task(pool: &SqlitePool, job_id: &str, seq: u32) -> Result<JobTask, String> {
+    let row = sqlx::query(&format!(
+        "SELECT {} FROM stacking_job_tasks WHERE job_id = ? AND seq = ?",
+        TASK_COLUMNS
+    ))
+    .bind(job_id)
+    .bind(seq as i64)
+    .fetch_optional(pool)
+    .await
+    .map_err(db_err)?
+    .ok_or_else(|| format!("No task {} in stacking job {}", seq, job_id))?;
+    task_from_row(&row)
+}
+
+/// Leases the next queued wafer (oldest job first) to `worker`.
+pub async fn lease(pool: &SqlitePool, worker: &str) -> Result<Option<Lease>, String> {
+    let now = now_ms();
+    // one statement, so two workers never get the same task
+    let row = sqlx::query(
+        "UPDATE stacking_job_tasks \
+         SET status = 'leased', worker = ?1, progress = 0, message = NULL, \
+             started_at = ?2, finished_at = NULL \
+         WHERE (job_id, seq) = ( \
+             SELECT t.job_id, t.seq FROM stacking_job_tasks t \
+             JOIN stacking_jobs j ON j.job_id = t.job_id \
+             WHERE t.status = 'queued' AND j.cancelled = 0 \
+             ORDER BY j.created_at, j.job_id, t.seq LIMIT 1) \
+         RETURNING job_id, seq, label, attempts, wafer",
+    )
+    .bind(worker)
+    .bind(now)
+    .fetch_optional(pool)
+    .await
+    .map_err(db_err)?;
+    let Some(row) = row else {
+        return Ok(None);
+    };
+
+    let job_id: String = row.try_get("job_id").map_err(db_err)?;
+    let seq = row.try_get::<i64, _>("seq").map_err(db_err)? as u32;
+    let wafer: String = row.try_get("wafer").map_err(db_err)?;
+    let options: String = sqlx::query_scalar("SELECT options FROM stacking_jobs WHERE job_id = ?")
+        .bind(&job_id)
+        .fetch_one(pool)
+        .await
+        .map_err(db_err)?;
+    let json = |text: &str| {
+        serde_json::from_str(text).map_err(|e| format!("Invalid job JSON in {}: {}", job_id, e))
+    };
+    Ok(Some(Lease {
+        seq,
+        label: row.try_get("label").map_err(db_err)?,
+        attempt: row.try_get::<i64, _>("attempts").map_err(db_err)? as u32 + 1,
+        wafer: json(&wafer)?,
+        options: json(&options)?,
+        job_id,
+    }))
+}
+
+/// Records a worker's progress (0..1) on its leased task.
+pub async fn report_progress(
+    pool: &SqlitePool,
+    job_id: &str,
+    seq: u32,
+    worker: &str,
+    progress: f64,
+    message: Option<&str>,
+) -> Result<(), String> {
+    sqlx::query(
+        "UPDATE stacking_job_tasks SET progress = ?, message = COALESCE(?, message) \
+         WHERE job_id = ? AND seq = ? AND status = 'leased' AND worker = ?",
+    )
+    .bind(progress.clamp(0.0, 1.0))
+    .bind(message)
+    .bind(job_id)
+    .bind(seq as i64)
+    .bind(worker)
+    .execute(pool)
+    .await
+    .map_err(db_err)?;
+    Ok(())
+}
+
+/// Ends a worker's lease. A failure is requeued while attempts remain; on a
+/// cancelled job it ends the task as cancelled.
+pub async fn finish_task(
+    pool: &SqlitePool,
+    job_id: &str,
+    seq: u32,
+    worker: &str,
+    outcome: &TaskOutcome,
+) -> Result<JobTask, String> {
+    let mut tx = pool.begin().await.map_err(db_err)?;
+    let row = sqlx::query(
+        "SELECT t.status, t.worker, t.attempts, j.max_attempts, j.cancelled \
+         FROM stacking_job_tasks t JOIN stacking_jobs j ON j.job_id = t.job_id \
+         WHERE t.job_id = ? AND t.seq = ?",
+    )
+    .bind(job_id)
+    .bind(seq as i64)
+    .fetch_optional(&mut *tx)
+    .await
+    .map_err(db_err)?
+    .ok_or_else(|| format!("No task {} in stacking job {}", seq, job_id))?;
+    let status: String = row.try_get("status").map_err(db_err)?;
+    let holder: Option<String> = row.try_get("worker").map_err(db_err)?;
+    if status != TaskStatus::Leased.as_str() || holder.as_deref() != Some(worker) {
+        return Err(format!(
+            "Task {} of stacking job {} is not leased by {}",
+            seq, job_id, worker
+        ));
+    }
+    let attempts: i64 = row.try_get("attempts").map_err(db_err)?;
+    let max_attempts: i64 = row.try_get("max_attempts").map_err(db_err)?;
+    let cancelled: bool = row.try_get("cancelled").map_err(db_err)?;
+
+    let now = now_ms();
+    match outcome {
+        TaskOutcome::Done { result } => {
+            sqlx::query(
+                "UPDATE stacking_job_tasks SET status = 'done', worker = NULL, progress = 1, \
+                 error = NULL, result = ?, finished_at = ? WHERE job_id = ? AND seq = ?",
+            )
+            .bind(result.to_string())
+            .bind(now)
+            .bind(job_id)
+            .bind(seq as i64)
+            .execute(&mut *tx)
+            .await
+            .map_err(db_err)?;
+        }
+        TaskOutcome::Failed { error } => {
+            let next = if cancelled {
+                TaskStatus::Cancelled
+            } else if attempts + 1 < max_attempts {
+                TaskStatus::Queued
+            } else {
+                TaskStatus::Failed
+            };
+            sqlx::query(
+                "UPDATE stacking_job_tasks SET status = ?, worker = NULL, progress = 0, \
+                 attempts = attempts + ?, error = ?, finished_at = ? \
+                 WHERE job_id = ? AND seq = ?",
+            )
+            .bind(next.as_str())
+            .bind(!cancelled as i64)
+            .bind(error)
+            .bind((next != TaskStatus::Queued).then_some(now))
+            .bind(job_id)
+            .bind(seq as i64)
+            .execute(&mut *tx)
+            .await
+            .map_err(db_err)?;
+        }
+    }
+    touch(&mut tx, job_id, now).await?;
+    tx.commit().await.map_err(db_err)?;
+    job_task(pool, job_id, seq).await
+}
+
+async fn touch(
+    tx: &mut sqlx::Transaction<'_, sqlx::Sqlite>,
+    job_id: &str,
+    now: i64,
+) -> Result<(), String> {
+    sqlx::query("UPDATE stacking_jobs SET updated_at = ? WHERE job_id = ?")
+        .bind(now)
+        .bind(job_id)
+        .execute(&mut **tx)
+        .await
+        .map_err(db_err)?;
+    Ok(())
+}
+
+/// Stops a job in the queue: queued wafers are cancelled, leased ones end as
+/// cancelled when their worker stops on `runner::cancel`.
+pub async fn cancel_job(pool: &SqlitePool, job_id: &str) -> Result<JobSummary, String> {
+    let mut tx = pool.begin().await.map_err(db_err)?;
+    let found = sqlx::query("UPDATE stacking_jobs SET cancelled = 1 WHERE job_id = ?")
+        .bind(job_id)
+        .execute(&mut *tx)
+        .await
+        .map_err(db_err)?
+        .rows_affected();
+    if found == 0 {
+        return Err(format!("No stacking job {}", job_id));
+    }
+    let now = now_ms();
+    sqlx::query(
+        "UPDATE stacking_job_tasks SET status = 'cancelled', finished_at = ? \
+         WHERE job_id = ? AND status = 'queued'",
+    )
+    .bind(now)
+    .bind(job_id)
+    .execute(&mut *tx)
+    .await
+    .map_err(db_err)?;
+    touch(&mut tx, job_id, now).await?;
+    tx.commit().await.map_err(db_err)?;
+    job_summary(pool, job_id).await
+}
+
+/// Requeues the wafers of a job that failed or were cancelled, with their
+/// attempts reset.
+pub async fn retry_job(pool: &SqlitePool, job_id: &str) -> Result<JobSummary, String> {
+    let mut tx = pool.begin().await.map_err(db_err)?;
+    let found = sqlx::query("UPDATE stacking_jobs SET cancelled = 0 WHERE job_id = ?")
+        .bind(job_id)
+        .execute(&mut *tx)
+        .await
+        .map_err(db_err)?
+        .rows_affected();
+    if found == 0 {
+        return Err(format!("No stacking job {}", job_id));
+    }
+    sqlx::query(
+        "UPDATE stacking_job_tasks SET status = 'queued', attempts = 0, progress = 0, \
+         finished_at = NULL WHERE job_id = ? AND status IN ('failed', 'cancelled')",
+    )
+    .bind(job_id)
+    .execute(&mut *tx)
+    .await
+    .map_err(db_err)?;
+    touch(&mut tx, job_id, now_ms()).await?;
+    tx.commit().await.map_err(db_err)?;
+    job_summary(pool, job_id).await
+}
+
+/// Deletes a job that no worker is busy with.
+pub async fn remove_job(pool: &SqlitePool, job_id: &str) -> Result<(), String> {
+    let summary = job_summary(pool, job_id).await?;
+    if summary.counts.leased > 0 {
+        return Err(format!(
+            "Stacking job {} still has {} wafer(s) in progress",
+            job_id, summary.counts.leased
+        ));
+    }
+    sqlx::query("DELETE FROM stacking_jobs WHERE job_id = ?")
+        .bind(job_id)
+        .execute(pool)
+        .await
+        .map_err(db_err)?;
+    Ok(())
+}
+
+/// Hands every leased wafer back to the queue, or ends it as cancelled on a
+/// cancelled job: their workers went with the process that leased them.
+/// Returns how many were handed back.
+pub async fn resume(pool: &SqlitePool) -> Result<u64, String> {
+    Ok(sqlx::query(
+        "UPDATE stacking_job_tasks SET worker = NULL, progress = 0, \
+         status = CASE WHEN j.cancelled THEN 'cancelled' ELSE 'queued' END, \
+         finished_at = CASE WHEN j.cancelled THEN ?1 END \
+         FROM stacking_jobs j \
+         WHERE j.job_id = stacking_job_tasks.job_id AND stacking_job_tasks.status = 'leased'",
+    )
+    .bind(now_ms())
+    .execute(pool)
+    .await
+    .map_err(db_err)?
+    .rows_affected())
+}
+
+/// Payload for `STACKING_JOB_CHANGED`.
+pub async fn job_event(
+    pool: &SqlitePool,
+    job_id: &str,
+    seq: Option<u32>,
+) -> Result<JobEvent, String> {
+    let task = match seq {
+        Some(seq) => Some(job_task(pool, job_id, seq).await?),
+        None => None,
+    };
+    Ok(JobEvent {
+        job: job_summary(pool, job_id).await?,
+        task,
+    })
+}
+
+/// Emits `STACKING_JOB_CHANGED` for a job (and one of its tasks); failures
+/// are only logged, the queue itself is already updated.
+pub async fn notify(app: &AppHandle, pool: &SqlitePool, job_id: &str, seq: Option<u32>) {
+    let sent = match job_event(pool, job_id, seq).await {
+        Ok(event) => app
+            .emit(STACKING_JOB_CHANGED, &event)
+            .map_err(|e| e.to_string()),
+        Err(e) => Err(e),
+    };
+    if let Err(e) = sent {
+        eprintln!("⚠️ [jobs] Failed to emit {}: {}", STACKING_JOB_CHANGED, e);
+    }
+}
@@ -0,0 +1,194 @@
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+
+use once_cell::sync::{Lazy, OnceCell};
+use sqlx::SqlitePool;
+use tauri::async_runtime::{self, Receiver, Sender};
+use tauri::{AppHandle, Manager};
+use tauri_plugin_sql::DbInstances;
+
+use super::{finish_task, lease, notify, report_progress, resume, stacking, Lease, TaskOutcome};
+use crate::db::app_pool;
+
+// =============================================================================
+// Job runner
+//
+// Works the queue in the app process, whether or not a window is open. A
+// dispatcher leases wafers to at most `WORKERS` tasks; each stacks its wafer
+// on the blocking pool with the `wafer::stack` engine (`cli::stack_job`) and
+// forwards its progress to the queue. The dispatcher looks for work when a
+// job is enqueued or retried (`wake`) and whenever a worker is done.
+// Every job has a cancellation token, checked before a wafer starts and
+// between its layers and outputs.
+// =============================================================================
+
+/// Wafers stacked at the same time.
+pub const WORKERS: usize = 2;
+
+/// Cancellation of one job, shared by the workers stacking its wafers.
+#[derive(Debug, Clone, Default)]
+pub struct CancelToken(Arc<AtomicBool>);
+
+impl CancelToken {
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::SeqCst);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::SeqCst)
+    }
+
+    /// `Err` once cancelled, to stop between steps with `?`.
+    pub fn check(&self) -> Result<(), String> {
+        if self.is_cancelled() {
+            Err("Job cancelled".into())
+        } else {
+            Ok(())
+        }
+    }
+}
+
+static TOKENS: Lazy<Mutex<HashMap<String, CancelToken>>> = Lazy::new(Default::default);
+
+/// The token of a job; the first call creates it.
+pub fn token(job_id: &str) -> CancelToken {
+    TOKENS
+        .lock()
+        .unwrap()
+        .entry(job_id.to_string())
+        .or_default()
+        .clone()
+}
+
+/// Stops the wafers of a job that are being stacked.
+pub fn cancel(job_id: &str) {
+    token(job_id).cancel();
+}
+
+/// Drops the token of a retried or removed job; wafers leased afterwards get
+/// a new one.
+pub fn forget(job_id: &str) {
+    TOKENS.lock().unwrap().remove(job_id);
+}
+
+enum Signal {
+    /// New work may be queued
+    Wake,
+    /// The worker in this slot is done
+    Finished(usize),
+}
+
+static SIGNALS: OnceCell<Sender<Signal>> = OnceCell::new();
+
+/// Starts the runner once (from the app's setup): hands the wafers of the
+/// last session back to the queue, then stacks until the app exits.
+pub fn start(app: AppHandle) {
+    let (signals, inbox) = async_runtime::channel(64);
+    if SIGNALS.set(signals.clone()).is_err() {
+        return;
+    }
+    async_runtime::spawn(async move {
+        let pool = match app_pool(&app.state::<DbInstances>()).await {
+            Ok(pool) => pool,
+            Err(e) => {
+                eprintln!("❌ [jobs] Stacking jobs will not run: {}", e);
+                return;
+            }
+        };
+        match resume(&pool).await {
+            Ok(0) => {}
+            Ok(n) => println!("🔁 [jobs] Requeued {} interrupted wafer(s)", n),
+            Err(e) => eprintln!("⚠️ [jobs] Failed to requeue interrupted wafers: {}", e),
+        }
+        dispatch(app, pool, signals, inbox).await;
+    });
+}
+
+/// Tells the runner that wafers were queued.
+pub fn wake() {
+    if let Some(signals) = SIGNALS.get() {
+        // a full inbox already holds a wake-up
+        let _ = signals.try_send(Signal::Wake);
+    }
+}
+
+fn worker_name(slot: usize) -> String {
+    format!("worker-{}", slot + 1)
+}
+
+async fn dispatch(
+    app: AppHandle,
+    pool: SqlitePool,
+    signals: Sender<Signal>,
+    mut inbox: Receiver<Signal>,
+) {
+    let mut busy = [false; WORKERS];
+    loop {
+        while let Some(slot) = busy.iter().position(|b| !b) {
+            let lease = match lease(&pool, &worker_name(slot)).await {
+                Ok(Some(lease)) => lease,
+                Ok(None) => break,
+                Err(e) => {
+                    eprintln!("⚠️ [jobs] Failed to lease a wafer: {}", e);
+                    break;
+                }
+            };
+            busy[slot] = true;
+            let (app, pool, signals) = (app.clone(), pool.clone(), signals.clone());
+            async_runtime::spawn(async move {
+                run_task(&app, &pool, &worker_name(slot), lease).await;
+                let _ = signals.send(Signal::Finished(slot)).await;
+            });
+        }
+        match inbox.recv().await {
+            Some(Signal::Finished(slot)) => busy[slot] = false,
+            Some(Signal::Wake) => {}
+            None => return,
+        }
+    }
+}
+
+/// Stacks one leased wafer and finishes its task.
+async fn run_task(app: &AppHandle, pool: &SqlitePool, worker: &str, lease: Lease) {
+    let (job_id, seq) = (lease.job_id.clone(), lease.seq);
+    notify(app, pool, &job_id, Some(seq)).await;
+
+    // progress goes to the queue from here, not from the blocking pool
+    let (progress, mut reports) = async_runtime::channel::<(f64, String)>(16);
+    let forward = {
+        let (app, pool, job_id, worker) = (
+            app.clone(),
+            pool.clone(),
+            job_id.clone(),
+            worker.to_string(),
+        );
+        async_runtime::spawn(async move {
+            while let Some((value, message)) = reports.recv().await {
+                let reported =
+                    report_progress(&pool, &job_id, seq, &worker, value, Some(&message)).await;
+                match reported {
+                    Ok(()) => notify(&app, &pool, &job_id, Some(seq)).await,
+                    Err(e) => eprintln!("⚠️ [jobs] {}: {}", job_id, e),
+                }
+            }
+        })
+    };
+    let token = token(&job_id);
+    let checkpoint = move |value: f64, message: &str| {
+        token.check()?;
+        // a full channel only drops a progress step
+        let _ = progress.try_send((value, message.to_string()));
+        Ok(())
+    };
+
+    let outcome = match stacking::stack_wafer(pool, &lease, checkpoint).await {
+        Ok(result) => TaskOutcome::Done { result },
+        Err(error) => TaskOutcome::Failed { error },
+    };
+    let _ = forward.await;
+    if let Err(e) = finish_task(pool, &job_id, seq, worker, &outcome).await {
+        eprintln!("⚠️ [jobs] {}: {}", lease.label, e);
+    }
+    notify(app, pool, &job_id, Some(seq)).await;
+}
@@ -0,0 +1,221 @@
+use std::path::Path;
+
+use serde::Deserialize;
+use serde_json::{json, Value};
+use sqlx::SqlitePool;
+
+use super::Lease;
+use crate::cli::{stack_job, stored_stage, OutputFormat, ProductSettings, Run, WaferJob};
+use crate::db::repo;
+use crate::db::tables::{SubstrateDefectRow, WaferMapChoiceRow, WaferMapRow};
+use crate::file::duplicates::{layer_key, pick_stacked_maps};
+use crate::parser::parse_die_layout_xls;
+use crate::render::RenderStyle;
+use crate::wafer::bins::BinSet;
+use crate::wafer::ds::AsciiDie;
+use crate::wafer::edge::InkRules;
+use crate::wafer::stack::sub_stage_number;
+
+// =============================================================================
+// Stacking a queued wafer
+//
+// What the stacking page queues (`JobItem`, `StoredWaferStackingJobOptions`) turned
+// into a `cli::WaferJob`: the selected layers with one map per stage as
+// chosen in `wafer_map_choice`, the substrate defect list and the die
+// layout sheet. The product settings come from the database, the defect
+// classes and ink rules from the job options.
+// =============================================================================
+
+/// A wafer as the stacking page queues it (`JobItem`).
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct QueuedWafer {
+    pub oem_product_id: String,
+    pub product_id: String,
+    pub batch_id: String,
+    pub wafer_id: Option<i64>,
+    pub sub_id: String,
+    #[serde(default)]
+    pub wafer_substrate: Option<SubstrateDefectRow>,
+    /// Defaults to stacking the substrate when there is one
+    #[serde(default)]
+    pub include_substrate_selected: Option<bool>,
+    #[serde(default)]
+    pub wafer_maps: Vec<WaferMapRow>,
+    /// `layer_key`s to stack; `None` stacks every layer
+    #[serde(default)]
+    pub selected_layer_keys: Option<Vec<String>>,
+}
+
+/// `StoredWaferStackingJobOptions`, shared by the wafers of a job.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct StackingOptions {
+    pub output_dir: String,
+    /// Used when `output_dir` is empty
+    pub final_output_dir: String,
+    pub die_layout_path: String,
+    /// `WaferStackingOutputId`s
+    pub selected_outputs: Vec<String>,
+    /// Empty stacks every defect class
+    pub selected_defect_classes: Vec<String>,
+    pub image_renderer: RenderStyle,
+    pub edge_removal_enabled: bool,
+    pub good_bins: Vec<String>,
+    pub edge_removal_fail_bins: Vec<String>,
+    pub ink_rules: Option<InkRules>,
+}
+
+impl StackingOptions {
+    /// The ink rules for `Ink/`, with the job's good and fail bins.
+    fn ink(&self) -> Option<InkRules> {
+        self.edge_removal_enabled.then(|| InkRules {
+            good_bins: self.good_bins.clone(),
+            fail_bins: Some(self.edge_removal_fail_bins.clone()),
+            ..self.ink_rules.clone().unwrap_or_default()
+        })
+    }
+}
+
+/// The die layout sheet of the product, else of the OEM product, else
+/// the first by name. An unreadable file only loses the layout.
+fn layout_dies(path: &str, wafer: &QueuedWafer) -> Vec<AsciiDie> {
+    if path.is_empty() {
+        return Vec::new();
+    }
+    let sheets = match parse_die_layout_xls(path.to_string()) {
+        Ok(sheets) => sheets,
+        Err(e) => {
+            eprintln!("⚠️ [jobs] Die layout not used: {}", e);
+            return Vec::new();
+        }
+    };
+    sheets
+        .get(&wafer.product_id)
+        .or_else(|| sheets.get(&wafer.oem_product_id))
+        .or_else(|| sheets.iter().min_by(|a, b| a.0.cmp(b.0)).map(|(_, s)| s))
+        .map(|sheet| sheet.dies.clone())
+        .unwrap_or_default()
+}
+
+/// The job for one wafer: the selected layers, one map each.
+fn wafer_job(wafer: &QueuedWafer, wafer_id: i64, choices: &[WaferMapChoiceRow]) -> WaferJob {
+    let selected: Vec<WaferMapRow> = wafer
+        .wafer_maps
+        .iter()
+        .filter(|map| {
+            wafer
+                .selected_layer_keys
+                .as_ref()
+                .is_none_or(|keys| keys.contains(&layer_key(map)))
+        })
+        .cloned()
+        .collect();
+    let layers = pick_stacked_maps(&selected, choices)
+        .into_iter()
+        .filter_map(|map| {
+            let stage = stored_stage(&map.stage)?;
+            let sub_stage = map.sub_stage.as_deref().and_then(sub_stage_number);
+            Some((map.file_path, stage, sub_stage))
+        })
+        .collect();
+    let substrate = wafer
+        .wafer_substrate
+        .as_ref()
+        .filter(|_| wafer.include_substrate_selected.unwrap_or(true))
+        .map(|s| s.file_path.clone());
+    let oem = &wafer.oem_product_id;
+    WaferJob {
+        name: format!(
+            "{}_{}_{}_{}_{}",
+            oem, wafer.product_id, wafer.batch_id, wafer_id, wafer.sub_id
+        ),
+        oem_product_id: (!oem.is_empty()).then(|| oem.clone()),
+        stats_key: Some((wafer.batch_id.clone(), wafer_id.to_string())),
+        layers,
+        substrate,
+        layout: Vec::new(),
+    }
+}
+
+/// Stacks and writes a leased wafer, stores its `wafer_stack_stats` row and
+/// returns the task result (`StackingTaskResult` on the stacking page).
+pub async fn stack_wafer<F>(
+    pool: &SqlitePool,
+    lease: &Lease,
+    checkpoint: F,
+) -> Result<Value, String>
+where
+    F: Fn(f64, &str) -> Result<(), String> + Send + Sync + 'static,
+{
+    checkpoint(0.0, "Loading product settings")?;
+    let wafer: QueuedWafer = serde_json::from_value(lease.wafer.clone())
+        .map_err(|e| format!("Invalid wafer in task {}: {}", lease.label, e))?;
+    let options: StackingOptions = serde_json::from_value(lease.options.clone())
+        .map_err(|e| format!("Invalid options of job {}: {}", lease.job_id, e))?;
+    let out = match options.output_dir.as_str() {
+        "" => options.final_output_dir.clone(),
+        dir => dir.to_string(),
+    };
+    if out.trim().is_empty() {
+        return Err("No output folder given".into());
+    }
+    let wafer_id = wafer
+        .wafer_id
+        .ok_or_else(|| format!("{} has no wafer id", lease.label))?;
+    let formats = options
+        .selected_outputs
+        .iter()
+        .map(|name| OutputFormat::parse(name))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let oem = wafer.oem_product_id.as_str();
+    let mut settings = match oem {
+        "" => ProductSettings::default(),
+        oem => ProductSettings::resolve(oem, Some(&repo::product_context(pool, oem).await?), None)?,
+    };
+    settings.defect_classes = (!options.selected_defect_classes.is_empty())
+        .then(|| options.selected_defect_classes.clone());
+    let choices = repo::wafer_map_choices(pool, Some(&wafer.product_id)).await?;
+    let mut job = wafer_job(&wafer, wafer_id, &choices);
+    if job.layers.is_empty() && job.substrate.is_none() {
+        return Err(format!("{}: no layer with a file is selected", lease.label));
+    }
+
+    let coords = settings.coords();
+    let output = tauri::async_runtime::spawn_blocking(move || {
+        job.layout = layout_dies(&options.die_layout_path, &wafer);
+        let pass = BinSet::pass_or_default(&options.good_bins);
+        let ink = options.ink();
+        let run = Run {
+            out: Path::new(&out),
+            formats: &formats,
+            pass_bins: &options.good_bins,
+            pass: &pass,
+            ink: ink.as_ref(),
+            style: options.image_renderer,
+        };
+        stack_job(&job, &settings, &run, &checkpoint)
+    })
+    .await
+    .map_err(|e| format!("Thread join error: {e}"))??;
+
+    if let Some(stats) = &output.stats {
+        if let Err(e) = repo::upsert_many(pool, std::slice::from_ref(stats)).await {
+            eprintln!("⚠️ [jobs] {}: stats not stored: {}", lease.label, e);
+        }
+    }
+    let wafer = &output.wafer;
+    Ok(json!({
+        "outputRootDir": output.dir.display().to_string(),
+        "mergedDieCount": wafer.dies.len(),
+        "files": output.files,
+        "reportWafer": {
+            "stats": output.stats,
+            "dies": wafer.dies,
+            "header": wafer.header,
+            "layers": wafer.layers,
+            "coords": coords,
+        },
+    }))
+}
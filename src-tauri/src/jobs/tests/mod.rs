@@ -0,0 +1,342 @@
+#[cfg(test)]
+async fn queue_pool() -> sqlx::SqlitePool {
+    use crate::db::migrations::{latest_version, migrate_up};
+
+    let pool = sqlx::sqlite::SqlitePoolOptions::new()
+        .max_connections(1)
+        .connect("sqlite::memory:")
+        .await
+        .unwrap();
+    migrate_up(&pool, latest_version()).await.unwrap();
+    pool
+}
+
+#[cfg(test)]
+fn lot(wafers: usize, max_attempts: u32) -> super::NewJob {
+    use super::{NewJob, NewTask};
+    use serde_json::json;
+
+    NewJob {
+        name: "LOT1".into(),
+        options: json!({ "outputDir": "/out" }),
+        wafers: (1..=wafers)
+            .map(|id| NewTask {
+                label: format!("LOT1 #{}", id),
+                wafer: json!({ "waferId": id }),
+            })
+            .collect(),
+        max_attempts,
+    }
+}
+
+#[test]
+fn job_queue_leases_retries_and_finishes() {
+    use super::*;
+    use serde_json::json;
+
+    tauri::async_runtime::block_on(async {
+        let pool = queue_pool().await;
+        let job = enqueue(&pool, &lot(3, 2)).await.unwrap();
+        assert_eq!(job.status, JobStatus::Queued);
+        assert_eq!(job.total, 3);
+
+        let a = lease(&pool, "w1").await.unwrap().unwrap();
+        let b = lease(&pool, "w2").await.unwrap().unwrap();
+        assert_eq!((a.seq, b.seq), (0, 1));
+        assert_eq!(a.wafer["waferId"], 1);
+        assert_eq!(a.options["outputDir"], "/out");
+        assert_eq!(a.attempt, 1);
+
+        report_progress(&pool, &a.job_id, a.seq, "w1", 0.5, Some("merging"))
+            .await
+            .unwrap();
+        let summary = job_summary(&pool, &job.job_id).await.unwrap();
+        assert_eq!(summary.status, JobStatus::Running);
+        assert!((summary.progress - 0.5 / 3.0).abs() < 1e-9);
+
+        // only the lease holder may finish a task
+        let done = TaskOutcome::Done {
+            result: json!({ "mergedDieCount": 10 }),
+        };
+        assert!(finish_task(&pool, &a.job_id, a.seq, "w2", &done)
+            .await
+            .is_err());
+        let task = finish_task(&pool, &a.job_id, a.seq, "w1", &done)
+            .await
+            .unwrap();
+        assert_eq!(task.status, TaskStatus::Done);
+        assert_eq!(task.result.unwrap()["mergedDieCount"], 10);
+
+        // the first failure is retried, the second sticks
+        let failed = TaskOutcome::Failed {
+            error: "bad map".into(),
+        };
+        let task = finish_task(&pool, &b.job_id, b.seq, "w2", &failed)
+            .await
+            .unwrap();
+        assert_eq!((task.status, task.attempts), (TaskStatus::Queued, 1));
+        let c = lease(&pool, "w2").await.unwrap().unwrap();
+        assert_eq!((c.seq, c.attempt), (1, 2));
+        finish_task(&pool, &c.job_id, c.seq, "w2", &failed)
+            .await
+            .unwrap();
+        let d = lease(&pool, "w2").await.unwrap().unwrap();
+        assert_eq!(d.seq, 2);
+        finish_task(&pool, &d.job_id, d.seq, "w2", &done)
+            .await
+            .unwrap();
+        assert!(lease(&pool, "w1").await.unwrap().is_none());
+
+        let summary = job_summary(&pool, &job.job_id).await.unwrap();
+        assert_eq!(summary.status, JobStatus::Failed);
+        assert_eq!((summary.counts.done, summary.counts.failed), (2, 1));
+        assert_eq!(summary.progress, 1.0);
+
+        let summary = retry_job(&pool, &job.job_id).await.unwrap();
+        assert_eq!(summary.counts.queued, 1);
+        let again = lease(&pool, "w1").await.unwrap().unwrap();
+        assert_eq!((again.seq, again.attempt), (1, 1));
+        finish_task(&pool, &again.job_id, again.seq, "w1", &done)
+            .await
+            .unwrap();
+        let summary = job_summary(&pool, &job.job_id).await.unwrap();
+        assert_eq!(summary.status, JobStatus::Done);
+        assert_eq!(
+            job_tasks(&pool, &job.job_id)
+                .await
+                .unwrap()
+                .iter()
+                .map(|t| t.status)
+                .collect::<Vec<_>>(),
+            vec![TaskStatus::Done; 3]
+        );
+    });
+}
+
+#[test]
+fn job_queue_cancels_and_resumes() {
+    use super::*;
+
+    tauri::async_runtime::block_on(async {
+        let pool = queue_pool().await;
+        let first = enqueue(&pool, &lot(3, 1)).await.unwrap();
+        let second = enqueue(&pool, &lot(2, 1)).await.unwrap();
+        assert!(enqueue(&pool, &lot(0, 1)).await.is_err());
+
+        let a = lease(&pool, "w1").await.unwrap().unwrap();
+        let b = lease(&pool, "w2").await.unwrap().unwrap();
+        assert_eq!((a.job_id.as_str(), b.seq), (first.job_id.as_str(), 1));
+        let summary = cancel_job(&pool, &first.job_id).await.unwrap();
+        assert_eq!((summary.counts.cancelled, summary.counts.leased), (1, 2));
+        assert!(remove_job(&pool, &first.job_id).await.is_err());
+
+        // the workers stop on the job's token
+        let token = runner::token(&first.job_id);
+        assert!(token.check().is_ok());
+        runner::cancel(&first.job_id);
+        assert!(token.check().is_err());
+        let task = finish_task(
+            &pool,
+            &a.job_id,
+            a.seq,
+            "w1",
+            &TaskOutcome::Failed {
+                error: "Job cancelled".into(),
+            },
+        )
+        .await
+        .unwrap();
+        assert_eq!((task.status, task.attempts), (TaskStatus::Cancelled, 0));
+        // a cancelled job hands out nothing
+        let c = lease(&pool, "w1").await.unwrap().unwrap();
+        assert_eq!(c.job_id, second.job_id);
+        report_progress(&pool, &c.job_id, c.seq, "w1", 0.5, None)
+            .await
+            .unwrap();
+
+        // restart: the leases of the last session go back to the queue, or
+        // end as cancelled on the cancelled job
+        assert_eq!(resume(&pool).await.unwrap(), 2);
+        assert_eq!(
+            job_summary(&pool, &first.job_id).await.unwrap().status,
+            JobStatus::Cancelled
+        );
+        let summary = job_summary(&pool, &second.job_id).await.unwrap();
+        assert_eq!(
+            (summary.status, summary.counts.queued),
+            (JobStatus::Queued, 2)
+        );
+        assert!(finish_task(
+            &pool,
+            &c.job_id,
+            c.seq,
+            "w1",
+            &TaskOutcome::Done {
+                result: Value::Null
+            }
+        )
+        .await
+        .is_err());
+
+        // a retried job gets a fresh token
+        retry_job(&pool, &first.job_id).await.unwrap();
+        runner::forget(&first.job_id);
+        assert!(runner::token(&first.job_id).check().is_ok());
+
+        cancel_job(&pool, &first.job_id).await.unwrap();
+        remove_job(&pool, &first.job_id).await.unwrap();
+        let jobs = list_jobs(&pool).await.unwrap();
+        assert_eq!(jobs.len(), 1);
+        assert!(job_tasks(&pool, &first.job_id).await.unwrap().is_empty());
+    });
+}
+
+#[cfg(test)]
+fn map_file(dir: &std::path::Path, name: &str, rows: &[&str]) -> String {
+    let (cols, count) = (rows[0].len().to_string(), rows.len().to_string());
+    let header = [
+        ("Device Name", "DEV1"),
+        ("Lot No.", "LOT1"),
+        ("Wafer ID", "7"),
+        ("Wafer Size", "6"),
+        ("Dice SizeX", "1200.000"),
+        ("Dice SizeY", "1400.000"),
+        ("Flat/Notch", "Down"),
+        ("Map Column", cols.as_str()),
+        ("Map Row", count.as_str()),
+        ("Total Tested", "0"),
+        ("Total Pass", "0"),
+        ("Total Fail", "0"),
+        ("Yield", "0.00%"),
+    ];
+    let mut text: String = header
+        .iter()
+        .map(|(key, value)| format!("{:<18}: {}\n", key, value))
+        .collect();
+    text.push('\n');
+    for row in rows {
+        text.push_str(row);
+        text.push('\n');
+    }
+    let path = dir.join(name);
+    std::fs::write(&path, text).unwrap();
+    path.display().to_string()
+}
+
+#[test]
+fn job_runner_stacks_a_queued_wafer() {
+    use super::stacking::stack_wafer;
+    use super::*;
+    use crate::db::repo;
+    use crate::db::tables::WaferMapChoiceRow;
+    use serde_json::json;
+
+    let dir = std::env::temp_dir().join("aoi_jobs_stacking");
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    let cp1 = map_file(&dir, "cp1.txt", &["S1115", "..1.."]);
+    let retest = map_file(&dir, "cp1_retest.txt", &["S1111", "..1.."]);
+    let aoi = map_file(&dir, "aoi.txt", &[".S1E1", "...1."]);
+    let out = dir.join("out");
+    let map = |stage: &str, sub_stage: &str, retest_count: i64, path: &str| {
+        json!({
+            "product_id": "P1", "batch_id": "LOT1", "wafer_id": 7, "stage": stage,
+            "sub_stage": sub_stage, "retest_count": retest_count, "time": null,
+            "file_path": path,
+        })
+    };
+
+    tauri::async_runtime::block_on(async {
+        let pool = queue_pool().await;
+        sqlx::query(
+            "INSERT INTO oem_product_map VALUES ('OEM1', 'P1'); \
+             INSERT INTO file_index VALUES (?, 1, NULL)",
+        )
+        .bind(&cp1)
+        .execute(&pool)
+        .await
+        .unwrap();
+        // the retest would stack; the first test is chosen instead
+        repo::upsert_many(
+            &pool,
+            &[WaferMapChoiceRow {
+                product_id: "P1".into(),
+                batch_id: "LOT1".into(),
+                wafer_id: 7,
+                stage: "cpProber".into(),
+                sub_stage: "1".into(),
+                file_path: cp1.clone(),
+            }],
+        )
+        .await
+        .unwrap();
+        let job = NewJob {
+            name: "LOT1".into(),
+            options: json!({
+                "outputDir": out.display().to_string(),
+                "selectedOutputs": ["mapEx", "SILAN"],
+                "goodBins": ["BIN 1"],
+                "imageRenderer": "bin",
+            }),
+            wafers: vec![NewTask {
+                label: "LOT1 #7".into(),
+                wafer: json!({
+                    "id": "item-1", "oemProductId": "OEM1", "productId": "P1",
+                    "batchId": "LOT1", "waferId": 7, "subId": "S01",
+                    "waferSubstrate": null,
+                    "waferMaps": [
+                        map("cpProber", "1", 0, &cp1),
+                        map("cpProber", "1", 1, &retest),
+                        map("aoi", "", 0, &aoi),
+                        map("wlbi", "", 0, "/missing.WaferMap"),
+                    ],
+                    "selectedLayerKeys": ["cpprober|1", "aoi|"],
+                }),
+            }],
+            max_attempts: 1,
+        };
+        enqueue(&pool, &job).await.unwrap();
+        let task = lease(&pool, "worker-1").await.unwrap().unwrap();
+
+        let steps = std::sync::Arc::new(std::sync::Mutex::new(Vec::new()));
+        let seen = steps.clone();
+        let result = stack_wafer(&pool, &task, move |_, step| {
+            seen.lock().unwrap().push(step.to_string());
+            Ok(())
+        })
+        .await
+        .unwrap();
+        assert_eq!(result["mergedDieCount"], 6);
+        assert_eq!(result["reportWafer"]["stats"]["oem_product_id"], "OEM1");
+        assert_eq!(result["reportWafer"]["layers"][0]["name"], "CP1");
+        let steps = steps.lock().unwrap().clone();
+        assert!(steps.contains(&"Reading AOI".to_string()));
+        assert_eq!(steps.last().unwrap(), "Writing outputs");
+
+        let base = out.join("OEM1_P1_LOT1_7_S01");
+        assert_eq!(result["outputRootDir"], base.display().to_string());
+        let merged =
+            std::fs::read_to_string(base.join("OEM1_P1_LOT1_7_S01_overlayed.txt")).unwrap();
+        // the chosen CP1 map (bin 5 in the last column) was stacked
+        assert!(merged
+            .lines()
+            .collect::<Vec<_>>()
+            .ends_with(&["S1E15", "..1.."]));
+        let silan = std::fs::read_to_string(base.join("OEM1_P1_LOT1_7_S01_SILAN.txt")).unwrap();
+        assert!(silan.contains("wafer Id                  : LOT1-7"));
+        assert!(silan.lines().any(|line| line.ends_with("+ X1X1X")));
+        let stored: (String, Option<String>) = sqlx::query_as(
+            "SELECT bin_counts, stage_waterfall FROM wafer_stack_stats \
+             WHERE oem_product_id = 'OEM1' AND batch_id = 'LOT1' AND wafer_id = '7'",
+        )
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+        assert!(stored.0.contains("\"1\":3"));
+        assert!(stored.1.is_some());
+
+        // a cancelled job stops at the next checkpoint
+        let cancelled = stack_wafer(&pool, &task, |_, _| Err("Job cancelled".into())).await;
+        assert_eq!(cancelled.unwrap_err(), "Job cancelled");
+    });
+}
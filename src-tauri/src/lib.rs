@@ -1,18 +1,31 @@
 // Learn more about Tauri commands at https://tauri.app/develop/calling-rust/
 mod crypto;
+mod db;
 mod file;
 mod parser;
 mod wafer;
+mod analysis;
+mod render;
+mod jobs;
+pub mod cli;
 mod commands;
 #[cfg(feature = "libtorch")]
 mod inference;
 #[cfg(not(feature = "libtorch"))]
 #[path = "inference_stub.rs"]
 mod inference;
+#[cfg(feature = "http-api")]
+mod http;
+#[cfg(not(feature = "http-api"))]
+#[path = "http_stub.rs"]
+mod http;
 
 use file::file_lock;
 use tauri::{RunEvent};
-use tauri_plugin_sql::{Migration, MigrationKind};
+use tauri_plugin_sql::Migration;
+
+/// Database preloaded by tauri-plugin-sql (see `tauri.conf.json`).
+pub(crate) const DB_URL: &str = "sqlite:data.db";
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
@@ -23,28 +36,24 @@ pub fn run() {
 }
 
 fn run_once() -> Result<(), tauri::Error> {
-    // Keep this in sync with your sql/init.sql
-    let migrations: Vec<Migration> = vec![Migration {
-        version: 1,
-        description: "Create initial tables",
-        sql: include_str!("../../sql/init.sql"),
-        kind: MigrationKind::Up,
-    }];
+    // Up and down scripts of every schema version (see sql/README.md)
+    let migrations: Vec<Migration> = db::migrations::plugin_migrations();
 
     println!("🐛 Preparing to initialize SQL plugin...");
     for m in &migrations {
         println!(
-            "📦 Migration v{} - {} (SQL length: {})",
+            "📦 Migration v{} {:?} - {} (SQL length: {})",
             m.version,
+            m.kind,
             m.description,
             m.sql.len()
         );
     }
 
-    println!("🔨 Building SQL plugin with target: sqlite:data.db");
+    println!("🔨 Building SQL plugin with target: {DB_URL}");
 
     let sql_plugin = tauri_plugin_sql::Builder::new()
-        .add_migrations("sqlite:data.db", migrations)
+        .add_migrations(DB_URL, migrations)
         .build();
 
     println!("✅ SQL plugin build complete, attaching to app...");
@@ -54,22 +63,39 @@ fn run_once() -> Result<(), tauri::Error> {
         .plugin(sql_plugin)
         .plugin(tauri_plugin_dialog::init())
         .plugin(tauri_plugin_opener::init())
+        // Stacking jobs run in the app process, with or without a window
+        .setup(|app| {
+            jobs::runner::start(app.handle().clone());
+            Ok(())
+        })
         .invoke_handler(tauri::generate_handler![
             // File IO related
             file_lock::lock_file,
+            file_lock::try_lock_file,
             file_lock::unlock_file,
+            file_lock::list_locks,
             // Commands
             commands::rust_read_file_stat_batch,
             commands::rust_read_dir,
+            commands::rust_scan_data_sources,
+            // Live ingestion
+            commands::rust_watch_start,
+            commands::rust_watch_stop,
+            commands::rust_watch_list,
+            commands::rust_index_files,
+            commands::rust_wafer_map_duplicates,
             // Cryptography
             commands::rust_sha1,
             commands::rust_sha1_batch,
             commands::rust_sha256,
+            commands::rust_sha256_file_batch,
             // Excel file parsing methods
             commands::rust_parse_product_mapping_xls,
             commands::rust_parse_product_xls,
             commands::rust_parse_substrate_defect_xls,
             commands::rust_parse_die_layout_xls,
+            commands::rust_parse_bin_map_xls,
+            commands::rust_parse_bin_map_json,
             commands::rust_debug_print_die_layout_coords,
             // Wafer parsing methods
             commands::rust_parse_wafer,
@@ -83,11 +109,57 @@ fn run_once() -> Result<(), tauri::Error> {
             commands::rust_print_wafer_map_data,
             commands::rust_export_wafer_hex,
             commands::rust_print_wafer_hex,
-            commands::rust_export_wafer_jpg,
             commands::rust_export_wafer_silan,
             commands::rust_print_wafer_silan,
             commands::rust_export_wafer_fab,
             commands::rust_print_wafer_fab,
+            // Physical die coordinates
+            commands::rust_overlay_substrate_defects,
+            commands::rust_place_substrate_dies,
+            // Edge exclusion & inking
+            commands::rust_apply_ink_rules,
+            // Gross die
+            commands::rust_gross_die,
+            // Rendering
+            commands::rust_render_wafer_map,
+            commands::rust_export_wafer_svg,
+            commands::rust_export_lot_report,
+            // Lot analytics
+            commands::rust_composite_lot_map,
+            commands::rust_render_composite_map,
+            commands::rust_classify_signatures,
+            commands::rust_zone_yield,
+            commands::rust_stage_waterfall,
+            commands::rust_compare_maps,
+            commands::rust_spc_lot_yields,
+            // Database
+            commands::rust_db_upsert,
+            commands::rust_db_product_context,
+            commands::rust_db_wafer_maps,
+            commands::rust_db_product_defect_maps,
+            commands::rust_db_substrate_defects,
+            commands::rust_db_file_index,
+            commands::rust_db_stack_stats,
+            commands::rust_db_schema_status,
+            commands::rust_db_migrate,
+            commands::rust_db_revert,
+            commands::rust_db_backup,
+            commands::rust_db_export_config,
+            commands::rust_db_import_config,
+            // Wafer search
+            commands::rust_search_wafers,
+            commands::rust_search_reindex,
+            // Stacking job queue
+            commands::rust_jobs_enqueue,
+            commands::rust_jobs_list,
+            commands::rust_jobs_tasks,
+            commands::rust_jobs_cancel,
+            commands::rust_jobs_retry,
+            commands::rust_jobs_remove,
+            // Local HTTP API
+            commands::rust_http_api_start,
+            commands::rust_http_api_stop,
+            commands::rust_http_api_status,
 
             // AOI inference
             commands::rust_aoi_inference_status,
@@ -100,8 +172,10 @@ fn run_once() -> Result<(), tauri::Error> {
     app.run(move |_app_handle, event| {
         match &event {
             RunEvent::ExitRequested { .. } => {
-                println!("🧹 Exit requested, cleaning up file locks...");
+                println!("🧹 Exit requested, cleaning up file locks and watchers...");
                 file_lock::clear_all_locks();
+                file::watcher::stop_all_watches();
+                http::stop();
                 println!("👋 Thank you for using our software!");
             }
             _ => {}
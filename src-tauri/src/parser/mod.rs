@@ -1,6 +1,9 @@
 mod tests;
 
-use crate::wafer::ds::{AsciiDie, BinValue, DefectRecordExcel, ProductRecord, ProductRecordExcel};
+use crate::wafer::bin_map::{BinMapRule, BinMapTable};
+use crate::wafer::ds::{
+    AsciiDie, BinMapRecordExcel, BinValue, DefectRecordExcel, ProductRecord, ProductRecordExcel,
+};
 
 use super::file::read_txt;
 use super::wafer::ds::{BinMapData, DefectRecord, MapData, ProductMappingRecord, Wafer};
@@ -320,6 +323,96 @@ pub fn debug_print_die_layout_coords(path: String) -> Result<(), String> {
     Ok(())
 }
 
+// =============================================================================
+// Bin translation tables
+// =============================================================================
+
+fn bin_map_table(
+    product_id: &str,
+    records: Vec<BinMapRecordExcel>,
+) -> Result<BinMapTable, String> {
+    let rules = records
+        .into_iter()
+        .enumerate()
+        // skip rows that were left blank in the sheet
+        .filter(|(_, r)| !(r.format.trim().is_empty() && r.native.trim().is_empty()))
+        .map(|(i, r)| {
+            BinMapRule::try_from(r)
+                .map_err(|e| format!("Bin map '{}' row {}: {}", product_id, i + 1, e))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let table = BinMapTable {
+        product_id: product_id.to_string(),
+        customer: None,
+        rules,
+    };
+    table.check()?;
+    Ok(table)
+}
+
+/// Parse a bin translation workbook:
+/// - Each sheet is a product ID.
+/// - Columns: 'Format', 'Native', 'Internal', optional 'Customer' and 'Description'.
+pub fn parse_bin_map_xls(path: String) -> Result<HashMap<String, BinMapTable>, String> {
+    let mut wb =
+        open_workbook_auto(&path).map_err(|e| format!("Failed to open Excel '{}': {}", path, e))?;
+
+    let mut result: HashMap<String, BinMapTable> = HashMap::new();
+
+    for sheet in wb.sheet_names().to_owned() {
+        let range = sheet_range(&mut wb, &sheet)?;
+
+        let rows = match RangeDeserializerBuilder::new()
+            .has_headers(true)
+            .from_range::<_, BinMapRecordExcel>(&range)
+        {
+            Ok(rows) => rows,
+            // This sheet likely doesn't have the required headers; skip it silently.
+            Err(_) => continue,
+        };
+
+        let records = rows
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| format!("Deserialization error in '{}': {}", sheet, e))?;
+        let table = bin_map_table(&sheet, records)?;
+        if !table.rules.is_empty() {
+            result.insert(sheet.clone(), table);
+        }
+    }
+
+    if result.is_empty() {
+        return Err(
+            "No sheets contained the required columns: 'Format', 'Native', 'Internal'.".into(),
+        );
+    }
+
+    Ok(result)
+}
+
+/// Parse a bin translation JSON file of the shape
+/// `{ "<product id>": [{ "format": "hex", "native": "0A", "internal": "10" }, ...] }`.
+pub fn parse_bin_map_json(path: String) -> Result<HashMap<String, BinMapTable>, String> {
+    let text = std::fs::read_to_string(&path)
+        .map_err(|e| format!("Failed to read bin map '{}': {}", path, e))?;
+    let by_product: HashMap<String, Vec<BinMapRecordExcel>> = serde_json::from_str(&text)
+        .map_err(|e| format!("Failed to parse bin map '{}': {}", path, e))?;
+
+    let mut result: HashMap<String, BinMapTable> = HashMap::new();
+    for (product_id, records) in by_product {
+        let table = bin_map_table(&product_id, records)?;
+        if !table.rules.is_empty() {
+            result.insert(product_id, table);
+        }
+    }
+
+    if result.is_empty() {
+        return Err(format!("Bin map '{}' has no rules", path));
+    }
+
+    Ok(result)
+}
+
 // =============================================================================
 // NOTE: Wrappings for the Tauri command
 // =============================================================================
@@ -199,3 +199,64 @@ fn test_parse_wafer_map_data() {
         Err(e) => panic!("Failed to parse wafer: {}", e),
     }
 }
+
+#[test]
+fn test_parse_bin_map_json() {
+    use super::parse_bin_map_json;
+    use crate::wafer::bin_map::BinFormat;
+    use crate::wafer::ds::BinValue;
+    use std::{env, fs};
+
+    let path = env::temp_dir().join("parse_bin_map.json");
+    fs::write(
+        &path,
+        r#"{ "P0094B": [
+            { "format": "hex", "native": "a", "internal": "2" },
+            { "format": "WLBI", "native": "257", "internal": "*", "customer": "SILAN" }
+        ] }"#,
+    )
+    .expect("failed to write temp bin map");
+
+    let tables = parse_bin_map_json(path.to_string_lossy().to_string())
+        .expect("Failed to parse bin map json");
+    let table = tables.get("P0094B").expect("missing product table");
+    assert_eq!(table.rules.len(), 2);
+    assert_eq!(table.rules[0].format, BinFormat::Hex);
+    assert_eq!(table.rules[0].native, "0A", "HEX codes should be normalized");
+    assert_eq!(table.rules[1].internal, BinValue::Special('*'));
+    assert_eq!(table.rules[1].customer.as_deref(), Some("SILAN"));
+}
+
+#[test]
+fn test_parse_bin_map_json_invalid_code() {
+    use super::parse_bin_map_json;
+    use std::{env, fs};
+
+    let path = env::temp_dir().join("parse_bin_map_invalid.json");
+    fs::write(&path, r#"{ "P0094B": [{ "format": "ascii", "native": "AB", "internal": "2" }] }"#)
+        .expect("failed to write temp bin map");
+
+    let result = parse_bin_map_json(path.to_string_lossy().to_string());
+    assert!(result.is_err(), "multi-character ASCII codes must be rejected");
+
+    fs::write(&path, r#"{ "P0094B": [{ "format": "hex", "native": "0A", "internal": "S" }] }"#)
+        .expect("failed to write temp bin map");
+    let result = parse_bin_map_json(path.to_string_lossy().to_string());
+    assert!(result.is_err(), "HEX rules must import to a byte");
+
+    fs::write(
+        &path,
+        r#"{ "P0094B": [
+            { "format": "hex", "native": "0A", "internal": "2" },
+            { "format": "hex", "native": "0B", "internal": "2" }
+        ] }"#,
+    )
+    .expect("failed to write temp bin map");
+    let result = parse_bin_map_json(path.to_string_lossy().to_string());
+    assert!(result.is_err(), "two codes for one internal bin make export ambiguous");
+
+    // like a workbook without rule sheets
+    fs::write(&path, r#"{ "P0094B": [] }"#).expect("failed to write temp bin map");
+    let result = parse_bin_map_json(path.to_string_lossy().to_string());
+    assert!(result.is_err(), "a bin map without rules must be rejected");
+}
@@ -0,0 +1,482 @@
+mod tests;
+
+pub mod font;
+pub mod pdf;
+pub mod raster;
+pub mod report;
+pub mod svg;
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::str::FromStr;
+
+use crate::analysis::composite::CompositeMap;
+use crate::wafer::bins::{bin_letter_to_number, BinSet};
+use crate::wafer::coords::{DieCoordinateSystem, DieRect};
+use crate::wafer::ds::{AsciiDie, BinValue};
+use crate::wafer::geometry::WaferGeometry;
+use crate::wafer::stats::{parse_bin_label, summarize};
+
+// =============================================================================
+// Wafer map scene
+//
+// Output-independent description of a rendered map: die rectangles in mm,
+// colours, wafer outline, notch, header text and legend. The raster, SVG and
+// PDF writers only draw a `MapScene`, so every format shows the same picture.
+// Physical placement comes from `DieCoordinateSystem`; the y axis points up,
+// writers flip it for image space.
+// =============================================================================
+
+pub type Rgb = [u8; 3];
+
+pub const BACKGROUND: Rgb = [0xff, 0xff, 0xff];
+pub const TEXT_COLOR: Rgb = [0x33, 0x33, 0x33];
+pub const GRID_COLOR: Rgb = [0xff, 0xff, 0xff];
+pub const OUTLINE_COLOR: Rgb = [0x99, 0x99, 0x99];
+pub const SUBSTRATE_COLOR: Rgb = [0x8c, 0xef, 0xa1];
+const DEFAULT_COLOR: Rgb = [0xcc, 0xcc, 0xcc];
+
+const fn hex(c: u32) -> Rgb {
+    [(c >> 16) as u8, (c >> 8) as u8, c as u8]
+}
+
+/// Bin colours, indexed by bin number; the reference palette for every rendered map.
+const BIN_COLORS: [Rgb; 21] = [
+    hex(0xd8a5bb),
+    hex(0x00ff00),
+    hex(0x7b7bc6),
+    hex(0xff78f6),
+    hex(0xfdfe00),
+    hex(0x00c8f7),
+    hex(0x2469d2),
+    hex(0xc85576),
+    hex(0xff00e2),
+    hex(0x394c44),
+    hex(0xcf1afc),
+    hex(0x2c31b0),
+    hex(0xa8cf7e),
+    hex(0x00eb7d),
+    hex(0xfbc03a),
+    hex(0x9000ca),
+    hex(0x085ab4),
+    hex(0x3a56b9),
+    hex(0xff0700),
+    hex(0x00b673),
+    hex(0x594543),
+];
+
+/// Ink marker `z` shares the colour of bin 20.
+const INK_BIN: i32 = 20;
+
+pub fn bin_color(bin: BinValue) -> Rgb {
+    let index = match bin {
+        BinValue::Number(n) => Some(n),
+        BinValue::Special('S') => return hex(0xd1191f),
+        BinValue::Special('z') => Some(INK_BIN),
+        BinValue::Special(c) if c.is_ascii_uppercase() => bin_letter_to_number(&c.to_string()),
+        BinValue::Special(_) => None,
+    };
+    index
+        .and_then(|n| usize::try_from(n).ok())
+        .and_then(|n| BIN_COLORS.get(n).copied())
+        .unwrap_or(DEFAULT_COLOR)
+}
+
+/// Fail-share ramp of composite maps: green (0) → yellow (0.5) → red (1).
+pub fn heat_color(share: f64) -> Rgb {
+    let t = share.clamp(0.0, 1.0);
+    let (r, g) = if t < 0.5 {
+        (t * 2.0, 1.0)
+    } else {
+        (1.0, (1.0 - t) * 2.0)
+    };
+    [(r * 255.0).round() as u8, (g * 200.0).round() as u8, 0x20]
+}
+
+/// Legend buckets of the composite heat map, in percent.
+const HEAT_BUCKETS: [(f64, f64); 5] = [
+    (0.0, 20.0),
+    (20.0, 40.0),
+    (40.0, 60.0),
+    (60.0, 80.0),
+    (80.0, 100.0),
+];
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ImageFormat {
+    Png,
+    #[default]
+    Jpeg,
+}
+
+impl ImageFormat {
+    /// From an output file extension; unknown extensions give `None`.
+    pub fn from_path(path: &str) -> Option<Self> {
+        let ext = std::path::Path::new(path).extension()?.to_str()?;
+        match ext.to_ascii_lowercase().as_str() {
+            "png" => Some(Self::Png),
+            "jpg" | "jpeg" => Some(Self::Jpeg),
+            _ => None,
+        }
+    }
+}
+
+/// `bin`: colour by bin; `substrate`: every die in the substrate colour.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum RenderStyle {
+    #[default]
+    Bin,
+    Substrate,
+}
+
+/// Notch/flat position on the drawn map.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum Notch {
+    Up,
+    Down,
+    Left,
+    Right,
+}
+
+impl FromStr for Notch {
+    type Err = String;
+
+    /// Accepts `Up/Down/Left/Right` (also `Top/Bottom`) or degrees measured
+    /// clockwise from the bottom: `0` down, `90` left, `180` up, `270` right.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let t = s.trim().to_ascii_lowercase();
+        match t.as_str() {
+            "up" | "top" | "u" | "180" => Ok(Self::Up),
+            "down" | "bottom" | "d" | "0" | "360" => Ok(Self::Down),
+            "left" | "l" | "90" => Ok(Self::Left),
+            "right" | "r" | "270" => Ok(Self::Right),
+            _ => match t.parse::<f64>() {
+                Ok(deg) => match ((deg.rem_euclid(360.0) + 45.0) / 90.0) as i32 % 4 {
+                    0 => Ok(Self::Down),
+                    1 => Ok(Self::Left),
+                    2 => Ok(Self::Up),
+                    _ => Ok(Self::Right),
+                },
+                Err(_) => Err(format!("Unknown notch direction: {}", s)),
+            },
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct WaferRenderOptions {
+    /// Output format; `None` picks it from the file extension
+    pub format: Option<ImageFormat>,
+    /// Pixels per inch of wafer (1 to `raster::MAX_DPI`); also written into the image metadata
+    pub dpi: u32,
+    pub jpeg_quality: u8,
+    pub style: RenderStyle,
+    pub grid_lines: bool,
+    pub show_outline: bool,
+    pub show_notch: bool,
+    pub show_header: bool,
+    pub show_legend: bool,
+    /// Pass bins for the yield line ("BIN 1", ...); empty uses the defaults
+    pub pass_bins: Vec<String>,
+    /// Overrides the header's `Flat/Notch`
+    pub notch: Option<String>,
+    /// Overrides the time printed in the header (default: now)
+    pub timestamp: Option<String>,
+}
+
+impl Default for WaferRenderOptions {
+    fn default() -> Self {
+        Self {
+            format: None,
+            dpi: 150,
+            jpeg_quality: 95,
+            style: RenderStyle::Bin,
+            grid_lines: true,
+            show_outline: true,
+            show_notch: true,
+            show_header: true,
+            show_legend: true,
+            pass_bins: Vec::new(),
+            notch: None,
+            timestamp: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct SceneDie {
+    pub x: i32,
+    pub y: i32,
+    pub bin: BinValue,
+    pub rect: DieRect,
+    pub color: Rgb,
+    /// Stacking layer the bin came from, when known
+    pub layer: Option<String>,
+}
+
+/// One input layer of a stacked map (aligned, in merge order).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MapLayer {
+    pub name: String,
+    pub dies: Vec<AsciiDie>,
+}
+
+#[derive(Debug, Clone)]
+pub struct LegendEntry {
+    pub label: String,
+    pub color: Rgb,
+}
+
+#[derive(Debug, Clone)]
+pub struct MapScene {
+    pub dies: Vec<SceneDie>,
+    /// Area to draw, in mm (y up: `top` is the lower edge numerically)
+    pub bounds: DieRect,
+    pub die_width_mm: f64,
+    pub die_height_mm: f64,
+    /// Wafer outline centred on the origin
+    pub wafer_radius_mm: Option<f64>,
+    pub notch: Option<Notch>,
+    pub header_lines: Vec<String>,
+    pub legend: Vec<LegendEntry>,
+}
+
+fn header_value<'a>(header: &'a HashMap<String, String>, keys: &[&str]) -> Option<&'a str> {
+    keys.iter()
+        .filter_map(|k| header.get(*k))
+        .map(|v| v.trim())
+        .find(|v| !v.is_empty())
+}
+
+impl MapScene {
+    /// Build the scene of a merged map.
+    ///
+    /// Without `coords` the dies are centred on the wafer using the pitch from
+    /// the header, falling back to 1 mm squares.
+    pub fn build(
+        dies: &[AsciiDie],
+        header: &HashMap<String, String>,
+        coords: Option<&DieCoordinateSystem>,
+        options: &WaferRenderOptions,
+    ) -> Result<Self, String> {
+        if dies.is_empty() {
+            return Err("No dies to render".to_string());
+        }
+        let geometry = WaferGeometry::from_header(header);
+        let coords = match (coords, geometry) {
+            (Some(c), _) => *c,
+            (None, Some(g)) => g.centered_coords(dies),
+            (None, None) => WaferGeometry::new(1.0, 1.0, 1.0)
+                .map(|g| g.centered_coords(dies))
+                .unwrap_or_else(|| DieCoordinateSystem::new(1.0, 1.0)),
+        };
+
+        let scene_dies: Vec<SceneDie> = dies
+            .iter()
+            .map(|d| SceneDie {
+                x: d.x,
+                y: d.y,
+                bin: d.bin,
+                rect: coords.die_rect(d.x, d.y),
+                color: match options.style {
+                    RenderStyle::Bin => bin_color(d.bin),
+                    RenderStyle::Substrate => SUBSTRATE_COLOR,
+                },
+                layer: None,
+            })
+            .collect();
+
+        let wafer_radius_mm = geometry.map(|g| g.radius_mm());
+        let mut bounds = scene_dies[0].rect;
+        for d in &scene_dies {
+            bounds.left = bounds.left.min(d.rect.left);
+            bounds.top = bounds.top.min(d.rect.top);
+            bounds.right = bounds.right.max(d.rect.right);
+            bounds.bottom = bounds.bottom.max(d.rect.bottom);
+        }
+        if let Some(r) = wafer_radius_mm.filter(|_| options.show_outline) {
+            bounds.left = bounds.left.min(-r);
+            bounds.top = bounds.top.min(-r);
+            bounds.right = bounds.right.max(r);
+            bounds.bottom = bounds.bottom.max(r);
+        }
+        let pad = coords.die_width_mm.max(coords.die_height_mm);
+        bounds = DieRect {
+            left: bounds.left - pad,
+            top: bounds.top - pad,
+            right: bounds.right + pad,
+            bottom: bounds.bottom + pad,
+        };
+
+        let notch_text = options
+            .notch
+            .as_deref()
+            .or_else(|| header_value(header, &["Flat/Notch", "Notch"]));
+        let notch = if options.show_notch {
+            notch_text.and_then(|n| n.parse::<Notch>().ok())
+        } else {
+            None
+        };
+
+        let summary = summarize(dies, &BinSet::from_ids(&options.pass_bins));
+        let header_lines = if options.show_header {
+            let timestamp = options
+                .timestamp
+                .clone()
+                .unwrap_or_else(|| chrono::Local::now().format("%Y/%m/%d %H:%M").to_string());
+            vec![
+                format!(
+                    "Product: {}_{}_{}",
+                    header_value(header, &["Product", "Device Name"]).unwrap_or("Unknown"),
+                    header_value(header, &["Lot No.", "Wafer Lots"]).unwrap_or(""),
+                    header_value(header, &["Wafer ID", "Wafer No"]).unwrap_or(""),
+                ),
+                format!(
+                    "Wafer size: {}   Pitch: [{}, {}]   Notch: {}",
+                    header_value(header, &["Wafer Size"]).unwrap_or("0"),
+                    header_value(header, &["Index X", "Dice SizeX"]).unwrap_or("0"),
+                    header_value(header, &["Index Y", "Dice SizeY"]).unwrap_or("0"),
+                    notch_text.unwrap_or("Unknown"),
+                ),
+                format!(
+                    "Time: {}   Tested: {}   Pass: {}   Fail: {}   Yield: {:.2}%",
+                    timestamp,
+                    summary.total_tested,
+                    summary.total_pass,
+                    summary.total_fail,
+                    summary.yield_percentage,
+                ),
+            ]
+        } else {
+            Vec::new()
+        };
+
+        let legend = if options.show_legend {
+            summary
+                .bins
+                .iter()
+                .map(|b| {
+                    let bin = parse_bin_label(&b.bin);
+                    LegendEntry {
+                        label: format!("BIN {} = {}", b.bin, b.count),
+                        color: match options.style {
+                            RenderStyle::Bin => bin_color(bin),
+                            RenderStyle::Substrate => SUBSTRATE_COLOR,
+                        },
+                    }
+                })
+                .collect()
+        } else {
+            Vec::new()
+        };
+
+        Ok(Self {
+            dies: scene_dies,
+            bounds,
+            die_width_mm: coords.die_width_mm,
+            die_height_mm: coords.die_height_mm,
+            wafer_radius_mm,
+            notch,
+            header_lines,
+            legend,
+        })
+    }
+
+    /// Composite heat map of a lot: each position coloured by how often it
+    /// failed, header listing the systematic positions.
+    pub fn build_composite(
+        composite: &CompositeMap,
+        header: &HashMap<String, String>,
+        coords: Option<&DieCoordinateSystem>,
+        options: &WaferRenderOptions,
+    ) -> Result<Self, String> {
+        let base = WaferRenderOptions {
+            show_header: false,
+            show_legend: false,
+            ..options.clone()
+        };
+        let mut scene = Self::build(&composite.dies(), header, coords, &base)?;
+        for (die, pos) in scene.dies.iter_mut().zip(&composite.positions) {
+            die.color = heat_color(pos.fail_percent / 100.0);
+        }
+
+        if options.show_header {
+            scene.header_lines = vec![
+                format!(
+                    "Product: {}   Lot: {}",
+                    header_value(header, &["Product", "Device Name"]).unwrap_or("Unknown"),
+                    header_value(header, &["Lot No.", "Wafer Lots"]).unwrap_or(""),
+                ),
+                format!(
+                    "Lot composite: {} wafers   Systematic (>= {:.0}% failing): {} positions",
+                    composite.wafer_count,
+                    composite.systematic_threshold,
+                    composite.systematic_count,
+                ),
+            ];
+        }
+        if options.show_legend {
+            scene.legend = HEAT_BUCKETS
+                .iter()
+                .enumerate()
+                .map(|(i, &(lo, hi))| {
+                    let count = composite
+                        .positions
+                        .iter()
+                        .filter(|p| {
+                            p.fail_percent >= lo
+                                && (p.fail_percent < hi || i == HEAT_BUCKETS.len() - 1)
+                        })
+                        .count();
+                    LegendEntry {
+                        label: format!("{:.0}-{:.0}% = {}", lo, hi, count),
+                        color: heat_color((lo + hi) / 200.0),
+                    }
+                })
+                .collect();
+        }
+        Ok(scene)
+    }
+
+    /// Tag every die with the first layer (in merge order) reporting its final
+    /// bin at that position, i.e. the layer that decided the stacked result.
+    pub fn attribute_layers(&mut self, layers: &[MapLayer]) {
+        let lookup: Vec<HashMap<(i32, i32), BinValue>> = layers
+            .iter()
+            .map(|l| l.dies.iter().map(|d| ((d.x, d.y), d.bin)).collect())
+            .collect();
+        for die in &mut self.dies {
+            die.layer = layers
+                .iter()
+                .zip(&lookup)
+                .find(|(_, bins)| bins.get(&(die.x, die.y)) == Some(&die.bin))
+                .map(|(l, _)| l.name.clone());
+        }
+    }
+
+    /// Triangle marking the notch, in mm (y up).
+    pub fn notch_triangle(&self) -> Option<[(f64, f64); 3]> {
+        let notch = self.notch?;
+        let r = self.wafer_radius_mm.unwrap_or_else(|| {
+            (self.bounds.right - self.bounds.left).min(self.bounds.bottom - self.bounds.top) / 2.0
+        });
+        let size = (r * 0.04).max(self.die_width_mm.max(self.die_height_mm) * 0.6);
+        // tip points inwards from the edge
+        let (ex, ey, dx, dy) = match notch {
+            Notch::Down => (0.0, -r, 0.0, 1.0),
+            Notch::Up => (0.0, r, 0.0, -1.0),
+            Notch::Left => (-r, 0.0, 1.0, 0.0),
+            Notch::Right => (r, 0.0, -1.0, 0.0),
+        };
+        let (px, py) = (-dy, dx);
+        Some([
+            (ex + px * size / 2.0, ey + py * size / 2.0),
+            (ex - px * size / 2.0, ey - py * size / 2.0),
+            (ex + dx * size, ey + dy * size),
+        ])
+    }
+}
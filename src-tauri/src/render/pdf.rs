@@ -0,0 +1,337 @@
+use std::io::Write;
+
+use super::{MapScene, Rgb, WaferRenderOptions, GRID_COLOR, OUTLINE_COLOR};
+
+// =============================================================================
+// Minimal PDF writer
+//
+// Just enough of PDF 1.4 for reports: filled/stroked paths and text in the
+// built-in Helvetica fonts (no embedding, WinAnsi encoding; characters outside
+// Latin-1 print as `?`). Page helpers take top-left based coordinates in points
+// and flip them to the PDF frame.
+// =============================================================================
+
+/// A4 portrait in points.
+pub const A4: (f64, f64) = (595.0, 842.0);
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Font {
+    Regular,
+    Bold,
+}
+
+impl Font {
+    fn resource(self) -> &'static str {
+        match self {
+            Font::Regular => "F1",
+            Font::Bold => "F2",
+        }
+    }
+}
+
+/// Helvetica advance widths (1/1000 em), grouped by glyph shape.
+fn char_width(c: char) -> f64 {
+    let w = match c {
+        'i' | 'j' | 'l' | '\'' | '|' => 222,
+        ' ' | '.' | ',' | ':' | ';' | '!' | 'f' | 't' | 'I' | '/' | '[' | ']' => 278,
+        '(' | ')' | '-' | 'r' => 333,
+        '*' => 389,
+        '0'..='9' | '_' | '#' | '$' | '?' => 556,
+        'm' => 833,
+        'w' => 722,
+        'M' => 833,
+        'W' => 944,
+        '%' => 889,
+        'A'..='Z' => 667,
+        _ => 556,
+    };
+    w as f64 / 1000.0
+}
+
+/// Approximate rendered width of `text` at `size` points.
+pub fn text_width(text: &str, size: f64) -> f64 {
+    text.chars().map(char_width).sum::<f64>() * size
+}
+
+fn color_op(c: Rgb, op: &str) -> String {
+    format!(
+        "{:.3} {:.3} {:.3} {}",
+        c[0] as f64 / 255.0,
+        c[1] as f64 / 255.0,
+        c[2] as f64 / 255.0,
+        op
+    )
+}
+
+/// Literal string in WinAnsi bytes.
+fn pdf_string(text: &str) -> Vec<u8> {
+    let mut out = vec![b'('];
+    for c in text.chars() {
+        let b = if (c as u32) < 256 {
+            c as u32 as u8
+        } else {
+            b'?'
+        };
+        if matches!(b, b'(' | b')' | b'\\') {
+            out.push(b'\\');
+        }
+        out.push(b);
+    }
+    out.push(b')');
+    out
+}
+
+pub struct PdfPage {
+    pub width: f64,
+    pub height: f64,
+    content: Vec<u8>,
+}
+
+impl PdfPage {
+    pub fn new(size: (f64, f64)) -> Self {
+        Self {
+            width: size.0,
+            height: size.1,
+            content: Vec::new(),
+        }
+    }
+
+    fn op(&mut self, line: &str) {
+        self.content.extend_from_slice(line.as_bytes());
+        self.content.push(b'\n');
+    }
+
+    pub fn fill_rect(&mut self, x: f64, top: f64, w: f64, h: f64, color: Rgb) {
+        let y = self.height - top - h;
+        self.op(&color_op(color, "rg"));
+        self.op(&format!("{:.2} {:.2} {:.2} {:.2} re f", x, y, w, h));
+    }
+
+    pub fn stroke_rect(&mut self, x: f64, top: f64, w: f64, h: f64, color: Rgb, line_width: f64) {
+        let y = self.height - top - h;
+        self.op(&color_op(color, "RG"));
+        self.op(&format!(
+            "{:.2} w {:.2} {:.2} {:.2} {:.2} re S",
+            line_width, x, y, w, h
+        ));
+    }
+
+    pub fn line(&mut self, from: (f64, f64), to: (f64, f64), color: Rgb, line_width: f64) {
+        self.op(&color_op(color, "RG"));
+        self.op(&format!(
+            "{:.2} w {:.2} {:.2} m {:.2} {:.2} l S",
+            line_width,
+            from.0,
+            self.height - from.1,
+            to.0,
+            self.height - to.1
+        ));
+    }
+
+    /// Circle from four Bézier arcs.
+    pub fn stroke_circle(&mut self, cx: f64, cy: f64, r: f64, color: Rgb, line_width: f64) {
+        const K: f64 = 0.552_284_75;
+        let cy = self.height - cy;
+        let k = r * K;
+        self.op(&color_op(color, "RG"));
+        self.op(&format!("{:.2} w {:.2} {:.2} m", line_width, cx + r, cy));
+        for (c1, c2, end) in [
+            ((cx + r, cy + k), (cx + k, cy + r), (cx, cy + r)),
+            ((cx - k, cy + r), (cx - r, cy + k), (cx - r, cy)),
+            ((cx - r, cy - k), (cx - k, cy - r), (cx, cy - r)),
+            ((cx + k, cy - r), (cx + r, cy - k), (cx + r, cy)),
+        ] {
+            self.op(&format!(
+                "{:.2} {:.2} {:.2} {:.2} {:.2} {:.2} c",
+                c1.0, c1.1, c2.0, c2.1, end.0, end.1
+            ));
+        }
+        self.op("S");
+    }
+
+    pub fn fill_polygon(&mut self, points: &[(f64, f64)], color: Rgb) {
+        let Some((first, rest)) = points.split_first() else {
+            return;
+        };
+        self.op(&color_op(color, "rg"));
+        self.op(&format!("{:.2} {:.2} m", first.0, self.height - first.1));
+        for p in rest {
+            self.op(&format!("{:.2} {:.2} l", p.0, self.height - p.1));
+        }
+        self.op("h f");
+    }
+
+    /// Text with its baseline at `baseline` (measured from the top).
+    pub fn text(&mut self, x: f64, baseline: f64, size: f64, font: Font, color: Rgb, text: &str) {
+        self.op(&color_op(color, "rg"));
+        let head = format!(
+            "BT /{} {:.1} Tf {:.2} {:.2} Td ",
+            font.resource(),
+            size,
+            x,
+            self.height - baseline
+        );
+        self.content.extend_from_slice(head.as_bytes());
+        self.content.extend_from_slice(&pdf_string(text));
+        self.op(" Tj ET");
+    }
+
+    pub fn text_right(
+        &mut self,
+        right: f64,
+        baseline: f64,
+        size: f64,
+        font: Font,
+        color: Rgb,
+        text: &str,
+    ) {
+        let x = right - text_width(text, size);
+        self.text(x, baseline, size, font, color, text);
+    }
+
+    pub fn text_centered(
+        &mut self,
+        center: f64,
+        baseline: f64,
+        size: f64,
+        font: Font,
+        color: Rgb,
+        text: &str,
+    ) {
+        let x = center - text_width(text, size) / 2.0;
+        self.text(x, baseline, size, font, color, text);
+    }
+
+    /// Draw the map part of a scene (dies, outline, notch) fitted into the box.
+    pub fn draw_scene(
+        &mut self,
+        scene: &MapScene,
+        options: &WaferRenderOptions,
+        x: f64,
+        top: f64,
+        w: f64,
+        h: f64,
+    ) {
+        let map_w = scene.bounds.right - scene.bounds.left;
+        let map_h = scene.bounds.bottom - scene.bounds.top;
+        if map_w <= 0.0 || map_h <= 0.0 {
+            return;
+        }
+        let scale = (w / map_w).min(h / map_h);
+        let ox = x + (w - map_w * scale) / 2.0;
+        let oy = top + (h - map_h * scale) / 2.0;
+        // mm (y up) -> page (y down from top)
+        let to_page = |mx: f64, my: f64| {
+            (
+                ox + (mx - scene.bounds.left) * scale,
+                oy + (scene.bounds.bottom - my) * scale,
+            )
+        };
+
+        let grid_width = (scene.die_width_mm.min(scene.die_height_mm) * scale * 0.06).min(0.5);
+        for die in &scene.dies {
+            let (dx, dy) = to_page(die.rect.left, die.rect.bottom);
+            let dw = (die.rect.right - die.rect.left) * scale;
+            let dh = (die.rect.bottom - die.rect.top) * scale;
+            self.fill_rect(dx, dy, dw, dh, die.color);
+            if options.grid_lines {
+                self.stroke_rect(dx, dy, dw, dh, GRID_COLOR, grid_width);
+            }
+        }
+        if let Some(r) = scene.wafer_radius_mm.filter(|_| options.show_outline) {
+            let (cx, cy) = to_page(0.0, 0.0);
+            self.stroke_circle(cx, cy, r * scale, OUTLINE_COLOR, 0.6);
+        }
+        if let Some(tri) = scene.notch_triangle() {
+            self.fill_polygon(&tri.map(|(mx, my)| to_page(mx, my)), OUTLINE_COLOR);
+        }
+    }
+}
+
+/// Pages plus document title; `to_bytes` lays out the object table.
+pub struct PdfDocument {
+    title: String,
+    pages: Vec<PdfPage>,
+}
+
+impl PdfDocument {
+    pub fn new(title: impl Into<String>) -> Self {
+        Self {
+            title: title.into(),
+            pages: Vec::new(),
+        }
+    }
+
+    pub fn add_page(&mut self, page: PdfPage) {
+        self.pages.push(page);
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        // 1 catalog, 2 page tree, 3/4 fonts, 5 info, then page + content pairs
+        const FIRST_PAGE: usize = 6;
+        let mut objects: Vec<Vec<u8>> = Vec::new();
+        let kids: Vec<String> = (0..self.pages.len())
+            .map(|i| format!("{} 0 R", FIRST_PAGE + 2 * i))
+            .collect();
+        objects.push(b"<< /Type /Catalog /Pages 2 0 R >>".to_vec());
+        objects.push(
+            format!(
+                "<< /Type /Pages /Kids [{}] /Count {} >>",
+                kids.join(" "),
+                self.pages.len()
+            )
+            .into_bytes(),
+        );
+        for base in ["Helvetica", "Helvetica-Bold"] {
+            objects.push(
+                format!(
+                    "<< /Type /Font /Subtype /Type1 /BaseFont /{} /Encoding /WinAnsiEncoding >>",
+                    base
+                )
+                .into_bytes(),
+            );
+        }
+        let mut info = b"<< /Title ".to_vec();
+        info.extend_from_slice(&pdf_string(&self.title));
+        info.extend_from_slice(b" /Producer (aoi-wafer-stacking) >>");
+        objects.push(info);
+
+        for (i, page) in self.pages.iter().enumerate() {
+            objects.push(
+                format!(
+                    "<< /Type /Page /Parent 2 0 R /MediaBox [0 0 {} {}] \
+                     /Resources << /Font << /F1 3 0 R /F2 4 0 R >> >> /Contents {} 0 R >>",
+                    page.width,
+                    page.height,
+                    FIRST_PAGE + 2 * i + 1
+                )
+                .into_bytes(),
+            );
+            let mut stream = format!("<< /Length {} >>\nstream\n", page.content.len()).into_bytes();
+            stream.extend_from_slice(&page.content);
+            stream.extend_from_slice(b"endstream");
+            objects.push(stream);
+        }
+
+        let mut out: Vec<u8> = b"%PDF-1.4\n%\xe2\xe3\xcf\xd3\n".to_vec();
+        let mut offsets = Vec::with_capacity(objects.len());
+        for (i, body) in objects.iter().enumerate() {
+            offsets.push(out.len());
+            let _ = writeln!(out, "{} 0 obj", i + 1);
+            out.extend_from_slice(body);
+            out.extend_from_slice(b"\nendobj\n");
+        }
+        let xref = out.len();
+        let _ = writeln!(out, "xref\n0 {}\n0000000000 65535 f ", objects.len() + 1);
+        for offset in offsets {
+            let _ = writeln!(out, "{:010} 00000 n ", offset);
+        }
+        let _ = writeln!(
+            out,
+            "trailer\n<< /Size {} /Root 1 0 R /Info 5 0 R >>\nstartxref\n{}\n%%EOF",
+            objects.len() + 1,
+            xref
+        );
+        out
+    }
+}
@@ -0,0 +1,328 @@
+use std::collections::HashMap;
+use std::io::Cursor;
+
+use crate::analysis::composite::CompositeMap;
+use crate::wafer::coords::DieCoordinateSystem;
+use crate::wafer::ds::AsciiDie;
+
+use super::font::{glyph, text_width, ADVANCE, GLYPH_HEIGHT, GLYPH_WIDTH};
+use super::{
+    ImageFormat, MapScene, Rgb, WaferRenderOptions, BACKGROUND, GRID_COLOR, OUTLINE_COLOR,
+    TEXT_COLOR,
+};
+
+// =============================================================================
+// PNG/JPEG rasterizer
+//
+// Layout follows the old canvas export: map on top, header lines below it,
+// then the bin legend (5 entries per row).
+// =============================================================================
+
+const MM_PER_INCH: f64 = 25.4;
+const LEGEND_COLUMNS: u32 = 5;
+/// Upper bound of `WaferRenderOptions::dpi`
+pub const MAX_DPI: u32 = 2400;
+/// 300 MB of RGB; a 200 mm wafer at `MAX_DPI` is about 56 million pixels.
+const MAX_PIXELS: usize = 100_000_000;
+
+/// 24-bit RGB pixel buffer.
+pub struct Canvas {
+    pub width: u32,
+    pub height: u32,
+    pub pixels: Vec<u8>,
+}
+
+impl Canvas {
+    pub fn new(width: u32, height: u32, background: Rgb) -> Result<Self, String> {
+        let len = (width as usize)
+            .checked_mul(height as usize)
+            .filter(|&n| n <= MAX_PIXELS)
+            .and_then(|n| n.checked_mul(3))
+            .ok_or_else(|| format!("Image of {}x{} pixels is too large", width, height))?;
+        let pixels = background.iter().copied().cycle().take(len).collect();
+        Ok(Self {
+            width,
+            height,
+            pixels,
+        })
+    }
+
+    pub fn put(&mut self, x: i64, y: i64, color: Rgb) {
+        if x < 0 || y < 0 || x >= self.width as i64 || y >= self.height as i64 {
+            return;
+        }
+        let i = (y as usize * self.width as usize + x as usize) * 3;
+        self.pixels[i..i + 3].copy_from_slice(&color);
+    }
+
+    /// Fill the half-open pixel box `[x0, x1) x [y0, y1)`.
+    pub fn fill_rect(&mut self, x0: i64, y0: i64, x1: i64, y1: i64, color: Rgb) {
+        let x0 = x0.max(0);
+        let y0 = y0.max(0);
+        let x1 = x1.min(self.width as i64);
+        let y1 = y1.min(self.height as i64);
+        for y in y0..y1 {
+            for x in x0..x1 {
+                self.put(x, y, color);
+            }
+        }
+    }
+
+    pub fn stroke_rect(&mut self, x0: i64, y0: i64, x1: i64, y1: i64, color: Rgb) {
+        self.fill_rect(x0, y0, x1, y0 + 1, color);
+        self.fill_rect(x0, y1 - 1, x1, y1, color);
+        self.fill_rect(x0, y0, x0 + 1, y1, color);
+        self.fill_rect(x1 - 1, y0, x1, y1, color);
+    }
+
+    pub fn stroke_circle(&mut self, cx: f64, cy: f64, r: f64, color: Rgb) {
+        let steps = ((r * std::f64::consts::TAU).ceil() as usize).max(16) * 2;
+        for i in 0..steps {
+            let a = i as f64 / steps as f64 * std::f64::consts::TAU;
+            self.put(
+                (cx + r * a.cos()).round() as i64,
+                (cy + r * a.sin()).round() as i64,
+                color,
+            );
+        }
+    }
+
+    pub fn fill_triangle(&mut self, p: [(f64, f64); 3], color: Rgb) {
+        let min_x = p.iter().map(|v| v.0).fold(f64::INFINITY, f64::min).floor() as i64;
+        let max_x = p
+            .iter()
+            .map(|v| v.0)
+            .fold(f64::NEG_INFINITY, f64::max)
+            .ceil() as i64;
+        let min_y = p.iter().map(|v| v.1).fold(f64::INFINITY, f64::min).floor() as i64;
+        let max_y = p
+            .iter()
+            .map(|v| v.1)
+            .fold(f64::NEG_INFINITY, f64::max)
+            .ceil() as i64;
+        let edge = |a: (f64, f64), b: (f64, f64), x: f64, y: f64| {
+            (b.0 - a.0) * (y - a.1) - (b.1 - a.1) * (x - a.0)
+        };
+        for y in min_y..=max_y {
+            for x in min_x..=max_x {
+                let (fx, fy) = (x as f64 + 0.5, y as f64 + 0.5);
+                let e0 = edge(p[0], p[1], fx, fy);
+                let e1 = edge(p[1], p[2], fx, fy);
+                let e2 = edge(p[2], p[0], fx, fy);
+                if (e0 >= 0.0 && e1 >= 0.0 && e2 >= 0.0) || (e0 <= 0.0 && e1 <= 0.0 && e2 <= 0.0) {
+                    self.put(x, y, color);
+                }
+            }
+        }
+    }
+
+    /// Draw `text` with its top-left corner at `(x, y)`, each font pixel `scale` wide.
+    pub fn draw_text(&mut self, x: i64, y: i64, text: &str, scale: u32, color: Rgb) {
+        let s = scale as i64;
+        for (i, c) in text.chars().enumerate() {
+            let ox = x + i as i64 * ADVANCE as i64 * s;
+            for (col, bits) in glyph(c).iter().enumerate() {
+                for row in 0..GLYPH_HEIGHT {
+                    if bits & (1 << row) != 0 {
+                        let px = ox + col as i64 * s;
+                        let py = y + row as i64 * s;
+                        self.fill_rect(px, py, px + s, py + s, color);
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Pixels covering `mm`; the canvas size check rejects what gets through.
+fn pixels(mm: f64, px_per_mm: f64) -> Result<u32, String> {
+    let px = (mm * px_per_mm).ceil();
+    if px.is_finite() && (0.0..=u32::MAX as f64).contains(&px) {
+        Ok(px as u32)
+    } else {
+        Err(format!("Map of {} mm cannot be rendered", mm))
+    }
+}
+
+/// Draw a scene at `options.dpi` (1 to `MAX_DPI`).
+pub fn render_scene(scene: &MapScene, options: &WaferRenderOptions) -> Result<Canvas, String> {
+    if !(1..=MAX_DPI).contains(&options.dpi) {
+        return Err(format!(
+            "DPI must be between 1 and {}, got {}",
+            MAX_DPI, options.dpi
+        ));
+    }
+    let dpi = options.dpi as f64;
+    let px_per_mm = dpi / MM_PER_INCH;
+    let text_scale = ((dpi / 72.0).round() as u32).max(1);
+    let line_height = (GLYPH_HEIGHT + 5) * text_scale;
+    let padding = 8 * text_scale;
+
+    let map_w = pixels(scene.bounds.right - scene.bounds.left, px_per_mm)?;
+    let map_h = pixels(scene.bounds.bottom - scene.bounds.top, px_per_mm)?;
+
+    let legend_item_w = scene
+        .legend
+        .iter()
+        .map(|e| text_width(&e.label) * text_scale + line_height + padding)
+        .max()
+        .unwrap_or(0);
+    let text_w = scene
+        .header_lines
+        .iter()
+        .map(|l| text_width(l) * text_scale)
+        .max()
+        .unwrap_or(0);
+    let width = map_w
+        .max(text_w + 2 * padding)
+        .max(legend_item_w * LEGEND_COLUMNS + 2 * padding)
+        .max(1);
+
+    let header_h = if scene.header_lines.is_empty() {
+        0
+    } else {
+        scene.header_lines.len() as u32 * line_height + 2 * padding
+    };
+    let legend_rows = (scene.legend.len() as u32).div_ceil(LEGEND_COLUMNS);
+    let legend_h = if legend_rows == 0 {
+        0
+    } else {
+        legend_rows * line_height + 2 * padding
+    };
+    let height = map_h
+        .checked_add(header_h)
+        .and_then(|h| h.checked_add(legend_h))
+        .ok_or_else(|| "Map is too tall to render".to_string())?
+        .max(1);
+
+    let mut canvas = Canvas::new(width, height, BACKGROUND)?;
+    let origin_x = (width - map_w) as f64 / 2.0;
+    // mm (y up) -> pixels (y down)
+    let to_px = |x: f64, y: f64| {
+        (
+            origin_x + (x - scene.bounds.left) * px_per_mm,
+            (scene.bounds.bottom - y) * px_per_mm,
+        )
+    };
+
+    for die in &scene.dies {
+        let (x0, y1) = to_px(die.rect.left, die.rect.top);
+        let (x1, y0) = to_px(die.rect.right, die.rect.bottom);
+        let (x0, y0, x1, y1) = (
+            x0.round() as i64,
+            y0.round() as i64,
+            x1.round() as i64,
+            y1.round() as i64,
+        );
+        canvas.fill_rect(x0, y0, x1, y1, die.color);
+        if options.grid_lines && x1 - x0 > 2 && y1 - y0 > 2 {
+            canvas.stroke_rect(x0, y0, x1, y1, GRID_COLOR);
+        }
+    }
+
+    if let Some(r) = scene.wafer_radius_mm.filter(|_| options.show_outline) {
+        let (cx, cy) = to_px(0.0, 0.0);
+        canvas.stroke_circle(cx, cy, r * px_per_mm, OUTLINE_COLOR);
+    }
+    if let Some(tri) = scene.notch_triangle() {
+        canvas.fill_triangle(tri.map(|(x, y)| to_px(x, y)), OUTLINE_COLOR);
+    }
+
+    let mut y = (map_h + padding) as i64;
+    for line in &scene.header_lines {
+        let w = text_width(line) * text_scale;
+        let x = (width.saturating_sub(w) / 2) as i64;
+        canvas.draw_text(x, y, line, text_scale, TEXT_COLOR);
+        y += line_height as i64;
+    }
+    if header_h > 0 {
+        y += padding as i64;
+    }
+
+    let col_w = (width - 2 * padding) / LEGEND_COLUMNS;
+    let swatch = GLYPH_HEIGHT * text_scale;
+    for (i, entry) in scene.legend.iter().enumerate() {
+        let row = i as u32 / LEGEND_COLUMNS;
+        let col = i as u32 % LEGEND_COLUMNS;
+        let x = (padding + col * col_w) as i64;
+        let top = y + (padding + row * line_height) as i64;
+        canvas.fill_rect(x, top, x + swatch as i64, top + swatch as i64, entry.color);
+        canvas.draw_text(
+            x + (swatch + GLYPH_WIDTH * text_scale) as i64,
+            top,
+            &entry.label,
+            text_scale,
+            TEXT_COLOR,
+        );
+    }
+
+    Ok(canvas)
+}
+
+/// Encode the canvas, storing the DPI in the file metadata.
+pub fn encode(
+    canvas: &Canvas,
+    format: ImageFormat,
+    dpi: u32,
+    quality: u8,
+) -> Result<Vec<u8>, String> {
+    let mut out = Vec::new();
+    match format {
+        ImageFormat::Png => {
+            let mut encoder = png::Encoder::new(Cursor::new(&mut out), canvas.width, canvas.height);
+            encoder.set_color(png::ColorType::Rgb);
+            encoder.set_depth(png::BitDepth::Eight);
+            let per_meter = (dpi as f64 / MM_PER_INCH * 1000.0).round() as u32;
+            encoder.set_pixel_dims(Some(png::PixelDimensions {
+                xppu: per_meter,
+                yppu: per_meter,
+                unit: png::Unit::Meter,
+            }));
+            let mut writer = encoder
+                .write_header()
+                .map_err(|e| format!("Failed to write PNG header: {}", e))?;
+            writer
+                .write_image_data(&canvas.pixels)
+                .map_err(|e| format!("Failed to write PNG data: {}", e))?;
+        }
+        ImageFormat::Jpeg => {
+            let (w, h) = (
+                u16::try_from(canvas.width).map_err(|_| "Image too wide for JPEG".to_string())?,
+                u16::try_from(canvas.height).map_err(|_| "Image too tall for JPEG".to_string())?,
+            );
+            let dpi = u16::try_from(dpi).unwrap_or(u16::MAX);
+            let mut encoder = jpeg_encoder::Encoder::new(&mut out, quality.clamp(1, 100));
+            encoder.set_density(jpeg_encoder::Density::Inch { x: dpi, y: dpi });
+            encoder
+                .encode(&canvas.pixels, w, h, jpeg_encoder::ColorType::Rgb)
+                .map_err(|e| format!("Failed to encode JPEG: {}", e))?;
+        }
+    }
+    Ok(out)
+}
+
+/// Render a lot composite heat map to encoded image bytes.
+pub fn render_composite(
+    composite: &CompositeMap,
+    header: &HashMap<String, String>,
+    coords: Option<&DieCoordinateSystem>,
+    options: &WaferRenderOptions,
+    format: ImageFormat,
+) -> Result<Vec<u8>, String> {
+    let scene = MapScene::build_composite(composite, header, coords, options)?;
+    let canvas = render_scene(&scene, options)?;
+    encode(&canvas, format, options.dpi, options.jpeg_quality)
+}
+
+/// Render a merged map straight to encoded image bytes.
+pub fn render_map(
+    dies: &[AsciiDie],
+    header: &HashMap<String, String>,
+    coords: Option<&DieCoordinateSystem>,
+    options: &WaferRenderOptions,
+    format: ImageFormat,
+) -> Result<Vec<u8>, String> {
+    let scene = MapScene::build(dies, header, coords, options)?;
+    let canvas = render_scene(&scene, options)?;
+    encode(&canvas, format, options.dpi, options.jpeg_quality)
+}
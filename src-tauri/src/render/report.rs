@@ -0,0 +1,479 @@
+use serde::Deserialize;
+use std::collections::HashMap;
+
+use crate::wafer::bins::BinSet;
+use crate::wafer::coords::DieCoordinateSystem;
+use crate::wafer::ds::AsciiDie;
+use crate::wafer::stats::{
+    bin_pareto, parse_bin_label, summarize, BinCount, MapSummary, ParetoEntry, WaferStackStats,
+};
+
+use super::pdf::{Font, PdfDocument, PdfPage, A4};
+use super::{bin_color, MapLayer, MapScene, Rgb, WaferRenderOptions, OUTLINE_COLOR, TEXT_COLOR};
+
+// =============================================================================
+// PDF lot report
+//
+// Cover page with the lot totals and a per-wafer table, then one page per
+// wafer: stacked map, fail-bin Pareto and a yield table per stacking stage.
+// Totals come from the `wafer_stack_stats` rows; maps and stage yields from
+// the stacking result when the caller has it.
+// =============================================================================
+
+const MARGIN: f64 = 48.0;
+const ROW_HEIGHT: f64 = 15.0;
+const BODY_SIZE: f64 = 9.0;
+const PARETO_ROWS: usize = 12;
+const MUTED: Rgb = [0x88, 0x88, 0x88];
+const RULE: Rgb = [0xcc, 0xcc, 0xcc];
+
+/// One wafer of the report: its stats row plus, optionally, the stacking result.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReportWafer {
+    pub stats: WaferStackStats,
+    /// Stacked (merged) map
+    #[serde(default)]
+    pub dies: Vec<AsciiDie>,
+    #[serde(default)]
+    pub header: HashMap<String, String>,
+    /// Input layers in merge order, for the stage yield table
+    #[serde(default)]
+    pub layers: Vec<MapLayer>,
+    #[serde(default)]
+    pub coords: Option<DieCoordinateSystem>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LotReport {
+    pub oem_product_id: String,
+    pub batch_id: String,
+    pub wafers: Vec<ReportWafer>,
+    /// Pass bins ("BIN 1", ...); empty uses the defaults
+    #[serde(default)]
+    pub pass_bins: Vec<String>,
+    #[serde(default)]
+    pub render: WaferRenderOptions,
+    /// Printed on the cover (default: now)
+    #[serde(default)]
+    pub generated_at: Option<String>,
+}
+
+/// Column of a table: header, x of its left (or right) edge, right aligned.
+struct Column {
+    title: &'static str,
+    x: f64,
+    right: bool,
+}
+
+fn table_row(page: &mut PdfPage, columns: &[Column], baseline: f64, cells: &[String], font: Font) {
+    for (col, cell) in columns.iter().zip(cells) {
+        if col.right {
+            page.text_right(col.x, baseline, BODY_SIZE, font, TEXT_COLOR, cell);
+        } else {
+            page.text(col.x, baseline, BODY_SIZE, font, TEXT_COLOR, cell);
+        }
+    }
+}
+
+fn table_header(page: &mut PdfPage, columns: &[Column], baseline: f64, left: f64, right: f64) {
+    let titles: Vec<String> = columns.iter().map(|c| c.title.to_string()).collect();
+    table_row(page, columns, baseline, &titles, Font::Bold);
+    page.line((left, baseline + 4.0), (right, baseline + 4.0), RULE, 0.6);
+}
+
+fn yield_text(pass: u32, tested: u32) -> String {
+    if tested == 0 {
+        "-".to_string()
+    } else {
+        format!("{:.2}%", pass as f64 / tested as f64 * 100.0)
+    }
+}
+
+fn wafer_bins(wafer: &ReportWafer, pass: &BinSet) -> Result<Vec<BinCount>, String> {
+    let bins = wafer.stats.bins()?;
+    if bins.is_empty() && !wafer.dies.is_empty() {
+        return Ok(summarize(&wafer.dies, pass).bins);
+    }
+    Ok(bins)
+}
+
+fn cover_page(report: &LotReport, pass: &BinSet) -> Result<Vec<PdfPage>, String> {
+    let (width, height) = A4;
+    let right = width - MARGIN;
+    let mut page = PdfPage::new(A4);
+
+    page.text(
+        MARGIN,
+        MARGIN + 20.0,
+        22.0,
+        Font::Bold,
+        TEXT_COLOR,
+        "Lot Report",
+    );
+    let generated = report
+        .generated_at
+        .clone()
+        .unwrap_or_else(|| chrono::Local::now().format("%Y/%m/%d %H:%M").to_string());
+    let info = [
+        format!("Product: {}", report.oem_product_id),
+        format!("Lot: {}", report.batch_id),
+        format!("Wafers: {}", report.wafers.len()),
+        format!("Generated: {}", generated),
+    ];
+    let mut y = MARGIN + 48.0;
+    for line in &info {
+        page.text(MARGIN, y, 11.0, Font::Regular, TEXT_COLOR, line);
+        y += 16.0;
+    }
+
+    let tested: u32 = report.wafers.iter().map(|w| w.stats.total_tested).sum();
+    let passed: u32 = report.wafers.iter().map(|w| w.stats.total_pass).sum();
+    let failed: u32 = report.wafers.iter().map(|w| w.stats.total_fail).sum();
+    y += 8.0;
+    page.stroke_rect(MARGIN, y, right - MARGIN, 64.0, RULE, 0.8);
+    page.text(
+        MARGIN + 14.0,
+        y + 24.0,
+        11.0,
+        Font::Regular,
+        MUTED,
+        "Lot yield",
+    );
+    page.text(
+        MARGIN + 14.0,
+        y + 50.0,
+        24.0,
+        Font::Bold,
+        TEXT_COLOR,
+        &yield_text(passed, tested),
+    );
+    let totals = format!("Tested: {}   Pass: {}   Fail: {}", tested, passed, failed);
+    page.text_right(
+        right - 14.0,
+        y + 50.0,
+        11.0,
+        Font::Regular,
+        TEXT_COLOR,
+        &totals,
+    );
+    y += 96.0;
+
+    let columns = [
+        Column {
+            title: "No.",
+            x: MARGIN,
+            right: false,
+        },
+        Column {
+            title: "Wafer ID",
+            x: MARGIN + 34.0,
+            right: false,
+        },
+        Column {
+            title: "Tested",
+            x: MARGIN + 200.0,
+            right: true,
+        },
+        Column {
+            title: "Pass",
+            x: MARGIN + 250.0,
+            right: true,
+        },
+        Column {
+            title: "Fail",
+            x: MARGIN + 300.0,
+            right: true,
+        },
+        Column {
+            title: "Yield",
+            x: MARGIN + 360.0,
+            right: true,
+        },
+        Column {
+            title: "Top fail bin",
+            x: MARGIN + 390.0,
+            right: false,
+        },
+    ];
+    let mut pages = Vec::new();
+    table_header(&mut page, &columns, y, MARGIN, right);
+    y += ROW_HEIGHT + 2.0;
+    for (i, wafer) in report.wafers.iter().enumerate() {
+        if y > height - MARGIN {
+            pages.push(page);
+            page = PdfPage::new(A4);
+            y = MARGIN + 12.0;
+            table_header(&mut page, &columns, y, MARGIN, right);
+            y += ROW_HEIGHT + 2.0;
+        }
+        let s = &wafer.stats;
+        let top = bin_pareto(&wafer_bins(wafer, pass)?, pass)
+            .first()
+            .map(|p| format!("BIN {} ({})", p.bin, p.count))
+            .unwrap_or_else(|| "-".to_string());
+        let cells = [
+            (i + 1).to_string(),
+            s.wafer_id.clone(),
+            s.total_tested.to_string(),
+            s.total_pass.to_string(),
+            s.total_fail.to_string(),
+            yield_text(s.total_pass, s.total_tested),
+            top,
+        ];
+        table_row(&mut page, &columns, y, &cells, Font::Regular);
+        y += ROW_HEIGHT;
+    }
+    pages.push(page);
+    Ok(pages)
+}
+
+fn draw_pareto(page: &mut PdfPage, pareto: &[ParetoEntry], x: f64, top: f64, w: f64) {
+    page.text(x, top, 12.0, Font::Bold, TEXT_COLOR, "Fail bin Pareto");
+    if pareto.is_empty() {
+        page.text(
+            x,
+            top + 20.0,
+            BODY_SIZE,
+            Font::Regular,
+            MUTED,
+            "No failing dies",
+        );
+        return;
+    }
+    let label_w = 44.0;
+    let numbers_w = 86.0;
+    let bar_w = w - label_w - numbers_w;
+    let max = pareto.iter().map(|p| p.count).max().unwrap_or(1).max(1) as f64;
+    let mut y = top + 20.0;
+    for entry in pareto.iter().take(PARETO_ROWS) {
+        page.text(
+            x,
+            y,
+            BODY_SIZE,
+            Font::Regular,
+            TEXT_COLOR,
+            &format!("BIN {}", entry.bin),
+        );
+        let len = bar_w * entry.count as f64 / max;
+        page.fill_rect(
+            x + label_w,
+            y - 8.0,
+            len.max(0.5),
+            9.0,
+            bin_color(parse_bin_label(&entry.bin)),
+        );
+        page.text_right(
+            x + w - 44.0,
+            y,
+            BODY_SIZE,
+            Font::Regular,
+            TEXT_COLOR,
+            &entry.count.to_string(),
+        );
+        page.text_right(
+            x + w,
+            y,
+            BODY_SIZE,
+            Font::Regular,
+            MUTED,
+            &format!("{:.1}%", entry.cumulative_percent),
+        );
+        y += ROW_HEIGHT;
+    }
+    if pareto.len() > PARETO_ROWS {
+        let rest: u32 = pareto[PARETO_ROWS..].iter().map(|p| p.count).sum();
+        let more = format!("{} more bins, {} dies", pareto.len() - PARETO_ROWS, rest);
+        page.text(x, y, BODY_SIZE, Font::Regular, MUTED, &more);
+    }
+}
+
+fn draw_stage_table(
+    page: &mut PdfPage,
+    stages: &[(String, MapSummary)],
+    stacked: &WaferStackStats,
+    x: f64,
+    top: f64,
+    w: f64,
+) {
+    page.text(x, top, 12.0, Font::Bold, TEXT_COLOR, "Yield by stage");
+    let columns = [
+        Column {
+            title: "Stage",
+            x,
+            right: false,
+        },
+        Column {
+            title: "Tested",
+            x: x + w - 150.0,
+            right: true,
+        },
+        Column {
+            title: "Pass",
+            x: x + w - 105.0,
+            right: true,
+        },
+        Column {
+            title: "Fail",
+            x: x + w - 60.0,
+            right: true,
+        },
+        Column {
+            title: "Yield",
+            x: x + w,
+            right: true,
+        },
+    ];
+    let mut y = top + 20.0;
+    table_header(page, &columns, y, x, x + w);
+    y += ROW_HEIGHT + 2.0;
+    for (name, s) in stages {
+        let cells = [
+            name.clone(),
+            s.total_tested.to_string(),
+            s.total_pass.to_string(),
+            s.total_fail.to_string(),
+            yield_text(s.total_pass, s.total_tested),
+        ];
+        table_row(page, &columns, y, &cells, Font::Regular);
+        y += ROW_HEIGHT;
+    }
+    let cells = [
+        "Stacked".to_string(),
+        stacked.total_tested.to_string(),
+        stacked.total_pass.to_string(),
+        stacked.total_fail.to_string(),
+        yield_text(stacked.total_pass, stacked.total_tested),
+    ];
+    page.line((x, y - 10.0), (x + w, y - 10.0), RULE, 0.6);
+    table_row(page, &columns, y, &cells, Font::Bold);
+}
+
+fn wafer_page(report: &LotReport, wafer: &ReportWafer, pass: &BinSet) -> Result<PdfPage, String> {
+    let (width, _) = A4;
+    let right = width - MARGIN;
+    let s = &wafer.stats;
+    let mut page = PdfPage::new(A4);
+
+    page.text(
+        MARGIN,
+        MARGIN + 16.0,
+        16.0,
+        Font::Bold,
+        TEXT_COLOR,
+        &format!("Wafer {}", s.wafer_id),
+    );
+    page.text_right(
+        right,
+        MARGIN + 16.0,
+        10.0,
+        Font::Regular,
+        MUTED,
+        &format!("{} / {}", report.oem_product_id, report.batch_id),
+    );
+    let time = match (&s.start_time, &s.stop_time) {
+        (Some(a), Some(b)) if a != b => format!("{} - {}", a, b),
+        (_, Some(t)) | (Some(t), None) => t.clone(),
+        (None, None) => "-".to_string(),
+    };
+    let summary = format!(
+        "Tested: {}   Pass: {}   Fail: {}   Yield: {}   Time: {}",
+        s.total_tested,
+        s.total_pass,
+        s.total_fail,
+        yield_text(s.total_pass, s.total_tested),
+        time
+    );
+    page.text(
+        MARGIN,
+        MARGIN + 34.0,
+        10.0,
+        Font::Regular,
+        TEXT_COLOR,
+        &summary,
+    );
+
+    let map_top = MARGIN + 48.0;
+    let map_h = 380.0;
+    if wafer.dies.is_empty() {
+        page.stroke_rect(MARGIN, map_top, right - MARGIN, map_h, RULE, 0.8);
+        page.text_centered(
+            width / 2.0,
+            map_top + map_h / 2.0,
+            11.0,
+            Font::Regular,
+            MUTED,
+            "No map data",
+        );
+    } else {
+        let options = WaferRenderOptions {
+            show_header: false,
+            show_legend: false,
+            pass_bins: report.pass_bins.clone(),
+            ..report.render.clone()
+        };
+        let scene = MapScene::build(&wafer.dies, &wafer.header, wafer.coords.as_ref(), &options)?;
+        page.draw_scene(&scene, &options, MARGIN, map_top, right - MARGIN, map_h);
+    }
+
+    let section_top = map_top + map_h + 30.0;
+    let col_w = (right - MARGIN - 24.0) / 2.0;
+    let pareto = bin_pareto(&wafer_bins(wafer, pass)?, pass);
+    draw_pareto(&mut page, &pareto, MARGIN, section_top, col_w);
+
+    let stages: Vec<(String, MapSummary)> = wafer
+        .layers
+        .iter()
+        .map(|l| (l.name.clone(), summarize(&l.dies, pass)))
+        .filter(|(_, s)| s.total_tested > 0)
+        .collect();
+    draw_stage_table(
+        &mut page,
+        &stages,
+        s,
+        MARGIN + col_w + 24.0,
+        section_top,
+        col_w,
+    );
+    Ok(page)
+}
+
+fn footer(page: &mut PdfPage, text: &str) {
+    let (width, height) = A4;
+    page.line(
+        (MARGIN, height - MARGIN + 10.0),
+        (width - MARGIN, height - MARGIN + 10.0),
+        RULE,
+        0.5,
+    );
+    page.text_right(
+        width - MARGIN,
+        height - MARGIN + 24.0,
+        8.0,
+        Font::Regular,
+        OUTLINE_COLOR,
+        text,
+    );
+}
+
+/// Build the lot report PDF.
+pub fn build_lot_report(report: &LotReport) -> Result<Vec<u8>, String> {
+    if report.wafers.is_empty() {
+        return Err(format!("Lot {} has no wafers to report", report.batch_id));
+    }
+    let pass = BinSet::from_ids(&report.pass_bins);
+    let mut pages = cover_page(report, &pass)?;
+    for wafer in &report.wafers {
+        pages.push(wafer_page(report, wafer, &pass)?);
+    }
+
+    let title = format!("{} {} lot report", report.oem_product_id, report.batch_id);
+    let total = pages.len();
+    let mut doc = PdfDocument::new(title.clone());
+    for (i, mut page) in pages.into_iter().enumerate() {
+        footer(&mut page, &format!("{}   {} / {}", title, i + 1, total));
+        doc.add_page(page);
+    }
+    Ok(doc.to_bytes())
+}
@@ -0,0 +1,229 @@
+use std::collections::HashMap;
+use std::fmt::Write;
+
+use crate::wafer::coords::DieCoordinateSystem;
+use crate::wafer::ds::AsciiDie;
+use crate::wafer::stats::bin_label;
+
+use super::{
+    MapLayer, MapScene, Rgb, WaferRenderOptions, BACKGROUND, GRID_COLOR, OUTLINE_COLOR, TEXT_COLOR,
+};
+
+// =============================================================================
+// SVG writer
+//
+// User units are millimetres, so the map prints at true wafer scale. Every die
+// is one `<rect class="die">` carrying `data-x`, `data-y`, `data-bin` and, for
+// stacked maps, `data-layer`, so reports can script or inspect single dies.
+// Layout matches the raster writer: map, header lines, legend.
+// =============================================================================
+
+const LEGEND_COLUMNS: usize = 5;
+/// Advance of a monospace glyph relative to the font size
+const CHAR_WIDTH: f64 = 0.6;
+
+pub fn rgb_hex(c: Rgb) -> String {
+    format!("#{:02x}{:02x}{:02x}", c[0], c[1], c[2])
+}
+
+fn escape(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&apos;"),
+            _ => out.push(c),
+        }
+    }
+    out
+}
+
+/// Trim float noise so coordinates stay short (`12.5`, not `12.500000000001`).
+fn num(v: f64) -> String {
+    let s = format!("{:.4}", v);
+    let s = s.trim_end_matches('0').trim_end_matches('.');
+    if s == "-0" {
+        "0".to_string()
+    } else {
+        s.to_string()
+    }
+}
+
+pub fn render_svg(scene: &MapScene, options: &WaferRenderOptions) -> String {
+    let map_w = scene.bounds.right - scene.bounds.left;
+    let map_h = scene.bounds.bottom - scene.bounds.top;
+    let font = (map_w.max(map_h) / 60.0).max(2.0);
+    let line_height = font * 1.4;
+    let padding = font;
+
+    let text_w = scene
+        .header_lines
+        .iter()
+        .map(|l| l.chars().count() as f64 * font * CHAR_WIDTH)
+        .fold(0.0, f64::max);
+    let legend_item_w = scene
+        .legend
+        .iter()
+        .map(|e| (e.label.chars().count() as f64 + 3.0) * font * CHAR_WIDTH)
+        .fold(0.0, f64::max);
+    let width = map_w
+        .max(text_w + 2.0 * padding)
+        .max(legend_item_w * LEGEND_COLUMNS as f64 + 2.0 * padding);
+
+    let header_h = if scene.header_lines.is_empty() {
+        0.0
+    } else {
+        scene.header_lines.len() as f64 * line_height + 2.0 * padding
+    };
+    let legend_rows = scene.legend.len().div_ceil(LEGEND_COLUMNS);
+    let legend_h = if legend_rows == 0 {
+        0.0
+    } else {
+        legend_rows as f64 * line_height + 2.0 * padding
+    };
+    let height = map_h + header_h + legend_h;
+
+    let origin_x = (width - map_w) / 2.0;
+    // mm (y up) -> SVG (y down)
+    let to_svg = |x: f64, y: f64| (origin_x + x - scene.bounds.left, scene.bounds.bottom - y);
+
+    let mut out = String::new();
+    let _ = writeln!(out, r#"<?xml version="1.0" encoding="UTF-8"?>"#);
+    let _ = writeln!(
+        out,
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="{w}mm" height="{h}mm" viewBox="0 0 {w} {h}">"#,
+        w = num(width),
+        h = num(height),
+    );
+    let _ = writeln!(
+        out,
+        r#"<rect width="100%" height="100%" fill="{}"/>"#,
+        rgb_hex(BACKGROUND)
+    );
+
+    let stroke = if options.grid_lines {
+        format!(
+            r#" stroke="{}" stroke-width="{}""#,
+            rgb_hex(GRID_COLOR),
+            num(scene.die_width_mm.min(scene.die_height_mm) * 0.05)
+        )
+    } else {
+        String::new()
+    };
+    let _ = writeln!(out, r#"<g class="dies"{}>"#, stroke);
+    for die in &scene.dies {
+        let (x, y) = to_svg(die.rect.left, die.rect.bottom);
+        let _ = write!(
+            out,
+            r#"<rect class="die" x="{}" y="{}" width="{}" height="{}" fill="{}" data-x="{}" data-y="{}" data-bin="{}""#,
+            num(x),
+            num(y),
+            num(die.rect.right - die.rect.left),
+            num(die.rect.bottom - die.rect.top),
+            rgb_hex(die.color),
+            die.x,
+            die.y,
+            escape(&bin_label(die.bin)),
+        );
+        if let Some(layer) = &die.layer {
+            let _ = write!(out, r#" data-layer="{}""#, escape(layer));
+        }
+        out.push_str("/>\n");
+    }
+    out.push_str("</g>\n");
+
+    if let Some(r) = scene.wafer_radius_mm.filter(|_| options.show_outline) {
+        let (cx, cy) = to_svg(0.0, 0.0);
+        let _ = writeln!(
+            out,
+            r#"<circle class="outline" cx="{}" cy="{}" r="{}" fill="none" stroke="{}" stroke-width="{}"/>"#,
+            num(cx),
+            num(cy),
+            num(r),
+            rgb_hex(OUTLINE_COLOR),
+            num(font * 0.1),
+        );
+    }
+    if let Some(tri) = scene.notch_triangle() {
+        let points: Vec<String> = tri
+            .iter()
+            .map(|&(x, y)| {
+                let (x, y) = to_svg(x, y);
+                format!("{},{}", num(x), num(y))
+            })
+            .collect();
+        let _ = writeln!(
+            out,
+            r#"<polygon class="notch" points="{}" fill="{}"/>"#,
+            points.join(" "),
+            rgb_hex(OUTLINE_COLOR),
+        );
+    }
+
+    let text_attrs = format!(
+        r#"font-family="monospace" font-size="{}" fill="{}""#,
+        num(font),
+        rgb_hex(TEXT_COLOR)
+    );
+    let mut y = map_h + padding;
+    if !scene.header_lines.is_empty() {
+        let _ = writeln!(
+            out,
+            r#"<g class="header" {} text-anchor="middle">"#,
+            text_attrs
+        );
+        for line in &scene.header_lines {
+            let _ = writeln!(
+                out,
+                r#"<text x="{}" y="{}">{}</text>"#,
+                num(width / 2.0),
+                num(y + font),
+                escape(line),
+            );
+            y += line_height;
+        }
+        out.push_str("</g>\n");
+        y += padding;
+    }
+
+    if !scene.legend.is_empty() {
+        let col_w = (width - 2.0 * padding) / LEGEND_COLUMNS as f64;
+        let _ = writeln!(out, r#"<g class="legend" {}>"#, text_attrs);
+        for (i, entry) in scene.legend.iter().enumerate() {
+            let x = padding + (i % LEGEND_COLUMNS) as f64 * col_w;
+            let top = y + padding + (i / LEGEND_COLUMNS) as f64 * line_height;
+            let _ = writeln!(
+                out,
+                r#"<rect x="{}" y="{}" width="{s}" height="{s}" fill="{}"/><text x="{}" y="{}">{}</text>"#,
+                num(x),
+                num(top),
+                rgb_hex(entry.color),
+                num(x + font * 1.6),
+                num(top + font * 0.9),
+                escape(&entry.label),
+                s = num(font),
+            );
+        }
+        out.push_str("</g>\n");
+    }
+
+    out.push_str("</svg>\n");
+    out
+}
+
+/// Render a parsed or stacked map to an SVG document. `layers` tags each die
+/// with the layer its bin came from.
+pub fn render_map_svg(
+    dies: &[AsciiDie],
+    header: &HashMap<String, String>,
+    coords: Option<&DieCoordinateSystem>,
+    layers: &[MapLayer],
+    options: &WaferRenderOptions,
+) -> Result<String, String> {
+    let mut scene = MapScene::build(dies, header, coords, options)?;
+    scene.attribute_layers(layers);
+    Ok(render_svg(&scene, options))
+}
@@ -0,0 +1,233 @@
+#[cfg(test)]
+fn sample_dies() -> Vec<crate::wafer::ds::AsciiDie> {
+    use crate::wafer::ds::{AsciiDie, BinValue};
+    (-3..=3)
+        .flat_map(|y| {
+            (-3..=3).map(move |x| AsciiDie {
+                x,
+                y,
+                bin: if x == y {
+                    BinValue::Number(5)
+                } else {
+                    BinValue::Number(1)
+                },
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+fn sample_header() -> std::collections::HashMap<String, String> {
+    [
+        ("Device Name", "P0094B"),
+        ("Lot No.", "B003332"),
+        ("Wafer ID", "01"),
+        ("Wafer Size", "1"),
+        ("Dice SizeX", "3000"),
+        ("Dice SizeY", "3000"),
+        ("Flat/Notch", "Down"),
+    ]
+    .into_iter()
+    .map(|(k, v)| (k.to_string(), v.to_string()))
+    .collect()
+}
+
+#[test]
+fn scene_header_and_legend() {
+    use super::{MapScene, Notch, WaferRenderOptions};
+    let options = WaferRenderOptions {
+        timestamp: Some("2025/01/01 00:00".into()),
+        ..WaferRenderOptions::default()
+    };
+    let scene = MapScene::build(&sample_dies(), &sample_header(), None, &options).expect("scene");
+    assert_eq!(scene.dies.len(), 49);
+    assert_eq!(scene.notch, Some(Notch::Down));
+    assert_eq!(scene.header_lines[0], "Product: P0094B_B003332_01");
+    assert!(scene.header_lines[2].contains("Tested: 49   Pass: 42   Fail: 7   Yield: 85.71%"));
+    let labels: Vec<&str> = scene.legend.iter().map(|e| e.label.as_str()).collect();
+    assert_eq!(labels, vec!["BIN 1 = 42", "BIN 5 = 7"]);
+}
+
+#[test]
+fn raster_png_and_jpeg() {
+    use super::raster::render_map;
+    use super::{bin_color, ImageFormat, WaferRenderOptions};
+    use crate::wafer::ds::BinValue;
+    let options = WaferRenderOptions::default();
+    let png = render_map(
+        &sample_dies(),
+        &sample_header(),
+        None,
+        &options,
+        ImageFormat::Png,
+    )
+    .expect("png");
+    assert_eq!(&png[1..4], b"PNG");
+    let jpeg = render_map(
+        &sample_dies(),
+        &sample_header(),
+        None,
+        &options,
+        ImageFormat::Jpeg,
+    )
+    .expect("jpeg");
+    assert_eq!(&jpeg[..2], &[0xff, 0xd8]);
+
+    assert_eq!(bin_color(BinValue::Number(1)), [0x00, 0xff, 0x00]);
+    assert_eq!(
+        bin_color(BinValue::Special('A')),
+        bin_color(BinValue::Number(10))
+    );
+}
+
+#[test]
+fn raster_die_pixels() {
+    use super::raster::render_scene;
+    use super::{bin_color, MapScene, WaferRenderOptions};
+    use crate::wafer::ds::BinValue;
+    let options = WaferRenderOptions {
+        dpi: 254, // 10 px per mm
+        show_header: false,
+        show_legend: false,
+        ..WaferRenderOptions::default()
+    };
+    let scene = MapScene::build(&sample_dies(), &sample_header(), None, &options).expect("scene");
+    let canvas = render_scene(&scene, &options).expect("canvas");
+    // 25.4 mm wafer + 3 mm padding each side
+    assert_eq!(canvas.width, 314);
+    // centre die (0,0) is bin 5; sample the middle of the image
+    let (cx, cy) = (canvas.width / 2, canvas.height / 2);
+    let i = ((cy * canvas.width + cx) * 3) as usize;
+    assert_eq!(&canvas.pixels[i..i + 3], &bin_color(BinValue::Number(5)));
+}
+
+#[test]
+fn raster_rejects_bad_dpi_and_oversized_canvas() {
+    use super::raster::{render_scene, Canvas, MAX_DPI};
+    use super::{MapScene, WaferRenderOptions, BACKGROUND};
+    let options = WaferRenderOptions::default();
+    let scene = MapScene::build(&sample_dies(), &sample_header(), None, &options).expect("scene");
+    for dpi in [0, MAX_DPI + 1, u32::MAX] {
+        let options = WaferRenderOptions {
+            dpi,
+            ..options.clone()
+        };
+        assert!(render_scene(&scene, &options).is_err(), "dpi {}", dpi);
+    }
+    assert!(render_scene(
+        &scene,
+        &WaferRenderOptions {
+            dpi: MAX_DPI,
+            ..options
+        }
+    )
+    .is_ok());
+
+    assert!(Canvas::new(u32::MAX, u32::MAX, BACKGROUND).is_err());
+    assert!(Canvas::new(100_000, 100_000, BACKGROUND).is_err());
+    assert_eq!(Canvas::new(4, 2, BACKGROUND).unwrap().pixels.len(), 24);
+}
+
+#[test]
+fn svg_die_attributes_and_layers() {
+    use super::svg::render_map_svg;
+    use super::{MapLayer, WaferRenderOptions};
+    use crate::wafer::ds::{AsciiDie, BinValue};
+    let dies = sample_dies();
+    let cp1 = MapLayer {
+        name: "CP1".into(),
+        dies: dies
+            .iter()
+            .map(|d| AsciiDie {
+                bin: BinValue::Number(1),
+                ..*d
+            })
+            .collect(),
+    };
+    let aoi = MapLayer {
+        name: "AOI".into(),
+        dies: dies.iter().filter(|d| d.x == d.y).copied().collect(),
+    };
+    let svg = render_map_svg(
+        &dies,
+        &sample_header(),
+        None,
+        &[aoi, cp1],
+        &WaferRenderOptions::default(),
+    )
+    .expect("svg");
+    assert!(svg.starts_with("<?xml"));
+    assert!(svg.contains(r#"width="3" height="3""#));
+    assert_eq!(svg.matches(r#"class="die""#).count(), 49);
+    assert_eq!(svg.matches(r#"data-layer="AOI""#).count(), 7);
+    assert_eq!(svg.matches(r#"data-layer="CP1""#).count(), 42);
+    assert!(svg.contains(r#"data-x="2" data-y="2" data-bin="5" data-layer="AOI""#));
+    assert!(svg.contains("Product: P0094B_B003332_01"));
+    assert!(svg.trim_end().ends_with("</svg>"));
+}
+
+#[test]
+fn pdf_lot_report_pages() {
+    use super::report::{build_lot_report, LotReport, ReportWafer};
+    use super::MapLayer;
+    use crate::wafer::stats::WaferStackStats;
+    let stats = |wafer_id: &str| WaferStackStats {
+        oem_product_id: "OEM-1".into(),
+        batch_id: "B003332".into(),
+        wafer_id: wafer_id.into(),
+        total_tested: 49,
+        total_pass: 42,
+        total_fail: 7,
+        yield_percentage: 85.71,
+        bin_counts: r#"{"1": 42, "5": 7}"#.into(),
+        start_time: Some("2025/01/01 00:00".into()),
+        stop_time: Some("2025/01/01 00:00".into()),
+    };
+    let report = LotReport {
+        oem_product_id: "OEM-1".into(),
+        batch_id: "B003332".into(),
+        wafers: vec![
+            ReportWafer {
+                stats: stats("01"),
+                dies: sample_dies(),
+                header: sample_header(),
+                layers: vec![MapLayer {
+                    name: "CP1".into(),
+                    dies: sample_dies(),
+                }],
+                coords: None,
+            },
+            // stats only: page without a map
+            ReportWafer {
+                stats: stats("02"),
+                dies: Vec::new(),
+                header: Default::default(),
+                layers: Vec::new(),
+                coords: None,
+            },
+        ],
+        pass_bins: vec!["BIN 1".into()],
+        render: Default::default(),
+        generated_at: Some("2025/01/02 08:00".into()),
+    };
+    let pdf = build_lot_report(&report).expect("pdf");
+    assert!(pdf.starts_with(b"%PDF-1.4"));
+    // skip the binary marker line so byte offsets match the text
+    let body = pdf.iter().position(|&b| b == b'\n').unwrap() + 1;
+    let body = body + pdf[body..].iter().position(|&b| b == b'\n').unwrap() + 1;
+    let text = String::from_utf8_lossy(&pdf[body..]);
+    assert!(text.trim_end().ends_with("%%EOF"));
+    assert!(text.contains("/Count 3"));
+    assert!(text.contains("(Lot yield)"));
+    assert!(text.contains("(85.71%)"));
+    assert!(text.contains("(BIN 5 \\(7\\))"));
+    assert!(text.contains("(No map data)"));
+
+    // xref offsets point at the objects
+    let start = text.rfind("startxref\n").expect("startxref") + "startxref\n".len();
+    let xref: usize = text[start..].lines().next().unwrap().parse().unwrap();
+    assert!(pdf[xref..].starts_with(b"xref"));
+    let first = text[xref - body..].lines().nth(3).unwrap();
+    let offset: usize = first[..10].parse().unwrap();
+    assert!(pdf[offset..].starts_with(b"1 0 obj"));
+}
@@ -0,0 +1,389 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::str::FromStr;
+
+use super::ds::{
+    AsciiDie, BinCountEntry, BinMapData, BinMapRecordExcel, BinValue, HexMapData, MapData,
+    SilanMapData, Wafer,
+};
+
+// =============================================================================
+// Bin translation tables
+//
+// Every stage writes bins in its own "native" numbering:
+// - ASCII maps (FAB CP / CP-prober / AOI): one character per die ('1', 'A', 'S', ...)
+// - WaferMap (WLBI): integers, with 257 used as the alignment marker
+// - HEX/.sinf: two-digit hex bytes
+// - SILAN: software bin names in the failed bin summary
+//
+// A `BinMapTable` translates those native codes to the internal bin numbering
+// used for stacking (import) and back to a customer's numbering (export).
+// =============================================================================
+
+/// The on-disk format a native bin code belongs to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum BinFormat {
+    Ascii,
+    WaferMap,
+    Hex,
+    Silan,
+}
+
+impl FromStr for BinFormat {
+    type Err = String;
+
+    /// Accepts both the format names and the stage/file aliases used in the
+    /// translation sheets (e.g. `mapEx`, `WLBI`, `sinf`).
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "ascii" | "txt" | "mapex" | "fab" | "fabcp" | "cp" | "aoi" => Ok(BinFormat::Ascii),
+            "wafermap" | "bin" | "wlbi" => Ok(BinFormat::WaferMap),
+            "hex" | "sinf" => Ok(BinFormat::Hex),
+            "silan" => Ok(BinFormat::Silan),
+            other => Err(format!("Unknown bin format '{}'", other)),
+        }
+    }
+}
+
+/// Which way a table is applied.
+/// - `Import`: native code in the file → internal bin
+/// - `Export`: internal bin → native code written to the file
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum BinMapDirection {
+    Import,
+    Export,
+}
+
+/// One row of a translation table.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BinMapRule {
+    pub format: BinFormat,
+    /// Code as it appears in the file (normalized, e.g. HEX is always `0A`)
+    pub native: String,
+    /// Internal bin used by the stacking engine
+    pub internal: BinValue,
+    /// Restricts the rule to one customer; `None` applies to every customer
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub customer: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+}
+
+/// Translation table for one product (sheet name / JSON key).
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BinMapTable {
+    pub product_id: String,
+    /// When set, rules for this customer take precedence over generic rules
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub customer: Option<String>,
+    pub rules: Vec<BinMapRule>,
+}
+
+/// Parse a bin written as text ("1", "257", "S") the same way the die layout sheets do.
+pub fn parse_bin_value(s: &str) -> Option<BinValue> {
+    let v = s.trim();
+    if v.is_empty() {
+        return None;
+    }
+    match v.parse::<i32>() {
+        Ok(num) => Some(BinValue::Number(num)),
+        Err(_) if v.chars().count() == 1 => v.chars().next().map(BinValue::Special),
+        Err(_) => None,
+    }
+}
+
+/// Normalize a native code so lookups do not depend on how the sheet was typed.
+pub fn normalize_native(format: BinFormat, native: &str) -> Result<String, String> {
+    let v = native.trim();
+    match format {
+        BinFormat::Ascii => {
+            if v.chars().count() != 1 {
+                return Err(format!("ASCII bin code '{}' must be a single character", v));
+            }
+            Ok(v.to_string())
+        }
+        BinFormat::WaferMap => v
+            .parse::<i32>()
+            .map(|n| n.to_string())
+            .map_err(|e| format!("WaferMap bin code '{}' is not an integer: {}", v, e)),
+        BinFormat::Hex => u8::from_str_radix(v.trim_start_matches("0x"), 16)
+            .map(|n| format!("{:02X}", n))
+            .map_err(|e| format!("HEX bin code '{}' is not a byte: {}", v, e)),
+        BinFormat::Silan => {
+            if v.is_empty() {
+                return Err("SILAN bin name must not be empty".into());
+            }
+            Ok(v.to_string())
+        }
+    }
+}
+
+/// Native code for a die bin as it would be written in `format`.
+fn native_key(format: BinFormat, bin: BinValue) -> String {
+    match (format, bin) {
+        (BinFormat::Hex, BinValue::Number(n)) if (0..=255).contains(&n) => format!("{:02X}", n),
+        _ => bin.to_string(),
+    }
+}
+
+/// Inverse of `native_key`: the `BinValue` a native code is stored as after parsing.
+fn native_to_bin(format: BinFormat, native: &str) -> Option<BinValue> {
+    match format {
+        BinFormat::Hex => u8::from_str_radix(native, 16)
+            .ok()
+            .map(|n| BinValue::Number(n as i32)),
+        BinFormat::Ascii => {
+            let ch = native.chars().next()?;
+            match ch.to_digit(10) {
+                Some(d) => Some(BinValue::Number(d as i32)),
+                None => Some(BinValue::Special(ch)),
+            }
+        }
+        BinFormat::WaferMap | BinFormat::Silan => parse_bin_value(native),
+    }
+}
+
+/// Single-character form of a bin for ASCII maps.
+/// Bins 10..=35 use letters (10 → 'A'), matching the frontend bin config.
+pub fn ascii_char_for_bin(bin: BinValue) -> Option<char> {
+    match bin {
+        BinValue::Number(n) if (0..=9).contains(&n) => char::from_digit(n as u32, 10),
+        BinValue::Number(n) if (10..=35).contains(&n) => Some((b'A' + (n - 10) as u8) as char),
+        BinValue::Number(_) => None,
+        BinValue::Special(c) => Some(c),
+    }
+}
+
+fn ascii_bin_for_char(ch: char) -> BinValue {
+    match ch.to_digit(10) {
+        Some(d) => BinValue::Number(d as i32),
+        None => BinValue::Special(ch),
+    }
+}
+
+impl TryFrom<BinMapRecordExcel> for BinMapRule {
+    type Error = String;
+
+    fn try_from(r: BinMapRecordExcel) -> Result<Self, Self::Error> {
+        let format = r.format.parse::<BinFormat>()?;
+        let native = normalize_native(format, &r.native)?;
+        let internal = parse_bin_value(&r.internal)
+            .ok_or_else(|| format!("Invalid internal bin '{}'", r.internal))?;
+        // HEX grids hold one byte per die, so an imported bin has to fit one
+        if format == BinFormat::Hex && !matches!(internal, BinValue::Number(0..=255)) {
+            return Err(format!(
+                "HEX bin code '{}' maps to internal bin '{}', which is not a byte",
+                native, r.internal
+            ));
+        }
+        let non_empty =
+            |s: Option<String>| s.map(|v| v.trim().to_string()).filter(|v| !v.is_empty());
+        Ok(Self {
+            format,
+            native,
+            internal,
+            customer: non_empty(r.customer),
+            description: non_empty(r.description),
+        })
+    }
+}
+
+impl BinMapTable {
+    /// Reject tables whose export would be ambiguous: two native codes of one
+    /// format (and customer) mapping to the same internal bin.
+    pub fn check(&self) -> Result<(), String> {
+        for (i, rule) in self.rules.iter().enumerate() {
+            if let Some(other) = self.rules[..i].iter().find(|r| {
+                r.format == rule.format
+                    && r.customer == rule.customer
+                    && r.internal == rule.internal
+            }) {
+                return Err(format!(
+                    "Bin map '{}': {:?} codes '{}' and '{}' both map to internal bin {}",
+                    self.product_id, rule.format, other.native, rule.native, rule.internal
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// Rules that apply to `format` for the table's customer, customer-specific first.
+    fn rules_for(&self, format: BinFormat) -> impl Iterator<Item = &BinMapRule> {
+        let customer = self.customer.as_deref();
+        let specific = self.rules.iter().filter(move |r| {
+            r.format == format && customer.is_some() && r.customer.as_deref() == customer
+        });
+        let generic = self
+            .rules
+            .iter()
+            .filter(move |r| r.format == format && r.customer.is_none());
+        specific.chain(generic)
+    }
+
+    /// Native code → internal bin. Unmapped bins pass through unchanged.
+    pub fn to_internal(&self, format: BinFormat, bin: BinValue) -> BinValue {
+        let key = native_key(format, bin);
+        self.rules_for(format)
+            .find(|r| r.native == key)
+            .map(|r| r.internal)
+            .unwrap_or(bin)
+    }
+
+    /// Internal bin → native code, as the `BinValue` the format stores it as.
+    /// Unmapped bins pass through unchanged.
+    pub fn to_native(&self, format: BinFormat, bin: BinValue) -> BinValue {
+        self.rules_for(format)
+            .find(|r| r.internal == bin)
+            .and_then(|r| native_to_bin(format, &r.native))
+            .unwrap_or(bin)
+    }
+
+    pub fn translate(
+        &self,
+        format: BinFormat,
+        direction: BinMapDirection,
+        bin: BinValue,
+    ) -> BinValue {
+        match direction {
+            BinMapDirection::Import => self.to_internal(format, bin),
+            BinMapDirection::Export => self.to_native(format, bin),
+        }
+    }
+
+    /// Translate a SILAN software bin name (the `bin_no` of the failed bin summary).
+    pub fn translate_silan_name(&self, direction: BinMapDirection, name: &str) -> String {
+        let rule = match direction {
+            BinMapDirection::Import => self.rules_for(BinFormat::Silan).find(|r| r.native == name),
+            BinMapDirection::Export => {
+                let bin = parse_bin_value(name);
+                self.rules_for(BinFormat::Silan)
+                    .find(|r| Some(r.internal) == bin)
+            }
+        };
+        match (direction, rule) {
+            (BinMapDirection::Import, Some(r)) => r.internal.to_string(),
+            (BinMapDirection::Export, Some(r)) => r.native.clone(),
+            (_, None) => name.to_string(),
+        }
+    }
+
+    pub fn translate_dies(
+        &self,
+        format: BinFormat,
+        direction: BinMapDirection,
+        dies: &mut [AsciiDie],
+    ) {
+        for die in dies.iter_mut() {
+            die.bin = self.translate(format, direction, die.bin);
+        }
+    }
+
+    /// Rewrite raw ASCII map rows in place; characters that cannot be
+    /// represented after translation are left untouched.
+    fn translate_ascii_raw(&self, direction: BinMapDirection, raw: &mut [String]) {
+        for line in raw.iter_mut() {
+            *line = line
+                .chars()
+                .map(|ch| {
+                    if ch == '.' || ch == ' ' {
+                        return ch;
+                    }
+                    let bin = self.translate(BinFormat::Ascii, direction, ascii_bin_for_char(ch));
+                    ascii_char_for_bin(bin).unwrap_or(ch)
+                })
+                .collect();
+        }
+    }
+}
+
+/// Parsed maps that carry bins in one of the native formats.
+pub trait TranslateBins {
+    fn translate_bins(&mut self, table: &BinMapTable, direction: BinMapDirection);
+}
+
+impl TranslateBins for Wafer {
+    fn translate_bins(&mut self, table: &BinMapTable, direction: BinMapDirection) {
+        table.translate_dies(BinFormat::Ascii, direction, &mut self.map.dies);
+        table.translate_ascii_raw(direction, &mut self.map.raw);
+    }
+}
+
+impl TranslateBins for MapData {
+    fn translate_bins(&mut self, table: &BinMapTable, direction: BinMapDirection) {
+        table.translate_dies(BinFormat::Ascii, direction, &mut self.map.dies);
+        table.translate_ascii_raw(direction, &mut self.map.raw);
+    }
+}
+
+impl TranslateBins for BinMapData {
+    fn translate_bins(&mut self, table: &BinMapTable, direction: BinMapDirection) {
+        for die in self.map.iter_mut() {
+            die.bin = table.translate(BinFormat::WaferMap, direction, die.bin);
+        }
+
+        // Re-key the bin summary; several native bins may collapse into one internal bin
+        let mut bins_acc: BTreeMap<u32, u32> = BTreeMap::new();
+        for entry in &self.bins {
+            let id = match table.translate(
+                BinFormat::WaferMap,
+                direction,
+                BinValue::Number(entry.bin as i32),
+            ) {
+                BinValue::Number(n) if n >= 0 => n as u32,
+                _ => entry.bin,
+            };
+            *bins_acc.entry(id).or_insert(0) += entry.count;
+        }
+        self.bins = bins_acc
+            .into_iter()
+            .map(|(bin, count)| BinCountEntry { bin, count })
+            .collect();
+    }
+}
+
+impl TranslateBins for HexMapData {
+    fn translate_bins(&mut self, table: &BinMapTable, direction: BinMapDirection) {
+        // Grid cells and dies must agree, so a bin that does not fit a byte is kept on both
+        let translate = |bin: BinValue| match table.translate(BinFormat::Hex, direction, bin) {
+            BinValue::Number(n @ 0..=255) => BinValue::Number(n),
+            _ => bin,
+        };
+        for row in self.map.grid.iter_mut() {
+            for cell in row.iter_mut() {
+                if let Some(v) = cell.0 {
+                    if let BinValue::Number(n) = translate(BinValue::Number(v as i32)) {
+                        cell.0 = Some(n as u8);
+                    }
+                }
+            }
+        }
+        for die in self.map.dies.iter_mut() {
+            die.bin = translate(die.bin);
+        }
+    }
+}
+
+impl TranslateBins for SilanMapData {
+    fn translate_bins(&mut self, table: &BinMapTable, direction: BinMapDirection) {
+        for entry in self.bin_summary.iter_mut() {
+            entry.bin_no = table.translate_silan_name(direction, &entry.bin_no);
+        }
+        table.translate_dies(BinFormat::Silan, direction, &mut self.map.dies);
+    }
+}
+
+/// Apply an optional table to a parsed map; used by the import/export commands.
+pub fn apply_bin_map<T: TranslateBins>(
+    mut value: T,
+    table: Option<&BinMapTable>,
+    direction: BinMapDirection,
+) -> T {
+    if let Some(table) = table {
+        value.translate_bins(table, direction);
+    }
+    value
+}
@@ -0,0 +1,152 @@
+use std::borrow::Cow;
+use std::collections::HashSet;
+
+use super::ds::BinValue;
+
+// =============================================================================
+// Bin classification helpers
+//
+// Mirrors `src/pages/Config/binConfig.ts` so pass/fail decisions made in Rust
+// agree with the frontend: a bin id such as "BIN 12" matches both the numeric
+// bin `12` and its letter form `C` (10 → 'A').
+// =============================================================================
+
+const LETTER_START_NUMBER: i32 = 10;
+
+/// Pass bins when none are configured.
+pub const DEFAULT_PASS_IDS: &[&str] = &["1", "G", "H", "I", "J"];
+
+/// Alignment markers: 'S' (CP-prober/AOI), '*' (FAB CP) and 257 (WLBI).
+pub fn is_alignment_marker(bin: BinValue) -> bool {
+    matches!(
+        bin,
+        BinValue::Special('S') | BinValue::Special('*') | BinValue::Number(257)
+    )
+}
+
+/// Whether the die counts towards tested/pass/fail statistics (`calculateStatsFromDies`).
+pub fn is_tested(bin: BinValue) -> bool {
+    !matches!(
+        bin,
+        BinValue::Special('S') | BinValue::Special('*') | BinValue::Special('.')
+    )
+}
+
+/// `numberToBinLetter`: 10 → "A", 35 → "Z", 36 → "AA"; numbers below 10 stay numeric.
+pub fn number_to_bin_letter(num: i32) -> String {
+    if num < LETTER_START_NUMBER {
+        return num.to_string();
+    }
+    let offset = (num - LETTER_START_NUMBER) as u32;
+    if offset < 26 {
+        return char::from_u32('A' as u32 + offset)
+            .unwrap_or('?')
+            .to_string();
+    }
+    let first = offset / 26;
+    let second = offset % 26;
+    [first + 'A' as u32 - 1, second + 'A' as u32]
+        .iter()
+        .filter_map(|c| char::from_u32(*c))
+        .collect()
+}
+
+/// `binLetterToNumber`: inverse of `number_to_bin_letter`.
+pub fn bin_letter_to_number(letter: &str) -> Option<i32> {
+    if !letter.is_empty() && letter.chars().all(|c| c.is_ascii_digit()) {
+        return letter.parse().ok();
+    }
+    let upper: Vec<u32> = letter
+        .to_ascii_uppercase()
+        .chars()
+        .map(|c| c as u32)
+        .collect();
+    let start = 'A' as u32;
+    match upper.as_slice() {
+        [c] if (start..='Z' as u32).contains(c) => Some(LETTER_START_NUMBER + (c - start) as i32),
+        [a, b] if *a >= start && *b >= start => {
+            Some(LETTER_START_NUMBER + ((a - start + 1) * 26 + (b - start)) as i32)
+        }
+        _ => None,
+    }
+}
+
+/// Every textual form a die bin can be matched against.
+pub fn bin_comparable_values(bin: BinValue) -> Vec<String> {
+    let mut values = Vec::with_capacity(3);
+    match bin {
+        BinValue::Number(n) => {
+            values.push(n.to_string());
+            values.push(number_to_bin_letter(n));
+        }
+        BinValue::Special(c) => {
+            values.push(c.to_string());
+            if c.is_ascii_alphabetic() {
+                if let Some(n) = bin_letter_to_number(&c.to_string()) {
+                    values.push(n.to_string());
+                    values.push(number_to_bin_letter(n));
+                }
+            }
+        }
+    }
+    values.dedup();
+    values
+}
+
+/// A set of bins selected in the UI ("BIN 1", "BIN 16", or raw values like "G").
+#[derive(Debug, Clone, Default)]
+pub struct BinSet {
+    values: HashSet<String>,
+}
+
+impl BinSet {
+    pub fn from_ids<S: AsRef<str>>(ids: &[S]) -> Self {
+        let mut values = HashSet::new();
+        for id in ids {
+            let id = id.as_ref().trim();
+            let num = id
+                .get(..3)
+                .filter(|prefix| prefix.eq_ignore_ascii_case("BIN"))
+                .and_then(|_| id[3..].trim().parse::<i32>().ok());
+            match num {
+                Some(n) => {
+                    values.insert(n.to_string());
+                    values.insert(number_to_bin_letter(n));
+                }
+                None => {
+                    values.insert(id.to_string());
+                }
+            }
+        }
+        Self { values }
+    }
+
+    /// `PASS_VALUES` in `priority.ts`, used when no pass bins are configured.
+    pub fn default_pass() -> Self {
+        Self::from_ids(DEFAULT_PASS_IDS)
+    }
+
+    /// Configured pass bins, or the defaults when none are configured.
+    pub fn pass_or_default<S: AsRef<str>>(ids: &[S]) -> Self {
+        Self::from_ids(ids).or_default_pass().into_owned()
+    }
+
+    /// This set, or the default pass bins when it is empty.
+    pub fn or_default_pass(&self) -> Cow<'_, Self> {
+        if self.is_empty() {
+            Cow::Owned(Self::default_pass())
+        } else {
+            Cow::Borrowed(self)
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+
+    pub fn matches(&self, bin: BinValue) -> bool {
+        bin_comparable_values(bin)
+            .iter()
+            .any(|v| self.values.contains(v))
+    }
+}
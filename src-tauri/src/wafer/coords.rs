@@ -0,0 +1,216 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+
+use super::bins::is_alignment_marker;
+use super::ds::{AsciiDie, BinValue};
+
+// =============================================================================
+// Physical die coordinates
+//
+// Dies are addressed by centred grid indices (`x0 = -(cols/2)`, rows grow
+// downwards). This module maps those indices to rectangles in millimetres in
+// the substrate frame (origin at the wafer centre), which is the frame the
+// defect lists (`X(mm)`, `Y(mm)`) are written in. Stacking, inking, rendering
+// and the substrate viewer all place dies through it; the HEX writers take
+// `REFPX/REFPY` from its reference die.
+// =============================================================================
+
+/// Row of `product_offsets`. Grid offsets are mm, defect size offsets are µm.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct ProductOffset {
+    pub x_offset: f64,
+    pub y_offset: f64,
+    pub defect_offset_x: f64,
+    pub defect_offset_y: f64,
+}
+
+/// Axis-aligned rectangle in mm (`top < bottom`).
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DieRect {
+    pub left: f64,
+    pub top: f64,
+    pub right: f64,
+    pub bottom: f64,
+}
+
+impl DieRect {
+    /// Overlap test that ignores rectangles only touching within `eps`.
+    pub fn overlaps(&self, other: &DieRect, eps: f64) -> bool {
+        !(self.right <= other.left + eps
+            || self.left >= other.right - eps
+            || self.bottom <= other.top + eps
+            || self.top >= other.bottom - eps)
+    }
+}
+
+/// A substrate defect as listed in the defect Excel: position in mm, size in µm.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct DefectRect {
+    pub x: f64,
+    pub y: f64,
+    pub w: f64,
+    pub h: f64,
+}
+
+impl DefectRect {
+    /// Grow the defect evenly by the product's size offset (µm)
+    /// and convert to a rectangle in mm.
+    pub fn to_rect(self, size_offset_um: (f64, f64)) -> DieRect {
+        let w = (self.w + 2.0 * size_offset_um.0).max(0.0) / 1000.0;
+        let h = (self.h + 2.0 * size_offset_um.1).max(0.0) / 1000.0;
+        let left = self.x - size_offset_um.0 / 1000.0;
+        let top = self.y - size_offset_um.1 / 1000.0;
+        DieRect {
+            left,
+            top,
+            right: left + w,
+            bottom: top + h,
+        }
+    }
+}
+
+/// Grid index ↔ mm conversion for one product.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DieCoordinateSystem {
+    /// Die size (`product_size.die_x/die_y`, HEX `XDIES/YDIES`)
+    pub die_width_mm: f64,
+    pub die_height_mm: f64,
+    /// Grid index of the reference die; its top-left corner sits at the offset
+    #[serde(default)]
+    pub reference_x: i32,
+    #[serde(default)]
+    pub reference_y: i32,
+    /// `product_offsets.x_offset/y_offset`
+    #[serde(default)]
+    pub offset_x_mm: f64,
+    #[serde(default)]
+    pub offset_y_mm: f64,
+    /// `product_offsets.defect_offset_x/y`
+    #[serde(default)]
+    pub defect_offset_x_um: f64,
+    #[serde(default)]
+    pub defect_offset_y_um: f64,
+}
+
+impl DieCoordinateSystem {
+    pub fn new(die_width_mm: f64, die_height_mm: f64) -> Self {
+        Self {
+            die_width_mm,
+            die_height_mm,
+            reference_x: 0,
+            reference_y: 0,
+            offset_x_mm: 0.0,
+            offset_y_mm: 0.0,
+            defect_offset_x_um: 0.0,
+            defect_offset_y_um: 0.0,
+        }
+    }
+
+    pub fn with_offset(mut self, offset: &ProductOffset) -> Self {
+        self.offset_x_mm = offset.x_offset;
+        self.offset_y_mm = offset.y_offset;
+        self.defect_offset_x_um = offset.defect_offset_x;
+        self.defect_offset_y_um = offset.defect_offset_y;
+        self
+    }
+
+    /// `REFPX/REFPY` of the reference die in a HEX grid whose first column
+    /// and row hold dies `min_x` and `min_y`.
+    pub fn hex_reference(&self, min_x: i32, min_y: i32) -> (u32, u32) {
+        (
+            (self.reference_x - min_x).max(0) as u32,
+            (self.reference_y - min_y).max(0) as u32,
+        )
+    }
+
+    /// The y index is flipped so larger rows move up.
+    pub fn die_rect(&self, x: i32, y: i32) -> DieRect {
+        let left = (x - self.reference_x) as f64 * self.die_width_mm + self.offset_x_mm;
+        let top = -((y - self.reference_y) as f64) * self.die_height_mm + self.offset_y_mm;
+        DieRect {
+            left,
+            top,
+            right: left + self.die_width_mm,
+            bottom: top + self.die_height_mm,
+        }
+    }
+
+    pub fn defect_rect(&self, defect: &DefectRect) -> DieRect {
+        defect.to_rect((self.defect_offset_x_um, self.defect_offset_y_um))
+    }
+}
+
+/// A die with its rectangle and whether a defect overlaps it.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PlacedDie {
+    pub x: i32,
+    pub y: i32,
+    pub rect: DieRect,
+    pub defective: bool,
+}
+
+/// Dies and defects in mm, for viewers that draw the substrate themselves.
+/// `defects` keeps the order of the input list.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SubstrateLayout {
+    pub dies: Vec<PlacedDie>,
+    pub defects: Vec<DieRect>,
+}
+
+/// Place the grid positions and mark the ones a defect overlaps; rectangles
+/// only touching each other do not count.
+pub fn place_dies(
+    positions: &[(i32, i32)],
+    defects: &[DefectRect],
+    coords: &DieCoordinateSystem,
+) -> SubstrateLayout {
+    const EPS: f64 = 1e-6;
+    let defects: Vec<DieRect> = defects.iter().map(|d| coords.defect_rect(d)).collect();
+    let dies = positions
+        .iter()
+        .map(|&(x, y)| {
+            let rect = coords.die_rect(x, y);
+            PlacedDie {
+                x,
+                y,
+                rect,
+                defective: defects.iter().any(|d| rect.overlaps(d, EPS)),
+            }
+        })
+        .collect();
+    SubstrateLayout { dies, defects }
+}
+
+/// The substrate stacking layer: every die overlapped by a defect becomes
+/// `E`. Alignment markers keep their bin.
+pub fn overlay_substrate_defects(
+    seeds: &[AsciiDie],
+    defects: &[DefectRect],
+    coords: &DieCoordinateSystem,
+) -> Vec<AsciiDie> {
+    let positions: Vec<(i32, i32)> = seeds.iter().map(|die| (die.x, die.y)).collect();
+    let defective: HashSet<(i32, i32)> = place_dies(&positions, defects, coords)
+        .dies
+        .into_iter()
+        .filter(|die| die.defective)
+        .map(|die| (die.x, die.y))
+        .collect();
+
+    seeds
+        .iter()
+        .map(|die| {
+            if defective.contains(&(die.x, die.y)) && !is_alignment_marker(die.bin) {
+                AsciiDie {
+                    bin: BinValue::Special('E'),
+                    ..*die
+                }
+            } else {
+                *die
+            }
+        })
+        .collect()
+}
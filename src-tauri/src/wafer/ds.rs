@@ -131,7 +131,24 @@ impl From<DefectRecordExcel> for DefectRecord {
 
 // =============================================================================
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+/// Bin translation sheet row (.xls/.xlsx, or the same keys in lowercase from JSON)
+#[derive(Debug, Deserialize)]
+pub struct BinMapRecordExcel {
+    #[serde(rename = "Format", alias = "format")]
+    pub format: String,
+    #[serde(rename = "Native", alias = "native")]
+    pub native: String,
+    #[serde(rename = "Internal", alias = "internal")]
+    pub internal: String,
+    #[serde(rename = "Customer", alias = "customer", default)]
+    pub customer: Option<String>,
+    #[serde(rename = "Description", alias = "description", default)]
+    pub description: Option<String>,
+}
+
+// =============================================================================
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub enum BinValue {
     Number(i32),
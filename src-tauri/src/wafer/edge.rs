@@ -0,0 +1,193 @@
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+
+use super::bins::{is_alignment_marker, BinSet};
+use super::coords::DieCoordinateSystem;
+use super::ds::{AsciiDie, BinValue};
+use super::geometry::WaferGeometry;
+
+// =============================================================================
+// Edge exclusion & inking
+//
+// Neighbour inking (formerly done in the frontend) plus a geometric edge
+// exclusion. Both rules read the merged map as it came out of stacking; dies
+// inked by one rule do not feed into the other.
+// =============================================================================
+
+const NEIGHBOR_DIRS: [(i32, i32); 8] = [
+    (-1, -1),
+    (-1, 0),
+    (-1, 1),
+    (0, -1),
+    (0, 1),
+    (1, -1),
+    (1, 0),
+    (1, 1),
+];
+
+/// Inking rules as stored in the stacking job config.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct InkRules {
+    /// Ink good dies whose footprint reaches into the edge exclusion ring
+    pub edge_exclusion_enabled: bool,
+    /// Width of the exclusion ring measured from the wafer edge
+    pub edge_exclusion_mm: f64,
+    pub edge_marker: char,
+
+    /// Ink good dies touching at least `fail_threshold` failing dies (8-neighbourhood)
+    pub neighbor_ink_enabled: bool,
+    pub fail_threshold: u32,
+    pub ink_marker: char,
+
+    /// Bin ids counted as good ("BIN 1", "BIN 16", ...)
+    pub good_bins: Vec<String>,
+    /// Bin ids counted as failing; `None` means "anything that is not good"
+    pub fail_bins: Option<Vec<String>>,
+}
+
+impl Default for InkRules {
+    fn default() -> Self {
+        Self {
+            edge_exclusion_enabled: false,
+            edge_exclusion_mm: 3.0,
+            edge_marker: 'z',
+            neighbor_ink_enabled: true,
+            fail_threshold: 2,
+            ink_marker: 'z',
+            good_bins: Vec::new(),
+            fail_bins: None,
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InkOutcome {
+    /// Full map with inked dies replaced by their marker
+    pub processed_dies: Vec<AsciiDie>,
+    /// Failing dies followed by every die inked by the rules
+    pub filtered_dies: Vec<AsciiDie>,
+    pub edge_count: u32,
+    pub ink_count: u32,
+}
+
+/// Positions of dies whose footprint crosses the `exclusion_mm` ring, measured
+/// from the wafer centre of `coords`.
+pub fn edge_die_positions(
+    dies: &[AsciiDie],
+    radius_mm: f64,
+    exclusion_mm: f64,
+    coords: &DieCoordinateSystem,
+) -> HashSet<(i32, i32)> {
+    let limit = (radius_mm - exclusion_mm.max(0.0)).max(0.0);
+    dies.iter()
+        .filter(|die| {
+            let r = coords.die_rect(die.x, die.y);
+            // farthest corner from the wafer centre
+            let far_x = r.left.abs().max(r.right.abs());
+            let far_y = r.top.abs().max(r.bottom.abs());
+            far_x.hypot(far_y) > limit
+        })
+        .map(|die| (die.x, die.y))
+        .collect()
+}
+
+/// Apply edge exclusion and neighbour inking to a merged map.
+///
+/// `coords` places the dies on the wafer; without it the wafer centre is taken
+/// as the middle of the die bounding box.
+///
+/// # Errors
+/// Returns an error if edge exclusion is enabled but no wafer geometry is known.
+pub fn apply_ink_rules(
+    dies: &[AsciiDie],
+    geometry: Option<&WaferGeometry>,
+    coords: Option<&DieCoordinateSystem>,
+    rules: &InkRules,
+) -> Result<InkOutcome, String> {
+    let good = BinSet::from_ids(&rules.good_bins);
+    let fail = rules.fail_bins.as_deref().map(BinSet::from_ids);
+
+    let is_good = |bin: BinValue| good.matches(bin);
+    let is_fail = |bin: BinValue| {
+        if is_alignment_marker(bin) {
+            return false;
+        }
+        match &fail {
+            Some(fail) => fail.matches(bin),
+            None => !good.matches(bin),
+        }
+    };
+
+    let by_pos: HashMap<(i32, i32), &AsciiDie> = dies.iter().map(|d| ((d.x, d.y), d)).collect();
+    let mut inked: HashMap<(i32, i32), char> = HashMap::new();
+    let mut edge_count = 0;
+    let mut ink_count = 0;
+
+    if rules.edge_exclusion_enabled {
+        let geometry = geometry
+            .ok_or_else(|| "Edge exclusion requires the wafer size and die pitch".to_string())?;
+        let coords = coords
+            .copied()
+            .unwrap_or_else(|| geometry.centered_coords(dies));
+        let edge = edge_die_positions(dies, geometry.radius_mm(), rules.edge_exclusion_mm, &coords);
+        for pos in edge {
+            if let Some(die) = by_pos.get(&pos) {
+                if is_good(die.bin) && !is_alignment_marker(die.bin) {
+                    inked.insert(pos, rules.edge_marker);
+                    edge_count += 1;
+                }
+            }
+        }
+    }
+
+    let fail_dies: Vec<&AsciiDie> = dies.iter().filter(|d| is_fail(d.bin)).collect();
+
+    if rules.neighbor_ink_enabled {
+        let mut fail_neighbors: HashMap<(i32, i32), u32> = HashMap::new();
+        for die in &fail_dies {
+            for (dx, dy) in NEIGHBOR_DIRS {
+                let pos = (die.x + dx, die.y + dy);
+                if by_pos.get(&pos).is_some_and(|n| is_good(n.bin)) {
+                    *fail_neighbors.entry(pos).or_insert(0) += 1;
+                }
+            }
+        }
+        for (pos, count) in fail_neighbors {
+            if count >= rules.fail_threshold && !inked.contains_key(&pos) {
+                inked.insert(pos, rules.ink_marker);
+                ink_count += 1;
+            }
+        }
+    }
+
+    let processed_dies: Vec<AsciiDie> = dies
+        .iter()
+        .map(|d| match inked.get(&(d.x, d.y)) {
+            Some(marker) => AsciiDie {
+                bin: BinValue::Special(*marker),
+                ..*d
+            },
+            None => *d,
+        })
+        .collect();
+
+    let filtered_dies = fail_dies
+        .into_iter()
+        .copied()
+        .chain(
+            processed_dies
+                .iter()
+                .copied()
+                .filter(|d| inked.contains_key(&(d.x, d.y))),
+        )
+        .collect();
+
+    Ok(InkOutcome {
+        processed_dies,
+        filtered_dies,
+        edge_count,
+        ink_count,
+    })
+}
@@ -0,0 +1,111 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+use super::coords::DieCoordinateSystem;
+use super::ds::AsciiDie;
+
+/// Physical size of a wafer and its die grid. All lengths are in millimetres.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WaferGeometry {
+    pub diameter_mm: f64,
+    pub die_pitch_x_mm: f64,
+    pub die_pitch_y_mm: f64,
+}
+
+/// Nominal diameters for wafer sizes given in inches.
+const NOMINAL_SIZES: [(f64, f64); 6] = [
+    (3.0, 76.2),
+    (4.0, 100.0),
+    (5.0, 125.0),
+    (6.0, 150.0),
+    (8.0, 200.0),
+    (12.0, 300.0),
+];
+
+/// Wafer size as written in map headers → diameter in mm.
+/// Values up to 18 are inches (`6`, `6"`, `6.000`), larger values are already mm.
+pub fn wafer_size_to_mm(size: f64) -> Option<f64> {
+    if !size.is_finite() || size <= 0.0 {
+        return None;
+    }
+    if size > 18.0 {
+        return Some(size);
+    }
+    let nominal = NOMINAL_SIZES
+        .iter()
+        .find(|(inch, _)| (inch - size).abs() < 1e-6)
+        .map(|(_, mm)| *mm);
+    Some(nominal.unwrap_or(size * 25.4))
+}
+
+/// Parse header text such as `6"`, `6 inch`, `150mm` or `150`.
+pub fn parse_wafer_size_mm(s: &str) -> Option<f64> {
+    let t = s.trim().to_ascii_lowercase();
+    if let Some(mm) = t.strip_suffix("mm") {
+        return mm.trim().parse::<f64>().ok().filter(|v| *v > 0.0);
+    }
+    let num: String = t
+        .chars()
+        .take_while(|c| c.is_ascii_digit() || *c == '.')
+        .collect();
+    num.parse::<f64>().ok().and_then(wafer_size_to_mm)
+}
+
+/// Die sizes in map headers are micrometres (`Dice SizeX: 4986.000`).
+pub fn um_to_mm(um: f64) -> f64 {
+    um / 1000.0
+}
+
+impl WaferGeometry {
+    pub fn new(diameter_mm: f64, die_pitch_x_mm: f64, die_pitch_y_mm: f64) -> Option<Self> {
+        let valid = |v: f64| v.is_finite() && v > 0.0;
+        if valid(diameter_mm) && valid(die_pitch_x_mm) && valid(die_pitch_y_mm) {
+            Some(Self {
+                diameter_mm,
+                die_pitch_x_mm,
+                die_pitch_y_mm,
+            })
+        } else {
+            None
+        }
+    }
+
+    pub fn radius_mm(&self) -> f64 {
+        self.diameter_mm / 2.0
+    }
+
+    /// Coordinate system with the wafer centre in the middle of the die bounding
+    /// box. Used when no product offset is known.
+    pub fn centered_coords(&self, dies: &[AsciiDie]) -> DieCoordinateSystem {
+        let span = |v: Vec<i32>| {
+            let min = v.iter().copied().min().unwrap_or(0);
+            let max = v.iter().copied().max().unwrap_or(0);
+            (min + max) as f64 / 2.0
+        };
+        let cx = span(dies.iter().map(|d| d.x).collect());
+        let cy = span(dies.iter().map(|d| d.y).collect());
+        let (w, h) = (self.die_pitch_x_mm, self.die_pitch_y_mm);
+        let mut coords = DieCoordinateSystem::new(w, h);
+        coords.offset_x_mm = -cx * w - w / 2.0;
+        coords.offset_y_mm = cy * h - h / 2.0;
+        coords
+    }
+
+    /// Combined stacking header as built by the frontend
+    /// (`extractMapDataHeader` / `extractBinMapHeader` keys).
+    pub fn from_header(header: &HashMap<String, String>) -> Option<Self> {
+        let num = |keys: &[&str]| {
+            keys.iter()
+                .filter_map(|k| header.get(*k))
+                .find_map(|v| v.trim().parse::<f64>().ok())
+                .filter(|v| *v > 0.0)
+        };
+        let diameter = header
+            .get("Wafer Size")
+            .and_then(|v| parse_wafer_size_mm(v))?;
+        let pitch_x = num(&["Dice SizeX", "Index X"])?;
+        let pitch_y = num(&["Dice SizeY", "Index Y"])?;
+        Self::new(diameter, um_to_mm(pitch_x), um_to_mm(pitch_y))
+    }
+}
@@ -0,0 +1,376 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+
+use super::bins::{is_alignment_marker, is_tested};
+use super::coords::{DieCoordinateSystem, DieRect};
+use super::ds::AsciiDie;
+use crate::render::Notch;
+
+// =============================================================================
+// Gross die
+//
+// Theoretical die grid of a wafer: every pitch cell whose die (pitch minus
+// scribe) lies inside the usable area, i.e. within `edge_exclusion_mm` of the
+// edge, off the flat and clear of the notch. Grid indices follow
+// `DieCoordinateSystem`, so the result can be compared position by position
+// with a die layout sheet or the footprint of a parsed map.
+// =============================================================================
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum WaferFlat {
+    None,
+    #[default]
+    Notch,
+    Flat,
+}
+
+fn default_edge_exclusion() -> f64 {
+    3.0
+}
+
+fn default_flat_side() -> Notch {
+    Notch::Down
+}
+
+fn default_notch_depth() -> f64 {
+    1.0
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GrossDieSpec {
+    pub diameter_mm: f64,
+    pub die_width_mm: f64,
+    pub die_height_mm: f64,
+    /// Street between dies; the pitch is die size plus scribe
+    #[serde(default)]
+    pub scribe_mm: f64,
+    #[serde(default = "default_edge_exclusion")]
+    pub edge_exclusion_mm: f64,
+    #[serde(default)]
+    pub flat: WaferFlat,
+    #[serde(default = "default_flat_side")]
+    pub flat_side: Notch,
+    /// Primary flat length; defaults to the SEMI length for the diameter
+    #[serde(default)]
+    pub flat_length_mm: Option<f64>,
+    #[serde(default = "default_notch_depth")]
+    pub notch_depth_mm: f64,
+}
+
+/// SEMI primary flat lengths by diameter.
+const FLAT_LENGTHS: [(f64, f64); 5] = [
+    (76.2, 22.22),
+    (100.0, 32.5),
+    (125.0, 42.5),
+    (150.0, 57.5),
+    (200.0, 57.5),
+];
+
+impl GrossDieSpec {
+    fn pitch(&self) -> (f64, f64) {
+        (
+            self.die_width_mm + self.scribe_mm,
+            self.die_height_mm + self.scribe_mm,
+        )
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        let valid = |v: f64| v.is_finite() && v > 0.0;
+        if !valid(self.diameter_mm) || !valid(self.die_width_mm) || !valid(self.die_height_mm) {
+            return Err("Wafer diameter and die size must be positive".to_string());
+        }
+        let non_negative = |v: f64| v.is_finite() && v >= 0.0;
+        if !non_negative(self.scribe_mm) || !non_negative(self.edge_exclusion_mm) {
+            return Err("Scribe width and edge exclusion must not be negative".to_string());
+        }
+        Ok(())
+    }
+
+    /// Distance from the centre to the flat chord, if the wafer has a flat.
+    fn flat_distance(&self) -> Result<Option<f64>, String> {
+        if self.flat != WaferFlat::Flat {
+            return Ok(None);
+        }
+        let r = self.diameter_mm / 2.0;
+        let length = match self.flat_length_mm {
+            Some(l) => l,
+            None => FLAT_LENGTHS
+                .iter()
+                .find(|(d, _)| (d - self.diameter_mm).abs() < 0.5)
+                .map(|(_, l)| *l)
+                .ok_or_else(|| format!("No standard flat length for {} mm", self.diameter_mm))?,
+        };
+        if !(length > 0.0 && length < self.diameter_mm) {
+            return Err(format!("Invalid flat length {} mm", length));
+        }
+        Ok(Some((r * r - (length / 2.0).powi(2)).sqrt()))
+    }
+}
+
+/// Position of a flat or notch: unit vector from the centre towards it.
+fn side_vector(side: Notch) -> (f64, f64) {
+    match side {
+        Notch::Down => (0.0, -1.0),
+        Notch::Up => (0.0, 1.0),
+        Notch::Left => (-1.0, 0.0),
+        Notch::Right => (1.0, 0.0),
+    }
+}
+
+struct UsableArea {
+    radius: f64,
+    /// Direction of the flat/notch
+    side: (f64, f64),
+    /// Max projection on `side` for a die off the flat
+    flat_limit: Option<f64>,
+    /// Centre and radius of the notch keep-out
+    notch: Option<((f64, f64), f64)>,
+}
+
+impl UsableArea {
+    fn new(spec: &GrossDieSpec) -> Result<Self, String> {
+        let r = spec.diameter_mm / 2.0;
+        let side = side_vector(spec.flat_side);
+        let excl = spec.edge_exclusion_mm;
+        Ok(Self {
+            radius: (r - excl).max(0.0),
+            side,
+            flat_limit: spec.flat_distance()?.map(|d| d - excl),
+            notch: (spec.flat == WaferFlat::Notch && spec.notch_depth_mm > 0.0)
+                .then_some(((side.0 * r, side.1 * r), spec.notch_depth_mm + excl)),
+        })
+    }
+
+    fn contains(&self, die: &DieRect) -> bool {
+        let corners = [
+            (die.left, die.top),
+            (die.right, die.top),
+            (die.left, die.bottom),
+            (die.right, die.bottom),
+        ];
+        if corners.iter().any(|(x, y)| x.hypot(*y) > self.radius) {
+            return false;
+        }
+        if let Some(limit) = self.flat_limit {
+            if corners
+                .iter()
+                .any(|(x, y)| x * self.side.0 + y * self.side.1 > limit)
+            {
+                return false;
+            }
+        }
+        if let Some(((nx, ny), nr)) = self.notch {
+            let cx = nx.clamp(die.left, die.right);
+            let cy = ny.clamp(die.top, die.bottom);
+            if (cx - nx).hypot(cy - ny) < nr {
+                return false;
+            }
+        }
+        true
+    }
+}
+
+/// Die area of a pitch cell: the cell shrunk by half the scribe on each side.
+fn die_area(coords: &DieCoordinateSystem, x: i32, y: i32, scribe: f64) -> DieRect {
+    let r = coords.die_rect(x, y);
+    let s = scribe / 2.0;
+    DieRect {
+        left: r.left + s,
+        top: r.top + s,
+        right: r.right - s,
+        bottom: r.bottom - s,
+    }
+}
+
+/// Upper bound on the pitch cells searched for one placement; a 300 mm wafer
+/// at 0.15 mm pitch stays below it.
+const MAX_GRID_CELLS: f64 = 4_000_000.0;
+
+fn grid_positions(
+    spec: &GrossDieSpec,
+    area: &UsableArea,
+    coords: &DieCoordinateSystem,
+) -> Result<Vec<(i32, i32)>, String> {
+    let r = spec.diameter_mm / 2.0;
+    let span = |offset: f64, pitch: f64| ((r + offset.abs()) / pitch).ceil() + 1.0;
+    let nx = span(coords.offset_x_mm, coords.die_width_mm);
+    let ny = span(coords.offset_y_mm, coords.die_height_mm);
+    let cells = (2.0 * nx + 1.0) * (2.0 * ny + 1.0);
+    if !cells.is_finite() || cells > MAX_GRID_CELLS {
+        return Err(format!(
+            "Pitch {} x {} mm is too small for a {} mm wafer",
+            coords.die_width_mm, coords.die_height_mm, spec.diameter_mm
+        ));
+    }
+    let (nx, ny) = (nx as i32, ny as i32);
+    let mut out = Vec::new();
+    for y in coords.reference_y.saturating_sub(ny)..=coords.reference_y.saturating_add(ny) {
+        for x in coords.reference_x.saturating_sub(nx)..=coords.reference_x.saturating_add(nx) {
+            if area.contains(&die_area(coords, x, y, spec.scribe_mm)) {
+                out.push((x, y));
+            }
+        }
+    }
+    Ok(out)
+}
+
+/// The four grid placements: a die or a street crossing at the centre, per axis.
+fn placements(spec: &GrossDieSpec) -> Vec<DieCoordinateSystem> {
+    let (px, py) = spec.pitch();
+    let mut out = Vec::new();
+    for ox in [-px / 2.0, 0.0] {
+        for oy in [-py / 2.0, 0.0] {
+            let mut c = DieCoordinateSystem::new(px, py);
+            c.offset_x_mm = ox;
+            c.offset_y_mm = oy;
+            out.push(c);
+        }
+    }
+    out
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LayoutCheck {
+    /// Product dies in the layout (no markers, no empty cells)
+    pub layout_dies: u32,
+    pub matched: u32,
+    /// Theoretical positions absent from the layout
+    pub missing: Vec<(i32, i32)>,
+    /// Layout positions outside the usable area
+    pub extra: Vec<(i32, i32)>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GrossDieResult {
+    pub pitch_x_mm: f64,
+    pub pitch_y_mm: f64,
+    pub usable_radius_mm: f64,
+    pub gross_die: u32,
+    pub positions: Vec<(i32, i32)>,
+    /// Grid placement used; pitch as die size, reference die at the offset
+    pub coords: DieCoordinateSystem,
+    pub layout: Option<LayoutCheck>,
+}
+
+fn check_layout(positions: &[(i32, i32)], layout: &HashSet<(i32, i32)>) -> LayoutCheck {
+    let theory: HashSet<(i32, i32)> = positions.iter().copied().collect();
+    let mut extra: Vec<(i32, i32)> = layout.difference(&theory).copied().collect();
+    extra.sort_by_key(|&(x, y)| (y, x));
+    let missing: Vec<(i32, i32)> = positions
+        .iter()
+        .filter(|p| !layout.contains(p))
+        .copied()
+        .collect();
+    LayoutCheck {
+        layout_dies: layout.len() as u32,
+        matched: (positions.len() - missing.len()) as u32,
+        missing,
+        extra,
+    }
+}
+
+/// Centroid of grid positions; a few stray dies barely move it.
+fn centroid(points: &[(i32, i32)]) -> (f64, f64) {
+    let n = points.len().max(1) as f64;
+    let (sx, sy) = points.iter().fold((0.0, 0.0), |(sx, sy), &(x, y)| {
+        (sx + x as f64, sy + y as f64)
+    });
+    (sx / n, sy / n)
+}
+
+/// Gross-die grid of a wafer, optionally checked against a layout.
+///
+/// With `coords` the grid placement is fixed (e.g. from product offsets).
+/// Otherwise the placement with the most dies is used, or, given a layout, the
+/// placement and index shift that best match it.
+pub fn gross_die(
+    spec: &GrossDieSpec,
+    layout: Option<&[AsciiDie]>,
+    coords: Option<&DieCoordinateSystem>,
+) -> Result<GrossDieResult, String> {
+    spec.validate()?;
+    let area = UsableArea::new(spec)?;
+    let (px, py) = spec.pitch();
+
+    let layout: Option<HashSet<(i32, i32)>> = layout.map(|dies| {
+        dies.iter()
+            .filter(|d| is_tested(d.bin) && !is_alignment_marker(d.bin))
+            .map(|d| (d.x, d.y))
+            .collect()
+    });
+
+    let (coords, positions) = match (coords, &layout) {
+        (Some(c), _) => {
+            let mut c = *c;
+            c.die_width_mm = px;
+            c.die_height_mm = py;
+            let positions = grid_positions(spec, &area, &c)?;
+            (c, positions)
+        }
+        (None, Some(layout)) if !layout.is_empty() => {
+            let target = centroid(&layout.iter().copied().collect::<Vec<_>>());
+            // most matches first, then fewest mismatches
+            let mut best_score = (0, i64::MIN);
+            let mut best: Option<(DieCoordinateSystem, Vec<(i32, i32)>)> = None;
+            for base in placements(spec) {
+                let base_positions = grid_positions(spec, &area, &base)?;
+                if base_positions.is_empty() {
+                    continue;
+                }
+                let own = centroid(&base_positions);
+                let (dx, dy) = (
+                    (target.0 - own.0).round() as i32,
+                    (target.1 - own.1).round() as i32,
+                );
+                for sy in dy - 1..=dy + 1 {
+                    for sx in dx - 1..=dx + 1 {
+                        let shifted: Vec<(i32, i32)> = base_positions
+                            .iter()
+                            .map(|&(x, y)| (x + sx, y + sy))
+                            .collect();
+                        let check = check_layout(&shifted, layout);
+                        let score = (
+                            check.matched,
+                            -((check.missing.len() + check.extra.len()) as i64),
+                        );
+                        if best.is_none() || score > best_score {
+                            let mut c = base;
+                            c.reference_x = sx;
+                            c.reference_y = sy;
+                            best_score = score;
+                            best = Some((c, shifted));
+                        }
+                    }
+                }
+            }
+            let (c, positions) =
+                best.ok_or_else(|| "No die fits inside the usable wafer area".to_string())?;
+            (c, positions)
+        }
+        _ => {
+            let mut best = (DieCoordinateSystem::new(px, py), Vec::new());
+            for c in placements(spec) {
+                let positions = grid_positions(spec, &area, &c)?;
+                // ties go to the later placement, as `max_by_key` did
+                if positions.len() >= best.1.len() {
+                    best = (c, positions);
+                }
+            }
+            best
+        }
+    };
+
+    Ok(GrossDieResult {
+        pitch_x_mm: px,
+        pitch_y_mm: py,
+        usable_radius_mm: area.radius,
+        gross_die: positions.len() as u32,
+        layout: layout.as_ref().map(|l| check_layout(&positions, l)),
+        positions,
+        coords,
+    })
+}
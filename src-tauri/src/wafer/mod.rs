@@ -1 +1,11 @@
+mod tests;
+
 pub mod ds;
+pub mod bin_map;
+pub mod bins;
+pub mod geometry;
+pub mod gross_die;
+pub mod edge;
+pub mod coords;
+pub mod stats;
+pub mod stack;
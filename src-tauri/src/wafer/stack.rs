@@ -0,0 +1,672 @@
+use chrono::{Local, Utc};
+use std::collections::{BTreeMap, HashMap};
+
+use super::bin_map::{apply_bin_map, BinMapDirection, BinMapTable};
+use super::bins::{is_alignment_marker, BinSet};
+use super::coords::{overlay_substrate_defects, DefectRect, DieCoordinateSystem};
+use super::ds::{
+    AsciiDie, AsciiMap, BinCountEntry, BinMapData, BinValue, HexCell, HexHeader, HexMap,
+    HexMapData, MapData, SilanBinSummary, SilanHeader, SilanMapData, SilanSum, Wafer, WaferMapDie,
+};
+use super::edge::{apply_ink_rules, InkRules};
+use super::geometry::WaferGeometry;
+use super::stats::{summarize, MapSummary};
+use crate::file::scanner::DataSourceType;
+use crate::parser::{parse_wafer, parse_wafer_bin, parse_wafer_map_data};
+use crate::render::MapLayer;
+
+// =============================================================================
+// Wafer stacking
+//
+// The stacking engine of the app's job runner (`jobs::runner`) and the
+// `stack` CLI command. Layers are sorted by stage priority, shifted onto the alignment markers of the first
+// one and merged: a higher layer always wins, a lower one only replaces dies
+// the higher layers passed. Alignment markers are never overwritten by bins.
+// =============================================================================
+
+pub type Header = HashMap<String, String>;
+
+/// `PRIORITY_RULES` in `priority.ts`: CP2 > WLBI > CP1 > FAB CP > Substrate > AOI.
+pub fn layer_priority(stage: DataSourceType, sub_stage: Option<u32>) -> i32 {
+    match (stage, sub_stage) {
+        (DataSourceType::CpProber, Some(2)) => 6,
+        (DataSourceType::Wlbi, _) => 5,
+        (DataSourceType::CpProber, Some(1)) => 4,
+        (DataSourceType::FabCp, _) => 3,
+        (DataSourceType::Substrate, _) => 2,
+        (DataSourceType::Aoi, _) => 1,
+        _ => 0,
+    }
+}
+
+/// The name of a layer in progress messages and the SVG legend.
+pub fn layer_name(stage: DataSourceType, sub_stage: Option<u32>) -> String {
+    match stage {
+        DataSourceType::CpProber => match sub_stage {
+            Some(n) => format!("CP{}", n),
+            None => "CP".to_string(),
+        },
+        DataSourceType::Wlbi => "WLBI".to_string(),
+        DataSourceType::Aoi => "AOI".to_string(),
+        DataSourceType::FabCp => "FAB CP".to_string(),
+        DataSourceType::Substrate => "Substrate".to_string(),
+    }
+}
+
+/// `subStageNum`: first number in a `wafer_maps.sub_stage` such as "2" or "CP-2".
+pub fn sub_stage_number(sub_stage: &str) -> Option<u32> {
+    let digits: String = sub_stage
+        .chars()
+        .skip_while(|c| !c.is_ascii_digit())
+        .take_while(|c| c.is_ascii_digit())
+        .collect();
+    digits.parse().ok()
+}
+
+/// One parsed input map.
+#[derive(Debug, Clone)]
+pub struct StackLayer {
+    pub name: String,
+    pub priority: i32,
+    pub header: Header,
+    pub dies: Vec<AsciiDie>,
+}
+
+fn header_of(pairs: &[(&str, String)]) -> Header {
+    pairs
+        .iter()
+        .map(|(k, v)| (k.to_string(), v.clone()))
+        .collect()
+}
+
+fn or_unknown(value: &str) -> String {
+    if value.is_empty() {
+        "Unknown".to_string()
+    } else {
+        value.to_string()
+    }
+}
+
+/// `extractMapDataHeader`
+fn map_data_header(data: &MapData) -> Header {
+    header_of(&[
+        ("Device Name", or_unknown(&data.device_name)),
+        ("Lot No.", or_unknown(&data.lot_no)),
+        ("Wafer ID", or_unknown(&data.wafer_id)),
+        ("Wafer Size", or_unknown(&data.wafer_size)),
+        ("Dice SizeX", data.dice_size_x.to_string()),
+        ("Dice SizeY", data.dice_size_y.to_string()),
+        ("Flat/Notch", or_unknown(&data.flat_notch)),
+        ("Total Tested", data.total_tested.to_string()),
+        ("Total Pass", data.total_pass.to_string()),
+        ("Total Fail", data.total_fail.to_string()),
+        ("Yield", data.yield_percent.to_string()),
+    ])
+}
+
+/// `extractBinMapHeader`
+fn bin_map_header(data: &BinMapData) -> Header {
+    header_of(&[
+        ("WaferType", data.wafer_type.to_string()),
+        ("DUT", data.dut.to_string()),
+        ("Mode", data.mode.to_string()),
+        ("Product", or_unknown(&data.product)),
+        ("Wafer Lots", or_unknown(&data.wafer_lots)),
+        ("Wafer No", or_unknown(&data.wafer_no)),
+        ("Wafer Size", data.wafer_size.to_string()),
+        ("Index X", data.index_x.to_string()),
+        ("Index Y", data.index_y.to_string()),
+    ])
+}
+
+/// `extractWaferHeader`
+fn wafer_header(wafer: &Wafer) -> Header {
+    header_of(&[
+        ("Operator", wafer.operator.clone()),
+        ("Device_fab", wafer.device.clone()),
+        ("Lot No.", wafer.lot_id.clone()),
+        ("Wafer ID", wafer.wafer_id.clone()),
+        ("Measurement Time", wafer.meas_time.clone()),
+        ("Gross Die", wafer.gross_die.to_string()),
+        ("Pass Die", wafer.pass_die.to_string()),
+        ("Fail Die", wafer.fail_die.to_string()),
+        ("Yield", wafer.total_yield.to_string()),
+        ("Notch", wafer.notch.clone()),
+    ])
+}
+
+/// `parseMapLayer`: read a map with the reader of its stage, its bins
+/// translated by the product's `bin_map`. `None` for stages without a map
+/// (substrate, CP other than 1/2) and empty maps.
+pub fn read_layer(
+    path: &str,
+    stage: DataSourceType,
+    sub_stage: Option<u32>,
+    bin_map: Option<&BinMapTable>,
+) -> Result<Option<StackLayer>, String> {
+    let import = BinMapDirection::Import;
+    let (header, dies) = match stage {
+        DataSourceType::Substrate => return Ok(None),
+        DataSourceType::CpProber if !matches!(sub_stage.unwrap_or(1), 1 | 2) => return Ok(None),
+        DataSourceType::CpProber | DataSourceType::Aoi => {
+            let data = apply_bin_map(parse_wafer_map_data(path.to_string())?, bin_map, import);
+            (map_data_header(&data), data.map.dies)
+        }
+        DataSourceType::Wlbi => {
+            let data = apply_bin_map(parse_wafer_bin(path.to_string())?, bin_map, import);
+            let dies = data
+                .map
+                .iter()
+                .map(|d| AsciiDie {
+                    x: d.x,
+                    y: d.y,
+                    bin: match d.bin {
+                        BinValue::Number(257) => BinValue::Special('*'),
+                        bin => bin,
+                    },
+                })
+                .collect();
+            (bin_map_header(&data), dies)
+        }
+        DataSourceType::FabCp => {
+            let wafer = apply_bin_map(parse_wafer(path.to_string())?, bin_map, import);
+            (wafer_header(&wafer), wafer.map.dies)
+        }
+    };
+    if dies.is_empty() {
+        return Ok(None);
+    }
+    let sub_stage = match stage {
+        DataSourceType::CpProber => Some(sub_stage.unwrap_or(1)),
+        _ => sub_stage,
+    };
+    Ok(Some(StackLayer {
+        name: layer_name(stage, sub_stage),
+        priority: layer_priority(stage, sub_stage),
+        header,
+        dies,
+    }))
+}
+
+/// `createSubstrateStackingLayer`: the dies of the highest-priority layer,
+/// marked `E` where a substrate defect overlaps them.
+pub fn substrate_layer(
+    layers: &[StackLayer],
+    defects: &[DefectRect],
+    coords: &DieCoordinateSystem,
+) -> Option<StackLayer> {
+    let base = layers.iter().reduce(|best, layer| {
+        if layer.priority > best.priority {
+            layer
+        } else {
+            best
+        }
+    })?;
+    let dies = overlay_substrate_defects(&base.dies, defects, coords);
+    if dies.is_empty() {
+        return None;
+    }
+    Some(StackLayer {
+        name: layer_name(DataSourceType::Substrate, None),
+        priority: layer_priority(DataSourceType::Substrate, None),
+        header: Header::new(),
+        dies,
+    })
+}
+
+/// `extractAlignmentMarkers` ('S' and '*'), sorted by row then column.
+pub fn alignment_markers(dies: &[AsciiDie]) -> Vec<(i32, i32)> {
+    let mut markers: Vec<(i32, i32)> = dies
+        .iter()
+        .filter(|d| matches!(d.bin, BinValue::Special('S') | BinValue::Special('*')))
+        .map(|d| (d.x, d.y))
+        .collect();
+    markers.sort_by_key(|&(x, y)| (y, x));
+    markers
+}
+
+/// `Math.round` of a half-integer mean: halves round up.
+fn mean_rounded(a: i32, b: i32) -> i32 {
+    (a + b).div_euclid(2) + (a + b).rem_euclid(2)
+}
+
+/// `calculateOffset`: shift moving `target` onto `base`. The first markers
+/// decide; with two or more on both sides the first two are averaged.
+pub fn marker_offset(base: &[(i32, i32)], target: &[(i32, i32)]) -> (i32, i32) {
+    let (Some(b0), Some(t0)) = (base.first(), target.first()) else {
+        return (0, 0);
+    };
+    let (dx, dy) = (b0.0 - t0.0, b0.1 - t0.1);
+    match (base.get(1), target.get(1)) {
+        (Some(b1), Some(t1)) => (mean_rounded(dx, b1.0 - t1.0), mean_rounded(dy, b1.1 - t1.1)),
+        _ => (dx, dy),
+    }
+}
+
+/// `alignStackingLayers`: move every layer onto the markers of the first.
+pub fn align_layers(layers: &mut [StackLayer]) {
+    let Some((base, rest)) = layers.split_first_mut() else {
+        return;
+    };
+    let base_markers = alignment_markers(&base.dies);
+    for layer in rest {
+        let (dx, dy) = marker_offset(&base_markers, &alignment_markers(&layer.dies));
+        for die in &mut layer.dies {
+            die.x += dx;
+            die.y += dy;
+        }
+    }
+}
+
+/// `mergeLayerToDieMap` over all layers, in their order. Dies keep the
+/// position they were first placed at, like the insertion order of the JS map.
+pub fn merge_layers(layers: &[StackLayer], pass: &BinSet) -> Vec<AsciiDie> {
+    let mut index: HashMap<(i32, i32), usize> = HashMap::new();
+    let mut merged: Vec<(AsciiDie, i32)> = Vec::new();
+
+    for layer in layers {
+        for die in &layer.dies {
+            let key = (die.x, die.y);
+            let existing = index.get(&key).map(|&i| merged[i]);
+            let overwrite = if matches!(die.bin, BinValue::Special('S') | BinValue::Special('*')) {
+                existing.is_none_or(|(_, priority)| layer.priority >= priority)
+            } else if die.bin == BinValue::Special('.') {
+                false
+            } else {
+                match existing {
+                    None => true,
+                    Some((old, _)) if is_alignment_marker(old.bin) => false,
+                    Some((_, priority)) if layer.priority > priority => true,
+                    Some((old, priority)) if layer.priority < priority => pass.matches(old.bin),
+                    Some(_) => false,
+                }
+            };
+            if !overwrite {
+                continue;
+            }
+            match index.get(&key) {
+                Some(&i) => merged[i] = (*die, layer.priority),
+                None => {
+                    index.insert(key, merged.len());
+                    merged.push((*die, layer.priority));
+                }
+            }
+        }
+    }
+    merged.into_iter().map(|(die, _)| die).collect()
+}
+
+/// A merged wafer with what the exporters need.
+#[derive(Debug, Clone)]
+pub struct StackedWafer {
+    /// Header for the outputs: first value per key over the layers, with
+    /// the CP1 header on top
+    pub header: Header,
+    /// Aligned layers in merge order
+    pub layers: Vec<MapLayer>,
+    pub dies: Vec<AsciiDie>,
+    pub summary: MapSummary,
+}
+
+/// From the parsed layers to the merged map.
+pub fn stack_layers(mut layers: Vec<StackLayer>, pass: &BinSet) -> Result<StackedWafer, String> {
+    if layers.is_empty() {
+        return Err("No map layers to stack".into());
+    }
+    layers.sort_by_key(|layer| std::cmp::Reverse(layer.priority));
+    align_layers(&mut layers);
+    let dies = merge_layers(&layers, pass);
+    if dies.is_empty() {
+        return Err("Merged map is empty".into());
+    }
+
+    let mut header = Header::new();
+    for layer in &layers {
+        for (key, value) in &layer.header {
+            header.entry(key.clone()).or_insert_with(|| value.clone());
+        }
+    }
+    if let Some(cp1) = layers.iter().find(|layer| layer.name == "CP1") {
+        header.extend(cp1.header.clone());
+    }
+
+    Ok(StackedWafer {
+        header,
+        summary: summarize(&dies, pass),
+        layers: layers
+            .into_iter()
+            .map(|layer| MapLayer {
+                name: layer.name,
+                dies: layer.dies,
+            })
+            .collect(),
+        dies,
+    })
+}
+
+/// `exportInkWaferFiles`: the merged map with the ink rules applied and its
+/// summary recounted. Edge exclusion takes the wafer size and die pitch from
+/// the header.
+pub fn ink_stacked(
+    wafer: &StackedWafer,
+    rules: &InkRules,
+    coords: Option<&DieCoordinateSystem>,
+    pass: &BinSet,
+) -> Result<StackedWafer, String> {
+    let geometry = WaferGeometry::from_header(&wafer.header);
+    let outcome = apply_ink_rules(&wafer.dies, geometry.as_ref(), coords, rules)?;
+    Ok(StackedWafer {
+        header: wafer.header.clone(),
+        layers: wafer.layers.clone(),
+        summary: summarize(&outcome.processed_dies, pass),
+        dies: outcome.processed_dies,
+    })
+}
+
+/// `countBinValues` as the JSON stored in `wafer_stack_stats.bin_counts`:
+/// letter bins A–J count as 10–19, other special bins are left out.
+pub fn bin_counts_json(dies: &[AsciiDie]) -> String {
+    let mut counts = serde_json::Map::new();
+    for die in dies {
+        let key = match die.bin {
+            BinValue::Number(n) => n.to_string(),
+            BinValue::Special(c @ 'A'..='J') => (10 + (c as i32 - 'A' as i32)).to_string(),
+            BinValue::Special('z') => "20".to_string(),
+            BinValue::Special(_) => continue,
+        };
+        let count = counts.get(&key).and_then(|v| v.as_u64()).unwrap_or(0);
+        counts.insert(key, (count + 1).into());
+    }
+    serde_json::Value::Object(counts).to_string()
+}
+
+// =============================================================================
+// Output formats (`convertTo*` in `waferSubstrateRenderer.ts`)
+// =============================================================================
+
+fn header_text(header: &Header, keys: &[&str], default: &str) -> String {
+    keys.iter()
+        .filter_map(|k| header.get(*k))
+        .find(|v| !v.is_empty())
+        .cloned()
+        .unwrap_or_else(|| default.to_string())
+}
+
+fn header_number(header: &Header, key: &str) -> f64 {
+    header
+        .get(key)
+        .and_then(|v| v.trim().parse().ok())
+        .unwrap_or(0.0)
+}
+
+fn bin_text(bin: BinValue) -> String {
+    match bin {
+        BinValue::Number(n) => n.to_string(),
+        BinValue::Special(c) => c.to_string(),
+    }
+}
+
+/// Grid bounds `(min_x, min_y, columns, rows)` of a non-empty map.
+fn bounds(dies: &[AsciiDie]) -> (i32, i32, usize, usize) {
+    let min_x = dies.iter().map(|d| d.x).min().unwrap_or(0);
+    let max_x = dies.iter().map(|d| d.x).max().unwrap_or(0);
+    let min_y = dies.iter().map(|d| d.y).min().unwrap_or(0);
+    let max_y = dies.iter().map(|d| d.y).max().unwrap_or(0);
+    (
+        min_x,
+        min_y,
+        (max_x - min_x + 1) as usize,
+        (max_y - min_y + 1) as usize,
+    )
+}
+
+/// ASCII rows with '.' where there is no die.
+fn raw_rows(dies: &[AsciiDie]) -> Vec<String> {
+    let (min_x, min_y, cols, rows) = bounds(dies);
+    let mut grid = vec![vec![".".to_string(); cols]; rows];
+    for die in dies {
+        grid[(die.y - min_y) as usize][(die.x - min_x) as usize] = bin_text(die.bin);
+    }
+    grid.into_iter().map(|row| row.concat()).collect()
+}
+
+fn lot_wafer(header: &Header) -> String {
+    match (header.get("Lot No."), header.get("Wafer ID")) {
+        (Some(lot), Some(wafer)) if !lot.is_empty() && !wafer.is_empty() => {
+            format!("{}-{}", lot, wafer)
+        }
+        _ => header_text(header, &["Wafer ID"], ""),
+    }
+}
+
+/// `convertToMapData` (mapEx).
+pub fn to_map_data(dies: &[AsciiDie], summary: &MapSummary, header: &Header) -> MapData {
+    let (_, _, cols, rows) = bounds(dies);
+    MapData {
+        device_name: header_text(header, &["Device Name"], "Unknown"),
+        lot_no: header_text(header, &["Lot No."], "Unknown"),
+        wafer_id: header_text(header, &["Wafer ID"], "Unknown"),
+        wafer_size: header_text(header, &["Wafer Size"], "6"),
+        dice_size_x: header_number(header, "Dice SizeX"),
+        dice_size_y: header_number(header, "Dice SizeY"),
+        flat_notch: header_text(header, &["Flat/Notch"], "Unknown"),
+        map_columns: cols as u32,
+        map_rows: rows as u32,
+        total_tested: summary.total_tested,
+        total_pass: summary.total_pass,
+        total_fail: summary.total_fail,
+        yield_percent: summary.yield_percentage,
+        map: AsciiMap {
+            raw: raw_rows(dies),
+            dies: dies.to_vec(),
+        },
+    }
+}
+
+/// `convertToBinMapData` (WLBI `.WaferMap`); markers are written as 257.
+pub fn to_bin_map_data(dies: &[AsciiDie], header: &Header) -> BinMapData {
+    let map: Vec<WaferMapDie> = dies
+        .iter()
+        .map(|d| WaferMapDie {
+            x: d.x,
+            y: d.y,
+            bin: if is_alignment_marker(d.bin) {
+                BinValue::Number(257)
+            } else {
+                d.bin
+            },
+            reserved: 0,
+        })
+        .collect();
+    let mut counts: Vec<BinCountEntry> = Vec::new();
+    for die in &map {
+        if let BinValue::Number(n) = die.bin {
+            match counts.iter_mut().find(|e| e.bin == n as u32) {
+                Some(entry) => entry.count += 1,
+                None => counts.push(BinCountEntry {
+                    bin: n as u32,
+                    count: 1,
+                }),
+            }
+        }
+    }
+    counts.sort_by_key(|e| e.bin);
+
+    let int = |key: &str| {
+        header
+            .get(key)
+            .and_then(|v| v.trim().parse().ok())
+            .unwrap_or(0)
+    };
+    BinMapData {
+        wafer_type: int("WaferType"),
+        dut: int("DUT"),
+        mode: int("Mode"),
+        product: header_text(header, &["Product", "Device Name"], "Unknown"),
+        wafer_lots: header_text(header, &["Wafer Lots", "Lot No."], "Unknown"),
+        wafer_no: header_text(header, &["Wafer No", "Wafer ID"], "Unknown"),
+        wafer_size: header_number(header, "Wafer Size"),
+        index_x: header_number(header, "Dice SizeX"),
+        index_y: header_number(header, "Dice SizeY"),
+        map,
+        bins: counts,
+    }
+}
+
+/// `convertToHexMapData` (HEX/.sinf); markers are left out, letter bins
+/// A–J become 10–19 and other special bins 99. `coords` gives `REFPX/REFPY`
+/// and `XDIES/YDIES`; without it the die size comes from the header.
+pub fn to_hex_map_data(
+    dies: &[AsciiDie],
+    header: &Header,
+    coords: Option<&DieCoordinateSystem>,
+) -> HexMapData {
+    let (min_x, min_y, cols, rows) = bounds(dies);
+    let (refpx, refpy) = coords.map_or((0, 0), |c| c.hex_reference(min_x, min_y));
+    let (x_dies, y_dies) = coords.map_or_else(
+        || {
+            (
+                header_number(header, "Dice SizeX") / 1000.0,
+                header_number(header, "Dice SizeY") / 1000.0,
+            )
+        },
+        |c| (c.die_width_mm, c.die_height_mm),
+    );
+    let mut grid = vec![vec![HexCell(None); cols]; rows];
+    for die in dies.iter().filter(|d| !is_alignment_marker(d.bin)) {
+        let value = match die.bin {
+            BinValue::Number(n) => u8::try_from(n).ok(),
+            BinValue::Special(c @ 'A'..='J') => Some(10 + (c as u8 - b'A')),
+            BinValue::Special(_) => Some(99),
+        };
+        grid[(die.y - min_y) as usize][(die.x - min_x) as usize] = HexCell(value);
+    }
+    HexMapData {
+        header: HexHeader {
+            device: header_text(header, &["Device Name"], "Unknown"),
+            lot: header_text(header, &["Lot No."], "Unknown"),
+            wafer: header_text(header, &["Wafer ID"], "Unknown"),
+            fnloc: None,
+            row_ct: rows as u32,
+            col_ct: cols as u32,
+            bcequ: None,
+            refpx,
+            refpy,
+            dut_ms: "MM".to_string(),
+            x_dies,
+            y_dies,
+        },
+        map: HexMap {
+            raw: Vec::new(),
+            grid,
+            dies: dies
+                .iter()
+                .filter(|d| matches!(d.bin, BinValue::Number(_)))
+                .copied()
+                .collect(),
+        },
+    }
+}
+
+/// `convertToFabWafer` (FAB CP text).
+pub fn to_fab_wafer(dies: &[AsciiDie], summary: &MapSummary, header: &Header) -> Wafer {
+    Wafer {
+        operator: header_text(header, &["Operator"], ""),
+        device: header_text(header, &["Device_fab"], ""),
+        lot_id: header_text(header, &["Lot No."], ""),
+        wafer_id: lot_wafer(header),
+        meas_time: header
+            .get("Measurement Time")
+            .filter(|v| !v.is_empty())
+            .cloned()
+            .unwrap_or_else(|| Utc::now().format("%Y-%m-%dT%H:%M:%S%.3fZ").to_string()),
+        gross_die: summary.total_tested,
+        pass_die: summary.total_pass,
+        fail_die: summary.total_fail,
+        total_yield: summary.yield_percentage,
+        notch: header_text(header, &["Notch", "Flat/Notch"], "UNKNOWN"),
+        map: AsciiMap {
+            raw: raw_rows(dies),
+            dies: dies.to_vec(),
+        },
+    }
+}
+
+/// The SILAN map body: column labels every 5 dies, then one row per y with
+/// '1' for bin 1, 'X' for any other die and ' ' where there is none.
+fn silan_rows(dies: &[AsciiDie], min: (i32, i32), max: (i32, i32)) -> Vec<String> {
+    let bins: HashMap<(i32, i32), BinValue> = dies.iter().map(|d| ((d.x, d.y), d.bin)).collect();
+    let labels: String = (min.0..=max.0)
+        .filter(|x| x % 5 == 0 || *x == min.0 || *x == max.0)
+        .map(|x| format!("{:>4}", x))
+        .collect();
+    let mut lines = vec![
+        format!("            {}", labels),
+        "        ----+----+----+----+----+---".to_string(),
+    ];
+    for y in min.1..=max.1 {
+        let sep = if y == min.1 || y == max.1 || y % 5 == 0 {
+            '+'
+        } else {
+            '|'
+        };
+        let row: String = (min.0..=max.0)
+            .map(|x| match bins.get(&(x, y)) {
+                None => ' ',
+                Some(BinValue::Number(1)) => '1',
+                Some(_) => 'X',
+            })
+            .collect();
+        lines.push(format!("        {:>4}  {} {}", y, sep, row));
+    }
+    lines
+}
+
+/// `convertToSilanMapData`; special bins are counted as bin 2.
+pub fn to_silan_map_data(dies: &[AsciiDie], summary: &MapSummary, header: &Header) -> SilanMapData {
+    let (min_x, min_y, cols, rows) = bounds(dies);
+    let (max_x, max_y) = (min_x + cols as i32 - 1, min_y + rows as i32 - 1);
+    let mut counts: BTreeMap<i32, u32> = BTreeMap::new();
+    for die in dies {
+        let bin = match die.bin {
+            BinValue::Number(n) => n,
+            BinValue::Special(_) => 2,
+        };
+        *counts.entry(bin).or_default() += 1;
+    }
+    SilanMapData {
+        header: SilanHeader {
+            wafer_map_data: Local::now().format("%Y/%m/%d_%H:%M").to_string(),
+            tester_name: header_text(header, &["Tester Name"], ""),
+            device_name: header_text(header, &["Device Name"], ""),
+            wafer_size: header_number(header, "Wafer Size"),
+            index_x: header_number(header, "Dice SizeX"),
+            index_y: header_number(header, "Dice SizeY"),
+            lot_id: header_text(header, &["Lot No."], ""),
+            wafer_id: lot_wafer(header),
+            map_bin_length: 1,
+            direction: header_text(header, &["Flat/Notch"], "Unknown"),
+        },
+        sum: SilanSum {
+            sample: summary.total_tested,
+            pass_num: summary.total_pass,
+            fail_num: summary.total_fail,
+            pass_percent: summary.yield_percentage,
+            x_min: min_x,
+            y_min: min_y,
+            x_max: max_x,
+            y_max: max_y,
+        },
+        bin_summary: counts
+            .into_iter()
+            .map(|(bin, count)| SilanBinSummary {
+                bin_no: bin.to_string(),
+                count,
+            })
+            .collect(),
+        map: AsciiMap {
+            raw: silan_rows(dies, (min_x, min_y), (max_x, max_y)),
+            dies: dies.to_vec(),
+        },
+    }
+}
@@ -0,0 +1,166 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+use super::bins::{is_tested, BinSet};
+use super::ds::{AsciiDie, BinValue};
+
+/// Die count of one bin.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BinCount {
+    pub bin: String,
+    pub count: u32,
+}
+
+/// `calculateStatsFromDies` plus per-bin counts of tested dies.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MapSummary {
+    pub total_tested: u32,
+    pub total_pass: u32,
+    pub total_fail: u32,
+    pub yield_percentage: f64,
+    /// Numeric bins ascending, then special bins
+    pub bins: Vec<BinCount>,
+}
+
+/// Text form of a bin as shown in legends and reports.
+pub fn bin_label(bin: BinValue) -> String {
+    match bin {
+        BinValue::Number(n) => n.to_string(),
+        BinValue::Special(c) => c.to_string(),
+    }
+}
+
+/// Inverse of [`bin_label`]: numbers become `Number`, anything else the first
+/// character.
+pub fn parse_bin_label(label: &str) -> BinValue {
+    let label = label.trim();
+    match label.parse::<i32>() {
+        Ok(n) => BinValue::Number(n),
+        Err(_) => BinValue::Special(label.chars().next().unwrap_or('?')),
+    }
+}
+
+/// Sort key of a bin: numeric bins ascending, then special bins.
+pub fn bin_order(bin: BinValue) -> (u8, i32) {
+    match bin {
+        BinValue::Number(n) => (0, n),
+        BinValue::Special(c) => (1, c as i32),
+    }
+}
+
+/// Summarize a map; an empty `pass` set falls back to the default pass bins.
+pub fn summarize(dies: &[AsciiDie], pass: &BinSet) -> MapSummary {
+    let pass = pass.or_default_pass();
+
+    let mut counts: Vec<(BinValue, u32)> = Vec::new();
+    let mut total_tested = 0;
+    let mut total_pass = 0;
+    for die in dies.iter().filter(|d| is_tested(d.bin)) {
+        total_tested += 1;
+        if pass.matches(die.bin) {
+            total_pass += 1;
+        }
+        match counts.iter_mut().find(|(b, _)| *b == die.bin) {
+            Some((_, n)) => *n += 1,
+            None => counts.push((die.bin, 1)),
+        }
+    }
+    counts.sort_by_key(|(b, _)| bin_order(*b));
+
+    MapSummary {
+        total_tested,
+        total_pass,
+        total_fail: total_tested - total_pass,
+        yield_percentage: if total_tested > 0 {
+            total_pass as f64 / total_tested as f64 * 100.0
+        } else {
+            0.0
+        },
+        bins: counts
+            .into_iter()
+            .map(|(b, count)| BinCount {
+                bin: bin_label(b),
+                count,
+            })
+            .collect(),
+    }
+}
+
+/// Row of `wafer_stack_stats`; `bin_counts` is the JSON object written by
+/// `countBinValues`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WaferStackStats {
+    pub oem_product_id: String,
+    pub batch_id: String,
+    pub wafer_id: String,
+    pub total_tested: u32,
+    pub total_pass: u32,
+    pub total_fail: u32,
+    pub yield_percentage: f64,
+    #[serde(default)]
+    pub bin_counts: String,
+    #[serde(default)]
+    pub start_time: Option<String>,
+    #[serde(default)]
+    pub stop_time: Option<String>,
+}
+
+impl WaferStackStats {
+    /// Parsed `bin_counts`, ordered like [`MapSummary::bins`].
+    pub fn bins(&self) -> Result<Vec<BinCount>, String> {
+        if self.bin_counts.trim().is_empty() {
+            return Ok(Vec::new());
+        }
+        let map: HashMap<String, u32> = serde_json::from_str(&self.bin_counts)
+            .map_err(|e| format!("Invalid bin_counts of wafer {}: {}", self.wafer_id, e))?;
+        let mut bins: Vec<BinCount> = map
+            .into_iter()
+            .filter(|(_, count)| *count > 0)
+            .map(|(bin, count)| BinCount { bin, count })
+            .collect();
+        bins.sort_by_key(|b| bin_order(parse_bin_label(&b.bin)));
+        Ok(bins)
+    }
+}
+
+/// One bar of a fail-bin Pareto.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ParetoEntry {
+    pub bin: String,
+    pub count: u32,
+    /// Share of all failing dies
+    pub percent: f64,
+    pub cumulative_percent: f64,
+}
+
+/// Failing bins sorted by count (ties by bin order), with cumulative share.
+pub fn bin_pareto(bins: &[BinCount], pass: &BinSet) -> Vec<ParetoEntry> {
+    let pass = pass.or_default_pass();
+
+    let mut fails: Vec<&BinCount> = bins
+        .iter()
+        .filter(|b| b.count > 0 && !pass.matches(parse_bin_label(&b.bin)))
+        .collect();
+    fails.sort_by(|a, b| {
+        b.count.cmp(&a.count).then_with(|| {
+            bin_order(parse_bin_label(&a.bin)).cmp(&bin_order(parse_bin_label(&b.bin)))
+        })
+    });
+    let total: u32 = fails.iter().map(|b| b.count).sum();
+    let mut cumulative = 0;
+    fails
+        .into_iter()
+        .map(|b| {
+            cumulative += b.count;
+            ParetoEntry {
+                bin: b.bin.clone(),
+                count: b.count,
+                percent: b.count as f64 / total as f64 * 100.0,
+                cumulative_percent: cumulative as f64 / total as f64 * 100.0,
+            }
+        })
+        .collect()
+}
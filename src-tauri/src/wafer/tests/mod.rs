@@ -0,0 +1,572 @@
+#[cfg(test)]
+fn sample_table() -> super::bin_map::BinMapTable {
+    use super::bin_map::{BinFormat, BinMapRule, BinMapTable};
+    use super::ds::BinValue;
+    let rule = |format, native: &str, internal, customer: Option<&str>| BinMapRule {
+        format,
+        native: native.to_string(),
+        internal,
+        customer: customer.map(str::to_string),
+        description: None,
+    };
+    BinMapTable {
+        product_id: "P0094B".into(),
+        customer: None,
+        rules: vec![
+            rule(BinFormat::WaferMap, "257", BinValue::Special('*'), None),
+            rule(BinFormat::Hex, "0A", BinValue::Number(2), None),
+            rule(BinFormat::Hex, "0B", BinValue::Number(3), Some("SILAN")),
+            rule(BinFormat::Ascii, "A", BinValue::Number(2), None),
+            rule(BinFormat::Silan, "OS_FAIL", BinValue::Number(2), None),
+        ],
+    }
+}
+
+#[test]
+fn bin_map_wafermap_marker_round_trip() {
+    use super::bin_map::{BinFormat, BinMapDirection};
+    use super::ds::BinValue;
+    let table = sample_table();
+    let imported = table.translate(
+        BinFormat::WaferMap,
+        BinMapDirection::Import,
+        BinValue::Number(257),
+    );
+    assert_eq!(imported, BinValue::Special('*'));
+    let exported = table.translate(BinFormat::WaferMap, BinMapDirection::Export, imported);
+    assert_eq!(exported, BinValue::Number(257));
+    // Unmapped bins pass through
+    assert_eq!(
+        table.translate(
+            BinFormat::WaferMap,
+            BinMapDirection::Import,
+            BinValue::Number(1)
+        ),
+        BinValue::Number(1)
+    );
+}
+
+#[test]
+fn bin_map_customer_rules_take_precedence() {
+    use super::bin_map::{BinFormat, BinMapDirection};
+    use super::ds::BinValue;
+    let mut table = sample_table();
+    assert_eq!(
+        table.translate(
+            BinFormat::Hex,
+            BinMapDirection::Import,
+            BinValue::Number(0x0B)
+        ),
+        BinValue::Number(0x0B),
+        "customer rule must not apply without a customer"
+    );
+    table.customer = Some("SILAN".into());
+    assert_eq!(
+        table.translate(
+            BinFormat::Hex,
+            BinMapDirection::Import,
+            BinValue::Number(0x0B)
+        ),
+        BinValue::Number(3)
+    );
+    // Internal 2 exports back to the generic HEX code
+    assert_eq!(
+        table.translate(BinFormat::Hex, BinMapDirection::Export, BinValue::Number(2)),
+        BinValue::Number(0x0A)
+    );
+}
+
+#[test]
+fn bin_map_rewrites_ascii_raw_rows() {
+    use super::bin_map::{apply_bin_map, BinMapDirection};
+    use super::ds::{BinValue, Wafer};
+    let lines: Vec<String> = [
+        "Operator: E0",
+        "Device: P0094B",
+        "Lot ID: B003332",
+        "Wafer ID: 1",
+        "Meas Time: 2025-01-01",
+        "Gross Die: 3",
+        "Pass Die: 1",
+        "Fail Die: 2",
+        "Total Yield: 33.33%",
+        "notch-Down",
+        "",
+        ".1A",
+        "*A.",
+    ]
+    .iter()
+    .map(|s| s.to_string())
+    .collect();
+    let wafer = Wafer::from_lines(&lines).expect("parse wafer");
+    let table = sample_table();
+
+    let imported = apply_bin_map(wafer, Some(&table), BinMapDirection::Import);
+    assert_eq!(imported.map.raw, vec![".12".to_string(), "*2.".to_string()]);
+    assert!(imported
+        .map
+        .dies
+        .iter()
+        .all(|d| d.bin != BinValue::Special('A')));
+
+    let exported = apply_bin_map(imported, Some(&table), BinMapDirection::Export);
+    assert_eq!(exported.map.raw, vec![".1A".to_string(), "*A.".to_string()]);
+}
+
+#[test]
+fn bin_map_silan_names() {
+    use super::bin_map::BinMapDirection;
+    let table = sample_table();
+    assert_eq!(
+        table.translate_silan_name(BinMapDirection::Import, "OS_FAIL"),
+        "2"
+    );
+    assert_eq!(
+        table.translate_silan_name(BinMapDirection::Export, "2"),
+        "OS_FAIL"
+    );
+    assert_eq!(
+        table.translate_silan_name(BinMapDirection::Export, "7"),
+        "7"
+    );
+}
+
+#[test]
+fn geometry_from_header_units() {
+    use super::geometry::WaferGeometry;
+    use std::collections::HashMap;
+    let header: HashMap<String, String> = [
+        ("Wafer Size", "6\""),
+        ("Dice SizeX", "4986.000"),
+        ("Dice SizeY", "3740.000"),
+    ]
+    .into_iter()
+    .map(|(k, v)| (k.to_string(), v.to_string()))
+    .collect();
+    let g = WaferGeometry::from_header(&header).expect("geometry");
+    assert_eq!(g.diameter_mm, 150.0);
+    assert!((g.die_pitch_x_mm - 4.986).abs() < 1e-9);
+    assert!((g.die_pitch_y_mm - 3.74).abs() < 1e-9);
+}
+
+#[test]
+fn ink_rules_neighbor_threshold() {
+    use super::ds::{AsciiDie, BinValue};
+    use super::edge::{apply_ink_rules, InkRules};
+    let mut dies = Vec::new();
+    for y in -1..=1 {
+        for x in -1..=1 {
+            dies.push(AsciiDie {
+                x,
+                y,
+                bin: BinValue::Number(1),
+            });
+        }
+    }
+    // two fails touching the centre, one alignment marker that must not count
+    dies[0].bin = BinValue::Number(5);
+    dies[2].bin = BinValue::Number(5);
+    dies[6].bin = BinValue::Special('S');
+    let rules = InkRules {
+        good_bins: vec!["BIN 1".into()],
+        ..InkRules::default()
+    };
+    let out = apply_ink_rules(&dies, None, None, &rules).expect("ink");
+    // centre (0,0) and top-middle (0,-1) touch both fails
+    let inked: Vec<(i32, i32)> = out
+        .processed_dies
+        .iter()
+        .filter(|d| d.bin == BinValue::Special('z'))
+        .map(|d| (d.x, d.y))
+        .collect();
+    assert_eq!(out.ink_count, 2);
+    assert!(inked.contains(&(0, 0)) && inked.contains(&(0, -1)));
+    assert_eq!(out.filtered_dies.len(), 4);
+}
+
+#[test]
+fn ink_rules_edge_exclusion() {
+    use super::ds::{AsciiDie, BinValue};
+    use super::edge::{apply_ink_rules, InkRules};
+    use super::geometry::WaferGeometry;
+    // 11 x 11 grid of 10 mm dies on a 100 mm wafer
+    let dies: Vec<AsciiDie> = (-5..=5)
+        .flat_map(|y| {
+            (-5..=5).map(move |x| AsciiDie {
+                x,
+                y,
+                bin: BinValue::Number(1),
+            })
+        })
+        .collect();
+    let geometry = WaferGeometry::new(100.0, 10.0, 10.0).unwrap();
+    let rules = InkRules {
+        edge_exclusion_enabled: true,
+        edge_exclusion_mm: 5.0,
+        edge_marker: 'E',
+        neighbor_ink_enabled: false,
+        good_bins: vec!["BIN 1".into()],
+        ..InkRules::default()
+    };
+    let out = apply_ink_rules(&dies, Some(&geometry), None, &rules).expect("ink");
+    let bin_at = |x: i32, y: i32| {
+        out.processed_dies
+            .iter()
+            .find(|d| d.x == x && d.y == y)
+            .unwrap()
+            .bin
+    };
+    assert_eq!(bin_at(0, 0), BinValue::Number(1));
+    assert_eq!(bin_at(-5, 0), BinValue::Special('E'));
+    assert_eq!(bin_at(3, 3), BinValue::Special('E'));
+    assert_eq!(bin_at(2, 3), BinValue::Number(1));
+    assert!(apply_ink_rules(&dies, None, None, &rules).is_err());
+}
+
+#[test]
+fn coords_die_rect_from_grid_index() {
+    use super::coords::{DieCoordinateSystem, ProductOffset};
+    let offset = ProductOffset {
+        x_offset: 1.5,
+        y_offset: -2.0,
+        defect_offset_x: 0.0,
+        defect_offset_y: 0.0,
+    };
+    let coords = DieCoordinateSystem::new(5.0, 4.0).with_offset(&offset);
+    // left = x * w + off.x, top = -y * h + off.y
+    let r = coords.die_rect(2, 3);
+    assert_eq!(
+        (r.left, r.top, r.right, r.bottom),
+        (11.5, -14.0, 16.5, -10.0)
+    );
+}
+
+#[test]
+fn coords_overlay_substrate_defects() {
+    use super::coords::{overlay_substrate_defects, DefectRect, DieCoordinateSystem};
+    use super::ds::{AsciiDie, BinValue};
+    let dies = vec![
+        AsciiDie {
+            x: 0,
+            y: 0,
+            bin: BinValue::Number(1),
+        },
+        AsciiDie {
+            x: 1,
+            y: 0,
+            bin: BinValue::Number(1),
+        },
+        AsciiDie {
+            x: 0,
+            y: 1,
+            bin: BinValue::Special('S'),
+        },
+    ];
+    let coords = DieCoordinateSystem::new(1.0, 1.0);
+    // die (0,0) spans [0,1]x[0,1]; defect 200x200 µm at (0.5, 0.5) mm, and one on (0,1)
+    let defects = vec![
+        DefectRect {
+            x: 0.5,
+            y: 0.5,
+            w: 200.0,
+            h: 200.0,
+        },
+        DefectRect {
+            x: 0.5,
+            y: -0.5,
+            w: 200.0,
+            h: 200.0,
+        },
+    ];
+    let out = overlay_substrate_defects(&dies, &defects, &coords);
+    assert_eq!(out[0].bin, BinValue::Special('E'));
+    assert_eq!(out[1].bin, BinValue::Number(1));
+    assert_eq!(out[2].bin, BinValue::Special('S'));
+}
+
+#[test]
+fn coords_place_dies_grows_defects_and_ignores_touching_edges() {
+    use super::coords::{place_dies, DefectRect, DieCoordinateSystem, DieRect, ProductOffset};
+    let coords = DieCoordinateSystem::new(1.0, 1.0).with_offset(&ProductOffset {
+        defect_offset_x: 50.0,
+        defect_offset_y: 100.0,
+        ..Default::default()
+    });
+    let defects = [DefectRect {
+        x: 10.0,
+        y: 20.0,
+        w: 100.0,
+        h: 200.0,
+    }];
+    let layout = place_dies(&[], &defects, &coords);
+    let DieRect {
+        left,
+        top,
+        right,
+        bottom,
+    } = layout.defects[0];
+    for (got, want) in [(left, 9.95), (top, 19.9), (right, 10.15), (bottom, 20.3)] {
+        assert!((got - want).abs() < 1e-9, "{} != {}", got, want);
+    }
+
+    // a 1 mm defect at x = 1 only touches die (0,0) and covers die (1,0)
+    let touching = [DefectRect {
+        x: 1.0,
+        y: 0.0,
+        w: 1000.0,
+        h: 1000.0,
+    }];
+    let layout = place_dies(
+        &[(0, 0), (1, 0)],
+        &touching,
+        &DieCoordinateSystem::new(1.0, 1.0),
+    );
+    let defective: Vec<bool> = layout.dies.iter().map(|d| d.defective).collect();
+    assert_eq!(defective, vec![false, true]);
+}
+
+#[test]
+fn hex_reference_die_from_coords() {
+    use super::coords::DieCoordinateSystem;
+    use super::ds::{AsciiDie, BinValue};
+    use super::stack::to_hex_map_data;
+    // an off-centre grid: columns -1..=3, rows 2..=4
+    let dies: Vec<AsciiDie> = [(-1, 2), (3, 4), (1, 3)]
+        .iter()
+        .map(|&(x, y)| AsciiDie {
+            x,
+            y,
+            bin: BinValue::Number(1),
+        })
+        .collect();
+    let mut coords = DieCoordinateSystem::new(2.5, 3.0);
+    coords.reference_x = 1;
+    coords.reference_y = 3;
+    let hex = to_hex_map_data(&dies, &Default::default(), Some(&coords));
+    assert_eq!((hex.header.refpx, hex.header.refpy), (2, 1));
+    assert_eq!((hex.header.x_dies, hex.header.y_dies), (2.5, 3.0));
+
+    let hex = to_hex_map_data(&dies, &Default::default(), None);
+    assert_eq!((hex.header.refpx, hex.header.refpy), (0, 0));
+}
+
+#[test]
+fn stats_bin_pareto_from_stack_stats() {
+    use crate::wafer::bins::BinSet;
+    use crate::wafer::stats::{bin_pareto, WaferStackStats};
+    let stats = WaferStackStats {
+        oem_product_id: "OEM".into(),
+        batch_id: "LOT".into(),
+        wafer_id: "1".into(),
+        total_tested: 100,
+        total_pass: 80,
+        total_fail: 20,
+        yield_percentage: 80.0,
+        bin_counts: r#"{"1": 80, "3": 5, "7": 12, "4": 3, "9": 0}"#.into(),
+        start_time: None,
+        stop_time: None,
+    };
+    let bins = stats.bins().expect("bins");
+    let labels: Vec<&str> = bins.iter().map(|b| b.bin.as_str()).collect();
+    assert_eq!(labels, vec!["1", "3", "4", "7"]);
+
+    let pareto = bin_pareto(&bins, &BinSet::from_ids(&["BIN 1"]));
+    let order: Vec<(&str, u32)> = pareto.iter().map(|p| (p.bin.as_str(), p.count)).collect();
+    assert_eq!(order, vec![("7", 12), ("3", 5), ("4", 3)]);
+    assert!((pareto[0].percent - 60.0).abs() < 1e-9);
+    assert!((pareto[1].cumulative_percent - 85.0).abs() < 1e-9);
+    assert!((pareto[2].cumulative_percent - 100.0).abs() < 1e-9);
+}
+
+#[test]
+fn gross_die_placement_and_flat() {
+    use super::gross_die::{gross_die, GrossDieSpec, WaferFlat};
+    // 10 mm pitch on a 30 mm wafer: four dies around a street crossing beat
+    // the single die centred on the wafer
+    let spec: GrossDieSpec = serde_json::from_value(serde_json::json!({
+        "diameterMm": 30.0,
+        "dieWidthMm": 9.0,
+        "dieHeightMm": 9.0,
+        "scribeMm": 1.0,
+        "edgeExclusionMm": 0.0,
+        "flat": "none",
+    }))
+    .unwrap();
+    let out = gross_die(&spec, None, None).unwrap();
+    assert_eq!((out.pitch_x_mm, out.pitch_y_mm), (10.0, 10.0));
+    assert_eq!(out.gross_die, 4);
+    assert_eq!((out.coords.offset_x_mm, out.coords.offset_y_mm), (0.0, 0.0));
+    // with 2 mm exclusion a die centred on one axis only leaves room for two
+    let narrow = gross_die(
+        &GrossDieSpec {
+            edge_exclusion_mm: 2.0,
+            ..spec.clone()
+        },
+        None,
+        None,
+    )
+    .unwrap();
+    assert_eq!(narrow.gross_die, 2);
+
+    // a flat 10 mm below the centre cuts the lowest rows
+    let round = GrossDieSpec {
+        diameter_mm: 30.0,
+        die_width_mm: 5.0,
+        die_height_mm: 5.0,
+        ..spec.clone()
+    };
+    let flat = GrossDieSpec {
+        flat: WaferFlat::Flat,
+        flat_length_mm: Some(2.0 * 125f64.sqrt()),
+        ..round.clone()
+    };
+    let all = gross_die(&round, None, None).unwrap();
+    let cut = gross_die(&flat, None, None).unwrap();
+    assert!(cut.gross_die < all.gross_die);
+    assert!(cut
+        .positions
+        .iter()
+        .all(|&(x, y)| cut.coords.die_rect(x, y).top >= -10.0 - 1e-9));
+}
+
+#[test]
+fn gross_die_rejects_tiny_pitch() {
+    use super::coords::DieCoordinateSystem;
+    use super::gross_die::{gross_die, GrossDieSpec, WaferFlat};
+    let spec = GrossDieSpec {
+        diameter_mm: 300.0,
+        die_width_mm: 1e-6,
+        die_height_mm: 1e-6,
+        scribe_mm: 0.0,
+        edge_exclusion_mm: 3.0,
+        flat: WaferFlat::None,
+        flat_side: crate::render::Notch::Down,
+        flat_length_mm: None,
+        notch_depth_mm: 1.0,
+    };
+    assert!(gross_die(&spec, None, None).is_err());
+    // a huge offset on a sane pitch is just as unbounded
+    let mut coords = DieCoordinateSystem::new(5.0, 5.0);
+    coords.offset_x_mm = 1e12;
+    let sane = GrossDieSpec {
+        die_width_mm: 5.0,
+        die_height_mm: 5.0,
+        ..spec
+    };
+    assert!(gross_die(&sane, None, Some(&coords)).is_err());
+    assert!(gross_die(&sane, None, None).unwrap().gross_die > 0);
+}
+
+#[test]
+fn gross_die_layout_missing_and_extra() {
+    use super::ds::{AsciiDie, BinValue};
+    use super::gross_die::{gross_die, GrossDieSpec, WaferFlat};
+    let spec = GrossDieSpec {
+        diameter_mm: 100.0,
+        die_width_mm: 8.0,
+        die_height_mm: 6.0,
+        scribe_mm: 0.0,
+        edge_exclusion_mm: 3.0,
+        flat: WaferFlat::Notch,
+        flat_side: crate::render::Notch::Down,
+        flat_length_mm: None,
+        notch_depth_mm: 1.0,
+    };
+    let theory = gross_die(&spec, None, None).unwrap();
+
+    // the same grid in layout indices shifted by (3, -2), one die dropped,
+    // one added outside the wafer and an alignment marker that is ignored
+    let mut layout: Vec<AsciiDie> = theory
+        .positions
+        .iter()
+        .skip(1)
+        .map(|&(x, y)| AsciiDie {
+            x: x + 3,
+            y: y - 2,
+            bin: BinValue::Number(1),
+        })
+        .collect();
+    layout.push(AsciiDie {
+        x: 40,
+        y: 0,
+        bin: BinValue::Number(1),
+    });
+    layout.push(AsciiDie {
+        x: 41,
+        y: 0,
+        bin: BinValue::Special('S'),
+    });
+
+    let out = gross_die(&spec, Some(&layout), None).unwrap();
+    let check = out.layout.expect("layout check");
+    assert_eq!(out.gross_die, theory.gross_die);
+    assert_eq!((out.coords.reference_x, out.coords.reference_y), (3, -2));
+    let (x0, y0) = theory.positions[0];
+    assert_eq!(check.missing, vec![(x0 + 3, y0 - 2)]);
+    assert_eq!(check.extra, vec![(40, 0)]);
+    assert_eq!(check.matched, theory.gross_die - 1);
+    assert_eq!(check.layout_dies, theory.gross_die);
+}
+
+#[test]
+fn stack_aligns_and_merges_by_priority() {
+    use super::bins::BinSet;
+    use super::ds::{AsciiDie, BinValue};
+    use super::stack::{bin_counts_json, marker_offset, stack_layers, to_map_data, StackLayer};
+    use std::collections::HashMap;
+
+    let row = |bins: &[(i32, BinValue)]| -> Vec<AsciiDie> {
+        bins.iter().map(|&(x, bin)| AsciiDie { x, y: 0, bin }).collect()
+    };
+    let layer = |name: &str, priority: i32, dies: Vec<AsciiDie>| StackLayer {
+        name: name.into(),
+        priority,
+        header: HashMap::from([("Lot No.".to_string(), name.to_string())]),
+        dies,
+    };
+    use BinValue::{Number as N, Special as S};
+
+    // halves round up like Math.round
+    assert_eq!(marker_offset(&[(0, 0), (4, 1)], &[(1, 0), (4, 0)]), (0, 1));
+    assert_eq!(marker_offset(&[(0, 0)], &[]), (0, 0));
+
+    let aoi = layer(
+        "AOI",
+        1,
+        // one column to the right of CP1
+        row(&[(1, S('S')), (2, N(5)), (3, N(7)), (4, S('.')), (5, S('S')), (6, N(1))]),
+    );
+    let cp1 = layer(
+        "CP1",
+        4,
+        row(&[(0, S('S')), (1, N(1)), (2, N(3)), (3, N(1)), (4, S('S'))]),
+    );
+    let wlbi = layer("WLBI", 5, row(&[(0, S('*')), (3, N(9)), (4, S('*'))]));
+
+    let wafer = stack_layers(vec![aoi, cp1, wlbi], &BinSet::default_pass()).unwrap();
+    let names: Vec<&str> = wafer.layers.iter().map(|l| l.name.as_str()).collect();
+    assert_eq!(names, vec!["WLBI", "CP1", "AOI"]);
+    // CP1's header wins over the first layer's
+    assert_eq!(wafer.header["Lot No."], "CP1");
+
+    let bins: Vec<(i32, BinValue)> = wafer.dies.iter().map(|d| (d.x, d.bin)).collect();
+    assert_eq!(
+        bins,
+        vec![
+            (0, S('*')),
+            (3, N(9)),  // WLBI beats CP1
+            (4, S('*')),
+            (1, N(5)),  // AOI fails a die CP1 passed
+            (2, N(3)),  // but cannot pass a die CP1 failed
+            (5, N(1)),  // and adds dies nobody else has
+        ]
+    );
+    assert_eq!(wafer.summary.total_tested, 4);
+    assert_eq!(wafer.summary.total_pass, 1);
+    let counts: serde_json::Value = serde_json::from_str(&bin_counts_json(&wafer.dies)).unwrap();
+    assert_eq!(counts, serde_json::json!({ "1": 1, "3": 1, "5": 1, "9": 1 }));
+
+    let map = to_map_data(&wafer.dies, &wafer.summary, &wafer.header);
+    assert_eq!((map.map_columns, map.map_rows), (6, 1));
+    assert_eq!(map.map.raw, vec!["*539*1".to_string()]);
+}
@@ -18,6 +18,9 @@ import { initialize } from '@/utils/init';
 import { IS_PROD, IS_DEV } from '@/env';
 import { setSqlDebugLogging } from '@/db';
 import { warmIndexCaches } from '@/utils/fs';
+import { ingestWatchBatch, watchDataSourceRoot } from '@/utils/dataSource';
+import { invokeWatchStop } from '@/api/tauri/fs';
+import { useDataSourceChanged } from '@/hooks';
 import { isAdmin, isPrivileged } from '@/utils/auth';
 
 // REDUX
@@ -91,6 +94,26 @@ export default function App() {
     const adminDefault = useSelector((s: RootState) => s.auth.adminDefaultPassword);
     const location = useLocation();
     const dispatch = useDispatch<AppDispatch>();
+    const rootPath = useSelector((s: RootState) => s.dataSourceConfig.rootPath);
+    const regex = useSelector((s: RootState) => s.dataSourceConfig.regex);
+
+    // Watch the data root once it is loaded; a rule change replaces the watch
+    useEffect(() => {
+        if (!rootPath) return;
+        watchDataSourceRoot(rootPath, regex).catch((err) => console.error('Failed to watch data source root', err));
+    }, [rootPath, regex]);
+
+    useEffect(() => {
+        if (!rootPath) return;
+        return () => {
+            invokeWatchStop(rootPath).catch((err) => console.error(err));
+        };
+    }, [rootPath]);
+
+    useDataSourceChanged((batch) => {
+        dispatch(refreshFolderStatuses());
+        ingestWatchBatch(batch).catch((err) => console.error(err));
+    });
 
     // A welcome message whenever the UI is loaded!
     useEffect(() => {
@@ -0,0 +1,84 @@
+import type {
+    FileIndexRow,
+    ProductDefectMapRow,
+    SubstrateDefectRow,
+    WaferMapRow,
+} from '@/db/types';
+import type { WaferStackStats } from '@/db/waferStackStats';
+import type {
+    BackupInfo,
+    BundleManifest,
+    ExportOptions,
+    ImportOptions,
+    ImportReport,
+    ProductContext,
+    SchemaStatus,
+    TableRows,
+    WaferMapFilter,
+} from '@/types/ipc';
+import { invokeSafe } from './index';
+
+// =============================================================================
+// Native database layer: the same tables as `src/db/*.ts`, queried in Rust so
+// bulk data does not round-trip through the webview.
+// =============================================================================
+
+// Returns the rows written
+export async function dbUpsert(batch: TableRows): Promise<number> {
+    return invokeSafe('rust_db_upsert', { batch });
+}
+
+export async function dbProductContext(oemProductId: string): Promise<ProductContext> {
+    return invokeSafe('rust_db_product_context', { oemProductId });
+}
+
+export async function dbWaferMaps(filter?: WaferMapFilter): Promise<WaferMapRow[]> {
+    return invokeSafe('rust_db_wafer_maps', { filter });
+}
+
+export async function dbProductDefectMaps(
+    oemProductId: string,
+    lotId?: string,
+): Promise<ProductDefectMapRow[]> {
+    return invokeSafe('rust_db_product_defect_maps', { oemProductId, lotId });
+}
+
+export async function dbSubstrateDefects(subIds: string[]): Promise<SubstrateDefectRow[]> {
+    return invokeSafe('rust_db_substrate_defects', { subIds });
+}
+
+export async function dbFileIndex(paths: string[]): Promise<FileIndexRow[]> {
+    return invokeSafe('rust_db_file_index', { paths });
+}
+
+export async function dbStackStats(oemProductId: string): Promise<WaferStackStats[]> {
+    return invokeSafe('rust_db_stack_stats', { oemProductId });
+}
+
+export async function dbSchemaStatus(): Promise<SchemaStatus> {
+    return invokeSafe('rust_db_schema_status');
+}
+
+// Applies pending migrations, e.g. after dbRevert
+export async function dbMigrate(): Promise<SchemaStatus> {
+    return invokeSafe('rust_db_migrate');
+}
+
+// Runs down migrations to `target` (>= 1) before installing an older build
+export async function dbRevert(target: number): Promise<SchemaStatus> {
+    return invokeSafe('rust_db_revert', { target });
+}
+
+// Snapshot of the whole database; replaces `dest` if present
+export async function dbBackup(dest: string): Promise<BackupInfo> {
+    return invokeSafe('rust_db_backup', { dest });
+}
+
+// `.json` paths get a single JSON file, anything else a zip
+export async function dbExportConfig(path: string, options?: ExportOptions): Promise<BundleManifest> {
+    return invokeSafe('rust_db_export_config', { path, options });
+}
+
+export async function dbImportConfig(path: string, options?: ImportOptions): Promise<ImportReport> {
+    return invokeSafe('rust_db_import_config', { path, options });
+}
@@ -1,4 +1,5 @@
-import { DirResult } from '@/types/ipc';
+import { listen, type UnlistenFn } from '@tauri-apps/api/event';
+import { DirResult, IndexOptions, IndexReport, LockAttempt, LockInfo, LockOptions, ScanOptions, ScanReport, WatchBatch, WatchOptions } from '@/types/ipc';
 import { invokeSafe } from '.';
 
 export interface FolderRequest { path: string };
@@ -40,6 +41,37 @@ export async function invokeReadDir(rootPath: string): Promise<DirResult[]> {
     return entries;
 }
 
+/**
+ * Walk a data root once in Rust: recognise source folders by regex and extract
+ * product/lot/wafer/stage metadata for every matching file. With `options.folders`
+ * only those source folders are read and `root` is ignored.
+ */
+export async function invokeScanDataSources(root: string, options?: ScanOptions): Promise<ScanReport> {
+    return await invokeSafe<ScanReport>('rust_scan_data_sources', { root, options });
+}
+
+/**
+ * Start watching a data root; new/changed/removed map files are written to
+ * `file_index` / `wafer_maps` and reported through `onDataSourceChanged`.
+ * @returns All watched roots
+ */
+export async function invokeWatchStart(root: string, options?: WatchOptions): Promise<string[]> {
+    return await invokeSafe<string[]>('rust_watch_start', { root, options });
+}
+
+export async function invokeWatchStop(root: string): Promise<string[]> {
+    return await invokeSafe<string[]>('rust_watch_stop', { root });
+}
+
+export async function invokeWatchList(): Promise<string[]> {
+    return await invokeSafe<string[]>('rust_watch_list');
+}
+
+/** Subscribe to processed watcher batches; call the returned function to unsubscribe. */
+export async function onDataSourceChanged(handler: (batch: WatchBatch) => void): Promise<UnlistenFn> {
+    return await listen<WatchBatch>('data-source-changed', (event) => handler(event.payload));
+}
+
 /**
  * Compute SHA1 hash in Rust.
  * @param input - The string to hash.
@@ -66,3 +98,48 @@ export async function invokeSha1Batch(inputs: string[]): Promise<string[]> {
 export async function invokeSha256(input: string): Promise<string> {
     return invokeSafe<string>('rust_sha256', { input });
 }
+
+/**
+ * Compute SHA256 of file **contents** in Rust (streamed, hashed in parallel).
+ * @param paths - Absolute file paths.
+ * @returns Hashes in the same order; `null` where a file could not be read.
+ */
+export async function invokeSha256FileBatch(paths: string[]): Promise<(string | null)[]> {
+    return invokeSafe<(string | null)[]>('rust_sha256_file_batch', { paths });
+}
+
+/**
+ * Incrementally update `file_index` for the given files: unchanged files are
+ * skipped, the rest hashed by content, and renamed/moved files re-keyed.
+ */
+export async function invokeIndexFiles(paths: string[], options?: IndexOptions): Promise<IndexReport> {
+    return invokeSafe<IndexReport>('rust_index_files', { paths, options });
+}
+
+/**
+ * Lock a file, waiting up to `options.timeoutMs` (default 30 s) for other holders.
+ * Rejects with the holder's user, host and pid when the file stays locked.
+ */
+export async function invokeLockFile(path: string, options?: LockOptions): Promise<LockInfo> {
+    return invokeSafe<LockInfo>('lock_file', { path, options });
+}
+
+/**
+ * Try to lock a file once (or until `options.timeoutMs`); resolves with who
+ * holds it instead of rejecting when it is locked.
+ */
+export async function invokeTryLockFile(path: string, options?: LockOptions): Promise<LockAttempt> {
+    return invokeSafe<LockAttempt>('try_lock_file', { path, options });
+}
+
+export async function invokeUnlockFile(path: string): Promise<void> {
+    return invokeSafe<void>('unlock_file', { path });
+}
+
+/**
+ * Locks held by this app, plus the state and holder of each of `paths`
+ * (e.g. maps on a network share).
+ */
+export async function invokeListLocks(paths?: string[]): Promise<LockInfo[]> {
+    return invokeSafe<LockInfo[]>('list_locks', { paths });
+}
@@ -0,0 +1,15 @@
+import type { HttpApiStatus } from '@/types/ipc';
+import { invokeSafe } from './index';
+
+// Localhost HTTP/JSON API for scripts on this machine; port defaults to 8765
+export async function startHttpApi(port?: number): Promise<HttpApiStatus> {
+    return invokeSafe('rust_http_api_start', { port });
+}
+
+export async function stopHttpApi(): Promise<HttpApiStatus> {
+    return invokeSafe('rust_http_api_stop');
+}
+
+export async function httpApiStatus(): Promise<HttpApiStatus> {
+    return invokeSafe('rust_http_api_status');
+}
@@ -0,0 +1,40 @@
+import { listen, type UnlistenFn } from '@tauri-apps/api/event';
+import type {
+    NewStackingJob,
+    StackingJobEvent,
+    StackingJobSummary,
+    StackingJobTask,
+} from '@/types/ipc';
+import { invokeSafe } from './index';
+
+// Queues the wafers of a job; the app's runner stacks them
+export async function jobsEnqueue<W, O>(job: NewStackingJob<W, O>): Promise<StackingJobSummary> {
+    return invokeSafe('rust_jobs_enqueue', { job });
+}
+
+export async function jobsList(): Promise<StackingJobSummary[]> {
+    return invokeSafe('rust_jobs_list');
+}
+
+export async function jobsTasks(jobId: string): Promise<StackingJobTask[]> {
+    return invokeSafe('rust_jobs_tasks', { jobId });
+}
+
+// Stops the wafers being stacked and drops the queued ones
+export async function jobsCancel(jobId: string): Promise<StackingJobSummary> {
+    return invokeSafe('rust_jobs_cancel', { jobId });
+}
+
+// Requeues the failed and cancelled wafers of a job
+export async function jobsRetry(jobId: string): Promise<StackingJobSummary> {
+    return invokeSafe('rust_jobs_retry', { jobId });
+}
+
+export async function jobsRemove(jobId: string): Promise<void> {
+    return invokeSafe('rust_jobs_remove', { jobId });
+}
+
+/** Subscribe to job and task changes; call the returned function to unsubscribe. */
+export async function onStackingJobChanged(handler: (event: StackingJobEvent) => void): Promise<UnlistenFn> {
+    return await listen<StackingJobEvent>('stacking-job-changed', (event) => handler(event.payload));
+}
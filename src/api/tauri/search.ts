@@ -0,0 +1,16 @@
+import type {
+    ReindexOptions,
+    ReindexReport,
+    WaferSearchPage,
+    WaferSearchQuery,
+} from '@/types/ipc';
+import { invokeSafe } from './index';
+
+export async function searchWafers(query: WaferSearchQuery = {}): Promise<WaferSearchPage> {
+    return invokeSafe('rust_search_wafers', { query });
+}
+
+// Parses headers for maps ingested without one (frontend ingest, pre-v4 data)
+export async function searchReindex(options?: ReindexOptions): Promise<ReindexReport> {
+    return invokeSafe('rust_search_reindex', { options });
+}
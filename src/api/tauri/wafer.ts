@@ -1,14 +1,41 @@
 import type {
     BinMapData,
+    BinMapTable,
+    BinMapTableResult,
+    AsciiDie,
+    CompositeMap,
+    CompositeOptions,
+    CompositeWafer,
+    CorrelationOptions,
+    DefectRect,
+    DieCoordinateSystem,
+    DuplicateReport,
+    InkOutcome,
+    MapLayer,
+    InkRules,
+    LotReport,
     HexMapData,
+    MapComparison,
     MapData,
     ProductMappingXlsResult,
     ProductXlsResult,
+    SignatureOptions,
+    SignatureReport,
+    SpcOptions,
+    SpcReport,
+    StageWaterfall,
     SubstrateDefectXlsResult,
+    SubstrateLayout,
     DieLayoutMap,
+    GrossDieResult,
+    GrossDieSpec,
 
     Wafer,
-    SilanMapData
+    SilanMapData,
+    WaferGeometry,
+    WaferRenderOptions,
+    ZoneOptions,
+    ZoneReport
 } from '@/types/ipc';
 
 import { invokeSafe } from './index';
@@ -33,6 +60,15 @@ export async function invokeParseDieLayoutXls(path: string): Promise<DieLayoutMa
     return invokeSafe('rust_parse_die_layout_xls', { path });
 }
 
+export async function invokeParseBinMapXls(path: string): Promise<BinMapTableResult> {
+    // Result<HashMap<String, BinMapTable>, String>
+    return invokeSafe('rust_parse_bin_map_xls', { path });
+}
+
+export async function invokeParseBinMapJson(path: string): Promise<BinMapTableResult> {
+    return invokeSafe('rust_parse_bin_map_json', { path });
+}
+
 // Debug helper: prints X/Y coords for each sheet server-side (console).
 export async function invokeDebugPrintDieLayout(path: string): Promise<void> {
     await invokeSafe('rust_debug_print_die_layout_coords', { path });
@@ -40,69 +76,205 @@ export async function invokeDebugPrintDieLayout(path: string): Promise<void> {
 
 // =============================================================================
 
-export async function invokeParseWafer(path: string): Promise<Wafer> {
+// `binMap` (optional) translates native bins to internal bins on import
+
+export async function invokeParseWafer(path: string, binMap?: BinMapTable): Promise<Wafer> {
     // Result<Wafer, String>
-    return invokeSafe('rust_parse_wafer', { path });
+    return invokeSafe('rust_parse_wafer', { path, binMap });
 }
 
-export async function parseWaferMap(path: string): Promise<BinMapData> {
+export async function parseWaferMap(path: string, binMap?: BinMapTable): Promise<BinMapData> {
     // Result<BinMapData, String>
-    return invokeSafe('rust_parse_wafer_bin', { path });
+    return invokeSafe('rust_parse_wafer_bin', { path, binMap });
 }
-export async function parseWaferMapEx(path: string): Promise<MapData> {
+export async function parseWaferMapEx(path: string, binMap?: BinMapTable): Promise<MapData> {
     // Result<MapData, String>
-    return invokeSafe('rust_parse_wafer_map_data', { path });
+    return invokeSafe('rust_parse_wafer_map_data', { path, binMap });
 }
 
+// `binMap` (optional) translates internal bins to the customer's codes on export
+
 // Wafer (.txt-style via Wafer::to_string)
-export async function exportWafer(wafer: Wafer, outputPath: string): Promise<void> {
-    await invokeSafe('rust_export_wafer', { wafer, outputPath: outputPath });
+export async function exportWafer(wafer: Wafer, outputPath: string, binMap?: BinMapTable): Promise<void> {
+    await invokeSafe('rust_export_wafer', { wafer, outputPath: outputPath, binMap });
 }
 export async function printWafer(wafer: Wafer): Promise<void> {
     await invokeSafe('rust_print_wafer', { wafer });
 }
 
 // Bin map (parsed as BinMapData)
-export async function exportWaferBin(wafer_bin: BinMapData, outputPath: string): Promise<void> {
-    await invokeSafe('rust_export_wafer_bin', { waferBin: wafer_bin, outputPath });
+export async function exportWaferBin(wafer_bin: BinMapData, outputPath: string, binMap?: BinMapTable): Promise<void> {
+    await invokeSafe('rust_export_wafer_bin', { waferBin: wafer_bin, outputPath, binMap });
 }
 export async function printWaferBin(wafer_bin: BinMapData): Promise<void> {
     await invokeSafe('rust_print_wafer_bin', { waferBin: wafer_bin });
 }
 
 // MapData (extended text format)
-export async function exportWaferMapData(data: MapData, outputPath: string): Promise<void> {
-    await invokeSafe('rust_export_wafer_map_data', { data, outputPath });
+export async function exportWaferMapData(data: MapData, outputPath: string, binMap?: BinMapTable): Promise<void> {
+    await invokeSafe('rust_export_wafer_map_data', { data, outputPath, binMap });
 }
 export async function printWaferMapData(data: MapData): Promise<void> {
     await invokeSafe('rust_print_wafer_map_data', { data });
 }
 
 // Hex / .sinf (HexMapData)
-export async function exportWaferHex(wafer_hex: HexMapData, outputPath: string): Promise<void> {
-    await invokeSafe('rust_export_wafer_hex', { waferHex: wafer_hex, outputPath });
+export async function exportWaferHex(wafer_hex: HexMapData, outputPath: string, binMap?: BinMapTable): Promise<void> {
+    await invokeSafe('rust_export_wafer_hex', { waferHex: wafer_hex, outputPath, binMap });
 }
 export async function printWaferHex(wafer_hex: HexMapData): Promise<void> {
     await invokeSafe('rust_print_wafer_hex', { waferHex: wafer_hex });
 }
 
-//jpg
-export async function exportWaferJpg(imageData: Uint8Array, outputPath: string): Promise<void> {
-    await invokeSafe('rust_export_wafer_jpg', { imageData, outputPath });
-}
-
-export async function exportWaferSilan(silan: SilanMapData, outputPath: string): Promise<void> {
-    await invokeSafe('rust_export_wafer_silan', { silan, outputPath });
+export async function exportWaferSilan(silan: SilanMapData, outputPath: string, binMap?: BinMapTable): Promise<void> {
+    await invokeSafe('rust_export_wafer_silan', { silan, outputPath, binMap });
 }
 
 export async function printWaferSilan(silan: SilanMapData): Promise<void> {
     await invokeSafe('rust_print_wafer_silan', { silan });
 }
 
-export async function exportFab(fab: Wafer, outputPath: string): Promise<void> {
-    await invokeSafe('rust_export_wafer_fab', { fab, outputPath });
+export async function exportFab(fab: Wafer, outputPath: string, binMap?: BinMapTable): Promise<void> {
+    await invokeSafe('rust_export_wafer_fab', { fab, outputPath, binMap });
 }
 
 export async function printFab(fab: Wafer): Promise<void> {
     await invokeSafe('rust_print_wafer_fab', { fab });
 }
+
+// Physical die coordinates
+// Marks dies overlapped by substrate defects with 'E' (the substrate stacking layer).
+export async function overlaySubstrateDefects(
+    dies: AsciiDie[],
+    defects: DefectRect[],
+    coords: DieCoordinateSystem,
+): Promise<AsciiDie[]> {
+    return invokeSafe('rust_overlay_substrate_defects', { dies, defects, coords });
+}
+
+// Die and defect rectangles in mm for the substrate viewer; positions are [x, y] grid indices.
+export async function placeSubstrateDies(
+    positions: [number, number][],
+    defects: DefectRect[],
+    coords: DieCoordinateSystem,
+): Promise<SubstrateLayout> {
+    return invokeSafe('rust_place_substrate_dies', { positions, defects, coords });
+}
+
+// Edge exclusion & inking
+export async function applyInkRules(
+    dies: AsciiDie[],
+    rules: InkRules,
+    header?: Record<string, string>,
+    geometry?: WaferGeometry,
+    coords?: DieCoordinateSystem,
+): Promise<InkOutcome> {
+    return invokeSafe('rust_apply_ink_rules', { dies, rules, header, geometry, coords });
+}
+
+// Rendering
+// Writes a PNG/JPEG wafer map; the format follows the extension unless `options.format` is set.
+export async function renderWaferMap(
+    dies: AsciiDie[],
+    header: Record<string, string>,
+    outputPath: string,
+    coords?: DieCoordinateSystem,
+    options?: WaferRenderOptions,
+): Promise<void> {
+    await invokeSafe('rust_render_wafer_map', { dies, header, outputPath, coords, options });
+}
+
+// SVG: one <rect> per die with data-x/y/bin and, given `layers`, data-layer.
+export async function exportWaferSvg(
+    dies: AsciiDie[],
+    header: Record<string, string>,
+    outputPath: string,
+    coords?: DieCoordinateSystem,
+    layers?: MapLayer[],
+    options?: WaferRenderOptions,
+): Promise<void> {
+    await invokeSafe('rust_export_wafer_svg', { dies, header, outputPath, coords, layers, options });
+}
+
+// PDF lot report: cover page with lot yield, then one page per wafer.
+export async function exportLotReport(report: LotReport, outputPath: string): Promise<void> {
+    await invokeSafe('rust_export_lot_report', { report, outputPath });
+}
+
+// Lot analytics
+export async function compositeLotMap(
+    wafers: CompositeWafer[],
+    options?: CompositeOptions,
+): Promise<CompositeMap> {
+    return invokeSafe('rust_composite_lot_map', { wafers, options });
+}
+
+// Writes the composite as a PNG/JPEG heat map (format from the extension).
+export async function renderCompositeMap(
+    wafers: CompositeWafer[],
+    header: Record<string, string>,
+    outputPath: string,
+    coords?: DieCoordinateSystem,
+    options?: CompositeOptions,
+    renderOptions?: WaferRenderOptions,
+): Promise<CompositeMap> {
+    return invokeSafe('rust_render_composite_map', { wafers, header, outputPath, coords, options, renderOptions });
+}
+
+export async function classifySignatures(
+    dies: AsciiDie[],
+    header?: Record<string, string>,
+    geometry?: WaferGeometry,
+    coords?: DieCoordinateSystem,
+    options?: SignatureOptions,
+): Promise<SignatureReport> {
+    return invokeSafe('rust_classify_signatures', { dies, header, geometry, coords, options });
+}
+
+export async function zoneYield(
+    dies: AsciiDie[],
+    header?: Record<string, string>,
+    geometry?: WaferGeometry,
+    coords?: DieCoordinateSystem,
+    options?: ZoneOptions,
+): Promise<ZoneReport> {
+    return invokeSafe('rust_zone_yield', { dies, header, geometry, coords, options });
+}
+
+export async function stageWaterfall(
+    layers: MapLayer[],
+    passBins?: string[],
+): Promise<StageWaterfall> {
+    return invokeSafe('rust_stage_waterfall', { layers, passBins });
+}
+
+// e.g. CP1 vs CP2 for retest recovery, AOI vs CP2 for AOI escapes
+export async function compareMaps(
+    a: MapLayer,
+    b: MapLayer,
+    options?: CorrelationOptions,
+): Promise<MapComparison> {
+    return invokeSafe('rust_compare_maps', { a, b, options });
+}
+
+// Reads wafer_stack_stats directly from the app database
+export async function spcLotYields(
+    oemProductId: string,
+    options?: SpcOptions,
+): Promise<SpcReport> {
+    return invokeSafe('rust_spc_lot_yields', { oemProductId, options });
+}
+
+// `layout`: DieLayoutSheet.dies or the dies of a parsed map
+export async function grossDie(
+    spec: GrossDieSpec,
+    layout?: AsciiDie[],
+    coords?: DieCoordinateSystem,
+): Promise<GrossDieResult> {
+    return invokeSafe('rust_gross_die', { spec, layout, coords });
+}
+
+// Duplicates, retest chains and conflicts among indexed wafer_maps
+export async function waferMapDuplicates(productId?: string): Promise<DuplicateReport> {
+    return invokeSafe('rust_wafer_map_duplicates', { productId });
+}
@@ -6,8 +6,15 @@ import type { CSS2DRenderer as CSS2DRendererType } from 'three/examples/jsm/rend
 import { Box, Slider, Paper, Group, Text, Button, Card } from '@mantine/core';
 import { IconRefresh } from '@tabler/icons-react';
 
-import { AsciiDie, WaferMapDie, SubstrateDefectXlsResult, SubstrateDefectRecord } from '@/types/ipc';
-import { computeDieRect, GridOffset, normalizeDefect, rectsOverlap } from '@/utils/substrateMapping';
+import { placeSubstrateDies } from '@/api/tauri/wafer';
+import {
+    AsciiDie,
+    DieCoordinateSystem,
+    WaferMapDie,
+    SubstrateDefectXlsResult,
+    SubstrateDefectRecord,
+    SubstrateLayout,
+} from '@/types/ipc';
 
 import { colorMap } from './constants';
 
@@ -18,7 +25,7 @@ interface SubstrateRendererProps {
     style?: React.CSSProperties;
     selectedSheetId: string | null;
     sheetsData: SubstrateDefectXlsResult;
-    gridOffset?: GridOffset;
+    gridOffset?: { x: number; y: number };
     dies: AsciiDie[] | WaferMapDie[] | null;
     defectSizeOffset?: { x: number; y: number };
     productId?: string | null;
@@ -72,7 +79,6 @@ export default function SubstrateRenderer({
     const { x: offsetX, y: offsetY } = gridOffset;
     // defectSizeOffset is specified in micrometers in the UI; convert with the raw defect data
     const { x: offsetX_defect, y: offsetY_defect } = defectSizeOffset;
-    const EPS = 1e-6; // keep overlap rules consistent with algorithm path
     const shouldResetViewRef = useRef(false);
     const raycasterRef = useRef<THREE.Raycaster | null>(null);
     const pointerRef = useRef<THREE.Vector2 | null>(null);
@@ -87,9 +93,6 @@ export default function SubstrateRenderer({
         objects.forEach((obj) => scene.remove(obj));
     }, []);
 
-    // Guard against negative defect dimensions from upstream data.
-    const clampDefectSize = useCallback((val: number) => Math.max(0, val), []);
-
     // Manual refresh — re-run WebGL init effect
     const refreshRenderer = useCallback(() => {
         setError('正在重载渲染器…');
@@ -384,6 +387,30 @@ export default function SubstrateRenderer({
         return Array.isArray(arr) ? arr : [];
     }, [selectedSheetId, sheetsData]);
 
+    // Die and defect rectangles come from the same coordinate system as the stacking overlay
+    const [layout, setLayout] = useState<SubstrateLayout | null>(null);
+    useEffect(() => {
+        const coords: DieCoordinateSystem = {
+            dieWidthMm: gridWidth,
+            dieHeightMm: gridHeight,
+            offsetXMm: offsetX,
+            offsetYMm: offsetY,
+            defectOffsetXUm: offsetX_defect,
+            defectOffsetYUm: offsetY_defect,
+        };
+        let cancelled = false;
+        placeSubstrateDies(mapCoordinates, activeDefects, coords)
+            .then((next) => {
+                if (!cancelled) setLayout(next);
+            })
+            .catch((e) => {
+                if (!cancelled) setError(`晶粒定位失败: ${String(e)}`);
+            });
+        return () => {
+            cancelled = true;
+        };
+    }, [mapCoordinates, activeDefects, gridWidth, gridHeight, offsetX, offsetY, offsetX_defect, offsetY_defect]);
+
     const createGridFromCoordinates = useCallback(() => {
         if (!threeReady || !sceneRef.current || !threeRef.current) return;
         const THREE = threeRef.current;
@@ -395,26 +422,8 @@ export default function SubstrateRenderer({
         if (!extents) return;
         const { minCoordX, minCoordY } = extents;
 
-        for (const [xCoord, yCoord] of mapCoordinatesRef.current) {
-            const gridRect = computeDieRect(
-                { x: xCoord, y: yCoord },
-                { width: gridWidth, height: gridHeight },
-                gridOffset as GridOffset
-            );
-            const hasOverlap = activeDefects && activeDefects.length
-                ? activeDefects.some(defect => {
-                    const norm = normalizeDefect(defect, { x: offsetX_defect, y: offsetY_defect });
-                    const defectRect = {
-                        left: norm.x,
-                        right: norm.x + norm.w,
-                        top: norm.y,
-                        bottom: norm.y + norm.h,
-                    };
-                    return rectsOverlap(gridRect, defectRect, EPS);
-                })
-                : false;
-
-            const material = hasOverlap
+        for (const { x: xCoord, y: yCoord, rect: gridRect, defective } of layout?.dies ?? []) {
+            const material = defective
                 ? new THREE.MeshBasicMaterial({ color: overlapColor, transparent: false, opacity: 1, side: THREE.DoubleSide })
                 : new THREE.MeshBasicMaterial({ color: baseGridColor, transparent: false, opacity: 1, side: THREE.DoubleSide });
 
@@ -507,7 +516,7 @@ export default function SubstrateRenderer({
 
         gridObjectsRef.current = gridObjs;
         gridMeshCoordsRef.current = gridMeshCoords;
-    }, [activeDefects, clampDefectSize, getCoordExtents, gridHeight, gridWidth, offsetX, offsetX_defect, offsetY, offsetY_defect, overlapColor, threeReady]);
+    }, [layout, getCoordExtents, gridHeight, gridWidth, offsetX, offsetY, overlapColor, threeReady]);
 
     // Rebuild scene on data changes (dies, sheet selection, offsets, etc.)
     useEffect(() => {
@@ -555,16 +564,19 @@ export default function SubstrateRenderer({
 
         if (activeDefects && activeDefects.length) {
             const nodes: THREE.Object3D[] = [];
-            activeDefects.forEach((item) => {
-                const norm = normalizeDefect(item, { x: offsetX_defect, y: offsetY_defect });
-                const adjW = norm.w;
-                const adjH = norm.h;
+            const defectRects = layout?.defects ?? [];
+            activeDefects.forEach((item, index) => {
+                // layout is placed asynchronously; skip defects it does not cover yet
+                const rect = defectRects[index];
+                if (!rect) return;
+                const adjW = rect.right - rect.left;
+                const adjH = rect.bottom - rect.top;
                 const hasColor = colorMap.has(item.class);
                 const sizeX = Math.max(adjW, gridWidth * 0.5);
                 const sizeY = Math.max(adjH, gridHeight * 0.5);
                 if (!hasColor) {
                     const sprite = makeQuestionMarkSprite(sizeX, sizeY);
-                    sprite.position.set(norm.x + sizeX / 2, norm.y + sizeY / 2, 0.2);
+                    sprite.position.set(rect.left + sizeX / 2, rect.top + sizeY / 2, 0.2);
                     sceneRef.current!.add(sprite);
                     nodes.push(sprite);
                 } else {
@@ -578,7 +590,7 @@ export default function SubstrateRenderer({
                         opacity: 1,
                     });
                     const mesh = new THREE.Mesh(geometry, material);
-                    mesh.position.set(norm.x + adjW / 2, norm.y + adjH / 2, 0);
+                    mesh.position.set(rect.left + adjW / 2, rect.top + adjH / 2, 0);
                     sceneRef.current!.add(mesh);
                     nodes.push(mesh);
                 }
@@ -599,7 +611,7 @@ export default function SubstrateRenderer({
         }
         setError(null);
         setManualRefresh(false);
-    }, [threeReady, reloadToken, manualRefresh, autoRefresh, activeDefects, dies, mapCoordinates, gridHeight, gridWidth, offsetX, offsetX_defect, offsetY, offsetY_defect, createGridFromCoordinates, clearSceneObjects,]);
+    }, [threeReady, reloadToken, manualRefresh, autoRefresh, activeDefects, layout, dies, mapCoordinates, gridHeight, gridWidth, createGridFromCoordinates, clearSceneObjects,]);
 
     // Keep renderer sized to container; ensure initial fit occurs once the square has a real size.
     useEffect(() => {
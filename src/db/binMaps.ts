@@ -0,0 +1,70 @@
+import { getDb } from '@/db/index';
+import type { BinMapTable } from '@/types/ipc';
+
+export interface ProductBinMap {
+    oem_product_id: string;
+    /** BinMapTable as JSON */
+    bin_map: string;
+}
+
+const TABLE = 'product_bin_map';
+const COLUMNS = 'oem_product_id, bin_map';
+
+const toTable = (row: ProductBinMap | undefined): BinMapTable | null => {
+    if (!row) return null;
+    try {
+        return JSON.parse(row.bin_map) as BinMapTable;
+    } catch (e) {
+        console.warn(`BIN映射表解析失败 (${row.oem_product_id}):`, e);
+        return null;
+    }
+};
+
+/**
+ * 获取指定OEM产品的BIN映射表
+ * @param oem_product_id OEM产品编号
+ * @returns 映射表，未配置时返回 null
+ */
+export async function getProductBinMap(oem_product_id: string): Promise<BinMapTable | null> {
+    const db = await getDb();
+    const rows = await db.select<ProductBinMap[]>(
+        `SELECT ${COLUMNS} FROM ${TABLE} WHERE oem_product_id = ?`,
+        [oem_product_id]
+    );
+    return toTable(rows[0]);
+}
+
+/** 所有已配置BIN映射表的OEM产品 */
+export async function getAllProductBinMaps(): Promise<Record<string, BinMapTable>> {
+    const db = await getDb();
+    const rows = await db.select<ProductBinMap[]>(
+        `SELECT ${COLUMNS} FROM ${TABLE} ORDER BY oem_product_id ASC`
+    );
+    const result: Record<string, BinMapTable> = {};
+    rows.forEach((row) => {
+        const table = toTable(row);
+        if (table) result[row.oem_product_id] = table;
+    });
+    return result;
+}
+
+/**
+ * 保存OEM产品的BIN映射表（导入与导出时按此表转换BIN）
+ * @param oem_product_id OEM产品编号
+ * @param table 映射表
+ */
+export async function saveProductBinMap(oem_product_id: string, table: BinMapTable): Promise<void> {
+    const db = await getDb();
+    await db.execute(
+        `INSERT INTO ${TABLE} (oem_product_id, bin_map)
+    VALUES (?, ?)
+    ON CONFLICT(oem_product_id) DO UPDATE SET
+      bin_map = excluded.bin_map`,
+        [oem_product_id, JSON.stringify(table)]
+    );
+}
+
+export async function deleteProductBinMap(oem_product_id: string): Promise<void> {
+    const db = await getDb();
+    await db.execute(`DELETE FROM ${TABLE} WHERE oem_product_id = ?`, [oem_product_id]);
+}
@@ -74,3 +74,25 @@ export interface WaferMapRow {
     time: number | null;       // epoch ms (nullable)
     file_path: string;         // NOT NULL
 }
+
+/** Row shape for wafer_map_choice: the map that stacks when a wafer/stage has several */
+export interface WaferMapChoiceRow {
+    product_id: string;
+    batch_id: string;
+    wafer_id: number;
+    stage: string;
+    sub_stage: string;         // '' when the maps have no sub-stage
+    file_path: string;
+}
+
+/** Row shape for wafer_map_meta: the parsed header of a wafer map */
+export interface WaferMapMetaRow {
+    file_path: string;                  // wafer_maps.file_path
+    device: string | null;
+    operator: string | null;
+    meas_time: number | null;           // epoch ms, from the header
+    total_tested: number | null;
+    total_pass: number | null;
+    yield_percentage: number | null;
+    bins: string;                       // bins present, space separated
+}
@@ -11,6 +11,8 @@ export interface WaferStackStats {
     bin_counts: string;
     start_time: string | null;
     stop_time: string | null;
+    /** JSON `StageWaterfall`; null for wafers stacked before it was recorded */
+    stage_waterfall?: string | null;
 }
 
 export interface WaferStatsIngestResult {
@@ -33,8 +35,9 @@ export async function upsertWaferStackStats(stats: WaferStackStats): Promise<Waf
         const sql = `
       INSERT INTO wafer_stack_stats (
         oem_product_id, batch_id, wafer_id, total_tested, total_pass, 
-        total_fail, yield_percentage, bin_counts, start_time, stop_time
-      ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+        total_fail, yield_percentage, bin_counts, start_time, stop_time,
+        stage_waterfall
+      ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
       ON CONFLICT(oem_product_id, batch_id, wafer_id) DO UPDATE SET
         total_tested = excluded.total_tested,
         total_pass = excluded.total_pass,
@@ -42,7 +45,8 @@ export async function upsertWaferStackStats(stats: WaferStackStats): Promise<Waf
         yield_percentage = excluded.yield_percentage,
         bin_counts = excluded.bin_counts,
         start_time = excluded.start_time,
-        stop_time = excluded.stop_time
+        stop_time = excluded.stop_time,
+        stage_waterfall = excluded.stage_waterfall
     `;
 
         await db.execute(sql, [
@@ -55,7 +59,8 @@ export async function upsertWaferStackStats(stats: WaferStackStats): Promise<Waf
             stats.yield_percentage,
             stats.bin_counts,
             stats.start_time,
-            stats.stop_time
+            stats.stop_time,
+            stats.stage_waterfall ?? null
         ]);
 
         if (existing.length > 0) {
@@ -89,7 +94,8 @@ export async function getWaferStackStatsByOem(
         ...row,
         bin_counts: row.bin_counts || '{}',
         start_time: row.start_time || null,
-        stop_time: row.stop_time || null
+        stop_time: row.stop_time || null,
+        stage_waterfall: row.stage_waterfall || null
     }));
 }
 
@@ -1,5 +1,5 @@
 import { getDb, MAX_PARAMS, vacuum, withRetry, existingSet as getExistingSet } from '@/db';
-import { WaferMapRow } from './types'; // Ensure this includes "idx?: number"
+import { WaferMapChoiceRow, WaferMapRow } from './types'; // Ensure this includes "idx?: number"
 
 const TABLE = 'wafer_maps';
 
@@ -52,6 +52,42 @@ ORDER BY COALESCE(time, 0) DESC, idx DESC`,
     );
 }
 
+/** Get the chosen maps (wafer_map_choice) of a triple, one per stage / sub-stage. */
+export async function getWaferMapChoices(
+    product_id: string,
+    batch_id: string,
+    wafer_id: number
+): Promise<WaferMapChoiceRow[]> {
+    const db = await getDb();
+    return db.select<WaferMapChoiceRow[]>(`
+SELECT product_id, batch_id, wafer_id, stage, sub_stage, file_path
+FROM wafer_map_choice
+WHERE product_id = ? AND batch_id = ? AND wafer_id = ?`,
+        [product_id, batch_id, wafer_id]
+    );
+}
+
+/** Choose the map that stacks for a wafer / stage / sub-stage (replaces an earlier choice). */
+export async function saveWaferMapChoice(row: WaferMapChoiceRow): Promise<void> {
+    const db = await getDb();
+    await db.execute(`
+INSERT INTO wafer_map_choice (product_id, batch_id, wafer_id, stage, sub_stage, file_path)
+VALUES (?, ?, ?, ?, ?, ?)
+ON CONFLICT(product_id, batch_id, wafer_id, stage, sub_stage) DO UPDATE SET file_path = excluded.file_path`,
+        [row.product_id, row.batch_id, row.wafer_id, row.stage, row.sub_stage, row.file_path]
+    );
+}
+
+/** Drop a choice; the recommended map (highest retest, then newest) stacks again. */
+export async function deleteWaferMapChoice(key: Omit<WaferMapChoiceRow, 'file_path'>): Promise<void> {
+    const db = await getDb();
+    await db.execute(`
+DELETE FROM wafer_map_choice
+WHERE product_id = ? AND batch_id = ? AND wafer_id = ? AND stage = ? AND sub_stage = ?`,
+        [key.product_id, key.batch_id, key.wafer_id, key.stage, key.sub_stage]
+    );
+}
+
 /** Get the LATEST row (by time, then idx) for a triple. */
 export async function getLatestWaferMapByTriple(
     product_id: string,
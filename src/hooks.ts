@@ -1,5 +1,27 @@
+import { useEffect, useRef } from 'react';
 import { TypedUseSelectorHook, useDispatch, useSelector } from 'react-redux';
+import { onDataSourceChanged } from '@/api/tauri/fs';
+import type { WatchBatch } from '@/types/ipc';
 import type { RootState, AppDispatch } from './store';
 
 export const useAppDispatch: () => AppDispatch = useDispatch;
-export const useAppSelector: TypedUseSelectorHook<RootState> = useSelector;
\ No newline at end of file
+export const useAppSelector: TypedUseSelectorHook<RootState> = useSelector;
+
+/** Call `handler` for every batch the data-source watcher has processed. */
+export function useDataSourceChanged(handler: (batch: WatchBatch) => void) {
+    const handlerRef = useRef(handler);
+    handlerRef.current = handler;
+
+    useEffect(() => {
+        let cancelled = false;
+        let unlisten: (() => void) | undefined;
+        onDataSourceChanged((batch) => handlerRef.current(batch)).then((fn) => {
+            if (cancelled) fn();
+            else unlisten = fn;
+        });
+        return () => {
+            cancelled = true;
+            unlisten?.();
+        };
+    }, []);
+}
@@ -0,0 +1,214 @@
+import { useCallback, useEffect, useState } from 'react';
+import {
+    ActionIcon,
+    Button,
+    Group,
+    Loader,
+    Paper,
+    ScrollArea,
+    Select,
+    Stack,
+    Table,
+    Text,
+    Title,
+} from '@mantine/core';
+import { IconRefresh, IconTrash, IconUpload } from '@tabler/icons-react';
+import { open as openDialog } from '@tauri-apps/plugin-dialog';
+
+import { invokeParseBinMapJson, invokeParseBinMapXls } from '@/api/tauri/wafer';
+import { deleteProductBinMap, getAllProductBinMaps, saveProductBinMap } from '@/db/binMaps';
+import { getOemProductMap } from '@/db/offsets';
+import type { OemProductMapRow } from '@/db/types';
+import type { BinMapTable, BinMapTableResult } from '@/types/ipc';
+import { infoToast, errorToast } from '@/components/UI/Toaster';
+
+// 通用规则（不区分客户）
+const GENERIC_CUSTOMER = '__generic__';
+
+/** Customers named by the table's rules, for the customer picker */
+function ruleCustomers(table: BinMapTable): string[] {
+    const customers = new Set<string>();
+    table.rules.forEach((rule) => {
+        if (rule.customer) customers.add(rule.customer);
+    });
+    return Array.from(customers).sort();
+}
+
+/**
+ * Sheets / JSON keys are product IDs; a table is stored for every OEM product
+ * whose OEM or internal product ID matches.
+ */
+function matchProducts(tables: BinMapTableResult, products: OemProductMapRow[]) {
+    const matched: Array<{ oemProductId: string; table: BinMapTable }> = [];
+    const unmatched: string[] = [];
+    Object.entries(tables).forEach(([key, table]) => {
+        const owners = products.filter((p) => p.oem_product_id === key || p.product_id === key);
+        if (owners.length === 0) {
+            unmatched.push(key);
+            return;
+        }
+        owners.forEach((p) => matched.push({ oemProductId: p.oem_product_id, table }));
+    });
+    return { matched, unmatched };
+}
+
+export default function BinMaps() {
+    const [products, setProducts] = useState<OemProductMapRow[]>([]);
+    const [tables, setTables] = useState<Record<string, BinMapTable>>({});
+    const [loading, setLoading] = useState(false);
+
+    const reload = useCallback(async () => {
+        setLoading(true);
+        try {
+            const [productRows, stored] = await Promise.all([getOemProductMap(), getAllProductBinMaps()]);
+            setProducts(productRows);
+            setTables(stored);
+        } catch (e) {
+            errorToast({ title: '读取失败', message: `加载BIN映射表失败: ${String(e)}` });
+        } finally {
+            setLoading(false);
+        }
+    }, []);
+
+    useEffect(() => {
+        reload();
+    }, [reload]);
+
+    const handleImport = async () => {
+        try {
+            const selection = await openDialog({
+                title: '选择 BIN 映射表',
+                multiple: false,
+                filters: [{ name: 'BIN 映射表', extensions: ['xlsx', 'xls', 'json'] }],
+            });
+            if (!selection) return;
+            const filePath = Array.isArray(selection) ? selection[0] : selection;
+            if (!filePath) return;
+
+            const parsed = filePath.toLowerCase().endsWith('.json')
+                ? await invokeParseBinMapJson(filePath)
+                : await invokeParseBinMapXls(filePath);
+            const { matched, unmatched } = matchProducts(parsed, products);
+            for (const { oemProductId, table } of matched) {
+                // keep the customer chosen for the product before the re-import
+                const customer = tables[oemProductId]?.customer;
+                const keep = customer && ruleCustomers(table).includes(customer) ? customer : undefined;
+                await saveProductBinMap(oemProductId, { ...table, customer: keep });
+            }
+            await reload();
+
+            const message = unmatched.length > 0
+                ? `已导入 ${matched.length} 个产品；未找到产品: ${unmatched.join(', ')}`
+                : `已导入 ${matched.length} 个产品`;
+            (matched.length > 0 ? infoToast : errorToast)({ title: '导入完成', message });
+        } catch (e) {
+            errorToast({ title: '导入失败', message: String(e) });
+        }
+    };
+
+    const handleCustomer = async (oemProductId: string, customer: string | null) => {
+        const table = tables[oemProductId];
+        if (!table) return;
+        const next = { ...table, customer: customer && customer !== GENERIC_CUSTOMER ? customer : undefined };
+        try {
+            await saveProductBinMap(oemProductId, next);
+            setTables((prev) => ({ ...prev, [oemProductId]: next }));
+        } catch (e) {
+            errorToast({ title: '保存失败', message: String(e) });
+        }
+    };
+
+    const handleDelete = async (oemProductId: string) => {
+        try {
+            await deleteProductBinMap(oemProductId);
+            await reload();
+        } catch (e) {
+            errorToast({ title: '删除失败', message: String(e) });
+        }
+    };
+
+    const productIdOf = (oemProductId: string) =>
+        products.find((p) => p.oem_product_id === oemProductId)?.product_id ?? '';
+    const rows = Object.entries(tables);
+
+    return (
+        <Stack gap="md">
+            <Group justify="space-between" align="center">
+                <Stack gap={2}>
+                    <Title order={4}>BIN 映射表</Title>
+                    <Text size="xs" c="dimmed">
+                        叠图时按产品的映射表将各站点原始BIN转换为内部BIN，导出时再转换为客户BIN。
+                        工作表名 / JSON 键为 OEM 产品 ID 或产品 ID。
+                    </Text>
+                </Stack>
+                <Group gap="xs">
+                    <Button size="xs" variant="light" leftSection={<IconRefresh size={14} />} onClick={reload} loading={loading}>
+                        刷新
+                    </Button>
+                    <Button size="xs" leftSection={<IconUpload size={14} />} onClick={handleImport} disabled={loading}>
+                        导入 Excel / JSON
+                    </Button>
+                </Group>
+            </Group>
+
+            <Paper withBorder radius="md" p="xs">
+                <ScrollArea>
+                    <Table highlightOnHover striped withColumnBorders>
+                        <Table.Thead>
+                            <Table.Tr>
+                                <Table.Th>OEM 产品 ID</Table.Th>
+                                <Table.Th>产品 ID</Table.Th>
+                                <Table.Th>规则数</Table.Th>
+                                <Table.Th>客户</Table.Th>
+                                <Table.Th style={{ width: 60 }}>操作</Table.Th>
+                            </Table.Tr>
+                        </Table.Thead>
+                        <Table.Tbody>
+                            {loading && rows.length === 0 ? (
+                                <Table.Tr>
+                                    <Table.Td colSpan={5}>
+                                        <Group justify="center"><Loader size="sm" /></Group>
+                                    </Table.Td>
+                                </Table.Tr>
+                            ) : rows.length === 0 ? (
+                                <Table.Tr>
+                                    <Table.Td colSpan={5}>
+                                        <Text size="sm" c="dimmed" ta="center">暂无BIN映射表</Text>
+                                    </Table.Td>
+                                </Table.Tr>
+                            ) : rows.map(([oemProductId, table]) => (
+                                <Table.Tr key={oemProductId}>
+                                    <Table.Td>{oemProductId}</Table.Td>
+                                    <Table.Td>{productIdOf(oemProductId)}</Table.Td>
+                                    <Table.Td>{table.rules.length}</Table.Td>
+                                    <Table.Td>
+                                        <Select
+                                            size="xs"
+                                            data={[
+                                                { value: GENERIC_CUSTOMER, label: '通用' },
+                                                ...ruleCustomers(table).map((c) => ({ value: c, label: c })),
+                                            ]}
+                                            value={table.customer ?? GENERIC_CUSTOMER}
+                                            onChange={(value) => handleCustomer(oemProductId, value)}
+                                            allowDeselect={false}
+                                        />
+                                    </Table.Td>
+                                    <Table.Td>
+                                        <ActionIcon
+                                            variant="subtle"
+                                            color="red"
+                                            onClick={() => handleDelete(oemProductId)}
+                                            aria-label="删除"
+                                        >
+                                            <IconTrash size={16} />
+                                        </ActionIcon>
+                                    </Table.Td>
+                                </Table.Tr>
+                            ))}
+                        </Table.Tbody>
+                    </Table>
+                </ScrollArea>
+            </Paper>
+        </Stack>
+    );
+}
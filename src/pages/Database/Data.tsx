@@ -13,6 +13,8 @@ import JobManager from '@/components/JobManager';
 import LayersSelector from '@/components/Form/LayersSelector';
 import ComingSoon from '../ComingSoon';
 import WaferMapIndex from './WaferMapIndex';
+import WaferMapDuplicates from './WaferMapDuplicates';
+import WaferSearch from './WaferSearch';
 
 import { appDataDir, join, basename } from '@tauri-apps/api/path';
 import { readFile, writeFile } from '@tauri-apps/plugin-fs';
@@ -30,6 +32,8 @@ import { infoToast, errorToast } from '@/components/UI/Toaster';
 const subpageOptions = [
     { label: '预览', value: 'browse' },
     { label: '索引', value: 'search' },
+    { label: '搜索', value: 'query' },
+    { label: '重复', value: 'duplicates' },
     { label: '更多', value: 'more' }
 ];
 
@@ -61,6 +65,8 @@ export default function DatabaseIndexPage() {
                         <Route path="/" element={<Navigate to="browse" replace />} />
                         <Route path="browse" element={<BrowsePage />} />
                         <Route path="search" element={<WaferMapIndex />} />
+                        <Route path="query" element={<WaferSearch />} />
+                        <Route path="duplicates" element={<WaferMapDuplicates />} />
                         <Route path="more" element={<MorePage />} />
                         <Route path="*" element={<ComingSoon />} />
                     </Routes>
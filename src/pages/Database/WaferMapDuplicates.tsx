@@ -0,0 +1,224 @@
+import { useCallback, useEffect, useState } from 'react';
+import {
+    Badge,
+    Button,
+    Group,
+    Loader,
+    Paper,
+    ScrollArea,
+    Select,
+    Stack,
+    Table,
+    Text,
+    TextInput,
+    Title,
+} from '@mantine/core';
+import { IconRefresh } from '@tabler/icons-react';
+
+import { waferMapDuplicates } from '@/api/tauri/wafer';
+import { deleteWaferMapChoice, saveWaferMapChoice } from '@/db/wafermaps';
+import type { DuplicateReport, MapKey, StackChoice } from '@/types/ipc';
+import { errorToast } from '@/components/UI/Toaster';
+
+const keyLabel = (key: MapKey) =>
+    `${key.productId} / ${key.batchId} / ${key.waferId} / ${key.stage}${key.subStage ? `-${key.subStage}` : ''}`;
+
+const sameKey = (a: MapKey, b: MapKey) =>
+    a.productId === b.productId &&
+    a.batchId === b.batchId &&
+    a.waferId === b.waferId &&
+    a.stage === b.stage &&
+    (a.subStage ?? '') === (b.subStage ?? '');
+
+const fileName = (filePath: string) => filePath.split(/[\\/]/).pop() ?? filePath;
+
+/**
+ * Duplicate / retested wafer maps: which copy stacks when a wafer has several
+ * maps for a stage. Without a choice the recommended one (highest retest, then newest) stacks.
+ */
+export default function WaferMapDuplicates() {
+    const [productId, setProductId] = useState('');
+    const [report, setReport] = useState<DuplicateReport | null>(null);
+    const [loading, setLoading] = useState(false);
+
+    const reload = useCallback(async (product: string) => {
+        setLoading(true);
+        try {
+            setReport(await waferMapDuplicates(product.trim() || undefined));
+        } catch (e) {
+            errorToast({ title: '读取失败', message: `加载重复图谱失败: ${String(e)}` });
+        } finally {
+            setLoading(false);
+        }
+    }, []);
+
+    useEffect(() => {
+        reload('');
+    }, [reload]);
+
+    const handleChoice = async (choice: StackChoice, filePath: string | null) => {
+        if (!filePath) return;
+        const key = {
+            product_id: choice.key.productId,
+            batch_id: choice.key.batchId,
+            wafer_id: choice.key.waferId,
+            stage: choice.key.stage,
+            sub_stage: choice.key.subStage ?? '',
+        };
+        try {
+            // picking the recommended map drops the choice, so a later retest is recommended again
+            if (filePath === choice.recommended) {
+                await deleteWaferMapChoice(key);
+            } else {
+                await saveWaferMapChoice({ ...key, file_path: filePath });
+            }
+            const chosen = filePath === choice.recommended ? null : filePath;
+            setReport((prev) => prev && {
+                ...prev,
+                choices: prev.choices.map((c) => (c === choice ? { ...c, chosen } : c)),
+            });
+        } catch (e) {
+            errorToast({ title: '保存失败', message: String(e) });
+        }
+    };
+
+    const inConflict = (key: MapKey) => report?.conflicts.some((c) => sameKey(c.key, key)) ?? false;
+    const choices = report?.choices ?? [];
+    const duplicates = report?.duplicates ?? [];
+
+    return (
+        <Stack gap="md">
+            <Group justify="space-between" align="end">
+                <Stack gap={2}>
+                    <Title order={4}>重复图谱</Title>
+                    <Text size="xs" c="dimmed">
+                        同一晶圆同一工序有多份图谱（复测或拷贝）时，选择参与叠图的图谱；未选择时使用推荐（复测次数最多、时间最新）。
+                    </Text>
+                </Stack>
+                <Group gap="xs" align="end">
+                    <TextInput
+                        size="xs"
+                        label="产品 ID"
+                        placeholder="全部"
+                        value={productId}
+                        onChange={(e) => setProductId(e.currentTarget.value)}
+                    />
+                    <Button
+                        size="xs"
+                        variant="light"
+                        leftSection={<IconRefresh size={14} />}
+                        onClick={() => reload(productId)}
+                        loading={loading}
+                    >
+                        刷新
+                    </Button>
+                </Group>
+            </Group>
+
+            {report && (
+                <Group gap="xs">
+                    <Badge variant="light">图谱 {report.maps}</Badge>
+                    <Badge variant="light" color="gray">未计算哈希 {report.unhashed}</Badge>
+                    <Badge variant="light" color="orange">重复 {report.duplicates.length}</Badge>
+                    <Badge variant="light" color="blue">复测 {report.retestChains.length}</Badge>
+                    <Badge variant="light" color="red">冲突 {report.conflicts.length}</Badge>
+                </Group>
+            )}
+            {report && report.unhashed > 0 && (
+                <Text size="xs" c="dimmed">
+                    未计算哈希的图谱不参与重复检测；刷新数据源后会重新索引。
+                </Text>
+            )}
+
+            <Paper withBorder radius="md" p="xs">
+                <ScrollArea>
+                    <Table highlightOnHover striped withColumnBorders>
+                        <Table.Thead>
+                            <Table.Tr>
+                                <Table.Th>产品 / 批次 / 片号 / 工序</Table.Th>
+                                <Table.Th>图谱数</Table.Th>
+                                <Table.Th>叠图图谱</Table.Th>
+                                <Table.Th>状态</Table.Th>
+                            </Table.Tr>
+                        </Table.Thead>
+                        <Table.Tbody>
+                            {loading && !report ? (
+                                <Table.Tr>
+                                    <Table.Td colSpan={4}>
+                                        <Group justify="center"><Loader size="sm" /></Group>
+                                    </Table.Td>
+                                </Table.Tr>
+                            ) : choices.length === 0 ? (
+                                <Table.Tr>
+                                    <Table.Td colSpan={4}>
+                                        <Text size="sm" c="dimmed" ta="center">暂无多份图谱的晶圆</Text>
+                                    </Table.Td>
+                                </Table.Tr>
+                            ) : choices.map((choice) => (
+                                <Table.Tr key={keyLabel(choice.key)}>
+                                    <Table.Td>{keyLabel(choice.key)}</Table.Td>
+                                    <Table.Td>{choice.candidates}</Table.Td>
+                                    <Table.Td>
+                                        <Select
+                                            size="xs"
+                                            data={[...choice.files].reverse().map((f) => ({
+                                                value: f,
+                                                label: f === choice.recommended ? `${fileName(f)}（推荐）` : fileName(f),
+                                            }))}
+                                            value={choice.chosen ?? choice.recommended}
+                                            onChange={(value) => handleChoice(choice, value)}
+                                            allowDeselect={false}
+                                        />
+                                    </Table.Td>
+                                    <Table.Td>
+                                        <Group gap={4}>
+                                            {choice.chosen
+                                                ? <Badge size="sm" variant="light">已选择</Badge>
+                                                : <Badge size="sm" variant="light" color="gray">推荐</Badge>}
+                                            {inConflict(choice.key) && <Badge size="sm" color="red">冲突</Badge>}
+                                        </Group>
+                                    </Table.Td>
+                                </Table.Tr>
+                            ))}
+                        </Table.Tbody>
+                    </Table>
+                </ScrollArea>
+            </Paper>
+
+            {duplicates.length > 0 && (
+                <Paper withBorder radius="md" p="xs">
+                    <Text size="sm" fw={500} mb="xs">内容相同的文件</Text>
+                    <ScrollArea>
+                        <Table striped withColumnBorders>
+                            <Table.Thead>
+                                <Table.Tr>
+                                    <Table.Th>哈希</Table.Th>
+                                    <Table.Th>文件</Table.Th>
+                                </Table.Tr>
+                            </Table.Thead>
+                            <Table.Tbody>
+                                {duplicates.map((group) => (
+                                    <Table.Tr key={group.fileHash}>
+                                        <Table.Td>
+                                            <Group gap={4}>
+                                                <Text size="xs" ff="monospace">{group.fileHash.slice(0, 12)}</Text>
+                                                {group.keysDiffer && <Badge size="sm" color="orange">键不同</Badge>}
+                                            </Group>
+                                        </Table.Td>
+                                        <Table.Td>
+                                            <Stack gap={0}>
+                                                {group.files.map((f) => (
+                                                    <Text key={f.idx} size="xs">{f.file_path}</Text>
+                                                ))}
+                                            </Stack>
+                                        </Table.Td>
+                                    </Table.Tr>
+                                ))}
+                            </Table.Tbody>
+                        </Table>
+                    </ScrollArea>
+                </Paper>
+            )}
+        </Stack>
+    );
+}
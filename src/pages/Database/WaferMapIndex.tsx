@@ -33,6 +33,7 @@ import {
     upsertWaferMap,
 } from '@/db/wafermaps';
 import { infoToast, errorToast } from '@/components/UI/Toaster';
+import { useDataSourceChanged } from '@/hooks';
 
 type FormState = {
     idx?: number;
@@ -161,6 +162,12 @@ export default function WaferMapIndex() {
         void loadData();
     }, [loadData]);
 
+    // the watcher writes new / changed maps straight into wafer_maps
+    useDataSourceChanged(() => {
+        void loadData();
+        void loadStages();
+    });
+
     const selectableRows = useMemo(
         () => rows.filter((row): row is WaferMapRow & { idx: number } => row.idx != null),
         [rows]
@@ -0,0 +1,263 @@
+import { useCallback, useEffect, useState } from 'react';
+import {
+    Badge,
+    Button,
+    Group,
+    Loader,
+    NumberInput,
+    Pagination,
+    Paper,
+    ScrollArea,
+    Select,
+    Stack,
+    Table,
+    Text,
+    TextInput,
+    Title,
+    Tooltip,
+} from '@mantine/core';
+import { useDebouncedValue } from '@mantine/hooks';
+import { IconRefresh, IconSearch } from '@tabler/icons-react';
+
+import { searchReindex, searchWafers } from '@/api/tauri/search';
+import type { SearchSort, WaferSearchPage, WaferSearchQuery } from '@/types/ipc';
+import { infoToast, errorToast } from '@/components/UI/Toaster';
+
+const PAGE_SIZE = 50;
+
+const SORT_OPTIONS: { value: SearchSort; label: string }[] = [
+    { value: 'time', label: '测量时间' },
+    { value: 'yield', label: '良率' },
+    { value: 'product', label: '产品' },
+    { value: 'path', label: '路径' },
+];
+
+type Filters = {
+    text: string;
+    productId: string;
+    batchId: string;
+    stage: string;
+    device: string;
+    operator: string;
+    bins: string;
+    minYield: number | string;
+    maxYield: number | string;
+    from: string;               // yyyy-mm-dd, local
+    to: string;                 // yyyy-mm-dd, local, inclusive
+};
+
+const EMPTY_FILTERS: Filters = {
+    text: '',
+    productId: '',
+    batchId: '',
+    stage: '',
+    device: '',
+    operator: '',
+    bins: '',
+    minYield: '',
+    maxYield: '',
+    from: '',
+    to: '',
+};
+
+/** Local midnight of a `yyyy-mm-dd` date, `days` later; header times are local too */
+function dayStart(date: string, days = 0): number | undefined {
+    if (!date) return undefined;
+    const d = new Date(`${date}T00:00:00`);
+    d.setDate(d.getDate() + days);
+    return Number.isNaN(d.getTime()) ? undefined : d.getTime();
+}
+
+function toQuery(filters: Filters, sort: SearchSort, page: number): WaferSearchQuery {
+    const text = (value: string) => value.trim() || undefined;
+    const num = (value: number | string) => (typeof value === 'number' ? value : undefined);
+    const bins = filters.bins.split(/[\s,]+/).filter(Boolean);
+    return {
+        text: text(filters.text),
+        productId: text(filters.productId),
+        batchId: text(filters.batchId),
+        stage: text(filters.stage),
+        device: text(filters.device),
+        operator: text(filters.operator),
+        bins: bins.length > 0 ? bins : undefined,
+        minYield: num(filters.minYield),
+        maxYield: num(filters.maxYield),
+        from: dayStart(filters.from),
+        to: dayStart(filters.to, 1),
+        sort,
+        page,
+        pageSize: PAGE_SIZE,
+    };
+}
+
+function formatTime(value: number | null | undefined) {
+    if (!value) return '—';
+    const date = new Date(value);
+    return Number.isNaN(date.getTime()) ? String(value) : date.toLocaleString();
+}
+
+/** Wafer maps by header (device, operator, measurement time, yield, bins) via `rust_search_wafers` */
+export default function WaferSearch() {
+    const [filters, setFilters] = useState<Filters>(EMPTY_FILTERS);
+    const [sort, setSort] = useState<SearchSort>('time');
+    const [page, setPage] = useState(1);
+    const [result, setResult] = useState<WaferSearchPage | null>(null);
+    const [loading, setLoading] = useState(false);
+    const [reindexing, setReindexing] = useState(false);
+    const [debounced] = useDebouncedValue(filters, 300);
+
+    const search = useCallback(async () => {
+        setLoading(true);
+        try {
+            setResult(await searchWafers(toQuery(debounced, sort, page - 1)));
+        } catch (e) {
+            errorToast({ title: '搜索失败', message: String(e) });
+        } finally {
+            setLoading(false);
+        }
+    }, [debounced, sort, page]);
+
+    useEffect(() => {
+        search();
+    }, [search]);
+
+    const setFilter = <K extends keyof Filters>(key: K, value: Filters[K]) => {
+        setFilters((prev) => ({ ...prev, [key]: value }));
+        setPage(1);
+    };
+
+    const handleReindex = async () => {
+        setReindexing(true);
+        try {
+            const report = await searchReindex();
+            const failed = report.failed.length > 0 ? `，失败 ${report.failed.length}` : '';
+            (report.failed.length > 0 ? errorToast : infoToast)({
+                title: '索引完成',
+                message: `已解析 ${report.parsed} 个图谱表头${failed}`,
+            });
+            await search();
+        } catch (e) {
+            errorToast({ title: '索引失败', message: String(e) });
+        } finally {
+            setReindexing(false);
+        }
+    };
+
+    const hits = result?.hits ?? [];
+    const totalPages = result ? Math.max(1, Math.ceil(result.total / PAGE_SIZE)) : 1;
+
+    return (
+        <Stack gap="md">
+            <Group justify="space-between" align="center">
+                <Stack gap={2}>
+                    <Title order={4}>晶圆搜索</Title>
+                    <Text size="xs" c="dimmed">
+                        按图谱表头（设备、操作员、测量时间、良率、BIN）检索；尚未解析表头的图谱请先更新索引。
+                    </Text>
+                </Stack>
+                <Group gap="xs">
+                    <Button size="xs" variant="light" leftSection={<IconRefresh size={14} />} onClick={handleReindex} loading={reindexing}>
+                        更新表头索引
+                    </Button>
+                    <Button size="xs" variant="subtle" onClick={() => { setFilters(EMPTY_FILTERS); setPage(1); }}>
+                        清空条件
+                    </Button>
+                </Group>
+            </Group>
+
+            <Paper withBorder radius="md" p="sm">
+                <Stack gap="xs">
+                    <TextInput
+                        size="xs"
+                        leftSection={<IconSearch size={14} />}
+                        placeholder="关键字：产品、批次、工序、设备、操作员、BIN、路径"
+                        value={filters.text}
+                        onChange={(e) => setFilter('text', e.currentTarget.value)}
+                    />
+                    <Group gap="xs" grow>
+                        <TextInput size="xs" label="产品 ID" value={filters.productId} onChange={(e) => setFilter('productId', e.currentTarget.value)} />
+                        <TextInput size="xs" label="批次" value={filters.batchId} onChange={(e) => setFilter('batchId', e.currentTarget.value)} />
+                        <TextInput size="xs" label="工序" value={filters.stage} onChange={(e) => setFilter('stage', e.currentTarget.value)} />
+                        <TextInput size="xs" label="设备" value={filters.device} onChange={(e) => setFilter('device', e.currentTarget.value)} />
+                        <TextInput size="xs" label="操作员" value={filters.operator} onChange={(e) => setFilter('operator', e.currentTarget.value)} />
+                    </Group>
+                    <Group gap="xs" grow>
+                        <NumberInput size="xs" label="最低良率 (%)" min={0} max={100} value={filters.minYield} onChange={(v) => setFilter('minYield', v)} />
+                        <NumberInput size="xs" label="最高良率 (%)" min={0} max={100} value={filters.maxYield} onChange={(v) => setFilter('maxYield', v)} />
+                        <TextInput size="xs" type="date" label="起始日期" value={filters.from} onChange={(e) => setFilter('from', e.currentTarget.value)} />
+                        <TextInput size="xs" type="date" label="截止日期" value={filters.to} onChange={(e) => setFilter('to', e.currentTarget.value)} />
+                        <TextInput size="xs" label="包含 BIN" placeholder="如 3 7" value={filters.bins} onChange={(e) => setFilter('bins', e.currentTarget.value)} />
+                        <Select
+                            size="xs"
+                            label="排序"
+                            data={SORT_OPTIONS}
+                            value={sort}
+                            onChange={(value) => { setSort((value as SearchSort) ?? 'time'); setPage(1); }}
+                            allowDeselect={false}
+                        />
+                    </Group>
+                </Stack>
+            </Paper>
+
+            <Paper withBorder radius="md" p="xs">
+                <Group justify="space-between" mb="xs">
+                    <Text size="sm" c="dimmed">共 {result?.total ?? 0} 条</Text>
+                    {loading && <Loader size="xs" />}
+                </Group>
+                <ScrollArea>
+                    <Table highlightOnHover striped withColumnBorders>
+                        <Table.Thead>
+                            <Table.Tr>
+                                <Table.Th>产品</Table.Th>
+                                <Table.Th>批次</Table.Th>
+                                <Table.Th>片号</Table.Th>
+                                <Table.Th>工序</Table.Th>
+                                <Table.Th>复测</Table.Th>
+                                <Table.Th>设备</Table.Th>
+                                <Table.Th>操作员</Table.Th>
+                                <Table.Th>测量时间</Table.Th>
+                                <Table.Th>良率</Table.Th>
+                                <Table.Th>文件</Table.Th>
+                            </Table.Tr>
+                        </Table.Thead>
+                        <Table.Tbody>
+                            {hits.length === 0 ? (
+                                <Table.Tr>
+                                    <Table.Td colSpan={10}>
+                                        <Text size="sm" c="dimmed" ta="center">{loading ? '搜索中…' : '无匹配的晶圆图谱'}</Text>
+                                    </Table.Td>
+                                </Table.Tr>
+                            ) : hits.map(({ map, meta }) => (
+                                <Table.Tr key={map.idx ?? map.file_path}>
+                                    <Table.Td>{map.product_id}</Table.Td>
+                                    <Table.Td>{map.batch_id}</Table.Td>
+                                    <Table.Td>{map.wafer_id}</Table.Td>
+                                    <Table.Td>{map.sub_stage ? `${map.stage}-${map.sub_stage}` : map.stage}</Table.Td>
+                                    <Table.Td>{map.retest_count}</Table.Td>
+                                    <Table.Td>{meta?.device ?? '—'}</Table.Td>
+                                    <Table.Td>{meta?.operator ?? '—'}</Table.Td>
+                                    <Table.Td>{formatTime(meta?.meas_time ?? map.time)}</Table.Td>
+                                    <Table.Td>
+                                        {meta?.yield_percentage != null
+                                            ? `${meta.yield_percentage.toFixed(2)}%`
+                                            : <Badge size="sm" variant="light" color="gray">未解析</Badge>}
+                                    </Table.Td>
+                                    <Table.Td>
+                                        <Tooltip label={map.file_path} openDelay={300}>
+                                            <Text size="xs" truncate maw={280}>{map.file_path}</Text>
+                                        </Tooltip>
+                                    </Table.Td>
+                                </Table.Tr>
+                            ))}
+                        </Table.Tbody>
+                    </Table>
+                </ScrollArea>
+                {totalPages > 1 && (
+                    <Group justify="center" mt="sm">
+                        <Pagination size="sm" total={totalPages} value={page} onChange={setPage} />
+                    </Group>
+                )}
+            </Paper>
+        </Stack>
+    );
+}
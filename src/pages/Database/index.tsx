@@ -18,11 +18,13 @@ import { useMemo } from 'react';
 import Cache from './Cache';
 import Data from './Data';
 import OffsetsAndSizes from './OffsetsAndSizes';
+import BinMaps from './BinMaps';
 import ComingSoon from '../ComingSoon';
 
 const subpageOptions = [
     { label: '晶圆数据', value: 'data' },
     { label: '参数', value: 'offsets' },
+    { label: 'BIN映射', value: 'binmaps' },
     { label: '缓存', value: 'cache' },
 ];
 
@@ -58,6 +60,7 @@ export default function DatabaseIndexPage() {
                         <Route path="/" element={<Navigate to="data" replace />} />
                         <Route path="data/*" element={<Data />} />
                         <Route path="offsets" element={<OffsetsAndSizes />} />
+                        <Route path="binmaps" element={<BinMaps />} />
                         <Route path="cache" element={<Cache />} />
                         <Route path="*" element={<ComingSoon />} />
                     </Routes>
@@ -1,8 +1,8 @@
 import { desktopDir } from '@tauri-apps/api/path';
-import { useEffect, useState } from 'react';
+import { useEffect, useRef, useState } from 'react';
 import { useAppSelector, useAppDispatch } from '@/hooks';
 import { IconDownload, IconRefresh, IconRepeat } from '@tabler/icons-react';
-import { Title, Group, Container, Stack, Button, Text, SimpleGrid, Divider, Input, Checkbox, Radio, Progress, Badge, Card, Box } from '@mantine/core';
+import { Title, Group, Container, Stack, Button, Text, SimpleGrid, Divider, Input, Checkbox, Radio, Progress, Badge, Card, Box, NumberInput } from '@mantine/core';
 import { PathPicker } from '@/components';
 import { ExcelMetadataCard, WaferFileMetadataCard } from '@/components/Card/MetadataCard';
 import JobManager from '@/components/JobManager';
@@ -16,11 +16,27 @@ import {
 } from '@/db/binSelection';
 // DB
 import { getOemOffset } from '@/db/offsets';
+import {
+    jobsCancel,
+    jobsEnqueue,
+    jobsList,
+    jobsRetry,
+    jobsTasks,
+} from '@/api/tauri/jobs';
 import { exportWaferStatsReport } from '@/utils/exportWaferReport';
 import { colorMap } from '@/components/Substrate/constants';
 
 // TYPES
 import { ExcelType } from '@/types/wafer';
+import type {
+    InkRules,
+    LotReportWafer,
+    StackingJobEvent,
+    StackingJobSummary,
+    StackingTaskStatus,
+    StoredWaferStackingJobOptions,
+    WaferStackingOutputId,
+} from '@/types/ipc';
 import { DataSourceType } from '@/types/dataSource';
 import { toWaferFileMetadata } from '@/types/helpers';
 
@@ -33,10 +49,15 @@ import {
     type BinConfigFile,
 } from '@/pages/Config/binConfig';
 import { buildBatchCompletionSummary, type BatchProcessingError } from './batchResults';
-import {
-    processWaferStackingJob,
-    type WaferStackingOutputId,
-} from './jobProcessor';
+import { isJobActive, waitForStackingJobs } from './jobRunner';
+
+/** What the runner leaves in a stacked wafer's task (`jobs::stacking::stack_wafer`) */
+interface StackingTaskResult {
+    outputRootDir: string;
+    mergedDieCount: number;
+    files: string[];
+    reportWafer: LotReportWafer;
+}
 
 export type OutputId = WaferStackingOutputId;
 export type BinId = 'Unclassified' | 'Particle' | 'Pit' | 'Bump' | 'MicroPipe' | 'Line' | 'Carrot' | 'Triangle' | 'Downfall' | 'Scratch' | 'PL_Black' | 'PL_White' | 'PL_BPD' | 'PL_SF' | 'PL_BSF';
@@ -80,6 +101,7 @@ const OUTPUT_OPTIONS = [
     { id: 'bin', label: 'BinMap' },
     { id: 'HEX', label: 'HexMap' },
     { id: 'image', label: 'Image' },
+    { id: 'svg', label: 'SVG' },
     { id: 'fab', label: 'fab' },
     { id: 'SILAN', label: 'SILAN' },
 ] as const satisfies readonly OutputOption[];
@@ -104,6 +126,28 @@ const OUTPUT_OPTIONS2 = [
 
 const EDGE_REMOVAL_STORAGE_KEY = 'wafer_edge_removal_enabled';
 const EDGE_REMOVAL_FAIL_BINS_STORAGE_KEY = 'wafer_edge_removal_fail_bins';
+const INK_RULES_STORAGE_KEY = 'wafer_ink_rules';
+
+// Good/fail bins are filled in from the bin selection when the outputs are written
+type InkRuleSettings = Required<Pick<InkRules, 'edgeExclusionEnabled' | 'edgeExclusionMm' | 'neighborInkEnabled' | 'failThreshold'>>;
+
+const DEFAULT_INK_RULES: InkRuleSettings = {
+    edgeExclusionEnabled: false,
+    edgeExclusionMm: 3,
+    neighborInkEnabled: true,
+    failThreshold: 2,
+};
+
+const loadStoredInkRules = (): InkRuleSettings => {
+    const saved = localStorage.getItem(INK_RULES_STORAGE_KEY);
+    if (!saved) return DEFAULT_INK_RULES;
+    try {
+        return { ...DEFAULT_INK_RULES, ...(JSON.parse(saved) as Partial<InkRuleSettings>) };
+    } catch (e) {
+        console.error('加载 ink rules 失败', e);
+        return DEFAULT_INK_RULES;
+    }
+};
 
 const DEFAULT_BIN_CONFIG: BinConfigFile = {
     binMappingRule: { startNumber: 10, startLetter: 'A' },
@@ -112,6 +156,17 @@ const DEFAULT_BIN_CONFIG: BinConfigFile = {
 
 const asDefectClass = (binId: BinId): string => binId as string;
 
+// Wafers are stacked by the backend's job runner; the page only queues them
+const STACKING_MAX_ATTEMPTS = 2;
+
+const TASK_STATUS: Record<StackingTaskStatus, JobStatus> = {
+    queued: 'queued',
+    leased: 'active',
+    done: 'done',
+    failed: 'error',
+    cancelled: 'error',
+};
+
 const statusStyles: Record<JobStatus, { color: string; label: string }> = {
     queued: { color: 'blue', label: '等待中' },
     active: { color: 'orange', label: '处理中' },
@@ -128,6 +183,8 @@ export default function WaferStacking() {
     const [batchProcessing, setBatchProcessing] = useState(false);
     const [batchProgress, setBatchProgress] = useState({ current: 0, total: 0 });
     const [batchErrors, setBatchErrors] = useState<BatchProcessingError[]>([]);
+    const [batchJobIds, setBatchJobIds] = useState<string[]>([]);
+    const [unfinishedJobs, setUnfinishedJobs] = useState<StackingJobSummary[]>([]);
     const [edgeRemovalEnabled, setEdgeRemovalEnabled] = useState(() => {
         return localStorage.getItem(EDGE_REMOVAL_STORAGE_KEY) === 'true';
     });
@@ -137,6 +194,7 @@ export default function WaferStacking() {
     );
     const [edgeRemovalFailBins, setEdgeRemovalFailBins] = useState<string[]>([]);
     const [edgeRemovalBinsLoaded, setEdgeRemovalBinsLoaded] = useState(false);
+    const [inkRules, setInkRules] = useState<InkRuleSettings>(loadStoredInkRules);
 
     const renderBinLabel = (opt: OutputOption2) => {
         const color = colorMap.get(opt.id) ?? 0x999999;
@@ -184,6 +242,8 @@ export default function WaferStacking() {
     ]);
 
     const [outputDir, setOutputDir] = useState<string>('');
+    // queue item ids of the enqueued jobs' wafers, by task seq
+    const queuedItems = useRef(new Map<string, string[]>());
 
     const dispatch = useAppDispatch();
     const jobState = useAppSelector((s) => s.stackingJob);
@@ -275,6 +335,10 @@ export default function WaferStacking() {
         localStorage.setItem(EDGE_REMOVAL_STORAGE_KEY, String(edgeRemovalEnabled));
     }, [edgeRemovalEnabled]);
 
+    useEffect(() => {
+        localStorage.setItem(INK_RULES_STORAGE_KEY, JSON.stringify(inkRules));
+    }, [inkRules]);
+
     useEffect(() => {
         if (!edgeRemovalBinsLoaded) return;
         localStorage.setItem(EDGE_REMOVAL_FAIL_BINS_STORAGE_KEY, JSON.stringify(edgeRemovalFailBins));
@@ -330,43 +394,31 @@ export default function WaferStacking() {
         return () => window.removeEventListener('binConfigChanged', handleConfigChange as EventListener);
     }, []);
 
-    const processSingleJob = async (jobItem: JobItem, useEdgeRemoval: boolean = false) => {
-        dispatch(queueUpdateJob({
-            id: jobItem.id,
-            changes: { status: 'active' }
-        }));
+    // Jobs still being stacked in the background, e.g. queued before the window was reopened
+    useEffect(() => {
+        jobsList()
+            .then((jobs) => setUnfinishedJobs(jobs.filter(isJobActive)))
+            .catch((e) => console.warn('读取未完成任务失败:', e));
+    }, []);
 
-        try {
-            await processWaferStackingJob(jobItem, {
-                outputDir,
-                finalOutputDir,
-                dieLayoutPath,
-                selectedOutputs,
-                selectedDefectClasses: selectedOutputs2.map(asDefectClass),
-                imageRenderer,
-                edgeRemovalEnabled: useEdgeRemoval,
-                goodBins,
-                edgeRemovalFailBins,
-                onFinalOutputDir: setFinalOutputDir,
-            });
+    const stackingOptions = (useEdgeRemoval: boolean): StoredWaferStackingJobOptions => ({
+        outputDir,
+        finalOutputDir,
+        dieLayoutPath,
+        selectedOutputs,
+        selectedDefectClasses: selectedOutputs2.map(asDefectClass),
+        imageRenderer,
+        edgeRemovalEnabled: useEdgeRemoval,
+        goodBins,
+        edgeRemovalFailBins,
+        inkRules,
+    });
 
-            dispatch(queueUpdateJob({
-                id: jobItem.id,
-                changes: { status: 'done' }
-            }));
-            return { success: true, jobId: jobItem.id };
-        } catch (error) {
-            dispatch(queueUpdateJob({
-                id: jobItem.id,
-                changes: { status: 'error' }
-            }));
-            const errorMsg = error instanceof Error ? error.message : String(error);
-            return {
-                success: false,
-                jobId: jobItem.id,
-                message: errorMsg
-            };
-        }
+    // Queue item statuses follow the tasks of the jobs they were enqueued with
+    const updateQueuedItem = ({ task }: StackingJobEvent) => {
+        const itemId = task && queuedItems.current.get(task.jobId)?.[task.seq];
+        if (!itemId) return;
+        dispatch(queueUpdateJob({ id: itemId, changes: { status: TASK_STATUS[task.status] } }));
     };
 
     const processMapping = async () => {
@@ -375,6 +427,7 @@ export default function WaferStacking() {
             return;
         }
         setProcessing(true);
+        const reportWafers: LotReportWafer[] = [];
         try {
             const tempJob: JobItem = {
                 id: 'current',
@@ -389,11 +442,22 @@ export default function WaferStacking() {
                 waferMaps: layerChoice.maps,
             };
 
-            const result = await processSingleJob(tempJob, edgeRemovalEnabled);
-            if (result.success) {
+            const label = `${jobBatchId} #${jobWaferId ?? ''}`;
+            const job = await jobsEnqueue<JobItem, StoredWaferStackingJobOptions>({
+                name: label,
+                options: stackingOptions(edgeRemovalEnabled),
+                wafers: [{ label, wafer: tempJob }],
+                maxAttempts: 1,
+            });
+            await waitForStackingJobs([job.jobId]);
+            const [task] = await jobsTasks(job.jobId);
+            const result = task?.status === 'done' ? task.result as StackingTaskResult | null : null;
+            if (result) {
+                reportWafers.push(result.reportWafer);
+                if (outputDir) setFinalOutputDir(result.outputRootDir);
                 infoToast({ title: '成功', message: '当前任务处理完成' });
             } else {
-                errorToast({ title: '处理失败', message: result.message });
+                errorToast({ title: '处理失败', message: task?.error ?? '已取消' });
             }
         } catch (error) {
             errorToast({ title: '处理失败', message: error instanceof Error ? error.message : String(error) });
@@ -402,55 +466,60 @@ export default function WaferStacking() {
         }
         try {
             const oemIds = getAllOemIdsFromQueue();
-            await exportWaferStatsReport(oemIds, outputDir);
+            await exportWaferStatsReport(oemIds, outputDir, { wafers: reportWafers, passBins: goodBins });
         } catch (e) {
             console.warn('导出统计报告失败:', e);
             errorToast({ title: '导出失败', message: '统计报告生成失败：' + String(e) });
         }
     };
 
-    const handleBatchProcess = async () => {
-        const jobsToProcess = [...queue];
-        if (jobsToProcess.length === 0) {
-            errorToast({
-                title: '无任务可处理',
-                message: '任务队列为空'
-            });
-            return;
-        }
+    // Follows the jobs until the runner is done with them, then exports the lot report
+    const runBatchJobs = async (jobIds: string[]) => {
         setBatchProcessing(true);
-        setBatchProgress({ current: 0, total: jobsToProcess.length });
+        setBatchJobIds(jobIds);
         setBatchErrors([]);
-        const nextBatchErrors: BatchProcessingError[] = [];
+        setBatchProgress({ current: 0, total: 0 });
 
-        for (let i = 0; i < jobsToProcess.length; i++) {
-            const jobItem = jobsToProcess[i];
-            try {
-                const result = await processSingleJob(jobItem, edgeRemovalEnabled);
-                if (!result.success) {
-                    const error = {
-                        id: result.jobId,
-                        message: result.message || ''
-                    };
-                    nextBatchErrors.push(error);
-                    setBatchErrors(prev => [...prev, error]);
-                }
-            } catch (error) {
-                const errorMsg = error instanceof Error ? error.message : String(error);
-                const batchError = {
-                    id: jobItem.id,
-                    message: errorMsg
-                };
-                nextBatchErrors.push(batchError);
-                setBatchErrors(prev => [...prev, batchError]);
-            } finally {
-                setBatchProgress(prev => ({ ...prev, current: i + 1 }));
-            }
+        const jobs = new Map<string, StackingJobSummary>();
+        try {
+            await waitForStackingJobs(jobIds, (event) => {
+                jobs.set(event.job.jobId, event.job);
+                updateQueuedItem(event);
+                const summaries = Array.from(jobs.values());
+                setBatchProgress({
+                    current: summaries.reduce((n, { counts }) => n + counts.done + counts.failed + counts.cancelled, 0),
+                    total: summaries.reduce((n, job) => n + job.total, 0),
+                });
+            });
+        } catch (error) {
+            errorToast({ title: '处理失败', message: error instanceof Error ? error.message : String(error) });
+        }
+
+        let total = 0;
+        const nextBatchErrors: BatchProcessingError[] = [];
+        // from the tasks, so wafers stacked before a restart are reported too
+        const reportWafers: LotReportWafer[] = [];
+        try {
+            const tasks = (await Promise.all(jobIds.map(jobsTasks))).flat();
+            total = tasks.length;
+            tasks.forEach((task) => {
+                const result = task.status === 'done' ? task.result as StackingTaskResult | null : null;
+                if (result?.reportWafer) reportWafers.push(result.reportWafer);
+                if (result && outputDir) setFinalOutputDir(result.outputRootDir);
+            });
+            tasks
+                .filter((task) => task.status === 'failed' || task.status === 'cancelled')
+                .forEach((task) => nextBatchErrors.push({
+                    id: task.label || `${task.jobId}#${task.seq}`,
+                    message: task.error ?? '已取消',
+                }));
+        } catch (e) {
+            console.warn('读取任务结果失败:', e);
         }
 
         setBatchErrors(nextBatchErrors);
         setBatchProcessing(false);
-        const summary = buildBatchCompletionSummary(jobsToProcess.length, nextBatchErrors);
+        const summary = buildBatchCompletionSummary(total, nextBatchErrors);
         const toast = summary.ok ? infoToast : errorToast;
         toast({
             title: summary.title,
@@ -458,12 +527,62 @@ export default function WaferStacking() {
         });
         try {
             const oemIds = getAllOemIdsFromQueue();
-            await exportWaferStatsReport(oemIds, outputDir);
+            await exportWaferStatsReport(oemIds, outputDir, { wafers: reportWafers, passBins: goodBins });
         } catch (e) {
             console.warn('导出统计报告失败:', e);
             errorToast({ title: '导出失败', message: '统计报告生成失败：' + String(e) });
         }
     };
+
+    const handleBatchProcess = async () => {
+        const jobsToProcess = [...queue];
+        if (jobsToProcess.length === 0) {
+            errorToast({
+                title: '无任务可处理',
+                message: '任务队列为空'
+            });
+            return;
+        }
+        try {
+            const job = await jobsEnqueue<JobItem, StoredWaferStackingJobOptions>({
+                name: Array.from(new Set(jobsToProcess.map((j) => j.batchId))).join(', '),
+                options: stackingOptions(edgeRemovalEnabled),
+                wafers: jobsToProcess.map((jobItem) => ({
+                    label: `${jobItem.batchId} #${jobItem.waferId ?? ''}`,
+                    wafer: jobItem,
+                })),
+                maxAttempts: STACKING_MAX_ATTEMPTS,
+            });
+            queuedItems.current.set(job.jobId, jobsToProcess.map((jobItem) => jobItem.id));
+            await runBatchJobs([job.jobId]);
+        } catch (error) {
+            errorToast({ title: '处理失败', message: error instanceof Error ? error.message : String(error) });
+        }
+    };
+
+    const handleBatchCancel = async () => {
+        try {
+            await Promise.all(batchJobIds.map(jobsCancel));
+        } catch (error) {
+            errorToast({ title: '取消失败', message: error instanceof Error ? error.message : String(error) });
+        }
+    };
+
+    const handleBatchRetry = async () => {
+        try {
+            await Promise.all(batchJobIds.map(jobsRetry));
+            await runBatchJobs(batchJobIds);
+        } catch (error) {
+            errorToast({ title: '重试失败', message: error instanceof Error ? error.message : String(error) });
+        }
+    };
+
+    const handleFollowJobs = async () => {
+        const jobIds = unfinishedJobs.map((job) => job.jobId);
+        setUnfinishedJobs([]);
+        await runBatchJobs(jobIds);
+    };
+
     return (
         <Container fluid p="md">
             <Stack gap="md">
@@ -564,17 +683,37 @@ export default function WaferStacking() {
                         {batchProcessing && (
                             <Stack gap="sm">
                                 <Progress
-                                    value={(batchProgress.current / batchProgress.total) * 100}
+                                    value={batchProgress.total ? (batchProgress.current / batchProgress.total) * 100 : 0}
                                 />
-                                <Text size="sm">
-                                    正在处理第 {batchProgress.current} 个任务
-                                </Text>
+                                <Group justify="space-between">
+                                    <Text size="sm">
+                                        已完成 {batchProgress.current} / {batchProgress.total} 个晶圆
+                                    </Text>
+                                    <Button size="xs" variant="light" color="red" onClick={handleBatchCancel}>
+                                        取消
+                                    </Button>
+                                </Group>
                             </Stack>
                         )}
                         {!batchProcessing && batchErrors.length > 0 && (
-                            <Text size="sm">
-                                有 {batchErrors.length} 个任务处理失败
-                            </Text>
+                            <Group justify="space-between">
+                                <Text size="sm">
+                                    有 {batchErrors.length} 个任务处理失败
+                                </Text>
+                                <Button size="xs" variant="light" onClick={handleBatchRetry}>
+                                    重试失败
+                                </Button>
+                            </Group>
+                        )}
+                        {!batchProcessing && unfinishedJobs.length > 0 && (
+                            <Group justify="space-between">
+                                <Text size="sm">
+                                    有 {unfinishedJobs.length} 个批量任务正在后台处理
+                                </Text>
+                                <Button size="xs" variant="light" color="green" onClick={handleFollowJobs}>
+                                    查看进度
+                                </Button>
+                            </Group>
                         )}
                         {!batchProcessing && queue.length > 0 && (
                             <Group gap="sm" wrap="wrap">
@@ -695,6 +834,43 @@ export default function WaferStacking() {
                                 </Stack>
                             )}
                         </Stack>
+                        <Group align="end" gap="md">
+                            <Checkbox
+                                checked={inkRules.edgeExclusionEnabled}
+                                onChange={(e) => setInkRules({ ...inkRules, edgeExclusionEnabled: e.target.checked })}
+                                label="晶圆边缘排除"
+                                disabled={!edgeRemovalEnabled || processing || batchProcessing}
+                                size="sm"
+                            />
+                            <NumberInput
+                                label="边缘宽度 (mm)"
+                                value={inkRules.edgeExclusionMm}
+                                onChange={(v) => setInkRules({ ...inkRules, edgeExclusionMm: Number(v) || 0 })}
+                                min={0}
+                                step={0.5}
+                                decimalScale={3}
+                                disabled={!edgeRemovalEnabled || !inkRules.edgeExclusionEnabled || processing || batchProcessing}
+                                size="xs"
+                                w={120}
+                            />
+                            <Checkbox
+                                checked={inkRules.neighborInkEnabled}
+                                onChange={(e) => setInkRules({ ...inkRules, neighborInkEnabled: e.target.checked })}
+                                label="邻近失效Ink"
+                                disabled={!edgeRemovalEnabled || processing || batchProcessing}
+                                size="sm"
+                            />
+                            <NumberInput
+                                label="相邻失效DIE数 ≥"
+                                value={inkRules.failThreshold}
+                                onChange={(v) => setInkRules({ ...inkRules, failThreshold: Math.max(1, Math.round(Number(v) || 1)) })}
+                                min={1}
+                                max={8}
+                                disabled={!edgeRemovalEnabled || !inkRules.neighborInkEnabled || processing || batchProcessing}
+                                size="xs"
+                                w={120}
+                            />
+                        </Group>
                         <Group align="end" grow>
                             <Checkbox
                                 checked={edgeRemovalEnabled}
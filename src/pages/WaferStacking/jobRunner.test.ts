@@ -0,0 +1,79 @@
+import { describe, expect, it, vi } from 'vitest';
+
+import type { StackingJobCounts, StackingJobEvent, StackingJobSummary } from '@/types/ipc';
+
+import { isJobActive, waitForStackingJobs, type StackingEventsApi } from './jobRunner';
+
+function summary(jobId: string, counts: Partial<StackingJobCounts>, cancelled = false): StackingJobSummary {
+    const full = { queued: 0, leased: 0, done: 0, failed: 0, cancelled: 0, ...counts };
+    return {
+        jobId,
+        name: jobId,
+        status: 'running',
+        total: Object.values(full).reduce((a, b) => a + b, 0),
+        counts: full,
+        progress: 0,
+        maxAttempts: 1,
+        cancelled,
+        createdAt: 0,
+        updatedAt: 0,
+    };
+}
+
+// In-memory stand-in for the runner's events
+function createEvents(jobs: StackingJobSummary[]) {
+    let handler: ((event: StackingJobEvent) => void) | null = null;
+    const unlisten = vi.fn(() => { handler = null; });
+    const api: StackingEventsApi = {
+        list: async () => jobs,
+        subscribe: async (h) => {
+            handler = h;
+            return unlisten;
+        },
+    };
+    return {
+        api,
+        unlisten,
+        emit: (job: StackingJobSummary) => handler?.({ job }),
+    };
+}
+
+const tick = () => new Promise((resolve) => setTimeout(resolve, 0));
+
+describe('isJobActive', () => {
+    it('keeps a cancelled job active until its stacking wafers stop', () => {
+        expect(isJobActive(summary('a', { queued: 2 }))).toBe(true);
+        expect(isJobActive(summary('a', { queued: 2 }, true))).toBe(false);
+        expect(isJobActive(summary('a', { leased: 1, cancelled: 1 }, true))).toBe(true);
+        expect(isJobActive(summary('a', { done: 1, failed: 1 }))).toBe(false);
+    });
+});
+
+describe('waitForStackingJobs', () => {
+    it('resolves once every followed job is finished', async () => {
+        const events = createEvents([summary('a', { queued: 1 }), summary('b', { leased: 1 })]);
+        const seen: string[] = [];
+        let done = false;
+        const wait = waitForStackingJobs(['a', 'b'], (e) => seen.push(e.job.jobId), events.api)
+            .then(() => { done = true; });
+
+        await tick();
+        events.emit(summary('a', { done: 1 }));
+        await tick();
+        expect(done).toBe(false);
+
+        events.emit(summary('other', { done: 1 }));
+        events.emit(summary('b', { failed: 1 }));
+        await wait;
+        expect(seen).toEqual(['a', 'b', 'a', 'b']);
+        expect(events.unlisten).toHaveBeenCalledOnce();
+    });
+
+    it('does not wait for finished or removed jobs', async () => {
+        const events = createEvents([summary('a', { done: 3 })]);
+        const onEvent = vi.fn();
+        await waitForStackingJobs(['a', 'gone'], onEvent, events.api);
+        expect(onEvent).toHaveBeenCalledOnce();
+        expect(events.unlisten).toHaveBeenCalledOnce();
+    });
+});
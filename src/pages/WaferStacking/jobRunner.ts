@@ -0,0 +1,64 @@
+import { jobsList, onStackingJobChanged } from '@/api/tauri/jobs';
+import type { StackingJobEvent, StackingJobSummary } from '@/types/ipc';
+
+export interface StackingEventsApi {
+    list: () => Promise<StackingJobSummary[]>;
+    subscribe: (handler: (event: StackingJobEvent) => void) => Promise<() => void>;
+}
+
+export const defaultStackingEventsApi: StackingEventsApi = {
+    list: jobsList,
+    subscribe: onStackingJobChanged,
+};
+
+/** The job still has wafers queued or being stacked */
+export function isJobActive(job: StackingJobSummary): boolean {
+    return job.counts.leased > 0 || (!job.cancelled && job.counts.queued > 0);
+}
+
+/**
+ * Follows jobs stacked by the app's runner until none of them has a wafer
+ * queued or being stacked. `onEvent` gets the current state of each job
+ * first, then every change of them.
+ */
+export async function waitForStackingJobs(
+    jobIds: string[],
+    onEvent?: (event: StackingJobEvent) => void,
+    api: StackingEventsApi = defaultStackingEventsApi
+): Promise<void> {
+    const pending = new Set(jobIds);
+    if (pending.size === 0) return;
+
+    let finish!: () => void;
+    const finished = new Promise<void>((resolve) => {
+        finish = resolve;
+    });
+    const settle = (job: StackingJobSummary) => {
+        if (!isJobActive(job)) pending.delete(job.jobId);
+        if (pending.size === 0) finish();
+    };
+
+    // subscribe before listing so no change is missed in between
+    const unlisten = await api.subscribe((event) => {
+        if (!pending.has(event.job.jobId)) return;
+        onEvent?.(event);
+        settle(event.job);
+    });
+    try {
+        const jobs = await api.list();
+        const listed = new Set(jobs.map((job) => job.jobId));
+        for (const jobId of jobIds) {
+            // removed meanwhile: nothing left to wait for
+            if (!listed.has(jobId)) pending.delete(jobId);
+        }
+        if (pending.size === 0) finish();
+        for (const job of jobs) {
+            if (!pending.has(job.jobId)) continue;
+            onEvent?.({ job });
+            settle(job);
+        }
+        await finished;
+    } finally {
+        unlisten();
+    }
+}
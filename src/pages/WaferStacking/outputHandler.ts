@@ -1,155 +0,0 @@
-import { join } from '@tauri-apps/api/path';
-import { mkdir } from '@tauri-apps/plugin-fs';
-import {
-    exportWaferHex,
-    exportWaferMapData,
-    exportWaferBin,
-    exportWaferJpg,
-    exportWaferSilan,
-    exportFab,
-} from '@/api/tauri/wafer';
-import { AsciiDie } from '@/types/ipc';
-import { renderAsJpg, renderSubstrateAsJpg } from './renderUtils';
-import { processInkRules } from './inkRuleProcessor';
-import {
-    convertToMapData,
-    convertToBinMapData,
-    convertToHexMapData,
-    calculateStatsFromDies,
-    convertToSilanMapData,
-    convertToFabWafer,
-} from '@/utils/waferSubstrateRenderer';
-import { createPassValueSet } from '@/pages/Config/binConfig';
-
-export interface WaferOutputConfig {
-    baseFileName: string;
-    outputRootDir: string;
-    mergedDies: AsciiDie[];
-    stats: ReturnType<typeof calculateStatsFromDies>;
-    useHeader: Record<string, string>;
-    selectedOutputs: ('mapEx' | 'bin' | 'HEX' | 'image' | 'fab' | 'SILAN')[];
-    imageRenderer: 'bin' | 'substrate';
-    allSubstrateDefects: Array<{ x: number; y: number; w: number; h: number; class: string }>;
-    currentDieSize: { x: number; y: number };
-    currentSubstrateOffset: { x: number; y: number };
-    selectedPassBins: string[];
-    edgeRemovalEnabled: boolean;
-    edgeRemovalFailBins: string[];
-}
-
-export const exportNormalWaferFiles = async (config: WaferOutputConfig) => {
-    const {
-        baseFileName,
-        outputRootDir,
-        mergedDies,
-        stats,
-        useHeader,
-        selectedOutputs,
-        imageRenderer,
-        allSubstrateDefects,
-        currentDieSize,
-        currentSubstrateOffset,
-    } = config;
-    const passValues = createPassValueSet(config.selectedPassBins);
-
-    if (selectedOutputs.includes('mapEx')) {
-        const mapExData = convertToMapData(mergedDies, stats, useHeader);
-        const mapExPath = await join(outputRootDir, `${baseFileName}_overlayed.txt`);
-        await exportWaferMapData(mapExData, mapExPath);
-    }
-
-    if (selectedOutputs.includes('HEX')) {
-        const hexData = convertToHexMapData(mergedDies, useHeader);
-        const hexPath = await join(outputRootDir, `${baseFileName}_overlayed.sinf`);
-        await exportWaferHex(hexData, hexPath);
-    }
-
-    if (selectedOutputs.includes('bin')) {
-        const binData = convertToBinMapData(mergedDies, useHeader);
-        const binPath = await join(outputRootDir, `${baseFileName}_overlayed.WaferMap`);
-        await exportWaferBin(binData, binPath);
-    }
-
-    if (selectedOutputs.includes('image')) {
-        const imagePath = await join(outputRootDir, `${baseFileName}_overlayed.jpg`);
-        const imageData = imageRenderer === 'substrate'
-            ? await renderSubstrateAsJpg(mergedDies, allSubstrateDefects, currentDieSize.x, currentDieSize.y, currentSubstrateOffset, useHeader, passValues)
-            : await renderAsJpg(mergedDies, allSubstrateDefects, currentDieSize.x, currentDieSize.y, currentSubstrateOffset, useHeader, passValues);
-        await exportWaferJpg(imageData, imagePath);
-    }
-
-    if (selectedOutputs.includes('SILAN')) {
-        const silanData = convertToSilanMapData(mergedDies, stats, useHeader);
-        const silanPath = await join(outputRootDir, `${baseFileName}_SILAN.txt`);
-        await exportWaferSilan(silanData, silanPath);
-    }
-
-    if (selectedOutputs.includes('fab')) {
-        const fabData = convertToFabWafer(mergedDies, stats, useHeader);
-        const fabPath = await join(outputRootDir, `${baseFileName}_FAB.txt`);
-        await exportFab(fabData, fabPath);
-    }
-};
-
-
-export const exportInkWaferFiles = async (config: WaferOutputConfig) => {
-    const {
-        baseFileName,
-        outputRootDir,
-        mergedDies,
-        useHeader,
-        selectedOutputs,
-        imageRenderer,
-        allSubstrateDefects,
-        currentDieSize,
-        currentSubstrateOffset,
-    } = config;
-
-    const mapExSubDir = await join(outputRootDir, 'Ink');
-    await mkdir(mapExSubDir, { recursive: true });
-
-    const passValues = createPassValueSet(config.selectedPassBins);
-    const failValues = createPassValueSet(config.edgeRemovalFailBins);
-    const { processedDies } = processInkRules(mergedDies, {
-        goodValues: passValues,
-        failValues,
-    });
-    const inkStats = calculateStatsFromDies(processedDies, passValues);
-    console.log('Ink Stats:', inkStats);
-    if (selectedOutputs.includes('mapEx')) {
-        const mapExData = convertToMapData(processedDies, inkStats, useHeader);
-        const mapExPath = await join(mapExSubDir, `${baseFileName}_overlayed.txt`);
-        await exportWaferMapData(mapExData, mapExPath);
-    }
-
-    if (selectedOutputs.includes('HEX')) {
-        const hexData = convertToHexMapData(processedDies, useHeader);
-        const hexPath = await join(mapExSubDir, `${baseFileName}_overlayed.sinf`);
-        await exportWaferHex(hexData, hexPath);
-    }
-
-    if (selectedOutputs.includes('bin')) {
-        const binData = convertToBinMapData(processedDies, useHeader);
-        const binPath = await join(mapExSubDir, `${baseFileName}_overlayed.WaferMap`);
-        await exportWaferBin(binData, binPath);
-    }
-
-    if (selectedOutputs.includes('image')) {
-        const imagePath = await join(mapExSubDir, `${baseFileName}_overlayed.jpg`);
-        const imageData = imageRenderer === 'substrate'
-            ? await renderSubstrateAsJpg(processedDies, allSubstrateDefects, currentDieSize.x, currentDieSize.y, currentSubstrateOffset, useHeader, passValues)
-            : await renderAsJpg(processedDies, allSubstrateDefects, currentDieSize.x, currentDieSize.y, currentSubstrateOffset, useHeader, passValues);
-        await exportWaferJpg(imageData, imagePath);
-    }
-};
-
-
-export const exportWaferFiles = async (config: WaferOutputConfig) => {
-    // 输出普通文件
-    await exportNormalWaferFiles(config);
-
-    // 输出INK文件
-    if (config.edgeRemovalEnabled) {
-        await exportInkWaferFiles(config);
-    }
-};
@@ -20,8 +20,9 @@ import { advanceStepper, setStepper } from './preferencesSlice';
 import { addFolder } from './dataSourceStateSlice';
 
 // UTILS
-import { arraysAreEqual, getSubfolders, sortBySubfolderName, norm } from '@/utils/fs';
-import { autoRecognizeFoldersByType } from '@/utils/dataSource';
+import { arraysAreEqual, sortBySubfolderName, norm } from '@/utils/fs';
+import { foldersByType, scanRegex } from '@/utils/dataSource';
+import { invokeScanDataSources } from '@/api/tauri/fs';
 import { isDataSourcePathsValid, isValidDataSourceConfig } from '@/utils/validators';
 import { mergeDefinedKeys } from '@/utils/helper';
 
@@ -160,9 +161,10 @@ export const scanDataSourceFolders = createAsyncThunk<
             const start = performance.now();
 
             if (!rootPath || rootPath === '') throw Error('请先设置根目录！');
-            const { folders: subfolders, totDir: totFolders, numRead, numCached } = await getSubfolders(rootPath, false);
-            if (totFolders === 0) throw new Error('未识别到任何符合的子文件夹。请检查正则表达式和文件夹结构。');
-            const folders = await autoRecognizeFoldersByType(subfolders, regex);
+            // direct subfolders only, matched by name
+            const report = await invokeScanDataSources(rootPath, { regex: scanRegex(regex), maxDepth: 1 });
+            if (report.folders.length === 0) throw new Error('未识别到任何符合的子文件夹。请检查正则表达式和文件夹结构。');
+            const folders = foldersByType(report);
 
             let totMatch = 0;
             let totAdded = 0;
@@ -191,7 +193,7 @@ export const scanDataSourceFolders = createAsyncThunk<
             await dispatch(revalidateDataSource());
 
             dirScanResultToast(
-                { totDirs: totFolders, numRead, numCached, totMatch, totAdded },
+                { totDirs: report.dirsVisited, numRead: report.dirsVisited, numCached: 0, totMatch, totAdded },
                 duration,
                 '子目录识别'
             );
@@ -2,6 +2,21 @@
 // NOTE: Tauri IPC
 ////////////////////////////////////////////////////////////////////////////////
 
+import type { WaferStackStats } from '@/db/waferStackStats';
+import type { ProductBinSelection } from '@/db/binSelection';
+import type { ProductBinMap } from '@/db/binMaps';
+import type {
+    FileIndexRow,
+    FolderIndexRow,
+    OemProductMapRow,
+    OemProductOffset,
+    ProductDefectMapRow,
+    ProductSize,
+    SubstrateDefectRow,
+    WaferMapMetaRow,
+    WaferMapRow,
+} from '@/db/types';
+
 // Mirror of Rust's src-tauri/src/file/file_io.rs::FileInfo (camelCase, epoch ms)
 export interface FileInfo {
     isFile: boolean;
@@ -259,6 +274,686 @@ export interface HexMapData {
     map: HexMap;
 }
 
+// NOTE: Bin translation tables
+
+/** Rust: enum BinFormat (serde camelCase) */
+export type BinFormat = 'ascii' | 'waferMap' | 'hex' | 'silan';
+
+/** One translation row: native code in a file <-> internal bin */
+export interface BinMapRule {
+    format: BinFormat;
+    native: string;           // normalized, e.g. HEX "0A", WaferMap "257"
+    internal: BinValue;
+    customer?: string;        // only applies to this customer when set
+    description?: string;
+}
+
+/** Per-product table; `customer` selects customer-specific rules */
+export interface BinMapTable {
+    productId: string;
+    customer?: string;
+    rules: BinMapRule[];
+}
+
+export type BinMapTableResult = Record<string, BinMapTable>;
+
+/** Physical wafer/die size in mm (`WaferGeometry` in Rust) */
+export interface WaferGeometry {
+    diameterMm: number;
+    diePitchXMm: number;
+    diePitchYMm: number;
+}
+
+/** Grid index ↔ mm mapping for a product (`DieCoordinateSystem` in Rust) */
+export interface DieCoordinateSystem {
+    dieWidthMm: number;         // product_size.die_x
+    dieHeightMm: number;        // product_size.die_y
+    referenceX?: number;        // reference die grid index (HEX REFPX/REFPY), default 0
+    referenceY?: number;
+    offsetXMm?: number;         // product_offsets.x_offset
+    offsetYMm?: number;         // product_offsets.y_offset
+    defectOffsetXUm?: number;   // product_offsets.defect_offset_x
+    defectOffsetYUm?: number;   // product_offsets.defect_offset_y
+}
+
+/** Substrate defect: x/y in mm, w/h in µm */
+export interface DefectRect {
+    x: number;
+    y: number;
+    w: number;
+    h: number;
+}
+
+/** Rectangle in mm (`top < bottom`) */
+export interface DieRect {
+    left: number;
+    top: number;
+    right: number;
+    bottom: number;
+}
+
+export interface PlacedDie {
+    x: number;
+    y: number;
+    rect: DieRect;
+    defective: boolean;         // overlapped by a defect
+}
+
+/** `rust_place_substrate_dies`; defects keep the input order, grown by the defect offsets */
+export interface SubstrateLayout {
+    dies: PlacedDie[];
+    defects: DieRect[];
+}
+
+/** Edge exclusion + neighbour inking rules; omitted fields use Rust defaults */
+export interface InkRules {
+    edgeExclusionEnabled?: boolean;
+    edgeExclusionMm?: number;   // default 3
+    edgeMarker?: string;        // default 'z'
+    neighborInkEnabled?: boolean;
+    failThreshold?: number;     // default 2
+    inkMarker?: string;         // default 'z'
+    goodBins?: string[];        // "BIN 1", ...
+    failBins?: string[];        // omitted = every non-good die fails
+}
+
+export interface InkOutcome {
+    processedDies: AsciiDie[];
+    filteredDies: AsciiDie[];
+    edgeCount: number;
+    inkCount: number;
+}
+
+export type ImageFormat = 'png' | 'jpeg';
+
+/** Wafer map image options; omitted fields use Rust defaults */
+export interface WaferRenderOptions {
+    format?: ImageFormat;       // default: from the output extension
+    dpi?: number;               // 1–2400, default 150
+    jpegQuality?: number;       // default 95
+    style?: 'bin' | 'substrate';
+    gridLines?: boolean;
+    showOutline?: boolean;
+    showNotch?: boolean;
+    showHeader?: boolean;
+    showLegend?: boolean;
+    passBins?: string[];        // "BIN 1", ...
+    notch?: string;             // overrides header Flat/Notch
+    timestamp?: string;         // default: now
+}
+
+/** One aligned input layer of a stacked map, in merge order */
+export interface MapLayer {
+    name: string;
+    dies: AsciiDie[];
+}
+
+/** A wafer of the PDF lot report: its stats row plus the stacking result, when available */
+export interface LotReportWafer {
+    stats: WaferStackStats;
+    dies?: AsciiDie[];          // stacked map
+    header?: Record<string, string>;
+    layers?: MapLayer[];        // per-stage yield table
+    coords?: DieCoordinateSystem;
+}
+
+export interface LotReport {
+    oemProductId: string;
+    batchId: string;
+    wafers: LotReportWafer[];
+    passBins?: string[];        // "BIN 1", ...
+    render?: WaferRenderOptions;
+    generatedAt?: string;       // default: now
+}
+
+/** A stacked wafer for the lot composite */
+export interface CompositeWafer {
+    waferId: string;
+    dies: AsciiDie[];
+}
+
+export interface CompositeOptions {
+    passBins?: string[];
+    systematicThreshold?: number;   // fail %, default 50
+    minWafers?: number;             // default 3
+}
+
+export interface PositionStat {
+    x: number;
+    y: number;
+    tested: number;
+    fail: number;
+    failPercent: number;
+    systematic: boolean;
+    failingWafers: string[];
+}
+
+export interface CompositeMap {
+    waferCount: number;
+    systematicThreshold: number;
+    positions: PositionStat[];
+    systematicCount: number;
+}
+
+export type SignatureKind = 'edgeRing' | 'centerCluster' | 'cluster' | 'scratch' | 'reticle';
+
+/** Spatial signature rules; omitted fields use Rust defaults */
+export interface SignatureOptions {
+    passBins?: string[];
+    centerRadius?: number;      // fraction of the radius, default 0.35
+    edgeRadius?: number;        // default 0.8
+    minClusterSize?: number;    // default 5
+    minLineLength?: number;     // default 5
+    lineElongation?: number;    // default 4
+    reticleCols?: number;       // shot size in dies
+    reticleRows?: number;
+    reticleOffsetX?: number;
+    reticleOffsetY?: number;
+    minScore?: number;          // default 0.3
+}
+
+export interface Signature {
+    kind: SignatureKind;
+    score: number;              // 0..1
+    detail: string;
+    dies: [number, number][];
+}
+
+export interface SignatureReport {
+    tested: number;
+    fail: number;
+    centerFailPercent: number;
+    middleFailPercent: number;
+    edgeFailPercent: number;
+    signatures: Signature[];
+}
+
+/** Radial/sector split; omitted fields use Rust defaults */
+export interface ZoneOptions {
+    passBins?: string[];
+    ringEdges?: number[];       // radius fractions in (0, 1), ascending, at most 32, default [0.5, 0.8]
+    sectors?: number;           // 1..=360, default 4 (quadrants)
+    sectorOffsetDeg?: number;   // counter-clockwise from +x, default 0
+}
+
+/** `calculateStatsFromDies` totals plus tested-die counts per bin */
+export interface MapSummary {
+    totalTested: number;
+    totalPass: number;
+    totalFail: number;
+    yieldPercentage: number;
+    bins: { bin: string; count: number }[];
+}
+
+export interface ZoneStat extends MapSummary {
+    label: string;              // e.g. "R 50-80%", "Q2 90-180°"
+    ring: number | null;
+    sector: number | null;
+}
+
+export interface ZoneReport {
+    total: MapSummary;
+    rings: ZoneStat[];
+    sectors: ZoneStat[];
+    cells: ZoneStat[];          // ring-major
+}
+
+export type Stage = 'substrate' | 'fabCp' | 'cp1' | 'wlbi' | 'cp2' | 'aoi';
+
+export interface ParetoEntry {
+    bin: string;
+    count: number;
+    percent: number;            // share of failing dies
+    cumulativePercent: number;
+}
+
+export interface StageYield {
+    stage: Stage;
+    layer: string;              // layer name as stacked
+    tested: number;             // the stage's own map
+    pass: number;
+    fail: number;
+    yieldPercentage: number;
+    lost: number;               // positions first failed at this stage
+    cumulativePass: number;
+    cumulativeYield: number;    // good after this stage, over all positions
+    incrementalLoss: number;    // percentage points
+    pareto: ParetoEntry[];
+}
+
+/** Stored as JSON in `wafer_stack_stats.stage_waterfall` */
+export interface StageWaterfall {
+    positions: number;
+    stages: StageYield[];       // substrate → FAB CP → CP1 → WLBI → CP2 → AOI
+    finalYield: number;
+    skipped: string[];          // layers without a known stage
+}
+
+export interface CorrelationOptions {
+    passBins?: string[];
+}
+
+/** Pass/fail of a (first word) against b (second word) */
+export interface PassFailMatrix {
+    passPass: number;
+    passFail: number;
+    failPass: number;
+    failFail: number;
+}
+
+export interface TransitionMatrix {
+    fromBins: string[];         // bins of a (rows)
+    toBins: string[];           // bins of b (columns)
+    counts: number[][];
+}
+
+export interface FlippedDie {
+    x: number;
+    y: number;
+    from: string;
+    to: string;
+    flip: 'recovered' | 'escaped';
+}
+
+/** Die-by-die comparison; `a` is the reference stage */
+export interface MapComparison {
+    a: string;
+    b: string;
+    compared: number;           // tested in both
+    onlyA: number;
+    onlyB: number;
+    confusion: PassFailMatrix;
+    agreement: number;          // percent
+    kappa: number;
+    binAgreement: number;       // percent
+    binKappa: number;
+    recoveryRate: number;       // percent of a's fails passing in b
+    escapeRate: number;         // percent of a's passes failing in b
+    transitions: TransitionMatrix;
+    flipped: FlippedDie[];
+}
+
+/** SPC filters; omitted fields use Rust defaults */
+export interface SpcOptions {
+    maxLots?: number;           // most recent lots only
+    since?: string;             // start_time format, e.g. "2025/03/01 00:00"
+    outlierZ?: number;          // modified z-score, default 3.5
+}
+
+export interface LotSubgroup {
+    batchId: string;
+    time: string | null;        // earliest start_time of the lot
+    wafers: number;
+    meanYield: number;
+    yieldRange: number;
+    tested: number;
+    fail: number;
+    failPercent: number;
+}
+
+export interface ChartPoint {
+    value: number;
+    ucl: number | null;         // null when the lot is too small for limits
+    lcl: number | null;
+    outOfControl: boolean;
+}
+
+export interface ControlChart {
+    center: number;
+    points: ChartPoint[];       // one per lot
+}
+
+export interface RuleViolation {
+    rule: 1 | 2 | 3 | 4;        // Western Electric
+    index: number;              // into lots
+    batchId: string;
+    description: string;
+}
+
+export interface OutlierWafer {
+    batchId: string;
+    waferId: string;
+    yieldPercentage: number;
+    z: number;
+}
+
+export interface SpcReport {
+    oemProductId: string;
+    lots: LotSubgroup[];
+    xbar: ControlChart;         // mean wafer yield, %
+    range: ControlChart;        // wafer yield range, %
+    p: ControlChart;            // fail fraction, %
+    violations: RuleViolation[];
+    outliers: OutlierWafer[];
+}
+
+/** Wafer and die dimensions in mm; omitted fields use Rust defaults */
+export interface GrossDieSpec {
+    diameterMm: number;
+    dieWidthMm: number;
+    dieHeightMm: number;
+    scribeMm?: number;          // pitch = die + scribe, default 0
+    edgeExclusionMm?: number;   // default 3
+    flat?: 'none' | 'notch' | 'flat'; // default notch
+    flatSide?: 'up' | 'down' | 'left' | 'right'; // default down
+    flatLengthMm?: number;      // default: SEMI primary flat for the diameter
+    notchDepthMm?: number;      // default 1
+}
+
+export interface LayoutCheck {
+    layoutDies: number;         // product dies, markers excluded
+    matched: number;
+    missing: [number, number][]; // in theory, absent from the layout
+    extra: [number, number][];   // in the layout, outside the usable area
+}
+
+export interface GrossDieResult {
+    pitchXMm: number;
+    pitchYMm: number;
+    usableRadiusMm: number;
+    grossDie: number;
+    positions: [number, number][];
+    coords: DieCoordinateSystem; // placement used, pitch as die size
+    layout: LayoutCheck | null;
+}
+
+// =============================================================================
+// Duplicate wafer maps
+
+/** wafer_maps row joined with file_index.file_hash */
+export interface WaferMapFile {
+    idx: number;
+    product_id: string;
+    batch_id: string;
+    wafer_id: number;
+    stage: string;
+    sub_stage: string | null;
+    retest_count: number;
+    time: number | null;
+    file_path: string;
+    file_hash: string | null;
+}
+
+export interface MapKey {
+    productId: string;
+    batchId: string;
+    waferId: number;
+    stage: string;
+    subStage: string | null;    // null when the map has no sub-stage
+}
+
+export interface DuplicateGroup {
+    fileHash: string;
+    files: WaferMapFile[];
+    keysDiffer: boolean;        // same bytes under different product/lot/wafer
+}
+
+export interface RetestChain {
+    key: MapKey;
+    files: WaferMapFile[];      // retest 0 first
+    retests: number[];
+}
+
+export interface MapConflict {
+    key: MapKey;
+    retestCount: number;
+    files: WaferMapFile[];      // one per distinct content
+}
+
+export interface StackChoice {
+    key: MapKey;
+    candidates: number;
+    files: string[];            // candidate file_paths, least preferred first
+    recommended: string;        // file_path: highest retest, then newest
+    chosen: string | null;      // wafer_map_choice, while still a candidate
+}
+
+export interface DuplicateReport {
+    maps: number;
+    unhashed: number;
+    duplicates: DuplicateGroup[];
+    retestChains: RetestChain[];
+    conflicts: MapConflict[];
+    choices: StackChoice[];
+}
+
+// =============================================================================
+// Native database layer (`rust_db_*`)
+
+/** One table's rows for `rust_db_upsert`; written in a single transaction */
+export type TableRows =
+    | { table: 'oem_product_map'; rows: OemProductMapRow[] }
+    | { table: 'product_offsets'; rows: OemProductOffset[] }
+    | { table: 'product_size'; rows: ProductSize[] }
+    | { table: 'product_bin_selection'; rows: ProductBinSelection[] }
+    | { table: 'product_bin_map'; rows: ProductBinMap[] }
+    | { table: 'product_defect_map'; rows: ProductDefectMapRow[] }
+    | { table: 'substrate_defect'; rows: SubstrateDefectRow[] }
+    | { table: 'wafer_maps'; rows: WaferMapRow[] }
+    | { table: 'wafer_map_meta'; rows: WaferMapMetaRow[] }
+    | { table: 'file_index'; rows: FileIndexRow[] }
+    | { table: 'folder_index'; rows: FolderIndexRow[] }
+    | { table: 'wafer_stack_stats'; rows: WaferStackStats[] };
+
+export interface ProductContext {
+    oemProductId: string;
+    mapping: OemProductMapRow | null;
+    offset: OemProductOffset | null;
+    size: ProductSize | null;
+    binSelection: ProductBinSelection | null;
+    binMap: ProductBinMap | null;
+}
+
+/** Unset fields match everything */
+export interface WaferMapFilter {
+    productId?: string;
+    batchId?: string;
+    waferId?: number;
+    stage?: string;
+}
+
+export interface AppliedMigration {
+    version: number;
+    description: string;
+    installedOn: string;
+}
+
+/** `_sqlx_migrations` versus the versions this build ships */
+export interface SchemaStatus {
+    current: number;            // 0 for an empty database
+    latest: number;
+    applied: AppliedMigration[];
+    pending: number[];
+    unknown: number[];          // applied by a newer build
+    modified: number[];         // applied script differs from this build's
+    failed: number[];           // half-applied
+}
+
+// =============================================================================
+// Backup and configuration bundles
+
+export interface BackupInfo {
+    path: string;
+    bytes: number;
+    elapsedMs: number;
+}
+
+/** Tables in a configuration bundle, parents first */
+export type ConfigTable =
+    | 'oem_product_map'
+    | 'product_offsets'
+    | 'product_size'
+    | 'product_bin_selection'
+    | 'product_bin_map'
+    | 'wafer_stack_stats';
+
+export interface BundleManifest {
+    format: string;             // 'aoi-wafer-stacking/config'
+    version: number;
+    schemaVersion: number;      // of the exporting database
+    appVersion: string;
+    createdAt: string;          // RFC 3339
+    tables: Partial<Record<ConfigTable, number>>;   // row counts
+}
+
+export interface ExportOptions {
+    tables?: ConfigTable[];     // all when unset
+}
+
+export type ConflictPolicy = 'keepExisting' | 'overwrite' | 'abort';
+
+export interface ImportOptions {
+    conflict?: ConflictPolicy;  // default keepExisting
+    tables?: ConfigTable[];
+    dryRun?: boolean;
+}
+
+export interface ImportConflict {
+    key: Record<string, unknown>;
+    existing: Record<string, unknown>;
+    incoming: Record<string, unknown>;
+}
+
+export interface TableImport {
+    table: ConfigTable;
+    incoming: number;
+    added: number;
+    unchanged: number;
+    overwritten: number;
+    kept: number;
+    conflicts: ImportConflict[];
+}
+
+export interface ImportReport {
+    manifest: BundleManifest;
+    tables: TableImport[];
+    applied: boolean;           // false for a dry run or an aborted import
+    warnings: string[];
+}
+
+// =============================================================================
+// Wafer search (`rust_search_*`)
+
+export type SearchSort = 'time' | 'yield' | 'product' | 'path';
+
+/** Unset fields match everything; all set fields must match */
+export interface WaferSearchQuery {
+    text?: string;              // prefix words over product, lot, stage, device, operator, bins, path
+    productId?: string;         // internal or OEM id
+    batchId?: string;
+    waferId?: number;
+    stage?: string;
+    subStage?: string;
+    retestCount?: number;
+    device?: string;            // substring, case-insensitive
+    operator?: string;
+    minYield?: number;          // percent, inclusive
+    maxYield?: number;
+    from?: number;              // epoch ms, inclusive
+    to?: number;                // epoch ms, exclusive
+    bins?: string[];            // all must be present
+    sort?: SearchSort;          // default 'time'
+    ascending?: boolean;        // default newest / highest first
+    page?: number;              // 0-based
+    pageSize?: number;          // default 50, max 500
+}
+
+export interface WaferSearchHit {
+    map: WaferMapRow;
+    meta: WaferMapMetaRow | null;   // null until the header is parsed
+}
+
+export interface WaferSearchPage {
+    total: number;
+    page: number;
+    pageSize: number;
+    hits: WaferSearchHit[];
+}
+
+export interface ReindexOptions {
+    productId?: string;
+    force?: boolean;            // reparse maps that already have a header
+}
+
+export interface ReindexReport {
+    parsed: number;
+    skipped: number;            // stages without a header (substrate)
+    failed: { filePath: string; error: string }[];
+    elapsedMs: number;
+}
+
+// =============================================================================
+// Stacking job queue (`rust_jobs_*`)
+
+export type StackingTaskStatus = 'queued' | 'leased' | 'done' | 'failed' | 'cancelled';
+export type StackingJobStatus = 'queued' | 'running' | 'done' | 'failed' | 'cancelled';
+
+/** `wafer` and `options` are stored as JSON and read by the runner (`jobs::stacking`) */
+export interface NewStackingJob<W = unknown, O = unknown> {
+    name?: string;
+    options?: O;
+    wafers: { label?: string; wafer: W }[];
+    maxAttempts?: number;       // tries per wafer, default 1
+}
+
+export interface StackingJobCounts {
+    queued: number;
+    leased: number;
+    done: number;
+    failed: number;
+    cancelled: number;
+}
+
+export interface StackingJobSummary {
+    jobId: string;
+    name: string;
+    status: StackingJobStatus;
+    total: number;
+    counts: StackingJobCounts;
+    progress: number;           // 0..1, including leased wafers' progress
+    maxAttempts: number;
+    cancelled: boolean;
+    createdAt: number;          // epoch ms
+    updatedAt: number;
+}
+
+export interface StackingJobTask {
+    jobId: string;
+    seq: number;
+    label: string;
+    status: StackingTaskStatus;
+    attempts: number;           // failed attempts so far
+    worker: string | null;
+    progress: number;
+    message: string | null;
+    error: string | null;
+    result: unknown;
+    startedAt: number | null;
+    finishedAt: number | null;
+}
+
+export type WaferStackingOutputId = 'mapEx' | 'bin' | 'HEX' | 'image' | 'svg' | 'fab' | 'SILAN';
+
+/** `options` of a stacking job (`jobs::stacking::StackingOptions`) */
+export interface StoredWaferStackingJobOptions {
+    outputDir: string;
+    finalOutputDir: string;         // used when `outputDir` is empty
+    dieLayoutPath: string;
+    selectedOutputs: WaferStackingOutputId[];
+    selectedDefectClasses: string[];    // empty stacks every class
+    imageRenderer: 'bin' | 'substrate';
+    edgeRemovalEnabled: boolean;
+    goodBins: string[];
+    edgeRemovalFailBins: string[];
+    inkRules?: InkRules;
+}
+
+/** Payload of the `stacking-job-changed` event */
+export interface StackingJobEvent {
+    job: StackingJobSummary;
+    task?: StackingJobTask;
+}
+
 // =============================================================================
 // NOTE: TAURI INTERFACES
 // =============================================================================
@@ -289,6 +984,122 @@ export interface DirResult {
     info?: FileInfo
 }
 
+// =============================================================================
+// Data-source scanner
+
+export type ScanSourceType = 'substrate' | 'fabCp' | 'cpProber' | 'wlbi' | 'aoi';
+
+export interface ScanSourceFolder {
+    stage: ScanSourceType;
+    path: string;
+}
+
+export interface ScanOptions {
+    regex?: Partial<Record<ScanSourceType, string>>; // folder-name regex, empty disables
+    maxDepth?: number;          // levels searched for source folders, default 3
+    folders?: ScanSourceFolder[]; // read these instead of recognising folders under the root
+}
+
+export interface ScannedFile {
+    stage: ScanSourceType;
+    kind: 'waferMap' | 'defectList' | 'productDefectMapping' | 'productMapping';
+    productModel?: string;      // FAB CP: OEM model, map via oem_product_map
+    batch?: string;
+    waferId?: string;
+    processSubStage?: number;
+    retestCount?: number;
+    id?: string;                // defect list number
+    oem?: string;               // substrate product sheet
+    time?: string;              // ISO, from the file name
+    filePath: string;
+    lastModified: number;       // epoch ms
+}
+
+export interface ScanReport {
+    root: string;
+    folders: ScanSourceFolder[];
+    files: ScannedFile[];
+    misaligned: string[];       // names disagree with their folders
+    unreadable: string[];
+    dirsVisited: number;
+    filesVisited: number;
+    elapsedMs: number;
+}
+
+// Live ingestion
+
+export interface WatchOptions extends ScanOptions {
+    debounceMs?: number;        // default 2000
+}
+
+export interface WatchChange {
+    path: string;
+    kind: 'added' | 'modified' | 'moved' | 'removed' | 'failed';
+    from?: string;              // moved: previous path
+    file?: ScannedFile;         // absent for removed files
+    error?: string;             // failed: unreadable, half-written or unknown product
+}
+
+/** Payload of the `data-source-changed` event */
+export interface WatchBatch {
+    root: string;
+    changes: WatchChange[];
+    error?: string;             // database update failed, nothing stored
+}
+
+// Incremental file index
+
+export interface IndexOptions {
+    force?: boolean;            // re-hash even when mtime is unchanged
+}
+
+export interface IndexReport {
+    unchanged: number;
+    added: string[];
+    modified: string[];         // content changed
+    touched: string[];          // mtime changed, same content
+    moved: { from: string; to: string }[];
+    failed: string[];           // missing or unreadable
+    elapsedMs: number;
+}
+
+// File locks
+
+export interface LockOptions {
+    shared?: boolean;           // reader lock; default exclusive
+    timeoutMs?: number;         // lock_file: default 30000; try_lock_file: default 0
+}
+
+/** Contents of the `<file>.lock-owner` sidecar */
+export interface LockOwner {
+    host: string;
+    pid: number;
+    user: string;
+    mode: 'shared' | 'exclusive';
+    acquiredAt: string;         // RFC 3339
+}
+
+export interface LockInfo {
+    path: string;               // canonical path
+    locked: boolean;
+    heldHere: boolean;          // held by this app
+    holders: number;            // this app's holds; shared locks are counted
+    owner: LockOwner | null;    // null: free, or a shared lock of another process
+}
+
+export interface LockAttempt {
+    acquired: boolean;
+    lock: LockInfo;
+}
+
+// Local HTTP API
+
+export interface HttpApiStatus {
+    enabled: boolean;           // built with the `http-api` feature
+    running: boolean;
+    url: string | null;         // http://127.0.0.1:<port> while running
+}
+
 // =============================================================================
 // AOI inference
 
@@ -1,33 +1,39 @@
-import { invokeReadFileStatBatch } from '@/api/tauri/fs';
-import { getDb } from '@/db/index';
+import { invokeIndexFiles, invokeReadFileStatBatch, invokeScanDataSources, invokeWatchStart } from '@/api/tauri/fs';
+import { getOemProductMap } from '@/db/offsets';
 import { DataSourceRegexState, DataSourceType, FolderGroupsState } from '@/types/dataSource';
-import type { DirResult } from '@/types/ipc';
-import { ExcelMetadata, ExcelType, DirCollection, RawWaferMetadataCollection, WaferFileMetadata } from '@/types/wafer';
+import type { DirResult, ScanOptions, ScanReport, ScannedFile, ScanSourceType, WatchBatch } from '@/types/ipc';
+import { ExcelMetadata, ExcelType, DirCollection, RawWaferMetadata, RawWaferMetadataCollection } from '@/types/wafer';
+import { processNSyncExcelDataWithStats } from '@/utils/wafer';
+import { dirScanResultToast } from '@/components/UI/Toaster';
 
 /**
- * Categorize subfolder **paths** by data source type using provided regex rules.
- *
- * How it works:
- * - Iterates over `regexMap` (from Redux), skipping the key `"lastModified"`.
- * - Compiles each regex string and tests it **against the folder basename** (not the full path).
- * - For every matching rule, pushes the **full** subfolder path into the corresponding bucket.
- * - Invalid regex strings or unknown type keys are safely skipped with a console warning.
+ * Regex rules (from Redux) in the shape `rust_scan_data_sources` takes.
  *
- * Notes:
- * - Matching is independent per type: a subfolder can appear in **multiple** buckets if multiple regexes match.
- * - Matching is case-sensitive by default (standard JS `RegExp`). Add flags like `(?i)` or use `/.../i` style in strings if needed.
- * - Input order is preserved within each bucket.
- *
- * @param subfolders Full paths of direct subfolders to classify (e.g., from `getSubfolders(...)`).
- * @param regexMap   Mapping of `DataSourceType` → regex string (e.g., `{ substrate: "^Substrate", aoi: "AOI" }`).
- *                   The special key `"lastModified"` (if present) is ignored.
+ * - The special key `"lastModified"` (if present) is ignored.
+ * - Rules that are not valid JS regexes are disabled with a console warning, as before.
+ * - Matching is against the folder basename, unanchored like `RegExp#test`.
+ */
+export function scanRegex(regexMap: DataSourceRegexState): NonNullable<ScanOptions['regex']> {
+    const regex: NonNullable<ScanOptions['regex']> = {};
+    for (const type of Object.values(DataSourceType)) {
+        const regexStr = regexMap[type] ?? '';
+        try {
+            new RegExp(regexStr);
+            regex[type as ScanSourceType] = regexStr;
+        } catch {
+            console.warn(`无效正则: ${type} → ${regexStr}`);
+            regex[type as ScanSourceType] = '';
+        }
+    }
+    return regex;
+}
+
+/**
+ * Source folders of a scan grouped by data source type.
  *
- * @returns A mapping from `DataSourceType` to arrays of **full** subfolder paths that matched, e.g.:
+ * A folder can appear in **multiple** buckets if multiple regexes match; scan order is preserved within each bucket.
  */
-export async function autoRecognizeFoldersByType(
-    subfolders: string[],
-    regexMap: DataSourceRegexState
-): Promise<Record<DataSourceType, string[]>> {
+export function foldersByType(report: ScanReport): Record<DataSourceType, string[]> {
     const folderMatches: Record<DataSourceType, string[]> = {
         substrate: [],
         fabCp: [],
@@ -35,27 +41,7 @@ export async function autoRecognizeFoldersByType(
         wlbi: [],
         aoi: [],
     };
-
-    for (const [key, regexStr] of Object.entries(regexMap)) {
-        if (key === 'lastModified') continue;
-        try {
-            const regex = new RegExp(regexStr as string);
-            const matchedPaths = subfolders.filter((folderPath) => {
-                const folderName = folderPath.split(/[\\/]/).pop() || '';
-                return regex.test(folderName);
-            });
-
-            if (key in folderMatches) {
-                folderMatches[key as DataSourceType] = matchedPaths;
-            } else {
-                console.warn(`无效类型: ${key}`);
-            }
-        } catch {
-            console.warn(`无效正则: ${key} → ${regexStr}`);
-        }
-    }
-
-    // console.debug("子目录自动识别结果:", folderMatches);
+    report.folders.forEach(({ stage, path }) => folderMatches[stage as DataSourceType].push(path));
     return folderMatches;
 }
 
@@ -75,36 +61,39 @@ export async function getAllWaferFolders(state: FolderGroupsState): Promise<DirC
     return dataSourceFolders;
 }
 
+/**
+ * Read the metadata of every map / Excel file below the given source folders in a single
+ * `rust_scan_data_sources` call. The folder layouts per source are documented in `file/scanner.rs`.
+ */
 export async function readAllWaferData(folders: DirCollection): Promise<RawWaferMetadataCollection> {
-    // Execute all at the same time
     try {
-        const substrate = await readSubstrateMetadata(folders.substrate);
-        const cpProber = await readCpProberMetadata(folders.cpProber);
-        const wlbi = await readWlbiMetadata(folders.wlbi);
-        const aoi = await readAoiMetadata(folders.aoi);
-        const fabCp = await readFabCpMetadata(folders.fabCp);
+        const picked = (Object.entries(folders) as [DataSourceType, DirResult[]][]).flatMap(([stage, dirs]) =>
+            dirs
+                .filter((f) => f.exists && f.info?.isDirectory)
+                .map((f) => ({ stage: stage as ScanSourceType, path: f.path }))
+        );
+        if (picked.length === 0) return [];
+
+        const report = await invokeScanDataSources('', { folders: picked });
+        if (report.misaligned.length > 0) {
+            console.error('Data misalignment in data source folders!', report.misaligned);
+        }
 
-        const totDir = substrate.totDir + cpProber.totDir + wlbi.totDir + aoi.totDir + fabCp.totDir;
-        const numRead = substrate.numRead + cpProber.numRead + wlbi.numRead + aoi.numRead + fabCp.numRead;
-        const numCached = substrate.numCached + cpProber.numCached + wlbi.numCached + aoi.numCached + fabCp.numCached;
-        const totMatch = substrate.totMatch + cpProber.totMatch + wlbi.totMatch + aoi.totMatch + fabCp.totMatch;
-        const totAdded = substrate.totAdded + cpProber.totAdded + wlbi.totAdded + aoi.totAdded + fabCp.totAdded;
-        // eslint-disable-next-line @typescript-eslint/no-explicit-any
-        const elapsed = (substrate as any).elapsed + (cpProber as any).elapsed + (wlbi as any).elapsed + (aoi as any).elapsed + (fabCp as any).elapsed;
+        // content hashes for file_index; renamed / moved files keep their rows
+        const index = await invokeIndexFiles(report.files.map((f) => f.filePath));
+        if (index.moved.length > 0) console.info('Moved data source files:', index.moved);
+
+        // FAB CP file names carry the OEM model
+        const oemToProduct = new Map((await getOemProductMap()).map((r) => [r.oem_product_id, r.product_id]));
+        const data = report.files.map((f) => toMetadata(f, oemToProduct));
 
         dirScanResultToast(
-            { totDirs: totDir, numRead, numCached, totMatch, totAdded },
-            elapsed,
+            { totDirs: report.dirsVisited, numRead: report.dirsVisited, numCached: 0, totMatch: data.length, totAdded: data.length },
+            report.elapsedMs,
             '读取元数据'
         );
 
-        return [
-            ...substrate.data,
-            ...cpProber.data,
-            ...wlbi.data,
-            ...aoi.data,
-            ...fabCp.data,
-        ];
+        return data;
     } catch (err) {
         console.error(err);
     }
@@ -113,457 +102,60 @@ export async function readAllWaferData(folders: DirCollection): Promise<RawWafer
 }
 
 /**
- * - `Substrate`
- *      - `Defect List`
- *          - `编号.xls`
- *      - `代工厂产品型号_年月日时分秒.xlsx`
- *      - `Product list.xlsx`
- * @param folders 
- * @returns 
+ * Watch the data root in Rust, replacing an earlier watch of the same root; new / changed
+ * map files are written to the database and reported through `useDataSourceChanged`.
  */
-import { listDirs, listFiles, match, nameFromPath, flushIndexQueues } from '@/utils/fs';
-import { scanPattern } from '@/utils/wafer';
-import { logCacheReport } from '@/utils/console';
-import { dirScanResultToast } from '@/components/UI/Toaster';
-export async function readSubstrateMetadata(
-    folders: DirResult[]
-): Promise<{ data: ExcelMetadata[]; totDir: number; numRead: number; numCached: number; totMatch: number; totAdded: number, elapsed: number }> {
-    const t0 = performance.now();
-    const result: ExcelMetadata[] = [];
-
-    const defectListFolder = /^Defect list$/;
-    const defectXls = /^([A-Za-z0-9]+)\.xls$/;
-    const productMap = /^([A-Za-z0-9]+)_([0-9]{8})([0-9]{6})\.xlsx$/;
-    const productList = /^Product list\.xlsx$/;
-
-    let totDir = 0, numRead = 0, numCached = 0, totMatch = 0, totAdded = 0;
-
-    for (const folder of folders) {
-        if (!folder.exists || !folder.info?.isDirectory) continue;
-
-        const {
-            dirs: dlFolders,
-            // cached: dlFoldersCached,
-            totDir: totDirFolder,
-            numRead: numReadFolders,
-            numCached: numCachedFolders,
-        } = await listDirs({ root: folder.path, name: defectListFolder });
-
-        totDir += totDirFolder;
-        numRead += numReadFolders;
-        numCached += numCachedFolders;
-
-        for (const dl of dlFolders) {
-            const dlPath = dl.path;
-
-            const {
-                dirs,
-                // cached,
-                totDir: _totDir,
-                numRead: _numRead,
-                numCached: _numCached,
-            } = await listFiles({ root: dlPath, name: defectXls });
-
-            totDir += _totDir; numRead += _numRead; numCached += _numCached;
-
-            for (const f of dirs) {
-                const m = match(defectXls, nameFromPath(f.path)); if (!m) continue;
-                const [, id] = m;
-                const filePath = f.path
-                result.push({
-                    type: ExcelType.DefectList,
-                    stage: DataSourceType.Substrate,
-                    id,
-                    filePath,
-                    lastModified: Number(f.info?.mtime),
-                });
-                totMatch++; totAdded++;
-            }
-        }
-
-        // 2) Root-level Excel files
-        const {
-            dirs,
-            // cached,
-            totDir: _totDir,
-            numRead: _numRead,
-            numCached: _numCached,
-        } = await listFiles({ root: folder.path, name: /.+/ });
-
-        totDir += _totDir; numRead += _numRead; numCached += _numCached;
-
-        for (const f of dirs) {
-            const m1 = match(productList, nameFromPath(f.path));
-            const m2 = match(productMap, nameFromPath(f.path));
-            const filePath = f.path;
-
-            if (m1) {
-                result.push({
-                    type: ExcelType.Mapping,
-                    stage: DataSourceType.Substrate,
-                    filePath,
-                    lastModified: Number(f.info?.mtime),
-                });
-                totMatch++; totAdded++;
-            } else if (m2) {
-                const [, oem, date, time] = m2;
-                result.push({
-                    type: ExcelType.Product,
-                    stage: DataSourceType.Substrate,
-                    oem,
-                    time: parseWaferMapTimestamp(date, time).toISOString(),
-                    filePath,
-                    lastModified: Number(f.info?.mtime),
-                });
-                totMatch++; totAdded++;
-            }
-        }
-    }
-
-    const elapsed = performance.now() - t0;
-
-    await flushIndexQueues();
-
-    logCacheReport({
-        dirs: 0,
-        totDir,
-        numCached,
-        numRead,
-        label: 'substrate',
-        durationMs: Math.round(elapsed),
-    });
-
-    return { data: result, totDir, numRead, numCached, totMatch, totAdded, elapsed };
+export async function watchDataSourceRoot(rootPath: string, regexMap: DataSourceRegexState): Promise<void> {
+    await invokeWatchStart(rootPath, { regex: scanRegex(regexMap) });
 }
 
 /**
- * TODO: Folder structure unclear at the moment (TBD)
- * @param folders 
- * @returns 
+ * The watcher stores wafer maps itself but only indexes substrate Excel files;
+ * those are re-ingested here.
  */
-// export async function readFabCpMetadata(folders: DirResult[]): Promise<WaferFileMetadata[]> {
-//     const result: WaferFileMetadata[] = [];
-//     folders.filter(() => true);
-//     return result;
-// }
-
-/**
- * Folder structure
- * CP-prober-XX/    (we are here already)
- *      产品型号_批次号_工序_复测次数/
- *          产品型号_批次号_片号/
- *              产品型号_批次号_片号_mapExt.txt
- * @param folders 
- */
-export async function readCpProberMetadata(
-    folders: DirResult[]
-): Promise<{ data: WaferFileMetadata[]; totDir: number; numRead: number; numCached: number; totMatch: number; totAdded: number, elapsed: number }> {
-    const roots = folders.filter(f => f.exists && f.info?.isDirectory).map(f => f.path);
-
-    // Folder: 产品型号_批次号_工序_复测次数
-    const processFolder = /^([A-Za-z0-9]+)_([A-Za-z0-9]+)_(\d+)_(\d+)$/;
-    //                            productModel   batch         processSubStage  retestCount
-    // Folder: 产品型号_批次号_片号
-    const waferFolder = /^([A-Za-z0-9]+)_([A-Za-z0-9]+)_(\d+)$/;
-    //                          productModel   batch         waferId
-    // File: 产品型号_批次号_片号_mapExt.txt
-    const fileName = /^([A-Za-z0-9]+)_([A-Za-z0-9]+)_(\d+)_mapEx\.txt$/;
-    //                   productModel   batch         waferId
-
-    type Ctx = {
-        productModel: string; batch: string; processSubStage: string; retestCount: string; waferId?: string;
-    };
-
-    const scanResult = await scanPattern<Ctx>(
-        roots,
-        {
-            steps: [
-                {
-                    name: processFolder,
-                    // onMatch: ([model, batch]) => true,
-                    onMatch: () => true
-                },
-                {
-                    name: waferFolder,
-                    onMatch: (/*return false if mismatch*/) => true,
-                },
-            ],
-            files: {
-                name: fileName,
-                onFile: () => { } // no-op; we"ll map after
-            }
-        },
-        (level, _name, g) => {
-            if (level === 0) {
-                const [productModel, batch, processSubStage, retestCount] = g;
-                return { productModel, batch, processSubStage, retestCount };
-            }
-            if (level === 1) {
-                const [, , waferId] = g; // model,batch,wafer
-                return { waferId };
-            }
-            return {};
-        }
-    );
-
-    const { data: items } = scanResult;
-
-    const result: WaferFileMetadata[] = [];
-    for (const { ctx, filePath, lastModified } of items) {
-        const ok =
-            ctx.productModel && ctx.batch && ctx.waferId &&
-            // sanity check (optional): names align across levels
-            true;
-
-        if (!ok) {
-            console.error('Data misalignment in CP-prober!', { ctx, filePath });
-            continue;
-        }
-
-        result.push({
-            stage: DataSourceType.CpProber,
-            productModel: ctx.productModel,
-            processSubStage: Number(ctx.processSubStage),
-            batch: ctx.batch,
-            waferId: ctx.waferId!,
-            retestCount: Number(ctx.retestCount),
-            filePath,
-            lastModified,
-        });
+export async function ingestWatchBatch(batch: WatchBatch): Promise<void> {
+    if (batch.error) {
+        console.error(`[watch] ${batch.root}: ${batch.error}`);
+        return;
     }
-
-    await flushIndexQueues();
-
-    return {
-        ...scanResult,
-        data: result,
-    };
-}
-
-/**
- * - `WLBI-XX`
- *      - `产品型号_批次号_工序_复测次数`
- *          - `WaferMap`
- *              - `批次号_片号_年月日_时分秒.WaferMap`
- * @param folders 
- * @returns 
- */
-export async function readWlbiMetadata(
-    folders: DirResult[]
-): Promise<{ data: WaferFileMetadata[]; totDir: number; numRead: number; numCached: number; totMatch: number; totAdded: number, elapsed: number }> {
-    const roots = folders.filter(f => f.exists && f.info?.isDirectory).map(f => f.path);
-
-    const processFolder = /^([A-Za-z0-9]+)_([A-Za-z0-9]+)_(\d+)_(\d+)$/; // model,batch,subStage,retest
-    const wlbiFolder = /^WaferMap$/;
-    const fileName = /^([A-Za-z0-9]+)_([0-9]+)_([0-9]{8})_([0-9]{6})\.WaferMap$/; // wafer,batch,date,time
-
-    type Ctx = { productModel: string; batch: string; processSubStage: string; retestCount: string };
-
-    const scanResult = await scanPattern<Ctx>(
-        roots,
-        {
-            steps: [{ name: processFolder }, { name: wlbiFolder }],
-            files: { name: fileName, onFile: () => { } }
-        },
-        (level, _name, g) => {
-            if (level === 0) {
-                const [productModel, batch, processSubStage, retestCount] = g;
-                return { productModel, batch, processSubStage, retestCount };
-            }
-            return {};
-        }
-    );
-
-    const {
-        data: items
-    } = scanResult;
-
-    const result: WaferFileMetadata[] = [];
-    for (const { ctx, filePath, lastModified } of items) {
-        const m = fileName.exec(filePath.split('/').pop()!);
-        if (!m) continue;
-        const [, batch2, waferId, date, time] = m;
-
-        if (batch2 !== ctx.batch) {
-            console.error(`Data misalignment in WLBI! ${batch2} != ${ctx.batch}`, { ctx, filePath });
-            continue;
-        }
-
-        result.push({
-            stage: DataSourceType.Wlbi,
-            productModel: ctx.productModel,
-            processSubStage: Number(ctx.processSubStage),
-            batch: batch2,
-            waferId,
-            retestCount: Number(ctx.retestCount),
-            time: parseWaferMapTimestamp(date, time).toISOString(),
-            filePath,
-            lastModified,
-        });
+    const excel: ExcelMetadata[] = [];
+    for (const change of batch.changes) {
+        if (change.kind === 'removed' || change.kind === 'failed') continue;
+        if (change.file && change.file.kind !== 'waferMap') excel.push(toExcelMetadata(change.file));
     }
-
-    await flushIndexQueues();
-
-    return {
-        ...scanResult,
-        data: result
-    };
+    if (excel.length > 0) await processNSyncExcelDataWithStats(excel);
 }
 
-/**
- * FAB CP 数据读取
- */
-export async function readFabCpMetadata(
-    folders: DirResult[]
-): Promise<{ data: WaferFileMetadata[]; totDir: number; numRead: number; numCached: number; totMatch: number; totAdded: number, elapsed: number }> {
-    const roots = folders.filter(f => f.exists && f.info?.isDirectory).map(f => f.path);
-    //test/data1/FAB CP/BinMap/B003990/P0097B_B003990_02.txt
-    const binMapFolder = /^BinMap$/;
-    const batchFolder = /^([A-Za-z0-9]+)$/;
-    const mapFile = /^([A-Za-z0-9]+)_([A-Za-z0-9]+)_(\d+)\.txt$/;
-    //                 oemModel   batch        waferId
-
-    type Ctx = { batch: string };
-
-    const scanResult = await scanPattern<Ctx>(
-        roots,
-        {
-            steps: [
-                { name: binMapFolder },
-                { name: batchFolder }
-            ],
-            files: { name: mapFile, onFile: () => { } }
-        },
-        (level, _name, g) => {
-            if (level === 0) {
-                return {};
-            }
-            if (level === 1) {
-                const [batch] = g;
-                return { batch };
-            }
-            return {};
-        }
-    );
-
-    const {
-        data: items
-    } = scanResult;
-
-    const result: WaferFileMetadata[] = [];
-    for (const { ctx, filePath, lastModified } of items) {
-        const m = mapFile.exec(filePath.split('/').pop()!);
-        if (!m) continue;
-        const [, oemModel, batchFromFile, waferId] = m;
-
-        if (batchFromFile !== ctx.batch) {
-            continue;
-        }
-        const db = await getDb();
-
-        const mapping = await db.select<Array<{ product_id: string }>>(
-            'SELECT product_id FROM oem_product_map WHERE oem_product_id = ? LIMIT 1',
-            [oemModel]
-        );
-        const productModel = mapping.length > 0 ? mapping[0].product_id : oemModel;
-
-        result.push({
-            stage: DataSourceType.FabCp,
-            productModel: productModel,
-            batch: ctx.batch,
-            waferId,
-            filePath,
-            lastModified,
-        });
-    }
+////////////////////////////////////////////////////////////////////////////////
+// NOTE: Helper methods
+////////////////////////////////////////////////////////////////////////////////
 
-    await flushIndexQueues();
+function toExcelMetadata(file: ScannedFile): ExcelMetadata {
     return {
-        ...scanResult,
-        data: result
+        type: file.kind as ExcelType,
+        stage: file.stage as DataSourceType,
+        id: file.id,
+        oem: file.oem,
+        time: file.time,
+        filePath: file.filePath,
+        lastModified: file.lastModified,
     };
 }
 
-/**
- * - `AOI-XX`
- *      - `产品型号_批次号`
- *          - `片号`
- *          - `产品型号_批次号_片号_年月日时分秒.txt`
- * @param folders 
- * @returns 
- */
-export async function readAoiMetadata(
-    folders: DirResult[]
-): Promise<{ data: WaferFileMetadata[]; totDir: number; numRead: number; numCached: number; totMatch: number; totAdded: number, elapsed: number }> {
-    const roots = folders.filter(f => f.exists && f.info?.isDirectory).map(f => f.path);
-    //test/data1/AOI-01/S1M040120B_B003990/S1M040120B_B003990_02_20250721095040.txt
-    // Folder: 产品型号_批次号
-    const processFolder = /^([A-Za-z0-9]+)_([A-Za-z0-9]+)$/;
-    //                            productModel   batch
-    // Folder: 片号片号
-    // const waferFolderRegex = /^WaferMap$/;           // Wafer folder so far has no use case
-    // File: 产品型号_批次号_片号_年月日时分秒.txt
-    const mapFile = /^([A-Za-z0-9]+)_([A-Za-z0-9]+)_([0-9]+)_([0-9]{8})([0-9]{6})\.txt$/
-    //                  productModel   batch        waferId    date & time
-
-    type Ctx = { productModel: string; batch: string };
-
-    const scanResult = await scanPattern<Ctx>(
-        roots,
-        {
-            steps: [{ name: processFolder }],
-            files: { name: mapFile, onFile: () => { } }
-        },
-        (level, _name, g) => (level === 0 ? { productModel: g[0], batch: g[1] } : {})
-    );
-
-    const {
-        data: items
-    } = scanResult;
-
-    const result: WaferFileMetadata[] = [];
-    for (const { ctx, filePath, lastModified } of items) {
-        const m = mapFile.exec(filePath.split('/').pop()!);
-        if (!m) continue;
-        const [, model2, batch2, waferId, date, time] = m;
-
-        if (model2 !== ctx.productModel || batch2 !== ctx.batch) {
-            console.error('Folder data structure misalignment in AOI!', { ctx, filePath });
-            continue;
-        }
-
-        result.push({
-            stage: DataSourceType.Aoi,
-            productModel: ctx.productModel,
-            batch: ctx.batch,
-            waferId,
-            time: parseWaferMapTimestamp(date, time).toISOString(),
-            filePath,
-            lastModified,
-        });
-    }
-
-    await flushIndexQueues();
+function toMetadata(file: ScannedFile, oemToProduct: Map<string, string>): RawWaferMetadata {
+    const stage = file.stage as DataSourceType;
+    if (file.kind !== 'waferMap') return toExcelMetadata(file);
 
+    const model = file.productModel ?? '';
     return {
-        ...scanResult,
-        data: result
+        stage,
+        productModel: stage === DataSourceType.FabCp ? oemToProduct.get(model) ?? model : model,
+        batch: file.batch ?? '',
+        waferId: file.waferId ?? '',
+        processSubStage: file.processSubStage,
+        retestCount: file.retestCount,
+        time: file.time,
+        filePath: file.filePath,
+        lastModified: file.lastModified,
     };
 }
-
-////////////////////////////////////////////////////////////////////////////////
-// NOTE: Helper methods
-////////////////////////////////////////////////////////////////////////////////
-
-function parseWaferMapTimestamp(dateStr: string, timeStr: string): Date {
-    // Example: dateStr = "20250709", timeStr = "120302"
-    const year = parseInt(dateStr.slice(0, 4), 10);
-    const month = parseInt(dateStr.slice(4, 6), 10) - 1; // JS months are 0-indexed
-    const day = parseInt(dateStr.slice(6, 8), 10);
-
-    const hours = parseInt(timeStr.slice(0, 2), 10);
-    const minutes = parseInt(timeStr.slice(2, 4), 10);
-    const seconds = parseInt(timeStr.slice(4, 6), 10);
-
-    return new Date(year, month, day, hours, minutes, seconds);
-}
\ No newline at end of file
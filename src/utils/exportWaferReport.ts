@@ -3,10 +3,19 @@ import { join } from '@tauri-apps/api/path';
 import { getWaferStackStatsByOem } from '@/db/waferStackStats';
 import { deleteWaferStackStatsByOem } from '@/db/waferStackStats';
 import { getProductSize } from '@/db/productSize';
+import { exportLotReport, renderCompositeMap } from '@/api/tauri/wafer';
+import type { LotReportWafer } from '@/types/ipc';
+
+export interface LotPdfReportOptions {
+    /** Stacking results of this run; wafers without one get a stats-only page */
+    wafers: LotReportWafer[];
+    passBins: string[];
+}
 
 export async function exportWaferStatsReport(
     oemProductIds: string | string[],
     outputDir: string,
+    pdfReport?: LotPdfReportOptions,
     machineId = '15',
     waferSize = 6, //默认 待增加
 ): Promise<string[]> {
@@ -137,6 +146,46 @@ export async function exportWaferStatsReport(
             const csvContent = content.replace(/\t/g, ',');
             await writeTextFile(outputPath, csvContent);
             exportedPaths.push(outputPath);
+
+            const stacked = pdfReport?.wafers.filter(w =>
+                w.stats.oem_product_id === oemProductId && w.stats.batch_id === batchId && w.dies?.length
+            ) ?? [];
+            if (stacked.length > 1) {
+                const compositePath = await join(dataDir, `${oemProductId}_${batchId}_composite.png`);
+                try {
+                    await renderCompositeMap(
+                        stacked.map(w => ({ waferId: w.stats.wafer_id, dies: w.dies ?? [] })),
+                        stacked[0].header ?? {},
+                        compositePath,
+                        stacked[0].coords,
+                        { passBins: pdfReport?.passBins },
+                    );
+                    exportedPaths.push(compositePath);
+                } catch (e) {
+                    console.warn(`叠加热图生成失败 ${oemProductId}_${batchId}:`, e);
+                }
+            }
+
+            if (pdfReport) {
+                const pdfPath = await join(dataDir, `${oemProductId}_${batchId}_Report.pdf`);
+                try {
+                    await exportLotReport({
+                        oemProductId,
+                        batchId,
+                        wafers: batchStats.map(stats =>
+                            pdfReport.wafers.find(w =>
+                                w.stats.oem_product_id === stats.oem_product_id &&
+                                w.stats.batch_id === stats.batch_id &&
+                                w.stats.wafer_id === stats.wafer_id
+                            ) ?? { stats }
+                        ),
+                        passBins: pdfReport.passBins,
+                    }, pdfPath);
+                    exportedPaths.push(pdfPath);
+                } catch (e) {
+                    console.warn(`PDF 报告生成失败 ${oemProductId}_${batchId}:`, e);
+                }
+            }
         }
 
         await deleteWaferStackStatsByOem(oemProductId);
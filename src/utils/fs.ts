@@ -4,7 +4,7 @@ import { stat } from '@tauri-apps/plugin-fs'; // or @tauri-apps/api/fs if you're
 import { DirResult } from '@/types/ipc';
 import { FileIndexRow, FolderIndexRow } from '@/db/types';
 import { deleteFolderIndexesByPaths, getAllFolderIndexes, getManyFolderIndexesByPaths, upsertManyFolderIndexes, upsertOneFolderIndex } from '@/db/folderIndex';
-import { invokeReadDir, invokeSha1Batch } from '@/api/tauri/fs';
+import { invokeReadDir, invokeSha256FileBatch } from '@/api/tauri/fs';
 import { deleteFileIndexesByPaths, getAllFileIndexes, getManyFileIndexesByPaths, upsertManyFileIndexes } from '@/db/fileIndex';
 
 // ---------- Globals ----------
@@ -280,7 +280,8 @@ async function scanEntries({
 
     // Batch-hash files once (avoids per-file IPC)
     if (kind === 'file' && cache && pendingFileHashes.length) {
-        const hashes = await invokeSha1Batch(pendingFileHashes.map(p => p.path));
+        // content hashes, so renamed/moved files can be recognised
+        const hashes = await invokeSha256FileBatch(pendingFileHashes.map(p => p.path));
         for (let i = 0; i < pendingFileHashes.length; i++) {
             const { path, mtime } = pendingFileHashes[i];
             const hash = hashes[i];
@@ -109,6 +109,18 @@ describe('waferSubstrateRenderer', () => {
         ]);
     });
 
+    it('takes REFPX/REFPY and the die size from the coordinate system', () => {
+        const shifted = dies.map((die) => ({ ...die, x: die.x - 3, y: die.y + 2 }));
+        const result = convertToHexMapData(shifted, header, {
+            dieWidthMm: 5,
+            dieHeightMm: 4,
+            referenceX: -2,
+            referenceY: 3,
+        });
+
+        expect(result.header).toMatchObject({ refpx: 1, refpy: 1, xDies: 5, yDies: 4 });
+    });
+
     it('converts dies, stats, and FAB header fields to a Wafer', () => {
         const result = convertToFabWafer(dies, stats, header);
 
@@ -11,6 +11,7 @@ import {
     isNumberBin,
     BinValue,
     SilanMapData,
+    DieCoordinateSystem,
 } from '@/types/ipc';
 import { PRIORITY_RULES, LayerMeta, PASS_VALUES } from '@/pages/WaferStacking/priority';
 import { binValueMatchesValues } from '@/pages/Config/binConfig';
@@ -333,10 +334,12 @@ export const convertToBinMapData = (
 
 /**
  * 转换为HexMapData类型
+ * @param coords 产品的晶粒坐标系，给出 REFPX/REFPY（参考晶粒）与 XDIES/YDIES
  */
 export const convertToHexMapData = (
     dies: AsciiDie[],
-    header?: Record<string, string>
+    header?: Record<string, string>,
+    coords?: DieCoordinateSystem
 ): HexMapData => {
     // Build quick lookup to avoid O(n^2) `.find` inside nested loops
     const xs = dies.map(die => die.x);
@@ -386,11 +389,11 @@ export const convertToHexMapData = (
             wafer: header?.['Wafer ID'] || 'Unknown',
             rowCt: maxY - minY + 1,
             colCt: maxX - minX + 1,
-            refpx: 0, //未知
-            refpy: 0, //未知
+            refpx: coords ? Math.max(0, (coords.referenceX ?? 0) - minX) : 0,
+            refpy: coords ? Math.max(0, (coords.referenceY ?? 0) - minY) : 0,
             dutMs: 'MM',
-            xDies: header?.['Dice SizeX'] ? parseFloat(header['Dice SizeX']) / 1000 : 0,
-            yDies: header?.['Dice SizeY'] ? parseFloat(header['Dice SizeY']) / 1000 : 0,
+            xDies: coords?.dieWidthMm ?? (header?.['Dice SizeX'] ? parseFloat(header['Dice SizeX']) / 1000 : 0),
+            yDies: coords?.dieHeightMm ?? (header?.['Dice SizeY'] ? parseFloat(header['Dice SizeY']) / 1000 : 0),
         },
         map: {
             raw: [],